@@ -0,0 +1,449 @@
+use crate::algo::fileio::{ScanError, ScanResult};
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Read, Result, Write},
+    path::Path,
+};
+
+const BUF_SIZE: usize = 1 << 16;
+
+/// A reader for the competitive-programming style input. It pulls the source into a 64KB buffer
+/// and scans the tokens in memory, instead of issuing a `read` for every single byte.
+pub struct FastReader<R: Read> {
+    r: R,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+    // The reused scratch buffer for the token which crosses two buffer fills.
+    tok: Vec<u8>,
+}
+
+/// The buffered writer paired with `FastReader`. The output is flushed when it is dropped.
+pub struct Writer<W: Write> {
+    w: BufWriter<W>,
+}
+
+#[inline]
+fn is_space(b: u8) -> bool {
+    b == b' ' || b == b'\t' || b == b'\r' || b == b'\n'
+}
+
+fn parse_error(token: &[u8], msg: &str) -> ScanError {
+    ScanError::Parse {
+        token: String::from_utf8_lossy(token).into_owned(),
+        msg: msg.to_string(),
+    }
+}
+
+pub(crate) fn parse_u64(token: &[u8]) -> ScanResult<u64> {
+    if token.is_empty() {
+        return Err(parse_error(token, "empty number"));
+    }
+
+    let mut ret: u64 = 0;
+    for &b in token {
+        if !b.is_ascii_digit() {
+            return Err(parse_error(token, "invalid digit found in string"));
+        }
+        ret = ret
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((b - b'0') as u64))
+            .ok_or_else(|| parse_error(token, "number too large to fit in target type"))?;
+    }
+    Ok(ret)
+}
+
+pub(crate) fn parse_i64(token: &[u8]) -> ScanResult<i64> {
+    let (is_negative, digits) = match token.first() {
+        Some(b'-') => (true, &token[1..]),
+        Some(b'+') => (false, &token[1..]),
+        _ => (false, token),
+    };
+
+    let v = parse_u64(digits).map_err(|_| parse_error(token, "invalid i64 literal"))?;
+    if is_negative {
+        if v == 1 << 63 {
+            Ok(i64::MIN)
+        } else if v < 1 << 63 {
+            Ok(-(v as i64))
+        } else {
+            Err(parse_error(token, "number too small to fit in target type"))
+        }
+    } else if v < 1 << 63 {
+        Ok(v as i64)
+    } else {
+        Err(parse_error(token, "number too large to fit in target type"))
+    }
+}
+
+impl FastReader<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> ScanResult<Self> {
+        Ok(FastReader::new(File::open(path)?))
+    }
+}
+
+impl FastReader<io::Stdin> {
+    pub fn stdin() -> Self {
+        FastReader::new(io::stdin())
+    }
+}
+
+impl<R: Read> FastReader<R> {
+    pub fn new(r: R) -> Self {
+        FastReader {
+            r,
+            buf: vec![0; BUF_SIZE],
+            pos: 0,
+            len: 0,
+            tok: Vec::new(),
+        }
+    }
+
+    /// Refill the buffer when all the bytes are consumed, false means the source is drained.
+    fn fill(&mut self) -> Result<bool> {
+        if self.pos < self.len {
+            return Ok(true);
+        }
+        loop {
+            match self.r.read(&mut self.buf) {
+                Ok(n) => {
+                    self.pos = 0;
+                    self.len = n;
+                    return Ok(n > 0);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Skip the leading spaces, false means there is no more token.
+    fn skip_spaces(&mut self) -> Result<bool> {
+        while self.fill()? {
+            let n = self.buf[self.pos..self.len]
+                .iter()
+                .take_while(|&&b| is_space(b))
+                .count();
+            self.pos += n;
+            if self.pos < self.len {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Scan the next token into `self.tok`, false means the input is drained.
+    fn token(&mut self) -> ScanResult<bool> {
+        self.tok.clear();
+        if !self.skip_spaces()? {
+            return Ok(false);
+        }
+
+        while self.fill()? {
+            let n = self.buf[self.pos..self.len]
+                .iter()
+                .take_while(|&&b| !is_space(b))
+                .count();
+            self.tok
+                .extend_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+            if self.pos < self.len {
+                break;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether there is any token left in the input.
+    pub fn has_next(&mut self) -> ScanResult<bool> {
+        Ok(self.skip_spaces()?)
+    }
+
+    pub fn read_u64(&mut self) -> ScanResult<u64> {
+        if !self.token()? {
+            return Err(ScanError::Eof);
+        }
+        parse_u64(&self.tok)
+    }
+
+    pub fn read_i64(&mut self) -> ScanResult<i64> {
+        if !self.token()? {
+            return Err(ScanError::Eof);
+        }
+        parse_i64(&self.tok)
+    }
+
+    pub fn read_i32(&mut self) -> ScanResult<i32> {
+        let v = self.read_i64()?;
+        i32::try_from(v).map_err(|e| parse_error(&self.tok, &e.to_string()))
+    }
+
+    pub fn read_u32(&mut self) -> ScanResult<u32> {
+        let v = self.read_u64()?;
+        u32::try_from(v).map_err(|e| parse_error(&self.tok, &e.to_string()))
+    }
+
+    pub fn read_usize(&mut self) -> ScanResult<usize> {
+        let v = self.read_u64()?;
+        usize::try_from(v).map_err(|e| parse_error(&self.tok, &e.to_string()))
+    }
+
+    pub fn read_f64(&mut self) -> ScanResult<f64> {
+        let word = self.read_word()?;
+        word.parse::<f64>().map_err(|e| ScanError::Parse {
+            token: word,
+            msg: e.to_string(),
+        })
+    }
+
+    pub fn read_word(&mut self) -> ScanResult<String> {
+        if !self.token()? {
+            return Err(ScanError::Eof);
+        }
+        String::from_utf8(self.tok.clone()).map_err(|e| parse_error(&self.tok, &e.to_string()))
+    }
+
+    pub fn read_vec_i64(&mut self, n: usize) -> ScanResult<Vec<i64>> {
+        (0..n).map(|_| self.read_i64()).collect()
+    }
+
+    /// Read the rest of the current line without the trailing LF or CRLF, or None at EOF.
+    pub fn read_line(&mut self) -> ScanResult<Option<String>> {
+        let mut line = Vec::new();
+        let mut found = false;
+        while self.fill()? {
+            found = true;
+            let rest = &self.buf[self.pos..self.len];
+            match rest.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    line.extend_from_slice(&rest[..i]);
+                    self.pos += i + 1;
+                    break;
+                }
+                None => {
+                    line.extend_from_slice(rest);
+                    self.pos = self.len;
+                }
+            }
+        }
+
+        if !found {
+            return Ok(None);
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        String::from_utf8(line)
+            .map(Some)
+            .map_err(|e| parse_error(e.as_bytes(), &e.utf8_error().to_string()))
+    }
+}
+
+impl Writer<File> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Writer::new(File::create(path)?))
+    }
+}
+
+impl Writer<io::Stdout> {
+    pub fn stdout() -> Self {
+        Writer::new(io::stdout())
+    }
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(w: W) -> Self {
+        Writer {
+            w: BufWriter::with_capacity(BUF_SIZE, w),
+        }
+    }
+
+    pub fn write_i64(&mut self, v: i64) -> Result<()> {
+        if v < 0 {
+            self.w.write_all(b"-")?;
+        }
+        self.write_u64(v.unsigned_abs())
+    }
+
+    pub fn write_u64(&mut self, mut v: u64) -> Result<()> {
+        // u64::MAX has 20 digits.
+        let mut digits = [0u8; 20];
+        let mut i = digits.len();
+        loop {
+            i -= 1;
+            digits[i] = b'0' + (v % 10) as u8;
+            v /= 10;
+            if v == 0 {
+                break;
+            }
+        }
+        self.w.write_all(&digits[i..])
+    }
+
+    /// Write the float with a fixed number of digits after the decimal point.
+    pub fn write_f64(&mut self, v: f64, precision: usize) -> Result<()> {
+        write!(self.w, "{:.*}", precision, v)
+    }
+
+    pub fn write_word(&mut self, s: &str) -> Result<()> {
+        self.w.write_all(s.as_bytes())
+    }
+
+    pub fn write_space(&mut self) -> Result<()> {
+        self.w.write_all(b" ")
+    }
+
+    pub fn newline(&mut self) -> Result<()> {
+        self.w.write_all(b"\n")
+    }
+
+    pub fn write_line(&mut self, s: &str) -> Result<()> {
+        self.w.write_all(s.as_bytes())?;
+        self.newline()
+    }
+
+    /// Write all the elements separated by `sep`, without the trailing separator or newline.
+    pub fn write_joined<T: Display>(&mut self, v: &[T], sep: &str) -> Result<()> {
+        for (i, x) in v.iter().enumerate() {
+            if i > 0 {
+                self.w.write_all(sep.as_bytes())?;
+            }
+            write!(self.w, "{}", x)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.w.flush()
+    }
+
+    /// Flush the buffer and return the underlying writer.
+    pub fn into_inner(self) -> Result<W> {
+        self.w.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+/// The (input, output) pair on stdin and stdout.
+pub fn stdio() -> (FastReader<io::Stdin>, Writer<io::Stdout>) {
+    (FastReader::stdin(), Writer::stdout())
+}
+
+/// The (input, output) pair on the given files, the output file is truncated if it exists.
+pub fn file_io<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+) -> ScanResult<(FastReader<File>, Writer<File>)> {
+    Ok((FastReader::open(input)?, Writer::create(output)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+    use std::env;
+    use std::fs;
+
+    // A tiny problem solved end-to-end: read n and n integers, print the sum and the sorted list.
+    fn solve<R: Read, W: Write>(r: &mut FastReader<R>, w: &mut Writer<W>) -> ScanResult<()> {
+        let n = r.read_usize()?;
+        let mut v = r.read_vec_i64(n)?;
+        v.sort();
+
+        w.write_i64(v.iter().sum())?;
+        w.newline()?;
+        w.write_joined(&v, " ")?;
+        w.newline()?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn read_tokens() {
+        let data = "  42\t-9223372036854775808 \r\n18446744073709551615 +7\n3.25 word\r\n";
+        let mut r = FastReader::new(data.as_bytes());
+        assert_eq!(r.read_i32().unwrap(), 42);
+        assert_eq!(r.read_i64().unwrap(), i64::MIN);
+        assert_eq!(r.read_u64().unwrap(), u64::MAX);
+        assert_eq!(r.read_i64().unwrap(), 7);
+        assert_eq!(r.read_f64().unwrap(), 3.25);
+        assert_eq!(r.read_word().unwrap(), "word");
+        assert!(!r.has_next().unwrap());
+        assert!(matches!(r.read_i64(), Err(ScanError::Eof)));
+    }
+
+    #[test]
+    pub fn read_errors() {
+        let mut r = FastReader::new(&b"12a 4294967296 -1 18446744073709551616 -"[..]);
+        assert!(matches!(r.read_i64(), Err(ScanError::Parse { .. })));
+        assert!(matches!(r.read_u32(), Err(ScanError::Parse { .. })));
+        assert!(matches!(r.read_u64(), Err(ScanError::Parse { .. })));
+        match r.read_u64() {
+            Err(ScanError::Parse { token, .. }) => assert_eq!(token, "18446744073709551616"),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+        assert!(matches!(r.read_i64(), Err(ScanError::Parse { .. })));
+    }
+
+    #[test]
+    pub fn read_lines() {
+        let mut r = FastReader::new(&b"first line\r\n\nlast"[..]);
+        assert_eq!(r.read_line().unwrap(), Some(String::from("first line")));
+        assert_eq!(r.read_line().unwrap(), Some(String::new()));
+        assert_eq!(r.read_line().unwrap(), Some(String::from("last")));
+        assert_eq!(r.read_line().unwrap(), None);
+    }
+
+    #[test]
+    pub fn token_across_buffer_boundary() {
+        // Every number crosses the 64KB buffer boundary at some point.
+        let v: Vec<i64> = (0..100000).map(|i| i * 1000003 - 50000000000).collect();
+        let data: Vec<String> = v.iter().map(|x| x.to_string()).collect();
+        let data = data.join(" ");
+
+        let mut r = FastReader::new(data.as_bytes());
+        assert_eq!(r.read_vec_i64(v.len()).unwrap(), v);
+        assert!(!r.has_next().unwrap());
+    }
+
+    #[test]
+    pub fn write_values() {
+        let mut w = Writer::new(Vec::new());
+        w.write_i64(i64::MIN).unwrap();
+        w.write_space().unwrap();
+        w.write_u64(0).unwrap();
+        w.write_space().unwrap();
+        w.write_u64(u64::MAX).unwrap();
+        w.newline().unwrap();
+        w.write_f64(1.23456, 2).unwrap();
+        w.write_space().unwrap();
+        w.write_f64(-0.5, 0).unwrap();
+        w.newline().unwrap();
+        w.write_word("hello").unwrap();
+        w.write_space().unwrap();
+        w.write_line("world").unwrap();
+        w.write_joined(&[1, 2, 3], ", ").unwrap();
+        w.write_joined::<i32>(&[], ", ").unwrap();
+
+        let out = String::from_utf8(w.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            out,
+            "-9223372036854775808 0 18446744073709551615\n1.23 -0\nhello world\n1, 2, 3"
+        );
+    }
+
+    #[test]
+    pub fn solve_with_files() {
+        let input = env::temp_dir().join(rand::gen_u32().to_string());
+        let output = env::temp_dir().join(rand::gen_u32().to_string());
+        fs::write(&input, "5\r\n3 -1 4 1 -5\r\n").expect("Failed to write");
+
+        {
+            let (mut r, mut w) = file_io(&input, &output).unwrap();
+            solve(&mut r, &mut w).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&output).unwrap(), "2\n-5 -1 1 3 4\n");
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+    }
+}
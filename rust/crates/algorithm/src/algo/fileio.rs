@@ -1,7 +1,9 @@
 use std::{
+    fmt,
     fs::File,
-    io::{BufRead, BufReader, Error, ErrorKind, Read, Result},
+    io::{self, BufRead, BufReader, Error, ErrorKind, Read, Result},
     path::Path,
+    str::FromStr,
 };
 
 pub struct FileIO {
@@ -19,14 +21,21 @@ fn to_digit(c: char) -> u8 {
     c as u8 - '0' as u8
 }
 
+/// Space, tab, CR and LF are all treated as the token separators, so files with CRLF line endings
+/// can be read in the same way.
+#[inline]
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\r' || c == '\n'
+}
+
 impl FileIO {
     /// Initialize a FileIO instance.
     /// Use the generic `AsRef<Path>` here because it want us to pass &PathBuf here for convenience.
-    pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        FileIO {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(FileIO {
             buf: vec![0; 1024],
-            r: BufReader::new(File::open(path).expect("open failed")),
-        }
+            r: BufReader::new(File::open(path)?),
+        })
     }
 
     pub fn read_line(&mut self, buf: &mut String) -> Result<usize> {
@@ -45,110 +54,130 @@ impl FileIO {
         Ok(n)
     }
 
+    /// Read the next integer into `v`, or return 0 and leave `v` unchanged at EOF.
     pub fn read_i32(&mut self, v: &mut i32) -> Result<usize> {
-        let mut ret = 0;
-        let mut is_negative = false;
-
-        let mut c: char = 0x00 as char;
-        let mut nread = 0;
-
-        while self.read_char(&mut c)? != 0 {
-            nread += 1;
-            if c != ' ' && c != '\n' {
-                break;
-            }
+        let mut x = 0;
+        let nread = self.read_i64(&mut x)?;
+        if nread > 0 {
+            *v = i32::try_from(x).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Overflow to convert to i32: {}", x),
+                )
+            })?;
         }
+        Ok(nread)
+    }
 
-        if c == '-' {
-            is_negative = true;
-        } else if is_digit(c) {
-            ret = to_digit(c) as u32;
-        } else {
-            return Err(Error::new(ErrorKind::Other, "Unexpected byte"));
+    /// Read the next integer into `v`, or return 0 and leave `v` unchanged at EOF.
+    pub fn read_u32(&mut self, v: &mut u32) -> Result<usize> {
+        let mut x = 0;
+        let nread = self.read_u64(&mut x)?;
+        if nread > 0 {
+            *v = u32::try_from(x).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Overflow to convert to u32: {}", x),
+                )
+            })?;
         }
+        Ok(nread)
+    }
 
-        while self.read_char(&mut c)? != 0 {
-            nread += 1;
-            if is_digit(c) {
-                ret = ret * 10 + to_digit(c) as u32;
-            } else {
-                break;
+    /// Skip the spaces, and return false if the input ends before any other char, which is left
+    /// in `c`.
+    fn skip_spaces(&mut self, c: &mut char, nread: &mut usize) -> Result<bool> {
+        while self.read_char(c)? != 0 {
+            *nread += 1;
+            if !is_space(*c) {
+                return Ok(true);
             }
         }
+        Ok(false)
+    }
 
-        if is_negative {
-            if ret == (1 << 31) {
-                *v = i32::MIN;
-            } else if ret < (1 << 31) {
-                *v = -(ret as i32);
-            } else {
-                panic!("Overflow to convert to i32: {}", ret);
+    /// Read the digits following the first one `c`, and fail if the number overflows u64. All the
+    /// digits are consumed even on the overflow, so the next read starts at the next token.
+    fn read_digits(&mut self, mut c: char, nread: &mut usize) -> Result<u64> {
+        let mut ret = Some(to_digit(c) as u64);
+        while self.read_char(&mut c)? != 0 {
+            *nread += 1;
+            if !is_digit(c) {
+                break;
             }
-        } else {
-            *v = ret as i32;
+            ret = ret
+                .and_then(|r| r.checked_mul(10))
+                .and_then(|r| r.checked_add(to_digit(c) as u64));
         }
-
-        Ok(nread)
+        ret.ok_or_else(|| Error::new(ErrorKind::InvalidData, "Overflow to convert to u64"))
     }
 
-    pub fn read_u32(&mut self, v: &mut u32) -> Result<usize> {
-        let mut c = 0 as char;
+    /// Read the next integer into `v`, or return 0 and leave `v` unchanged at EOF.
+    pub fn read_i64(&mut self, v: &mut i64) -> Result<usize> {
+        let mut c: char = 0x00 as char;
         let mut nread = 0;
+        if !self.skip_spaces(&mut c, &mut nread)? {
+            return Ok(0);
+        }
 
-        while self.read_char(&mut c)? != 0 {
-            nread += 1;
-            if c != ' ' && c != '\n' {
-                break;
+        let is_negative = c == '-';
+        if is_negative {
+            if self.read_char(&mut c)? == 0 || !is_digit(c) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Expect digits after '-'",
+                ));
             }
+            nread += 1;
+        } else if !is_digit(c) {
+            return Err(Error::other("Unexpected byte"));
         }
+        let ret = self.read_digits(c, &mut nread)?;
 
-        let mut ret = to_digit(c) as u32;
-        while self.read_char(&mut c)? != 0 {
-            nread += 1;
-            if is_digit(c) {
-                ret = ret * 10 + to_digit(c) as u32;
+        if is_negative {
+            if ret == (1 << 63) {
+                *v = i64::MIN;
+            } else if ret < (1 << 63) {
+                *v = -(ret as i64);
             } else {
-                break;
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Overflow to convert to i64: -{}", ret),
+                ));
             }
+        } else if ret < (1 << 63) {
+            *v = ret as i64;
+        } else {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Overflow to convert to i64: {}", ret),
+            ));
         }
 
-        *v = ret;
         Ok(nread)
     }
 
-    pub fn read_i64(&mut self) -> i64 {
-        todo!()
-    }
-
+    /// Read the next integer into `v`, or return 0 and leave `v` unchanged at EOF.
     pub fn read_u64(&mut self, v: &mut u64) -> Result<usize> {
         let mut c = 0 as char;
         let mut nread = 0;
-
-        while self.read_char(&mut c)? != 0 {
-            nread += 1;
-            if c != ' ' && c != '\n' {
-                break;
-            }
+        if !self.skip_spaces(&mut c, &mut nread)? {
+            return Ok(0);
         }
-
-        let mut ret = to_digit(c) as u64;
-        while self.read_char(&mut c)? != 0 {
-            nread += 1;
-            if is_digit(c) {
-                ret = ret * 10 + to_digit(c) as u64;
-            } else {
-                break;
-            }
+        if !is_digit(c) {
+            return Err(Error::other("Unexpected byte"));
         }
 
-        *v = ret;
+        *v = self.read_digits(c, &mut nread)?;
         Ok(nread)
     }
 
     pub fn read_f32(&mut self, v: &mut f32) -> Result<usize> {
         let mut s = String::new();
         let nread = self.read_word(&mut s)?;
-        *v = s.parse::<f32>().unwrap();
+        *v = s
+            .parse::<f32>()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{:?}: {}", s, e)))?;
         Ok(nread)
     }
 
@@ -158,7 +187,7 @@ impl FileIO {
 
         while self.read_char(&mut c)? != 0 {
             nread += 1;
-            if c != ' ' && c != '\n' {
+            if !is_space(c) {
                 break;
             }
         }
@@ -176,7 +205,7 @@ impl FileIO {
                     }
 
                     nread += 1;
-                    if c == ' ' || c == '\n' {
+                    if is_space(c) {
                         // Read an empty space or blank.
                         break;
                     } else {
@@ -191,6 +220,168 @@ impl FileIO {
     }
 }
 
+/// The error returned by `Scanner`, which tells apart the underlying IO failure, the token which
+/// cannot be parsed into the expected type, and the input which ends before the expected token.
+#[derive(Debug)]
+pub enum ScanError {
+    Io(Error),
+    Parse { token: String, msg: String },
+    Eof,
+}
+
+pub type ScanResult<T> = std::result::Result<T, ScanError>;
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Io(e) => write!(f, "IO error: {}", e),
+            ScanError::Parse { token, msg } => write!(f, "Failed to parse {:?}: {}", token, msg),
+            ScanError::Eof => write!(f, "Unexpected end of input"),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ScanError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for ScanError {
+    fn from(e: Error) -> Self {
+        ScanError::Io(e)
+    }
+}
+
+impl From<ScanError> for Error {
+    fn from(e: ScanError) -> Self {
+        match e {
+            ScanError::Io(e) => e,
+            ScanError::Eof => Error::new(ErrorKind::UnexpectedEof, e.to_string()),
+            ScanError::Parse { .. } => Error::new(ErrorKind::InvalidData, e.to_string()),
+        }
+    }
+}
+
+/// A whitespace separated token reader over any `Read` source, such as a file, the stdin or a
+/// `&[u8]`. Each token is parsed by `FromStr`, so it works for all the integers, floats, `char`
+/// and `String`.
+pub struct Scanner<R: Read> {
+    r: BufReader<R>,
+}
+
+impl Scanner<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> ScanResult<Self> {
+        Ok(Scanner::new(File::open(path)?))
+    }
+}
+
+impl Scanner<io::Stdin> {
+    pub fn stdin() -> Self {
+        Scanner::new(io::stdin())
+    }
+}
+
+impl<R: Read> Scanner<R> {
+    pub fn new(r: R) -> Self {
+        Scanner {
+            r: BufReader::new(r),
+        }
+    }
+
+    /// Read the next whitespace separated token as raw bytes, or None if the input is drained.
+    fn read_token(&mut self) -> ScanResult<Option<Vec<u8>>> {
+        // Skip all the leading spaces.
+        loop {
+            let buf = self.r.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            let n = buf.iter().take_while(|&&b| is_space(b as char)).count();
+            let found = n < buf.len();
+            self.r.consume(n);
+            if found {
+                break;
+            }
+        }
+
+        let mut token = Vec::new();
+        loop {
+            let buf = self.r.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let n = buf.iter().take_while(|&&b| !is_space(b as char)).count();
+            token.extend_from_slice(&buf[..n]);
+            let found = n < buf.len();
+            self.r.consume(n);
+            if found {
+                break;
+            }
+        }
+
+        Ok(Some(token))
+    }
+
+    /// Parse the next token as `T`, Ok(None) means the input has been drained cleanly.
+    pub fn next_token<T: FromStr>(&mut self) -> ScanResult<Option<T>>
+    where
+        T::Err: fmt::Display,
+    {
+        let token = match self.read_token()? {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+
+        let s = String::from_utf8(token).map_err(|e| ScanError::Parse {
+            token: String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            msg: e.to_string(),
+        })?;
+
+        match s.parse::<T>() {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => Err(ScanError::Parse {
+                msg: e.to_string(),
+                token: s,
+            }),
+        }
+    }
+
+    /// Same as `next_token`, but the missing token is reported as `ScanError::Eof`.
+    pub fn read<T: FromStr>(&mut self) -> ScanResult<T>
+    where
+        T::Err: fmt::Display,
+    {
+        self.next_token::<T>()?.ok_or(ScanError::Eof)
+    }
+
+    /// Read `n` tokens of type `T`, fails with `ScanError::Eof` if there are less than `n` tokens.
+    pub fn read_vec<T: FromStr>(&mut self, n: usize) -> ScanResult<Vec<T>>
+    where
+        T::Err: fmt::Display,
+    {
+        (0..n).map(|_| self.read::<T>()).collect()
+    }
+
+    /// Read the rest of the current line without the trailing LF or CRLF, or None at EOF.
+    pub fn next_line(&mut self) -> ScanResult<Option<String>> {
+        let mut line = String::new();
+        if self.r.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -221,7 +412,7 @@ mod tests {
         let data = String::from("abc\n eof\n\nhello world.");
         let path = f_write(data.clone()).expect("Failed to write");
 
-        let mut io = FileIO::new(&path);
+        let mut io = FileIO::new(&path).unwrap();
         let mut c = 0x00 as char;
         for i in 0..data.len() {
             check_io(io.read_char(&mut c), 1);
@@ -234,7 +425,7 @@ mod tests {
         let data = String::from("abc\n eof\n\nhello world.");
         let path = f_write(data.clone()).expect("Failed to write");
 
-        let mut io = FileIO::new(&path);
+        let mut io = FileIO::new(&path).unwrap();
         let mut buf = String::new();
 
         check_io(io.read_line(&mut buf), 4);
@@ -262,7 +453,7 @@ mod tests {
         let data = String::from("323 2342 -21 -2147483648   2147483647");
         let path = f_write(data.clone()).expect("Failed to write");
 
-        let mut io = FileIO::new(&path);
+        let mut io = FileIO::new(&path).unwrap();
         let mut v = 0;
         check_io(io.read_i32(&mut v), 4);
         assert_eq!(323, v);
@@ -278,6 +469,33 @@ mod tests {
 
         check_io(io.read_i32(&mut v), 12);
         assert_eq!(i32::MAX, v);
+
+        check_io(io.read_i32(&mut v), 0);
+        assert_eq!(i32::MAX, v);
+    }
+
+    #[test]
+    pub fn read_i32_u32_invalid() {
+        let data = String::from("2147483648 -2147483649 4294967296 -5 x 4294967295 \n");
+        let path = f_write(data.clone()).expect("Failed to write");
+
+        let mut io = FileIO::new(&path).unwrap();
+        let (mut i, mut u) = (0, 0);
+        let kind = |r: Result<usize>| r.unwrap_err().kind();
+        assert_eq!(kind(io.read_i32(&mut i)), ErrorKind::InvalidData);
+        assert_eq!(kind(io.read_i32(&mut i)), ErrorKind::InvalidData);
+        assert_eq!(kind(io.read_u32(&mut u)), ErrorKind::InvalidData);
+        // Only the unexpected byte is consumed, so the digits after '-' are read next.
+        assert_eq!(kind(io.read_u32(&mut u)), ErrorKind::Other);
+        check_io(io.read_u32(&mut u), 2);
+        assert_eq!(5, u);
+        assert_eq!(kind(io.read_u32(&mut u)), ErrorKind::Other);
+        check_io(io.read_u32(&mut u), 12);
+        assert_eq!(u32::MAX, u);
+
+        // Only the spaces are left, which is the EOF rather than a panic.
+        check_io(io.read_u32(&mut u), 0);
+        assert_eq!(u32::MAX, u);
     }
 
     #[test]
@@ -285,7 +503,7 @@ mod tests {
         let data = String::from("18446744073709551615 0 123 2147483647");
         let path = f_write(data.clone()).expect("Failed to write");
 
-        let mut io = FileIO::new(&path);
+        let mut io = FileIO::new(&path).unwrap();
         let mut v = 0 as u64;
         check_io(io.read_u64(&mut v), 21);
         assert_eq!(18446744073709551615, v);
@@ -307,7 +525,7 @@ mod tests {
         let data = String::from("1e-1 0.0 -2.122 -3.40282347e+38 3.40282347e+38");
         let path = f_write(data.clone()).expect("Failed to write");
 
-        let mut io = FileIO::new(&path);
+        let mut io = FileIO::new(&path).unwrap();
         let mut v = 0 as f32;
         check_io(io.read_f32(&mut v), 5);
         assert_eq!(1e-1, v);
@@ -326,4 +544,115 @@ mod tests {
 
         check_io(io.read_f32(&mut v), 0);
     }
+
+    #[test]
+    pub fn read_i64() {
+        let data = String::from("-9223372036854775808\t9223372036854775807\r\n42");
+        let path = f_write(data.clone()).expect("Failed to write");
+
+        let mut io = FileIO::new(&path).unwrap();
+        let mut v = 0 as i64;
+        check_io(io.read_i64(&mut v), 21);
+        assert_eq!(i64::MIN, v);
+
+        check_io(io.read_i64(&mut v), 20);
+        assert_eq!(i64::MAX, v);
+
+        check_io(io.read_i64(&mut v), 3);
+        assert_eq!(42, v);
+
+        // EOF leaves the value unchanged as `read_u64` does.
+        check_io(io.read_i64(&mut v), 0);
+        assert_eq!(42, v);
+    }
+
+    #[test]
+    pub fn read_overflow() {
+        let data = String::from(
+            "123456789012345678901234 -99999999999999999999 9223372036854775808 1 \n ",
+        );
+        let path = f_write(data.clone()).expect("Failed to write");
+
+        let mut io = FileIO::new(&path).unwrap();
+        let mut v: i64 = 7;
+        for _ in 0..3 {
+            assert_eq!(
+                io.read_i64(&mut v).unwrap_err().kind(),
+                ErrorKind::InvalidData
+            );
+        }
+        assert_eq!(v, 7);
+        check_io(io.read_i64(&mut v), 2);
+        assert_eq!(v, 1);
+        // The trailing spaces end with EOF, which isn't an error.
+        check_io(io.read_i64(&mut v), 0);
+        assert_eq!(v, 1);
+
+        let mut io = FileIO::new(&path).unwrap();
+        let mut u: u64 = 0;
+        assert_eq!(
+            io.read_u64(&mut u).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    pub fn open_missing_file() {
+        let path = env::temp_dir().join("no-such-dir").join("no-such-file");
+        assert!(FileIO::new(&path).is_err());
+        assert!(matches!(Scanner::open(&path), Err(ScanError::Io(_))));
+    }
+
+    #[test]
+    pub fn scanner_basics() {
+        let data = "3 -7\t2.5\r\nhello  x\n\n18446744073709551615\r\n";
+        let mut sc = Scanner::new(data.as_bytes());
+
+        assert_eq!(sc.next_token::<usize>().unwrap(), Some(3));
+        assert_eq!(sc.next_token::<i32>().unwrap(), Some(-7));
+        assert_eq!(sc.next_token::<f64>().unwrap(), Some(2.5));
+        assert_eq!(
+            sc.next_token::<String>().unwrap(),
+            Some(String::from("hello"))
+        );
+        assert_eq!(sc.next_token::<char>().unwrap(), Some('x'));
+        assert_eq!(sc.read::<u64>().unwrap(), u64::MAX);
+        assert_eq!(sc.next_token::<i32>().unwrap(), None);
+        assert!(matches!(sc.read::<i32>(), Err(ScanError::Eof)));
+    }
+
+    #[test]
+    pub fn scanner_errors() {
+        let mut sc = Scanner::new(&b"12 abc 256 \xff"[..]);
+        assert_eq!(sc.read::<i32>().unwrap(), 12);
+
+        match sc.next_token::<i32>() {
+            Err(ScanError::Parse { token, .. }) => assert_eq!(token, "abc"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert!(matches!(
+            sc.next_token::<u8>(),
+            Err(ScanError::Parse { .. })
+        ));
+        assert!(matches!(
+            sc.next_token::<String>(),
+            Err(ScanError::Parse { .. })
+        ));
+        assert_eq!(sc.next_token::<String>().unwrap(), None);
+    }
+
+    #[test]
+    pub fn scanner_vec_and_lines() {
+        let data = String::from("3\r\n1 2 3\r\nthe last line\r\n");
+        let path = f_write(data).expect("Failed to write");
+
+        let mut sc = Scanner::open(&path).unwrap();
+        let n = sc.read::<usize>().unwrap();
+        assert_eq!(sc.read_vec::<i64>(n).unwrap(), vec![1, 2, 3]);
+        // Drain the remaining CRLF of the current line.
+        assert_eq!(sc.next_line().unwrap(), Some(String::new()));
+        assert_eq!(sc.next_line().unwrap(), Some(String::from("the last line")));
+        assert_eq!(sc.next_line().unwrap(), None);
+        assert!(matches!(sc.read_vec::<i64>(1), Err(ScanError::Eof)));
+    }
 }
@@ -0,0 +1,491 @@
+/**
+ * The gzip file format and the DEFLATE decompression algorithm.
+ * Please see: RFC 1951 https://www.rfc-editor.org/rfc/rfc1951 and RFC 1952 https://www.rfc-editor.org/rfc/rfc1952
+ */
+use std::io::{Error, ErrorKind, Result};
+
+const MAX_BITS: usize = 15;
+
+// The base length and the extra bits for the length codes 257..285.
+const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LEN_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+// The base distance and the extra bits for the distance codes 0..29.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+// The order of the code length code lengths in the dynamic block header.
+const CLEN_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Reads the bits from the least significant bit of each byte, as DEFLATE requires.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buf: u32,
+    cnt: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            pos: 0,
+            buf: 0,
+            cnt: 0,
+        }
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.cnt < n {
+            if self.pos >= self.data.len() {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "truncated deflate stream",
+                ));
+            }
+            self.buf |= (self.data[self.pos] as u32) << self.cnt;
+            self.pos += 1;
+            self.cnt += 8;
+        }
+
+        let v = self.buf & ((1u64 << n) - 1) as u32;
+        self.buf >>= n;
+        self.cnt -= n;
+        Ok(v)
+    }
+
+    /// Drop the remaining bits of the current byte.
+    fn align(&mut self) {
+        self.buf = 0;
+        self.cnt = 0;
+    }
+}
+
+/// The canonical Huffman code, stored as the number of codes of each length and the symbols
+/// ordered by their codes.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Reject the over-subscribed code, the incomplete code is allowed as zlib does.
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(invalid("over-subscribed huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (sym, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = sym as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, br: &mut BitReader) -> Result<u16> {
+        // code: the code read so far, first: the first code of the current length,
+        // index: the index of the first symbol of the current length in `symbols`.
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..=MAX_BITS {
+            code |= br.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid huffman code"))
+    }
+}
+
+fn fixed_tables() -> Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    for (sym, len) in lengths.iter_mut().enumerate() {
+        *len = match sym {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+fn dynamic_tables(br: &mut BitReader) -> Result<(Huffman, Huffman)> {
+    let nlen = br.bits(5)? as usize + 257;
+    let ndist = br.bits(5)? as usize + 1;
+    let ncode = br.bits(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err(invalid("bad dynamic block counts"));
+    }
+
+    let mut clens = [0u8; 19];
+    for &idx in CLEN_ORDER.iter().take(ncode) {
+        clens[idx] = br.bits(3)? as u8;
+    }
+    let clen_code = Huffman::new(&clens)?;
+
+    // The literal/length and the distance code lengths are encoded as one sequence.
+    let mut lengths = vec![0u8; nlen + ndist];
+    let mut i = 0;
+    while i < nlen + ndist {
+        let sym = clen_code.decode(br)?;
+        let (len, repeat) = match sym {
+            0..=15 => (sym as u8, 1),
+            16 => {
+                if i == 0 {
+                    return Err(invalid("repeat with no previous length"));
+                }
+                (lengths[i - 1], 3 + br.bits(2)? as usize)
+            }
+            17 => (0, 3 + br.bits(3)? as usize),
+            _ => (0, 11 + br.bits(7)? as usize),
+        };
+        if i + repeat > nlen + ndist {
+            return Err(invalid("too many code lengths"));
+        }
+        for _ in 0..repeat {
+            lengths[i] = len;
+            i += 1;
+        }
+    }
+
+    if lengths[256] == 0 {
+        return Err(invalid("missing end-of-block code"));
+    }
+    Ok((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..])?,
+    ))
+}
+
+fn inflate_block(
+    br: &mut BitReader,
+    out: &mut Vec<u8>,
+    lencode: &Huffman,
+    distcode: &Huffman,
+) -> Result<()> {
+    loop {
+        let sym = lencode.decode(br)? as usize;
+        if sym < 256 {
+            out.push(sym as u8);
+        } else if sym == 256 {
+            return Ok(());
+        } else {
+            let sym = sym - 257;
+            if sym >= LEN_BASE.len() {
+                return Err(invalid("invalid length symbol"));
+            }
+            let len = LEN_BASE[sym] as usize + br.bits(LEN_EXTRA[sym] as u32)? as usize;
+
+            let dsym = distcode.decode(br)? as usize;
+            if dsym >= DIST_BASE.len() {
+                return Err(invalid("invalid distance symbol"));
+            }
+            let dist = DIST_BASE[dsym] as usize + br.bits(DIST_EXTRA[dsym] as u32)? as usize;
+            if dist > out.len() {
+                return Err(invalid("distance too far back"));
+            }
+
+            // The source and the destination may overlap, so copy byte by byte.
+            let start = out.len() - dist;
+            for i in 0..len {
+                out.push(out[start + i]);
+            }
+        }
+    }
+}
+
+/// Decompress the raw DEFLATE stream, returns the output and the number of input bytes consumed.
+pub fn inflate(data: &[u8]) -> Result<(Vec<u8>, usize)> {
+    let mut br = BitReader::new(data);
+    let mut out = Vec::new();
+
+    loop {
+        let last = br.bits(1)?;
+        match br.bits(2)? {
+            0 => {
+                br.align();
+                if br.pos + 4 > data.len() {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "truncated stored block",
+                    ));
+                }
+                let len = u16::from_le_bytes([data[br.pos], data[br.pos + 1]]) as usize;
+                let nlen = u16::from_le_bytes([data[br.pos + 2], data[br.pos + 3]]) as usize;
+                if len != !nlen & 0xffff {
+                    return Err(invalid("stored block length mismatch"));
+                }
+                br.pos += 4;
+                if br.pos + len > data.len() {
+                    return Err(Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "truncated stored block",
+                    ));
+                }
+                out.extend_from_slice(&data[br.pos..br.pos + len]);
+                br.pos += len;
+            }
+            1 => {
+                let (lencode, distcode) = fixed_tables()?;
+                inflate_block(&mut br, &mut out, &lencode, &distcode)?;
+            }
+            2 => {
+                let (lencode, distcode) = dynamic_tables(&mut br)?;
+                inflate_block(&mut br, &mut out, &lencode, &distcode)?;
+            }
+            _ => return Err(invalid("invalid block type")),
+        }
+
+        if last == 1 {
+            break;
+        }
+    }
+
+    Ok((out, br.pos))
+}
+
+/// The CRC-32 (IEEE 802.3) checksum used by gzip.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+
+    let mut crc = 0xffffffffu32;
+    for &b in data {
+        crc = table[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xffffffff
+}
+
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+/// Decompress the gzip file, all the concatenated members are decoded and the CRC-32 and the size
+/// of each member are verified.
+pub fn gunzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let d = &data[pos..];
+        if d.len() < 18 || d[0] != 0x1f || d[1] != 0x8b {
+            return Err(invalid("not in gzip format"));
+        }
+        if d[2] != 8 {
+            return Err(invalid("unknown gzip compression method"));
+        }
+
+        let flags = d[3];
+        // Skip the magic, method, flags, mtime, extra flags and OS.
+        let mut i = 10;
+        if flags & FEXTRA != 0 {
+            let xlen = u16::from_le_bytes([d[i], d[i + 1]]) as usize;
+            i += 2 + xlen;
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                match d.get(i..).and_then(|s| s.iter().position(|&b| b == 0)) {
+                    Some(n) => i += n + 1,
+                    None => return Err(invalid("truncated gzip header")),
+                }
+            }
+        }
+        if flags & FHCRC != 0 {
+            i += 2;
+        }
+        if i > d.len() {
+            return Err(invalid("truncated gzip header"));
+        }
+
+        let (member, n) = inflate(&d[i..])?;
+        i += n;
+        if i + 8 > d.len() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "truncated gzip trailer",
+            ));
+        }
+
+        let crc = u32::from_le_bytes([d[i], d[i + 1], d[i + 2], d[i + 3]]);
+        let size = u32::from_le_bytes([d[i + 4], d[i + 5], d[i + 6], d[i + 7]]);
+        if crc != crc32(&member) {
+            return Err(invalid("gzip crc32 mismatch"));
+        }
+        if size != member.len() as u32 {
+            return Err(invalid("gzip size mismatch"));
+        }
+
+        out.extend_from_slice(&member);
+        pos += i + 8;
+    }
+
+    Ok(out)
+}
+
+/// Wrap the data into a gzip file with the stored (uncompressed) blocks, used to generate the test
+/// data.
+#[cfg(test)]
+pub(crate) fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff];
+    let mut chunks = data.chunks(0xffff).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        out.push(if chunks.peek().is_none() { 1 } else { 0 });
+        out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(parts: &[&str]) -> Vec<u8> {
+        let s = parts.concat();
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // The gzip file of "hello hello hello world\n", compressed with the fixed huffman codes.
+    const FIXED: &[&str] =
+        &["1f8b0800000000000203cb48cdc9c957c84022cbf38b7252b80088d95be018000000"];
+
+    // The gzip file of "stored block\n", which is stored without compression.
+    const STORED: &[&str] =
+        &["1f8b0800000000000403010d00f2ff73746f72656420626c6f636b0a6d7588c50d000000"];
+
+    // Two concatenated gzip members "first\n" (with the file name "a.txt") and "second\n".
+    const MULTI_MEMBER: &[&str] = &[
+        "1f8b08080000000002ff612e747874004bcb2c2a2ee102002ab34ac706000000",
+        "1f8b08000000000002032b4e4dcecf4be102007ec00f0607000000",
+    ];
+
+    // The gzip file of `squares()`, compressed with the dynamic huffman codes.
+    const DYNAMIC: &[&str] = &[
+        "1f8b080000000000020325d23bb6e5300844d19c5138ee487c6d056ffed3ea3adcacbcb464b680f3fc7bcef3f71c7325",
+        "57720ba5502a4ba554ba564ac5e9582bb662b48de228e6d8abf872e9daa7f8294ed955bc8a9f9b1f0a50cb8faa6db9ad",
+        "173aa3a453d3abcc29ebb9d5ae39a57d6bdf31a7ba6f79d577003e8bd1190607119feec1701c19fa2712bf6bd50bb104",
+        "969225b004962a9dedf3f7fd5f5960092c1dd7024b60e9772cb004969125b00496e10c4b6079b98725b0bcfc134b6c5b",
+        "542fb124962b4b62492c57cec492db97a347e4ce631b73f4c24493db19d7f3134ef6b6549ec493b39f6a5c024a409eea",
+        "6a22caefd771fd195242f2d63c0a53edac46a802553bacd1240b55adead58c0b55adead3f46b57e5372fed45a1aa1dd8",
+        "91aa50d54eccb54b85aa766471741755a18a3cfa33aa421575dc1a559f9db6548daa51c5708aaa51c5abbb8daa739741",
+        "7f6e548d2a2e3bbc4b8c2a5135aade4d5677ac51f5fb5b1ddd45d5bb49a9f736aade552a756350cdd9cd926a500daa7c",
+        "d5c94135a8f2539f07d5a0caab29fc0743095ce67e030000",
+    ];
+
+    fn squares() -> Vec<u8> {
+        (0..64)
+            .map(|i| format!("{} * {} = {}\n", i, i, i * i))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[test]
+    pub fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(
+            crc32(b"The quick brown fox jumps over the lazy dog"),
+            0x414fa339
+        );
+    }
+
+    #[test]
+    pub fn test_gunzip() {
+        assert_eq!(gunzip(&unhex(FIXED)).unwrap(), b"hello hello hello world\n");
+        assert_eq!(gunzip(&unhex(STORED)).unwrap(), b"stored block\n");
+        assert_eq!(gunzip(&unhex(MULTI_MEMBER)).unwrap(), b"first\nsecond\n");
+        assert_eq!(gunzip(&unhex(DYNAMIC)).unwrap(), squares());
+        assert_eq!(gunzip(b"").unwrap(), b"");
+
+        let large: Vec<u8> = (0..200000).map(|i| (i % 251) as u8).collect();
+        assert_eq!(gunzip(&gzip_stored(&large)).unwrap(), large);
+        assert_eq!(gunzip(&gzip_stored(b"")).unwrap(), b"");
+    }
+
+    #[test]
+    pub fn test_corrupted() {
+        let data = unhex(DYNAMIC);
+
+        // Flip one bit in the trailing CRC-32.
+        let mut bad_crc = data.clone();
+        let n = bad_crc.len();
+        bad_crc[n - 8] ^= 1;
+        assert_eq!(gunzip(&bad_crc).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // Every truncation must be rejected instead of panicking.
+        for len in 1..data.len() {
+            assert!(gunzip(&data[..len]).is_err());
+        }
+
+        // Any corruption of the compressed stream is either detected or caught by the CRC-32.
+        for i in 10..data.len() - 8 {
+            let mut bad = data.clone();
+            bad[i] ^= 0x55;
+            assert!(gunzip(&bad).is_err());
+        }
+
+        assert!(gunzip(b"not a gzip file at all").is_err());
+    }
+}
@@ -0,0 +1,389 @@
+use crate::algo::fastio::{parse_i64, parse_u64};
+use std::{
+    ffi::{c_int, c_void},
+    fs::File,
+    io::{Error, ErrorKind, Result},
+    os::unix::io::AsRawFd,
+    path::Path,
+    ptr, slice, thread,
+};
+
+// Link the mmap(2) and munmap(2) from libc directly, in the same way as `basic::rand`.
+extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: i64,
+    ) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+const PROT_READ: c_int = 0x1;
+const MAP_PRIVATE: c_int = 0x2;
+
+/// A read-only memory mapping of the whole file. The file content is paged in lazily by the
+/// kernel, so a multi-gigabyte file can be scanned without copying it into the user space buffer.
+pub struct Mmap {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// The mapping is read-only, so it's safe to share it between threads.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            // mmap(2) rejects the zero length mapping with EINVAL.
+            return Ok(Mmap {
+                ptr: ptr::null_mut(),
+                len: 0,
+            });
+        }
+
+        let addr = unsafe {
+            mmap(
+                ptr::null_mut(),
+                len,
+                PROT_READ,
+                MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        // MAP_FAILED is ((void *) -1).
+        if addr as isize == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        // The mapping keeps valid after the file descriptor is closed.
+        Ok(Mmap { ptr: addr, len })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe {
+                munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+#[inline]
+fn is_space(b: u8) -> bool {
+    b == b' ' || b == b'\t' || b == b'\r' || b == b'\n'
+}
+
+/// The reader over a memory mapped file, which shares the same typed API as `FileIO`: every
+/// reader fills the out-parameter and returns the number of bytes consumed, including the trailing
+/// separator. Reading any value at EOF returns Ok(0).
+pub struct MmapReader {
+    m: Mmap,
+    pos: usize,
+}
+
+impl MmapReader {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(MmapReader {
+            m: Mmap::open(path)?,
+            pos: 0,
+        })
+    }
+
+    /// Returns the byte range [start, end) of the next token, and moves the position after the
+    /// trailing separator.
+    fn token(&mut self) -> Option<(usize, usize)> {
+        let data = self.m.as_slice();
+        let mut i = self.pos;
+        while i < data.len() && is_space(data[i]) {
+            i += 1;
+        }
+        if i == data.len() {
+            self.pos = i;
+            return None;
+        }
+
+        let start = i;
+        while i < data.len() && !is_space(data[i]) {
+            i += 1;
+        }
+        self.pos = if i < data.len() { i + 1 } else { i };
+        Some((start, i))
+    }
+
+    fn read_token<T, F>(&mut self, v: &mut T, parse: F) -> Result<usize>
+    where
+        F: Fn(&[u8]) -> Result<T>,
+    {
+        let old = self.pos;
+        match self.token() {
+            None => Ok(0),
+            Some((start, end)) => {
+                *v = parse(&self.m.as_slice()[start..end])?;
+                Ok(self.pos - old)
+            }
+        }
+    }
+
+    pub fn read_line(&mut self, buf: &mut String) -> Result<usize> {
+        let data = &self.m.as_slice()[self.pos..];
+        let n = match data.iter().position(|&b| b == b'\n') {
+            Some(i) => i + 1,
+            None => data.len(),
+        };
+        let s =
+            std::str::from_utf8(&data[..n]).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        buf.push_str(s);
+        self.pos += n;
+        Ok(n)
+    }
+
+    pub fn read_char(&mut self, v: &mut char) -> Result<usize> {
+        let mut b = 0;
+        let n = self.read_u8(&mut b)?;
+        *v = b as char;
+        Ok(n)
+    }
+
+    pub fn read_u8(&mut self, v: &mut u8) -> Result<usize> {
+        match self.m.as_slice().get(self.pos) {
+            Some(&b) => {
+                *v = b;
+                self.pos += 1;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    pub fn read_i32(&mut self, v: &mut i32) -> Result<usize> {
+        self.read_token(v, |t| {
+            let x = parse_i64(t)?;
+            i32::try_from(x).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        })
+    }
+
+    pub fn read_u32(&mut self, v: &mut u32) -> Result<usize> {
+        self.read_token(v, |t| {
+            let x = parse_u64(t)?;
+            u32::try_from(x).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        })
+    }
+
+    pub fn read_i64(&mut self, v: &mut i64) -> Result<usize> {
+        self.read_token(v, |t| Ok(parse_i64(t)?))
+    }
+
+    pub fn read_u64(&mut self, v: &mut u64) -> Result<usize> {
+        self.read_token(v, |t| Ok(parse_u64(t)?))
+    }
+
+    pub fn read_f32(&mut self, v: &mut f32) -> Result<usize> {
+        self.read_token(v, |t| {
+            let s = std::str::from_utf8(t).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            s.parse::<f32>()
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{:?}: {}", s, e)))
+        })
+    }
+
+    pub fn read_word(&mut self, v: &mut String) -> Result<usize> {
+        self.read_token(v, |t| {
+            String::from_utf8(t.to_vec()).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        })
+    }
+}
+
+/// Split `data` into at most `n` chunks whose boundaries are all right after a '\n', so that no
+/// token is cut into two chunks.
+fn split_lines(data: &[u8], n: usize) -> Vec<&[u8]> {
+    let n = n.max(1);
+    let chunk_size = data.len() / n + 1;
+
+    let mut chunks = Vec::with_capacity(n);
+    let mut start = 0;
+    while start < data.len() {
+        let mut end = (start + chunk_size).min(data.len());
+        match data[end..].iter().position(|&b| b == b'\n') {
+            Some(i) => end += i + 1,
+            None => end = data.len(),
+        }
+        chunks.push(&data[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+fn parse_chunk(chunk: &[u8]) -> Result<Vec<i64>> {
+    chunk
+        .split(|&b| is_space(b))
+        .filter(|t| !t.is_empty())
+        .map(|t| Ok(parse_i64(t)?))
+        .collect()
+}
+
+/// Parse all the whitespace separated integers in `data` with `threads` threads. The chunks are
+/// split on line boundaries and the integers are returned in the same order as the input.
+pub fn parse_i64_parallel(data: &[u8], threads: usize) -> Result<Vec<i64>> {
+    let chunks = split_lines(data, threads);
+
+    let results: Vec<Result<Vec<i64>>> = thread::scope(|s| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|&chunk| s.spawn(move || parse_chunk(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("Unexpected panic in the parser thread"))
+            .collect()
+    });
+
+    let mut res = Vec::new();
+    for r in results {
+        res.extend(r?);
+    }
+    Ok(res)
+}
+
+/// Memory map the file and parse all its integers with `threads` threads.
+pub fn read_i64_parallel<P: AsRef<Path>>(path: P, threads: usize) -> Result<Vec<i64>> {
+    let m = Mmap::open(path)?;
+    parse_i64_parallel(m.as_slice(), threads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn f_write(s: &str) -> PathBuf {
+        let path = env::temp_dir().join(rand::gen_u32().to_string());
+        fs::write(&path, s).expect("Failed to write");
+        path
+    }
+
+    #[test]
+    pub fn mmap_file() {
+        let path = f_write("hello mmap\n");
+        let m = Mmap::open(&path).unwrap();
+        assert_eq!(m.len(), 11);
+        assert_eq!(m.as_slice(), b"hello mmap\n");
+
+        let empty = f_write("");
+        let m = Mmap::open(&empty).unwrap();
+        assert!(m.is_empty());
+        assert_eq!(m.as_slice(), b"");
+
+        assert!(Mmap::open(env::temp_dir().join("no-such-dir").join("no-such-file")).is_err());
+    }
+
+    #[test]
+    pub fn read_values() {
+        let path = f_write("323 -21\t-9223372036854775808\r\n4294967295 2.5 word\nline\n");
+        let mut r = MmapReader::new(&path).unwrap();
+
+        let mut i = 0;
+        assert_eq!(r.read_i32(&mut i).unwrap(), 4);
+        assert_eq!(i, 323);
+        assert_eq!(r.read_i32(&mut i).unwrap(), 4);
+        assert_eq!(i, -21);
+
+        let mut l = 0;
+        assert_eq!(r.read_i64(&mut l).unwrap(), 21);
+        assert_eq!(l, i64::MIN);
+
+        let mut u = 0;
+        assert_eq!(r.read_u32(&mut u).unwrap(), 12);
+        assert_eq!(u, u32::MAX);
+
+        let mut f = 0.0;
+        assert_eq!(r.read_f32(&mut f).unwrap(), 4);
+        assert_eq!(f, 2.5);
+
+        let mut w = String::new();
+        assert_eq!(r.read_word(&mut w).unwrap(), 5);
+        assert_eq!(w, "word");
+
+        let mut line = String::new();
+        assert_eq!(r.read_line(&mut line).unwrap(), 5);
+        assert_eq!(line, "line\n");
+
+        let mut c = 'x';
+        assert_eq!(r.read_char(&mut c).unwrap(), 0);
+        assert_eq!(r.read_u64(&mut 0).unwrap(), 0);
+    }
+
+    #[test]
+    pub fn read_invalid() {
+        let path = f_write("12a 2147483648");
+        let mut r = MmapReader::new(&path).unwrap();
+        let mut v = 0;
+        assert_eq!(
+            r.read_i32(&mut v).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            r.read_i32(&mut v).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    pub fn split_on_lines() {
+        let data = b"1 2 3\n44 55\n666\n7";
+        for n in 1..10 {
+            let chunks = split_lines(data, n);
+            assert!(chunks.len() <= n);
+            assert_eq!(chunks.concat(), data.to_vec());
+            for c in &chunks[..chunks.len() - 1] {
+                assert_eq!(c.last(), Some(&b'\n'));
+            }
+        }
+        assert!(split_lines(b"", 4).is_empty());
+    }
+
+    #[test]
+    pub fn parallel_parse() {
+        let v: Vec<i64> = (0..200000)
+            .map(|_| rand::gen_i32() as i64 * (rand::gen_i32() as i64 % 1000))
+            .collect();
+        let mut data = String::new();
+        for (i, x) in v.iter().enumerate() {
+            data.push_str(&x.to_string());
+            data.push_str(if i % 7 == 6 { "\r\n" } else { " " });
+        }
+        let path = f_write(&data);
+
+        for threads in [1, 2, 3, 8] {
+            assert_eq!(read_i64_parallel(&path, threads).unwrap(), v);
+        }
+
+        let bad = f_write("1 2\n3 x\n4\n");
+        assert!(read_i64_parallel(&bad, 2).is_err());
+    }
+}
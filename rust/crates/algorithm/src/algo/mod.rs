@@ -1,5 +1,9 @@
+pub mod fastio;
 pub mod fileio;
+pub mod gzip;
+pub mod mmap;
 pub mod prog11;
 pub mod prog12;
 pub mod prog13;
+pub mod tar;
 pub mod util;
@@ -0,0 +1,328 @@
+/**
+ * The reader of the tar archive, which supports the ustar format plus the GNU long name and the
+ * pax path extensions.
+ * Please see: https://www.gnu.org/software/tar/manual/html_node/Standard.html
+ */
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Component, Path, PathBuf},
+};
+
+const BLOCK_SIZE: usize = 512;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EntryType {
+    File,
+    Dir,
+    Other(u8),
+}
+
+pub struct Entry<'a> {
+    pub path: String,
+    pub kind: EntryType,
+    pub mode: u32,
+    pub data: &'a [u8],
+}
+
+fn invalid(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Parse the numeric field, which is either an octal string terminated by NUL or space, or a
+/// big-endian base-256 number with the highest bit set (the GNU extension for large files).
+fn parse_number(field: &[u8]) -> Result<u64> {
+    if !field.is_empty() && field[0] & 0x80 != 0 {
+        let mut v: u64 = (field[0] & 0x7f) as u64;
+        for &b in &field[1..] {
+            v = v
+                .checked_mul(256)
+                .ok_or_else(|| invalid(String::from("tar number overflow")))?
+                | b as u64;
+        }
+        return Ok(v);
+    }
+
+    let mut v: u64 = 0;
+    for &b in field.iter().skip_while(|&&b| b == b' ') {
+        if b == 0 || b == b' ' {
+            break;
+        }
+        if !(b'0'..=b'7').contains(&b) {
+            return Err(invalid(format!("invalid octal field {:?}", field)));
+        }
+        v = v
+            .checked_mul(8)
+            .ok_or_else(|| invalid(String::from("tar number overflow")))?
+            + (b - b'0') as u64;
+    }
+    Ok(v)
+}
+
+fn parse_str(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// The checksum is the sum of all the header bytes, with the checksum field itself as spaces.
+fn verify_checksum(header: &[u8]) -> Result<()> {
+    let expected = parse_number(&header[148..156])?;
+    let actual: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as u64)
+        .sum();
+    if expected != actual {
+        return Err(invalid(format!(
+            "tar header checksum mismatch: expected {}, actual {}",
+            expected, actual
+        )));
+    }
+    Ok(())
+}
+
+/// Find the `path` record in the pax extended header, whose records are "<len> <key>=<value>\n".
+fn pax_path(data: &[u8]) -> Option<String> {
+    let mut rest = data;
+    while !rest.is_empty() {
+        let sp = rest.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..sp]).ok()?.parse().ok()?;
+        if len <= sp || len > rest.len() {
+            return None;
+        }
+
+        let record = &rest[sp + 1..len];
+        if let Some(value) = record.strip_prefix(b"path=") {
+            let value = value.strip_suffix(b"\n").unwrap_or(value);
+            return Some(String::from_utf8_lossy(value).into_owned());
+        }
+        rest = &rest[len..];
+    }
+    None
+}
+
+/// Parse all the entries of the tar archive. The GNU long name and pax headers are folded into the
+/// path of the following entry, instead of being returned.
+pub fn entries(data: &[u8]) -> Result<Vec<Entry<'_>>> {
+    let mut res = Vec::new();
+    let mut pos = 0;
+    let mut long_path: Option<String> = None;
+
+    while pos + BLOCK_SIZE <= data.len() {
+        let header = &data[pos..pos + BLOCK_SIZE];
+        if header.iter().all(|&b| b == 0) {
+            // The archive ends with two zero blocks.
+            break;
+        }
+        verify_checksum(header)?;
+
+        let size = parse_number(&header[124..136])? as usize;
+        let start = pos + BLOCK_SIZE;
+        if size > data.len() - start {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "truncated tar entry"));
+        }
+        let body = &data[start..start + size];
+        pos = start + size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+
+        let typeflag = header[156];
+        match typeflag {
+            b'L' => {
+                long_path = Some(parse_str(body));
+                continue;
+            }
+            b'x' => {
+                long_path = pax_path(body).or(long_path);
+                continue;
+            }
+            b'g' => continue,
+            _ => {}
+        }
+
+        let path = match long_path.take() {
+            Some(path) => path,
+            None => {
+                let name = parse_str(&header[0..100]);
+                let prefix = if &header[257..262] == b"ustar" {
+                    parse_str(&header[345..500])
+                } else {
+                    String::new()
+                };
+                if prefix.is_empty() {
+                    name
+                } else {
+                    format!("{}/{}", prefix, name)
+                }
+            }
+        };
+
+        let kind = match typeflag {
+            b'0' | 0 | b'7' => EntryType::File,
+            b'5' => EntryType::Dir,
+            t => EntryType::Other(t),
+        };
+        let mode = parse_number(&header[100..108])? as u32;
+        res.push(Entry {
+            path,
+            kind,
+            mode,
+            data: body,
+        });
+    }
+
+    Ok(res)
+}
+
+/// Reject the absolute path and the `..` component, so the archive cannot write outside of `dst`.
+fn safe_join(dst: &Path, path: &str) -> Result<PathBuf> {
+    let mut res = dst.to_path_buf();
+    for c in Path::new(path).components() {
+        match c {
+            Component::Normal(p) => res.push(p),
+            Component::CurDir => {}
+            _ => return Err(invalid(format!("unsafe path in tar archive: {:?}", path))),
+        }
+    }
+    Ok(res)
+}
+
+/// Extract all the regular files and directories into `dst`, returns the paths of the extracted
+/// files. Other entries such as symlinks and devices are skipped.
+pub fn unpack(data: &[u8], dst: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in entries(data)? {
+        let path = safe_join(dst, &entry.path)?;
+        match entry.kind {
+            EntryType::Dir => fs::create_dir_all(&path)?,
+            EntryType::File => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, entry.data)?;
+                files.push(path);
+            }
+            EntryType::Other(_) => {}
+        }
+    }
+    Ok(files)
+}
+
+/// Build the tar archive of the (path, content) files in memory, used to generate the test data.
+#[cfg(test)]
+pub(crate) fn pack(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for &(path, content) in files {
+        let mut header = [0u8; BLOCK_SIZE];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[108..115].copy_from_slice(b"0000000");
+        header[116..123].copy_from_slice(b"0000000");
+        header[124..135].copy_from_slice(format!("{:011o}", content.len()).as_bytes());
+        header[136..147].copy_from_slice(b"00000000000");
+        header[156] = if path.ends_with('/') { b'5' } else { b'0' };
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        header[148..156].copy_from_slice(b"        ");
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+
+        out.extend_from_slice(&header);
+        out.extend_from_slice(content);
+        out.resize(out.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+    }
+    out.resize(out.len() + 2 * BLOCK_SIZE, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+    use std::env;
+
+    #[test]
+    pub fn test_parse_number() {
+        assert_eq!(parse_number(b"0000644\0").unwrap(), 0o644);
+        assert_eq!(parse_number(b"   12 \0").unwrap(), 0o12);
+        assert_eq!(parse_number(b"\0\0\0").unwrap(), 0);
+        assert_eq!(parse_number(&[0x80, 0, 0, 1, 0]).unwrap(), 256);
+        assert!(parse_number(b"0009").is_err());
+    }
+
+    #[test]
+    pub fn test_entries() {
+        let data = pack(&[
+            ("dir/", b""),
+            ("dir/a.txt", b"hello"),
+            ("dir/empty", b""),
+            ("dir/b.txt", &[b'x'; 1000]),
+        ]);
+        let entries = entries(&data).unwrap();
+        assert_eq!(entries.len(), 4);
+
+        assert_eq!(entries[0].path, "dir/");
+        assert_eq!(entries[0].kind, EntryType::Dir);
+
+        assert_eq!(entries[1].path, "dir/a.txt");
+        assert_eq!(entries[1].kind, EntryType::File);
+        assert_eq!(entries[1].mode, 0o644);
+        assert_eq!(entries[1].data, b"hello");
+
+        assert_eq!(entries[2].data, b"");
+        assert_eq!(entries[3].data, &[b'x'; 1000][..]);
+    }
+
+    #[test]
+    pub fn test_long_names() {
+        let long = "d/".repeat(80) + "file";
+        let mut data = pack(&[("././@LongLink", long.as_bytes())]);
+        data.truncate(data.len() - 2 * BLOCK_SIZE);
+        data[156] = b'L';
+        data[148..156].copy_from_slice(b"        ");
+        let sum: u32 = data[..BLOCK_SIZE].iter().map(|&b| b as u32).sum();
+        data[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        data.extend(pack(&[("short", b"content")]));
+
+        let entries = entries(&data).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, long);
+        assert_eq!(entries[0].data, b"content");
+
+        assert_eq!(
+            pax_path(b"20 mtime=1700000000\n17 path=a/b/c.in\n"),
+            Some(String::from("a/b/c.in"))
+        );
+    }
+
+    #[test]
+    pub fn test_corrupted() {
+        let mut data = pack(&[("a.txt", b"hello")]);
+        data[0] = b'b';
+        assert!(entries(&data).is_err());
+
+        let data = pack(&[("a.txt", &[b'x'; 1000])]);
+        assert!(entries(&data[..BLOCK_SIZE + 100]).is_err());
+    }
+
+    #[test]
+    pub fn test_unpack() {
+        let dst = env::temp_dir().join(rand::gen_u32().to_string());
+        let data = pack(&[
+            ("top/", b""),
+            ("top/sub/x.in", b"1 2\n"),
+            ("top/x.out", b"3\n"),
+        ]);
+
+        let files = unpack(&data, &dst).unwrap();
+        assert_eq!(files, vec![dst.join("top/sub/x.in"), dst.join("top/x.out")]);
+        assert_eq!(fs::read(dst.join("top/sub/x.in")).unwrap(), b"1 2\n");
+        assert_eq!(fs::read(dst.join("top/x.out")).unwrap(), b"3\n");
+
+        let evil = pack(&[("../evil", b"")]);
+        assert!(unpack(&evil, &dst).is_err());
+        let evil = pack(&[("/tmp/evil", b"")]);
+        assert!(unpack(&evil, &dst).is_err());
+
+        fs::remove_dir_all(&dst).unwrap();
+    }
+}
@@ -1,41 +1,307 @@
+use crate::algo::{gzip, tar};
 use std::io::{Error, ErrorKind};
-use std::{env, io, path::PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::UNIX_EPOCH;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
 
-pub fn testdata_dir() -> io::Result<PathBuf> {
-    let cur_dir = env::current_dir()?;
-
-    let testdata_dir = cur_dir
-        .parent()
-        .unwrap()
-        .join("testdata")
-        .join("algorithm-and-analysis");
-
-    let testdata_tgz = cur_dir
-        .parent()
-        .unwrap()
-        .join("testdata")
-        .join("algorithm-and-analysis.tar.gz");
-
-    if !testdata_dir.exists() {
-        return Err(Error::new(
+const DATASET: &str = "algorithm-and-analysis";
+
+// The manifest written after the extraction. The first line identifies the archive it comes from,
+// and each following line is "<crc32> <relative path>" of an extracted file.
+const MANIFEST: &str = ".manifest";
+
+/// The `algorithm-and-analysis` test data set, which is shipped as a tar.gz archive in the
+/// `testdata` directory and extracted next to it on the first use.
+pub struct TestData {
+    root: PathBuf,
+}
+
+/// One test case of a program: the input file and its expected output file.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Case {
+    pub name: String,
+    pub input: PathBuf,
+    pub output: PathBuf,
+}
+
+impl TestData {
+    /// Use the given `testdata` directory, which holds the archive or the extracted data set.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        TestData {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Search the `testdata` directory from the crate directory up to the file system root, so the
+    /// result won't depend on the current directory where `cargo` is invoked.
+    pub fn locate() -> io::Result<Self> {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        for dir in manifest_dir.ancestors() {
+            let td = TestData::new(dir.join("testdata"));
+            if td.archive().exists() || td.data_dir().exists() {
+                return Ok(td);
+            }
+        }
+
+        Err(Error::new(
             ErrorKind::NotFound,
             format!(
-                "Cannot locate the algorithm test data directory in {:?}, please uncompress 'tar xzvf {:?}'",
-                testdata_dir.to_str(), testdata_tgz.to_str()
+                "Cannot locate testdata/{}.tar.gz in {:?} or any of its parents",
+                DATASET, manifest_dir
             ),
+        ))
+    }
+
+    pub fn archive(&self) -> PathBuf {
+        self.root.join(format!("{}.tar.gz", DATASET))
+    }
+
+    fn data_dir(&self) -> PathBuf {
+        self.root.join(DATASET)
+    }
+
+    /// The identity of the archive, the cached data set is extracted again once it changes.
+    fn archive_id(&self) -> io::Result<String> {
+        let meta = fs::metadata(self.archive())?;
+        let mtime = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Ok(format!("archive {} {}", meta.len(), mtime))
+    }
+
+    fn is_cached(&self) -> io::Result<bool> {
+        let dir = self.data_dir();
+        if !dir.exists() {
+            return Ok(false);
+        }
+        if !self.archive().exists() {
+            // Extracted by hand, and nothing to compare with.
+            return Ok(true);
+        }
+
+        match fs::read_to_string(dir.join(MANIFEST)) {
+            Ok(manifest) => Ok(manifest.lines().next() == Some(self.archive_id()?.as_str())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The extracted data set directory, the archive is extracted if it is not cached yet.
+    pub fn dir(&self) -> io::Result<PathBuf> {
+        if !self.is_cached()? {
+            self.extract()?;
+        }
+        Ok(self.data_dir())
+    }
+
+    /// Extract into a temporary directory and rename it at last, so that the concurrent tests
+    /// never see a partially extracted data set.
+    fn extract(&self) -> io::Result<()> {
+        static SEQ: AtomicUsize = AtomicUsize::new(0);
+
+        let id = self.archive_id()?;
+        let data = gzip::gunzip(&fs::read(self.archive())?)?;
+
+        let tmp = self.root.join(format!(
+            ".{}.{}.{}",
+            DATASET,
+            process::id(),
+            SEQ.fetch_add(1, Ordering::SeqCst)
         ));
+        fs::create_dir_all(&tmp)?;
+        let files = tar::unpack(&data, &tmp)?;
+
+        // The archive either has the top-level data set directory, or holds the files directly.
+        let extracted = if tmp.join(DATASET).is_dir() {
+            tmp.join(DATASET)
+        } else {
+            tmp.clone()
+        };
+
+        let mut manifest = id;
+        for file in files {
+            let rel = file.strip_prefix(&extracted).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{:?} is out of the {} directory", file, DATASET),
+                )
+            })?;
+            let crc = gzip::crc32(&fs::read(&file)?);
+            manifest.push_str(&format!("\n{:08x} {}", crc, rel.display()));
+        }
+        fs::write(extracted.join(MANIFEST), manifest)?;
+
+        let dir = self.data_dir();
+        if dir.exists() && !self.is_cached()? {
+            // The stale data set of an old archive.
+            fs::remove_dir_all(&dir)?;
+        }
+        let res = fs::rename(&extracted, &dir);
+        fs::remove_dir_all(&tmp).ok();
+        match res {
+            Ok(()) => Ok(()),
+            // Someone else has extracted the same archive in the meantime.
+            Err(_) if self.is_cached()? => Ok(()),
+            Err(e) => Err(e),
+        }
     }
 
-    Ok(testdata_dir)
+    /// Verify all the extracted files against the checksums recorded in the manifest.
+    pub fn verify(&self) -> io::Result<()> {
+        let dir = self.dir()?;
+        let manifest = fs::read_to_string(dir.join(MANIFEST))?;
+
+        for line in manifest.lines().skip(1) {
+            let (crc, rel) = line.split_once(' ').ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("bad manifest line {:?}", line),
+                )
+            })?;
+            let expected =
+                u32::from_str_radix(crc, 16).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            let actual = gzip::crc32(&fs::read(dir.join(rel))?);
+            if expected != actual {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "checksum mismatch for {}: expected {:08x}, actual {:08x}",
+                        rel, expected, actual
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// All the test cases of the program, such as `prog11`. Each `<name>.in` input file is paired
+    /// with the `<name>.out` expected output file, and the cases are sorted by name.
+    pub fn cases(&self, prog: &str) -> io::Result<Vec<Case>> {
+        let dir = self.dir()?.join(prog);
+
+        let mut cases = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let input = entry?.path();
+            if input.extension().and_then(|ext| ext.to_str()) != Some("in") {
+                continue;
+            }
+
+            let output = input.with_extension("out");
+            if !output.exists() {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("Missing the expected output {:?}", output),
+                ));
+            }
+            let name = input.file_stem().unwrap().to_string_lossy().into_owned();
+            cases.push(Case {
+                name,
+                input,
+                output,
+            });
+        }
+
+        cases.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(cases)
+    }
+}
+
+pub fn testdata_dir() -> io::Result<PathBuf> {
+    TestData::locate()?.dir()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic::rand;
+    use std::env;
+
+    fn write_archive(root: &Path, files: &[(&str, &[u8])]) {
+        let td = TestData::new(root);
+        fs::write(td.archive(), gzip::gzip_stored(&tar::pack(files))).unwrap();
+    }
 
     #[test]
     pub fn test() {
         let ret = testdata_dir();
         assert_eq!(true, ret.unwrap().exists());
     }
+
+    #[test]
+    pub fn extract_and_cases() {
+        let root = env::temp_dir().join(rand::gen_u32().to_string());
+        fs::create_dir_all(&root).unwrap();
+        write_archive(
+            &root,
+            &[
+                ("algorithm-and-analysis/", b""),
+                ("algorithm-and-analysis/prog11/", b""),
+                ("algorithm-and-analysis/prog11/2.in", b"3 4\n"),
+                ("algorithm-and-analysis/prog11/2.out", b"7\n"),
+                ("algorithm-and-analysis/prog11/1.in", b"1 2\n"),
+                ("algorithm-and-analysis/prog11/1.out", b"3\n"),
+                ("algorithm-and-analysis/prog11/README", b"readme"),
+                ("algorithm-and-analysis/prog12/1.in", b"no output"),
+            ],
+        );
+
+        let td = TestData::new(&root);
+        let dir = td.dir().unwrap();
+        assert_eq!(dir, root.join(DATASET));
+        td.verify().unwrap();
+
+        let cases = td.cases("prog11").unwrap();
+        assert_eq!(
+            cases,
+            vec![
+                Case {
+                    name: String::from("1"),
+                    input: dir.join("prog11/1.in"),
+                    output: dir.join("prog11/1.out"),
+                },
+                Case {
+                    name: String::from("2"),
+                    input: dir.join("prog11/2.in"),
+                    output: dir.join("prog11/2.out"),
+                },
+            ]
+        );
+        assert_eq!(fs::read(&cases[1].input).unwrap(), b"3 4\n");
+        assert_eq!(td.cases("prog12").unwrap_err().kind(), ErrorKind::NotFound);
+        assert!(td.cases("prog99").is_err());
+
+        // The cached data set is not extracted again, so the corruption will be caught.
+        fs::write(dir.join("prog11/1.out"), b"4\n").unwrap();
+        assert_eq!(td.dir().unwrap(), dir);
+        assert_eq!(td.verify().unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // A new archive replaces the stale data set.
+        write_archive(
+            &root,
+            &[
+                ("algorithm-and-analysis/prog13/1.in", b"x"),
+                ("algorithm-and-analysis/prog13/1.out", b"y"),
+            ],
+        );
+        td.verify().unwrap();
+        assert!(!dir.join("prog11").exists());
+        assert_eq!(td.cases("prog13").unwrap().len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    pub fn missing_archive() {
+        let root = env::temp_dir().join(rand::gen_u32().to_string());
+        assert_eq!(
+            TestData::new(&root).dir().unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
 }
@@ -0,0 +1,227 @@
+/**
+ * The Aho–Corasick automaton, which finds all the occurrences of many patterns in one pass.
+ * Please see: https://en.wikipedia.org/wiki/Aho%E2%80%93Corasick_algorithm
+ */
+use std::collections::{BTreeMap, VecDeque};
+
+const ROOT: usize = 0;
+
+struct Node {
+    children: BTreeMap<u8, usize>,
+    // The node of the longest proper suffix of this one which is also in the trie.
+    fail: usize,
+    // The node of the longest proper suffix which ends some patterns, or ROOT if there's none.
+    output: usize,
+    // The patterns which end exactly at this node.
+    patterns: Vec<usize>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Node {
+            children: BTreeMap::new(),
+            fail: ROOT,
+            output: ROOT,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// The occurrence of the pattern `pattern` at `text[start..end]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    lens: Vec<usize>,
+}
+
+impl AhoCorasick {
+    /// Build the automaton of the patterns, which are identified by their indexes. The empty
+    /// patterns never match.
+    pub fn new<P: AsRef<[u8]>>(patterns: &[P]) -> Self {
+        let mut ac = AhoCorasick {
+            nodes: vec![Node::new()],
+            lens: patterns.iter().map(|p| p.as_ref().len()).collect(),
+        };
+        for (i, p) in patterns.iter().enumerate() {
+            ac.insert(i, p.as_ref());
+        }
+        ac.build_links();
+        ac
+    }
+
+    fn insert(&mut self, pattern: usize, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        let mut node = ROOT;
+        for &c in bytes {
+            node = match self.nodes[node].children.get(&c) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(Node::new());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.insert(c, child);
+                    child
+                }
+            };
+        }
+        self.nodes[node].patterns.push(pattern);
+    }
+
+    // Set the links in BFS order, so the links of the shorter nodes are ready when they're used.
+    fn build_links(&mut self) {
+        let mut queue: VecDeque<usize> = self.nodes[ROOT].children.values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(u8, usize)> = self.nodes[node]
+                .children
+                .iter()
+                .map(|(&c, &child)| (c, child))
+                .collect();
+            for (c, child) in children {
+                let fail = self.step(self.nodes[node].fail, c);
+                self.nodes[child].fail = fail;
+                self.nodes[child].output = if self.nodes[fail].patterns.is_empty() {
+                    self.nodes[fail].output
+                } else {
+                    fail
+                };
+                queue.push_back(child);
+            }
+        }
+    }
+
+    // Follow the fail links from `node` until `c` can be matched, or stop at the root.
+    fn step(&self, mut node: usize, c: u8) -> usize {
+        loop {
+            if let Some(&child) = self.nodes[node].children.get(&c) {
+                return child;
+            }
+            if node == ROOT {
+                return ROOT;
+            }
+            node = self.nodes[node].fail;
+        }
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.lens.len()
+    }
+
+    /// Call `f` with every match in the ascending order of the end, and the longer patterns first
+    /// for the same end. The search stops when `f` returns false.
+    pub fn for_each_match<F: FnMut(Match) -> bool>(&self, text: &[u8], mut f: F) {
+        let mut node = ROOT;
+        for (i, &c) in text.iter().enumerate() {
+            node = self.step(node, c);
+            let mut out = node;
+            while out != ROOT {
+                for &pattern in &self.nodes[out].patterns {
+                    let end = i + 1;
+                    let start = end - self.lens[pattern];
+                    if !f(Match {
+                        pattern,
+                        start,
+                        end,
+                    }) {
+                        return;
+                    }
+                }
+                out = self.nodes[out].output;
+            }
+        }
+    }
+
+    /// All the matches, in the order of `for_each_match`.
+    pub fn find_all(&self, text: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        self.for_each_match(text, |m| {
+            matches.push(m);
+            true
+        });
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+
+    fn naive(patterns: &[&[u8]], text: &[u8]) -> Vec<Match> {
+        let mut matches = Vec::new();
+        for end in 1..=text.len() {
+            let mut at_end: Vec<Match> = patterns
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| !p.is_empty() && p.len() <= end)
+                .filter(|(_, p)| &text[end - p.len()..end] == **p)
+                .map(|(pattern, p)| Match {
+                    pattern,
+                    start: end - p.len(),
+                    end,
+                })
+                .collect();
+            at_end.sort_by_key(|m| (m.start, m.pattern));
+            matches.extend(at_end);
+        }
+        matches
+    }
+
+    #[test]
+    pub fn test_find_all() {
+        let patterns: [&[u8]; 4] = [b"he", b"she", b"his", b"hers"];
+        let ac = AhoCorasick::new(&patterns);
+        assert_eq!(ac.pattern_count(), 4);
+        let found: Vec<(usize, usize)> = ac
+            .find_all(b"ushers")
+            .iter()
+            .map(|m| (m.pattern, m.start))
+            .collect();
+        assert_eq!(found, vec![(1, 1), (0, 2), (3, 2)]);
+        assert_eq!(ac.find_all(b""), vec![]);
+        assert_eq!(ac.find_all(b"xyz"), vec![]);
+    }
+
+    #[test]
+    pub fn test_overlaps_and_duplicates() {
+        let patterns: [&[u8]; 5] = [b"a", b"aa", b"", b"aa", b"\0a"];
+        let ac = AhoCorasick::new(&patterns);
+        let text = b"aa\0a";
+        assert_eq!(ac.find_all(text), naive(&patterns, text));
+        assert_eq!(ac.find_all(text).len(), 6);
+
+        // The search stops as soon as the callback returns false.
+        let mut n = 0;
+        ac.for_each_match(text, |_| {
+            n += 1;
+            n < 2
+        });
+        assert_eq!(n, 2);
+    }
+
+    #[test]
+    pub fn test_random() {
+        for _ in 0..50 {
+            let gen = |len: usize| -> Vec<u8> {
+                (0..len)
+                    .map(|_| b"abc"[(rand::gen_i32().unsigned_abs() % 3) as usize])
+                    .collect()
+            };
+            let patterns: Vec<Vec<u8>> = (0..8)
+                .map(|_| gen(1 + (rand::gen_i32().unsigned_abs() % 4) as usize))
+                .collect();
+            let patterns: Vec<&[u8]> = patterns.iter().map(|p| &p[..]).collect();
+            let text = gen(200);
+            assert_eq!(
+                AhoCorasick::new(&patterns).find_all(&text),
+                naive(&patterns, &text)
+            );
+        }
+    }
+}
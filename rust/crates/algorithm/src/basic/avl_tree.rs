@@ -1,4 +1,8 @@
+use crate::basic::map::{range_for_each, BinaryNode, InOrder, Map, OrderedMap};
+use std::borrow::Borrow;
 use std::cmp::Ordering;
+use std::mem;
+use std::ops::RangeBounds;
 
 macro_rules! left_child {
     ($node: expr) => {
@@ -52,21 +56,25 @@ where
 {
     /**
      * Right rotate the tree :
+     * ```text
      *             (A)
      *             /
      *           (B)        =>       (B)
      *           /                  /   \
      *         (C)                (C)   (A)
+     * ```
      */
     fn right_rotate(&mut self);
 
     /**
      *  Left rotate the tree:
+     *  ```text
      *          (A)
      *            \
      *            (B)       =>       (B)
      *              \               /   \
      *              (C)           (A)   (C)
+     *  ```
      */
     fn left_rotate(&mut self);
 
@@ -74,19 +82,23 @@ where
      *  Balance the right side after insert a new key-value.
      *  If the new node is added as the following, then do the right_rotate(A):
      *
+     *  ```text
      *             (A)
      *               \
      *               (B)    =>         (B)
      *                 \              /   \
      *                (New)         (A)   (New)
+     *  ```
      *
      *  Otherwise do the right_rotate(B) and left_rotate(A):
      *
+     *  ```text
      *              (A)          (A)
      *                \            \
      *                (B)   =>     (New)  =>     (New)
      *                /              \           /   \
      *              (New)            (B)       (A)   (B)
+     *  ```
      *
      */
     fn right_balance(&mut self);
@@ -95,23 +107,52 @@ where
      *  Balance the left side tree after insert a new key-value.
      *  If the new node is added as the following, then do the left_rotate(A):
      *
+     *  ```text
      *             (A)
      *             /
      *           (B)        =>       (B)
      *           /                  /   \
      *         (New)             (New)   (A)
+     *  ```
      *
      *  Otherwise do the left_rotate(B) and right_rotate(A):
      *
+     *  ```text
      *             (A)            (A)
      *             /              /
      *           (B)      =>    (New)   =>   (New)
      *             \            /            /   \
      *            (New)       (B)          (B)   (A)
+     *  ```
      */
     fn left_balance(&mut self);
 
-    fn get(&self, k: K) -> Option<&V>;
+    /**
+     *  Rebalance the tree after its left subtree gets shorter by a deletion, and return whether
+     *  the tree itself gets shorter. If the right side becomes too high and the right child is
+     *  balanced, then only do the left_rotate(A), and the height keeps the same:
+     *
+     *  ```text
+     *             (A)                  (B)
+     *               \                 /   \
+     *               (B)      =>      (A)   (D)
+     *              /   \               \
+     *            (C)   (D)             (C)
+     *  ```
+     *
+     *  Otherwise it's the same as the right_balance(..) after an insertion.
+     */
+    fn left_shrunk(&mut self) -> bool;
+
+    // Rebalance the tree after its right subtree gets shorter, which is symmetric to left_shrunk.
+    fn right_shrunk(&mut self) -> bool;
+
+    fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized;
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V>;
 
     // Get the height of balanced binary tree. It will panic if find any invalid
     // balance factor during visiting the whole tree.
@@ -121,6 +162,17 @@ where
     // The 1th returned bool indicate whether the pair is inserted or not.
     // The 2nd returned bool indicate whether the tree has been taller after the insertion.
     fn add(&mut self, k: K, v: V) -> (bool, bool);
+
+    // Remove the key from the tree.
+    // The 1st returned value is the value of the removed key.
+    // The 2nd returned bool indicate whether the tree has been shorter after the deletion.
+    fn remove<Q>(&mut self, k: &Q) -> (Option<V>, bool)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized;
+
+    // Remove the min key from the non-empty tree, just like the 2nd returned value of remove(..).
+    fn remove_min(&mut self) -> ((K, V), bool);
 }
 
 impl<K, V> TreeNode<K, V> for Link<K, V>
@@ -222,10 +274,73 @@ where
         }
     }
 
-    fn get(&self, k: K) -> Option<&V> {
+    fn left_shrunk(&mut self) -> bool {
+        let node = self.as_mut().unwrap();
+        match node.bf {
+            BF::LeftHigh => {
+                node.bf = BF::Equal;
+                true
+            }
+            BF::Equal => {
+                node.bf = BF::RightHigh;
+                false
+            }
+            BF::RightHigh => {
+                if let BF::Equal = right_child!(self).as_ref().unwrap().bf {
+                    right_mut_child!(self).as_mut().unwrap().bf = BF::LeftHigh;
+                    self.left_rotate();
+                    false
+                } else {
+                    self.right_balance();
+                    true
+                }
+            }
+        }
+    }
+
+    fn right_shrunk(&mut self) -> bool {
+        let node = self.as_mut().unwrap();
+        match node.bf {
+            BF::RightHigh => {
+                node.bf = BF::Equal;
+                true
+            }
+            BF::Equal => {
+                node.bf = BF::LeftHigh;
+                false
+            }
+            BF::LeftHigh => {
+                if let BF::Equal = left_child!(self).as_ref().unwrap().bf {
+                    left_mut_child!(self).as_mut().unwrap().bf = BF::RightHigh;
+                    self.right_rotate();
+                    false
+                } else {
+                    self.left_balance();
+                    true
+                }
+            }
+        }
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        match self {
+            None => None,
+            Some(node) => match Ord::cmp(k, &node.k) {
+                Ordering::Less => node.l.get_mut(k),
+                Ordering::Equal => Some(&mut node.v),
+                Ordering::Greater => node.r.get_mut(k),
+            },
+        }
+    }
+
+    fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
         match self {
             None => None,
-            Some(node) => match Ord::cmp(&k, &node.k) {
+            Some(node) => match Ord::cmp(k, node.k.borrow()) {
                 Ordering::Less => node.l.get(k),
                 Ordering::Equal => Some(&node.v),
                 Ordering::Greater => node.r.get(k),
@@ -311,12 +426,56 @@ where
             }
         }
     }
-}
 
-pub struct Iter<'a, K, V> {
-    stack: Vec<&'a Node<K, V>>,
+    fn remove<Q>(&mut self, k: &Q) -> (Option<V>, bool)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = match self {
+            None => return (None, false),
+            Some(node) => node,
+        };
+
+        match Ord::cmp(k, node.k.borrow()) {
+            Ordering::Less => {
+                let (v, shorter) = node.l.remove(k);
+                (v, shorter && self.left_shrunk())
+            }
+            Ordering::Greater => {
+                let (v, shorter) = node.r.remove(k);
+                (v, shorter && self.right_shrunk())
+            }
+            Ordering::Equal => {
+                if node.l.is_none() || node.r.is_none() {
+                    let node = *self.take().unwrap();
+                    *self = if node.l.is_none() { node.r } else { node.l };
+                    return (Some(node.v), true);
+                }
+
+                // Replace the node with its successor.
+                let ((k, v), shorter) = node.r.remove_min();
+                node.k = k;
+                let v = mem::replace(&mut node.v, v);
+                (Some(v), shorter && self.right_shrunk())
+            }
+        }
+    }
+
+    fn remove_min(&mut self) -> ((K, V), bool) {
+        if left_child!(self).is_none() {
+            let node = *self.take().unwrap();
+            *self = node.r;
+            return ((node.k, node.v), true);
+        }
+
+        let (kv, shorter) = left_mut_child!(self).remove_min();
+        (kv, shorter && self.left_shrunk())
+    }
 }
 
+pub type Iter<'a, K, V> = InOrder<'a, Node<K, V>>;
+
 impl<K, V> AVLTree<K, V>
 where
     K: Ord,
@@ -341,45 +500,111 @@ where
     }
 
     pub fn get(&self, key: K) -> Option<&V> {
+        self.root.get(&key)
+    }
+
+    /// Get the value by the borrowed form of the key, e.g. `&[u8]` for `Vec<u8>`.
+    pub fn find<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
         self.root.get(key)
     }
 
-    pub fn height(&self) -> usize {
-        self.root.height()
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.root.get_mut(&key)
     }
 
-    pub fn iter<'a>(&'a self) -> Iter<'a, K, V> {
-        let mut stack = Vec::new();
+    pub fn delete(&mut self, key: K) -> Option<V> {
+        self.delete_ref(&key)
+    }
 
-        let mut cur = &self.root;
-        while let Some(node) = cur.as_deref() {
-            stack.push(node);
-            cur = &node.l;
+    /// Delete the key by its borrowed form like `find`.
+    pub fn delete_ref<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (v, _) = self.root.remove(key);
+        if v.is_some() {
+            self.size -= 1;
         }
+        v
+    }
+
+    pub fn height(&self) -> usize {
+        self.root.height()
+    }
 
-        Iter { stack }
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        InOrder::new(self.root.as_deref())
     }
 }
 
-impl<'a, K, V> Iterator for Iter<'a, K, V> {
-    type Item = &'a Node<K, V>;
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Val = V;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.stack.pop() {
-            None => None,
-            Some(node) => {
-                // Enqueue left path of the first right child.
-                let mut cur = &node.r;
-                while let Some(cur_node) = cur.as_deref() {
-                    self.stack.push(cur_node);
-                    cur = &cur_node.l;
-                }
+    fn key(&self) -> &K {
+        &self.k
+    }
+
+    fn val(&self) -> &V {
+        &self.v
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.l.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.r.as_deref()
+    }
+}
 
-                // Just return the pop node from the stack.
-                Some(node)
+// The inherent `insert` keeps the old value of an existing key, while the map replaces it.
+impl<K: Ord, V> Map<K, V> for AVLTree<K, V> {
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        match self.root.get_mut(&k) {
+            Some(old) => Some(mem::replace(old, v)),
+            None => {
+                AVLTree::insert(self, k, v);
+                None
             }
         }
     }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.delete_ref(k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.root.get(k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.root.get_mut(k)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        AVLTree::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for AVLTree<K, V> {
+    fn range_for_each(&self, range: impl RangeBounds<K>, f: impl FnMut(&K, &V)) {
+        range_for_each(self.root.as_deref(), range, f);
+    }
 }
 
 #[cfg(test)]
@@ -416,9 +641,9 @@ mod tests {
             for i in 0..max {
                 match iter.next() {
                     None => panic!("We're expected to read more element from the iter."),
-                    Some(node) => {
-                        assert_eq!(node.k, i);
-                        assert_eq!(node.v, i);
+                    Some((&k, &v)) => {
+                        assert_eq!(k, i);
+                        assert_eq!(v, i);
                     }
                 }
             }
@@ -438,4 +663,26 @@ mod tests {
             tree.height();
         }
     }
+
+    #[test]
+    pub fn test_delete() {
+        let max = 2000;
+        let mut tree = AVLTree::new();
+        let mut input: Vec<i32> = (0..max).collect();
+        rand::shuffle(&mut input);
+        for &x in input.iter() {
+            tree.insert(x, x);
+        }
+
+        rand::shuffle(&mut input);
+        for (i, &x) in input.iter().enumerate() {
+            assert_eq!(tree.delete(x), Some(x));
+            assert_eq!(tree.delete(x), None);
+            assert_eq!(tree.get(x), None);
+            assert_eq!(tree.size(), max as usize - i - 1);
+            // Verify the balance factors.
+            tree.height();
+        }
+        assert!(tree.iter().next().is_none());
+    }
 }
@@ -0,0 +1,474 @@
+/**
+ * The in-memory B-tree map. Each node keeps up to `order - 1` sorted keys in a vector, so a lookup
+ * touches O(log_order(n)) nodes and binary searches the contiguous keys inside each node, which is
+ * much more cache-friendly than chasing one pointer per key in the binary trees.
+ *
+ * The order is the max number of the children of a node, and any order no less than 3 works: the
+ * overflowed node is split after the insertion, and the underflowed node borrows a key from its
+ * sibling or is merged with it after the deletion.
+ *
+ * Please see: https://en.wikipedia.org/wiki/B-tree
+ */
+use crate::basic::map::{after_end, before_start, Map, OrderedMap};
+use std::mem;
+use std::ops::RangeBounds;
+
+const DEFAULT_ORDER: usize = 16;
+
+pub struct BTree<K, V> {
+    order: usize,
+    size: usize,
+    root: Node<K, V>,
+}
+
+struct Node<K, V> {
+    keys: Vec<K>,
+    vals: Vec<V>,
+    // Empty for the leaf, otherwise `keys.len() + 1` children.
+    children: Vec<Node<K, V>>,
+}
+
+// The separator key and value, and the new right node after splitting a node.
+type Split<K, V> = (K, V, Node<K, V>);
+
+pub struct Iter<'a, K, V> {
+    // The nodes on the path and the index of the next key to visit in each of them.
+    stack: Vec<(&'a Node<K, V>, usize)>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new() -> Self {
+        Node {
+            keys: Vec::new(),
+            vals: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+impl<K: Ord, V> Node<K, V> {
+    // Insert the pair into the subtree, and return the old value and the split if the node
+    // overflows.
+    fn insert(&mut self, k: K, v: V, max_keys: usize) -> (Option<V>, Option<Split<K, V>>) {
+        match self.keys.binary_search(&k) {
+            Ok(i) => return (Some(mem::replace(&mut self.vals[i], v)), None),
+            Err(i) => {
+                if self.is_leaf() {
+                    self.keys.insert(i, k);
+                    self.vals.insert(i, v);
+                } else {
+                    let (old, split) = self.children[i].insert(k, v, max_keys);
+                    if old.is_some() {
+                        return (old, None);
+                    }
+                    if let Some((k, v, right)) = split {
+                        self.keys.insert(i, k);
+                        self.vals.insert(i, v);
+                        self.children.insert(i + 1, right);
+                    }
+                }
+            }
+        }
+
+        if self.keys.len() <= max_keys {
+            return (None, None);
+        }
+
+        // Move the upper half into the new right node, and the middle one goes up to the parent.
+        let mid = self.keys.len() / 2;
+        let mut right = Node::new();
+        right.keys = self.keys.split_off(mid + 1);
+        right.vals = self.vals.split_off(mid + 1);
+        if !self.is_leaf() {
+            right.children = self.children.split_off(mid + 1);
+        }
+        let k = self.keys.pop().unwrap();
+        let v = self.vals.pop().unwrap();
+        (None, Some((k, v, right)))
+    }
+
+    fn remove(&mut self, k: &K, min_keys: usize) -> Option<V> {
+        match self.keys.binary_search(k) {
+            Ok(i) if self.is_leaf() => {
+                self.keys.remove(i);
+                Some(self.vals.remove(i))
+            }
+            Ok(i) => {
+                // Replace the key with its precursor, which is always in a leaf.
+                let (pk, pv) = self.children[i].remove_max(min_keys);
+                self.keys[i] = pk;
+                let v = mem::replace(&mut self.vals[i], pv);
+                self.fix_child(i, min_keys);
+                Some(v)
+            }
+            Err(_) if self.is_leaf() => None,
+            Err(i) => {
+                let v = self.children[i].remove(k, min_keys);
+                if v.is_some() {
+                    self.fix_child(i, min_keys);
+                }
+                v
+            }
+        }
+    }
+
+    fn remove_max(&mut self, min_keys: usize) -> (K, V) {
+        if self.is_leaf() {
+            return (self.keys.pop().unwrap(), self.vals.pop().unwrap());
+        }
+        let i = self.children.len() - 1;
+        let kv = self.children[i].remove_max(min_keys);
+        self.fix_child(i, min_keys);
+        kv
+    }
+
+    // Refill the i-th child if it has less than `min_keys` keys.
+    fn fix_child(&mut self, i: usize, min_keys: usize) {
+        if self.children[i].keys.len() >= min_keys {
+            return;
+        }
+
+        if i > 0 && self.children[i - 1].keys.len() > min_keys {
+            // Rotate the last key of the left sibling through the parent.
+            let (left, right) = self.children.split_at_mut(i);
+            let (left, child) = (&mut left[i - 1], &mut right[0]);
+            let k = mem::replace(&mut self.keys[i - 1], left.keys.pop().unwrap());
+            let v = mem::replace(&mut self.vals[i - 1], left.vals.pop().unwrap());
+            child.keys.insert(0, k);
+            child.vals.insert(0, v);
+            if !left.is_leaf() {
+                child.children.insert(0, left.children.pop().unwrap());
+            }
+        } else if i + 1 < self.children.len() && self.children[i + 1].keys.len() > min_keys {
+            // Rotate the first key of the right sibling through the parent.
+            let (left, right) = self.children.split_at_mut(i + 1);
+            let (child, right) = (&mut left[i], &mut right[0]);
+            let k = mem::replace(&mut self.keys[i], right.keys.remove(0));
+            let v = mem::replace(&mut self.vals[i], right.vals.remove(0));
+            child.keys.push(k);
+            child.vals.push(v);
+            if !right.is_leaf() {
+                child.children.push(right.children.remove(0));
+            }
+        } else {
+            // Merge the child, its sibling and the key between them.
+            let j = if i > 0 { i - 1 } else { i };
+            let right = self.children.remove(j + 1);
+            let left = &mut self.children[j];
+            left.keys.push(self.keys.remove(j));
+            left.vals.push(self.vals.remove(j));
+            left.keys.extend(right.keys);
+            left.vals.extend(right.vals);
+            left.children.extend(right.children);
+        }
+    }
+}
+
+impl<K, V> BTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::with_order(DEFAULT_ORDER)
+    }
+
+    /// Create the B-tree whose nodes have at most `order` children.
+    pub fn with_order(order: usize) -> Self {
+        assert!(order >= 3, "The order of the B-tree must be at least 3");
+        BTree {
+            order,
+            size: 0,
+            root: Node::new(),
+        }
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    // Every node except the root has at least `min_keys` keys.
+    fn min_keys(&self) -> usize {
+        (self.order - 1) / 2
+    }
+
+    /// Insert the pair, and return the old value if the key exists.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let (old, split) = self.root.insert(k, v, self.order - 1);
+        if let Some((k, v, right)) = split {
+            // The tree only grows at the root.
+            let left = mem::replace(&mut self.root, Node::new());
+            self.root.keys.push(k);
+            self.root.vals.push(v);
+            self.root.children = vec![left, right];
+        }
+        if old.is_none() {
+            self.size += 1;
+        }
+        old
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let mut node = &self.root;
+        loop {
+            match node.keys.binary_search(k) {
+                Ok(i) => return Some(&node.vals[i]),
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => node = &node.children[i],
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        loop {
+            match node.keys.binary_search(k) {
+                Ok(i) => return Some(&mut node.vals[i]),
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => node = &mut node.children[i],
+            }
+        }
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.get(k).is_some()
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        let min_keys = self.min_keys();
+        let v = self.root.remove(k, min_keys)?;
+        // The tree only shrinks at the root.
+        if self.root.keys.is_empty() && !self.root.is_leaf() {
+            self.root = self.root.children.pop().unwrap();
+        }
+        self.size -= 1;
+        Some(v)
+    }
+
+    pub fn min(&self) -> Option<(&K, &V)> {
+        let mut node = &self.root;
+        while !node.is_leaf() {
+            node = &node.children[0];
+        }
+        Some((node.keys.first()?, node.vals.first()?))
+    }
+
+    pub fn max(&self) -> Option<(&K, &V)> {
+        let mut node = &self.root;
+        while !node.is_leaf() {
+            node = node.children.last().unwrap();
+        }
+        Some((node.keys.last()?, node.vals.last()?))
+    }
+
+    /// The number of the levels, which is the same for all the leaves.
+    pub fn height(&self) -> usize {
+        if self.size == 0 {
+            return 0;
+        }
+        let mut height = 1;
+        let mut node = &self.root;
+        while !node.is_leaf() {
+            node = &node.children[0];
+            height += 1;
+        }
+        height
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(&self.root);
+        iter
+    }
+}
+
+impl<K: Ord, V> Default for BTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut node: &'a Node<K, V>) {
+        loop {
+            self.stack.push((node, 0));
+            match node.children.first() {
+                None => break,
+                Some(child) => node = child,
+            }
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, i) = *self.stack.last()?;
+            if i < node.keys.len() {
+                self.stack.last_mut().unwrap().1 += 1;
+                // The keys in the (i + 1)-th child are between the i-th and the (i + 1)-th keys.
+                if !node.is_leaf() {
+                    self.push_left(&node.children[i + 1]);
+                }
+                return Some((&node.keys[i], &node.vals[i]));
+            }
+            self.stack.pop();
+        }
+    }
+}
+
+impl<K: Ord, V> Map<K, V> for BTree<K, V> {
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        BTree::insert(self, k, v)
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        BTree::remove(self, k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        BTree::get(self, k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        BTree::get_mut(self, k)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        BTree::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for BTree<K, V> {
+    fn range_for_each(&self, range: impl RangeBounds<K>, mut f: impl FnMut(&K, &V)) {
+        // Seek to the first key in the range, each level of the stack points to the first key
+        // not before the start, and the child before it is visited first.
+        let mut iter = Iter { stack: Vec::new() };
+        let mut node = &self.root;
+        loop {
+            let i = node.keys.partition_point(|k| before_start(&range, k));
+            iter.stack.push((node, i));
+            if node.is_leaf() {
+                break;
+            }
+            node = &node.children[i];
+        }
+        for (k, v) in iter.take_while(|(k, _)| !after_end(&range, *k)) {
+            f(k, v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+
+    // Check the key counts, the order and the depth of the leaves, and return the depth.
+    fn verify<K: Ord, V>(node: &Node<K, V>, order: usize, is_root: bool) -> usize {
+        assert!(node.keys.len() < order);
+        assert!(is_root || node.keys.len() >= (order - 1) / 2);
+        assert_eq!(node.keys.len(), node.vals.len());
+        assert!(node.keys.windows(2).all(|w| w[0] < w[1]));
+        if node.is_leaf() {
+            return 1;
+        }
+
+        assert_eq!(node.children.len(), node.keys.len() + 1);
+        let depth = verify(&node.children[0], order, false);
+        for (i, child) in node.children.iter().enumerate() {
+            assert_eq!(verify(child, order, false), depth);
+            if i > 0 {
+                assert!(node.keys[i - 1] < child.keys[0]);
+            }
+            if i < node.keys.len() {
+                assert!(*child.keys.last().unwrap() < node.keys[i]);
+            }
+        }
+        depth + 1
+    }
+
+    #[test]
+    pub fn basics() {
+        let mut tree = BTree::with_order(3);
+        assert_eq!(tree.height(), 0);
+        assert_eq!(tree.min(), None);
+        assert_eq!(tree.remove(&1), None);
+
+        for i in 0..100 {
+            assert_eq!(tree.insert(i, i * 2), None);
+            verify(&tree.root, 3, true);
+        }
+        assert_eq!(tree.insert(7, 0), Some(14));
+        *tree.get_mut(&8).unwrap() = 1;
+        assert_eq!(tree.get(&7), Some(&0));
+        assert_eq!(tree.get(&8), Some(&1));
+        assert_eq!(tree.get(&100), None);
+        assert_eq!(tree.len(), 100);
+        assert_eq!(tree.min(), Some((&0, &0)));
+        assert_eq!(tree.max(), Some((&99, &198)));
+        // The 2-3 tree of 100 keys has at most log2(101) levels.
+        assert!(tree.height() <= 6);
+
+        for i in (0..100).rev().step_by(3) {
+            assert!(tree.remove(&i).is_some());
+            verify(&tree.root, 3, true);
+        }
+        assert!(tree
+            .iter()
+            .map(|(k, _)| *k)
+            .eq((0..100).filter(|i| i % 3 != 0)));
+    }
+
+    #[test]
+    pub fn random_keep_invariants() {
+        for order in [3, 4, 7, 32] {
+            let mut tree = BTree::with_order(order);
+            for _ in 0..5000 {
+                let k = rand::gen_i32() % 500;
+                if rand::gen_u32().is_multiple_of(3) {
+                    tree.remove(&k);
+                } else {
+                    tree.insert(k, ());
+                }
+                verify(&tree.root, order, true);
+            }
+            assert_eq!(tree.iter().count(), tree.len());
+        }
+    }
+
+    #[test]
+    pub fn large_order_is_shallow() {
+        let mut tree = BTree::with_order(64);
+        for i in 0..100_000 {
+            tree.insert(i, i);
+        }
+        assert!(tree.height() <= 4);
+        assert!(tree
+            .iter()
+            .map(|(k, v)| k + v)
+            .eq((0..100_000).map(|i| i * 2)));
+    }
+}
@@ -0,0 +1,835 @@
+/**
+ * The thread-safe queues and deques:
+ *
+ * 1. `MutexDeque`: the double linked list of `double_linked_list_v1` guarded by one mutex, which
+ *    also guards the `Rc` links of its nodes, so no node needs a lock of its own.
+ * 2. `MsQueue`: the lock-free Michael-Scott FIFO queue.
+ *    Please see: https://www.cs.rochester.edu/~scott/papers/1996_PODC_queues.pdf
+ * 3. `Worker` and `Stealer`: the Chase-Lev work-stealing deque, where the owner pushes and pops at
+ *    the bottom and the other threads steal from the top.
+ *    Please see: https://fzn.fr/readings/ppopp13.pdf
+ *
+ * The lock-free structures can't free an unlinked node at once, because other threads may still
+ * be reading it. The unlinked nodes are retired instead, and freed in batches after a grace period
+ * in which all the operations started before they were retired have finished.
+ */
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicIsize, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::basic::double_linked_list_v1::List;
+
+struct Locked<T> {
+    list: List<T>,
+    len: usize,
+}
+
+/// The double linked list shared between threads, all the operations take `&self`.
+pub struct MutexDeque<T> {
+    locked: Mutex<Locked<T>>,
+}
+
+// SAFETY: the `Rc` links of the nodes never leave the mutex, so they're only cloned and dropped by
+// the thread holding it, and only the elements move between the threads.
+unsafe impl<T: Send> Send for MutexDeque<T> {}
+unsafe impl<T: Send> Sync for MutexDeque<T> {}
+
+impl<T> MutexDeque<T> {
+    pub fn new() -> Self {
+        MutexDeque {
+            locked: Mutex::new(Locked {
+                list: List::new(),
+                len: 0,
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.locked.lock().unwrap().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push_front(&self, elem: T) {
+        let mut locked = self.locked.lock().unwrap();
+        locked.list.push_front(elem);
+        locked.len += 1;
+    }
+
+    pub fn push_back(&self, elem: T) {
+        let mut locked = self.locked.lock().unwrap();
+        locked.list.push_back(elem);
+        locked.len += 1;
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        let mut locked = self.locked.lock().unwrap();
+        let elem = locked.list.pop_front()?;
+        locked.len -= 1;
+        Some(elem)
+    }
+
+    pub fn pop_back(&self) -> Option<T> {
+        let mut locked = self.locked.lock().unwrap();
+        let elem = locked.list.pop_back()?;
+        locked.len -= 1;
+        Some(elem)
+    }
+
+    pub fn peek_front(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let locked = self.locked.lock().unwrap();
+        let elem = locked.list.peek_front().map(|elem| elem.clone());
+        elem
+    }
+
+    pub fn peek_back(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let locked = self.locked.lock().unwrap();
+        let elem = locked.list.peek_back().map(|elem| elem.clone());
+        elem
+    }
+}
+
+impl<T> Default for MutexDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Retired {
+    ptr: *mut u8,
+    free: unsafe fn(*mut u8),
+    next: *mut Retired,
+}
+
+unsafe fn free_box<N>(p: *mut u8) {
+    drop(Box::from_raw(p as *mut N));
+}
+
+// The retired nodes are flushed in a batch once there are `RETIRED_CAP` of them, so the retired
+// list is bounded whatever the contention is.
+const RETIRED_CAP: usize = 64;
+
+// Every operation pins the reclaimer during its whole life, and it's counted in the phase it's
+// pinned in. A retired node is unreachable for the operations pinned after it was retired, so the
+// flush takes the batch, flips the phase, and waits until the old phase has no operation, which
+// is a grace period since the new operations are counted in the new phase.
+struct Reclaimer {
+    phase: AtomicUsize,
+    active: [AtomicUsize; 2],
+    retired: AtomicPtr<Retired>,
+    retired_len: AtomicUsize,
+    // Only one thread flushes at a time, the others just skip the flush.
+    flushing: Mutex<()>,
+}
+
+struct Guard<'a> {
+    r: &'a Reclaimer,
+    phase: usize,
+}
+
+impl Reclaimer {
+    fn new() -> Self {
+        Reclaimer {
+            phase: AtomicUsize::new(0),
+            active: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            retired: AtomicPtr::new(ptr::null_mut()),
+            retired_len: AtomicUsize::new(0),
+            flushing: Mutex::new(()),
+        }
+    }
+
+    fn pin(&self) -> Guard<'_> {
+        loop {
+            let phase = self.phase.load(Ordering::SeqCst);
+            self.active[phase].fetch_add(1, Ordering::SeqCst);
+            // The phase may have flipped before we're counted, then the flush may not wait for us.
+            if self.phase.load(Ordering::SeqCst) == phase {
+                return Guard { r: self, phase };
+            }
+            self.active[phase].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    // SAFETY: `p` must come from `Box::into_raw`, and be unreachable for the new operations.
+    unsafe fn retire<N>(&self, p: *mut N) {
+        let node = Box::into_raw(Box::new(Retired {
+            ptr: p as *mut u8,
+            free: free_box::<N>,
+            next: ptr::null_mut(),
+        }));
+        // Counted before it's pushed, so the flush which takes it never makes the length negative.
+        self.retired_len.fetch_add(1, Ordering::SeqCst);
+        let mut head = self.retired.load(Ordering::Acquire);
+        loop {
+            (*node).next = head;
+            match self
+                .retired
+                .compare_exchange(head, node, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return,
+                Err(h) => head = h,
+            }
+        }
+    }
+
+    // Free all the nodes retired so far once every operation pinned before has finished. It must
+    // not be called by a pinned thread, which would wait for itself.
+    fn flush(&self) {
+        let _flushing = match self.flushing.try_lock() {
+            Ok(lock) => lock,
+            Err(_) => return,
+        };
+        let batch = self.retired.swap(ptr::null_mut(), Ordering::SeqCst);
+        if batch.is_null() {
+            return;
+        }
+        let old = self.phase.load(Ordering::SeqCst);
+        self.phase.store(1 - old, Ordering::SeqCst);
+        while self.active[old].load(Ordering::SeqCst) != 0 {
+            std::thread::yield_now();
+        }
+        let freed = unsafe { Reclaimer::free_all(batch) };
+        self.retired_len.fetch_sub(freed, Ordering::SeqCst);
+    }
+
+    unsafe fn free_all(mut cur: *mut Retired) -> usize {
+        let mut freed = 0;
+        while !cur.is_null() {
+            let node = Box::from_raw(cur);
+            (node.free)(node.ptr);
+            cur = node.next;
+            freed += 1;
+        }
+        freed
+    }
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        let r = self.r;
+        r.active[self.phase].fetch_sub(1, Ordering::SeqCst);
+        if r.retired_len.load(Ordering::SeqCst) >= RETIRED_CAP {
+            r.flush();
+        }
+    }
+}
+
+impl Drop for Reclaimer {
+    fn drop(&mut self) {
+        unsafe { Reclaimer::free_all(*self.retired.get_mut()) };
+    }
+}
+
+struct MsNode<T> {
+    // It's uninitialized for the dummy node, and moved out once the node becomes the dummy.
+    value: MaybeUninit<T>,
+    next: AtomicPtr<MsNode<T>>,
+}
+
+impl<T> MsNode<T> {
+    fn alloc(value: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(MsNode {
+            value,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+/// The lock-free unbounded FIFO queue. The head always points to a dummy node, and the tail
+/// points to the last or the second last node.
+pub struct MsQueue<T> {
+    head: AtomicPtr<MsNode<T>>,
+    tail: AtomicPtr<MsNode<T>>,
+    reclaimer: Reclaimer,
+}
+
+unsafe impl<T: Send> Send for MsQueue<T> {}
+unsafe impl<T: Send> Sync for MsQueue<T> {}
+
+impl<T> MsQueue<T> {
+    pub fn new() -> Self {
+        let dummy = MsNode::alloc(MaybeUninit::uninit());
+        MsQueue {
+            head: AtomicPtr::new(dummy),
+            tail: AtomicPtr::new(dummy),
+            reclaimer: Reclaimer::new(),
+        }
+    }
+
+    pub fn push(&self, value: T) {
+        let node = MsNode::alloc(MaybeUninit::new(value));
+        let _guard = self.reclaimer.pin();
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+            if tail != self.tail.load(Ordering::Acquire) {
+                continue;
+            }
+
+            if next.is_null() {
+                let linked = unsafe {
+                    (*tail).next.compare_exchange(
+                        ptr::null_mut(),
+                        node,
+                        Ordering::Release,
+                        Ordering::Relaxed,
+                    )
+                };
+                if linked.is_ok() {
+                    // It's fine to fail, someone else has swung the tail already.
+                    let _ = self.tail.compare_exchange(
+                        tail,
+                        node,
+                        Ordering::Release,
+                        Ordering::Relaxed,
+                    );
+                    return;
+                }
+            } else {
+                // The tail is lagging behind, help to swing it.
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let _guard = self.reclaimer.pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let tail = self.tail.load(Ordering::Acquire);
+            let next = unsafe { (*head).next.load(Ordering::Acquire) };
+            if head != self.head.load(Ordering::Acquire) {
+                continue;
+            }
+
+            if next.is_null() {
+                return None;
+            }
+            if head == tail {
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                // Only the winner of the CAS moves the value out, and `next` becomes the dummy.
+                let value = unsafe { ptr::read((*next).value.as_ptr()) };
+                unsafe { self.reclaimer.retire(head) };
+                return Some(value);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let _guard = self.reclaimer.pin();
+        let head = self.head.load(Ordering::Acquire);
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+}
+
+impl<T> Default for MsQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for MsQueue<T> {
+    fn drop(&mut self) {
+        unsafe {
+            // The dummy has no value, all the following nodes do.
+            let mut cur = *self.head.get_mut();
+            let mut dummy = true;
+            while !cur.is_null() {
+                let mut node = Box::from_raw(cur);
+                if !dummy {
+                    node.value.assume_init_drop();
+                }
+                dummy = false;
+                cur = *node.next.get_mut();
+            }
+        }
+    }
+}
+
+struct Buffer<T> {
+    // The capacity is a power of two, so the index wraps around by the mask.
+    mask: isize,
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+impl<T> Buffer<T> {
+    fn alloc(cap: usize) -> *mut Self {
+        Box::into_raw(Box::new(Buffer {
+            mask: cap as isize - 1,
+            slots: (0..cap)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+        }))
+    }
+
+    fn cap(&self) -> isize {
+        self.mask + 1
+    }
+
+    unsafe fn write(&self, i: isize, value: T) {
+        (*self.slots[(i & self.mask) as usize].get()).write(value);
+    }
+
+    // The value is a bitwise copy, the caller must forget it if the slot is not taken.
+    unsafe fn read(&self, i: isize) -> T {
+        ptr::read((*self.slots[(i & self.mask) as usize].get()).as_ptr())
+    }
+}
+
+struct Inner<T> {
+    top: AtomicIsize,
+    bottom: AtomicIsize,
+    buffer: AtomicPtr<Buffer<T>>,
+    reclaimer: Reclaimer,
+}
+
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let top = *self.top.get_mut();
+        let bottom = *self.bottom.get_mut();
+        unsafe {
+            let buffer = Box::from_raw(*self.buffer.get_mut());
+            for i in top..bottom {
+                drop(buffer.read(i));
+            }
+        }
+    }
+}
+
+/// The result of a steal.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Steal<T> {
+    Empty,
+    Success(T),
+    // Lost the race with another thief or the owner, and it's worth retrying.
+    Retry,
+}
+
+/// The owner side of the work-stealing deque. It can be moved to another thread but not shared.
+pub struct Worker<T> {
+    inner: Arc<Inner<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// The thief side of the work-stealing deque, which can be cloned and shared by many threads.
+pub struct Stealer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+const MIN_CAP: usize = 16;
+
+/// Create the work-stealing deque, returns the owner and the thief handles.
+pub fn work_stealing<T>() -> (Worker<T>, Stealer<T>) {
+    let inner = Arc::new(Inner {
+        top: AtomicIsize::new(0),
+        bottom: AtomicIsize::new(0),
+        buffer: AtomicPtr::new(Buffer::alloc(MIN_CAP)),
+        reclaimer: Reclaimer::new(),
+    });
+    (
+        Worker {
+            inner: inner.clone(),
+            _not_sync: PhantomData,
+        },
+        Stealer { inner },
+    )
+}
+
+impl<T> Worker<T> {
+    pub fn len(&self) -> usize {
+        let b = self.inner.bottom.load(Ordering::Relaxed);
+        let t = self.inner.top.load(Ordering::Relaxed);
+        (b - t).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: self.inner.clone(),
+        }
+    }
+
+    // Copy the live range into a buffer of the double capacity. Only the owner writes the buffer
+    // pointer, and the old buffer is retired since the thieves may still read it.
+    unsafe fn grow(&self, old: *mut Buffer<T>, b: isize, t: isize) -> *mut Buffer<T> {
+        let new = Buffer::alloc((*old).cap() as usize * 2);
+        for i in t..b {
+            (*new).write(i, (*old).read(i));
+        }
+        self.inner.buffer.store(new, Ordering::Release);
+        self.inner.reclaimer.retire(old);
+        new
+    }
+
+    pub fn push(&self, value: T) {
+        let inner = &*self.inner;
+        let _guard = inner.reclaimer.pin();
+        let b = inner.bottom.load(Ordering::Relaxed);
+        let t = inner.top.load(Ordering::Acquire);
+        let mut buffer = inner.buffer.load(Ordering::Relaxed);
+        unsafe {
+            if b - t >= (*buffer).cap() {
+                buffer = self.grow(buffer, b, t);
+            }
+            (*buffer).write(b, value);
+        }
+        fence(Ordering::Release);
+        inner.bottom.store(b + 1, Ordering::Relaxed);
+    }
+
+    /// Pop the most recently pushed value, which races with the thieves only for the last one.
+    pub fn pop(&self) -> Option<T> {
+        let inner = &*self.inner;
+        let _guard = inner.reclaimer.pin();
+        let b = inner.bottom.load(Ordering::Relaxed) - 1;
+        let buffer = inner.buffer.load(Ordering::Relaxed);
+        inner.bottom.store(b, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let t = inner.top.load(Ordering::Relaxed);
+
+        if t > b {
+            // Empty.
+            inner.bottom.store(b + 1, Ordering::Relaxed);
+            return None;
+        }
+
+        let value = unsafe { (*buffer).read(b) };
+        if t < b {
+            return Some(value);
+        }
+
+        // The last value, win it from the thieves by moving the top.
+        let won = inner
+            .top
+            .compare_exchange(t, t + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok();
+        inner.bottom.store(b + 1, Ordering::Relaxed);
+        if won {
+            Some(value)
+        } else {
+            std::mem::forget(value);
+            None
+        }
+    }
+}
+
+impl<T> Stealer<T> {
+    /// Steal the least recently pushed value.
+    pub fn steal(&self) -> Steal<T> {
+        let inner = &*self.inner;
+        let _guard = inner.reclaimer.pin();
+        let t = inner.top.load(Ordering::Acquire);
+        fence(Ordering::SeqCst);
+        let b = inner.bottom.load(Ordering::Acquire);
+        if t >= b {
+            return Steal::Empty;
+        }
+
+        let buffer = inner.buffer.load(Ordering::Acquire);
+        let value = unsafe { (*buffer).read(t) };
+        if inner
+            .top
+            .compare_exchange(t, t + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok()
+        {
+            Steal::Success(value)
+        } else {
+            std::mem::forget(value);
+            Steal::Retry
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let t = self.inner.top.load(Ordering::Acquire);
+        let b = self.inner.bottom.load(Ordering::Acquire);
+        t >= b
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Stealer {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    #[test]
+    pub fn mutex_deque_basics() {
+        let deque = MutexDeque::new();
+        assert_eq!(deque.pop_front(), None);
+
+        deque.push_front(2);
+        deque.push_front(1);
+        deque.push_back(3);
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque.peek_front(), Some(1));
+        assert_eq!(deque.peek_back(), Some(3));
+
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_front(), Some(2));
+        assert_eq!(deque.pop_back(), None);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    pub fn mutex_deque_threads() {
+        let deque = Arc::new(MutexDeque::new());
+
+        // Each thread pushes its own values at both ends, then pops half of them.
+        let mut threads = vec![];
+        for id in 0..8 {
+            let deque = deque.clone();
+            threads.push(thread::spawn(move || {
+                let mut popped = vec![];
+                for i in 0..1000 {
+                    if i % 2 == 0 {
+                        deque.push_back(id * 1000 + i);
+                    } else {
+                        deque.push_front(id * 1000 + i);
+                    }
+                }
+                for i in 0..500 {
+                    let v = if i % 2 == 0 {
+                        deque.pop_front()
+                    } else {
+                        deque.pop_back()
+                    };
+                    popped.push(v.expect("Unexpected empty deque"));
+                }
+                popped
+            }));
+        }
+
+        let mut all = vec![];
+        for t in threads {
+            all.extend(t.join().unwrap());
+        }
+        while let Some(v) = deque.pop_front() {
+            all.push(v);
+        }
+        all.sort();
+        assert_eq!(all, Vec::from_iter(0..8000));
+    }
+
+    #[test]
+    pub fn ms_queue_basics() {
+        let q = MsQueue::new();
+        assert!(q.is_empty());
+        assert_eq!(q.pop(), None);
+
+        for i in 0..100 {
+            q.push(i.to_string());
+        }
+        for i in 0..50 {
+            assert_eq!(q.pop(), Some(i.to_string()));
+        }
+        assert!(!q.is_empty());
+        // The remaining values are dropped with the queue.
+    }
+
+    #[test]
+    pub fn ms_queue_threads() {
+        let producers = 4;
+        let consumers = 4;
+        let n = 20000;
+        let q = Arc::new(MsQueue::new());
+        let done = Arc::new(AtomicBool::new(false));
+
+        let mut threads = vec![];
+        for id in 0..producers {
+            let q = q.clone();
+            threads.push(thread::spawn(move || {
+                for i in 0..n {
+                    q.push((id, i));
+                }
+                vec![]
+            }));
+        }
+        for _ in 0..consumers {
+            let q = q.clone();
+            let done = done.clone();
+            threads.push(thread::spawn(move || {
+                let mut got = vec![];
+                loop {
+                    match q.pop() {
+                        Some(v) => got.push(v),
+                        None if done.load(Ordering::SeqCst) => break,
+                        None => thread::yield_now(),
+                    }
+                }
+                got
+            }));
+        }
+
+        let mut results = vec![];
+        for (i, t) in threads.into_iter().enumerate() {
+            if i == producers {
+                // All the producers are joined.
+                done.store(true, Ordering::SeqCst);
+            }
+            results.push(t.join().unwrap());
+        }
+
+        // Every consumer sees the values of each producer in the FIFO order.
+        let mut count = vec![0; producers];
+        for got in &results {
+            let mut last = vec![-1; producers];
+            for &(id, i) in got {
+                assert!(i > last[id]);
+                last[id] = i;
+                count[id] += 1;
+            }
+        }
+        assert_eq!(count, vec![n as usize; producers]);
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    pub fn reclaimer_flushes_under_contention() {
+        struct Counted(Arc<AtomicUsize>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        // The operations of the threads overlap all the time, so there's hardly a moment without
+        // any operation in flight, but the retired list is still flushed.
+        let r = Arc::new(Reclaimer::new());
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let r = r.clone();
+                let dropped = dropped.clone();
+                thread::spawn(move || {
+                    for _ in 0..5000 {
+                        let _guard = r.pin();
+                        let p = Box::into_raw(Box::new(Counted(dropped.clone())));
+                        unsafe { r.retire(p) };
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert!(dropped.load(Ordering::SeqCst) > 0);
+
+        // The next operation flushes whatever is left over by the skipped flushes.
+        drop(r.pin());
+        assert!(r.retired_len.load(Ordering::SeqCst) < RETIRED_CAP);
+        assert_eq!(
+            dropped.load(Ordering::SeqCst) + r.retired_len.load(Ordering::SeqCst),
+            20000
+        );
+        drop(Arc::try_unwrap(r).ok().unwrap());
+        assert_eq!(dropped.load(Ordering::SeqCst), 20000);
+    }
+
+    #[test]
+    pub fn work_stealing_basics() {
+        let (w, s) = work_stealing();
+        assert_eq!(w.pop(), None);
+        assert_eq!(s.steal(), Steal::Empty);
+
+        // Grow the buffer a few times.
+        for i in 0..100 {
+            w.push(i);
+        }
+        assert_eq!(w.len(), 100);
+        assert_eq!(w.pop(), Some(99));
+        assert_eq!(s.steal(), Steal::Success(0));
+        assert_eq!(s.clone().steal(), Steal::Success(1));
+        assert_eq!(w.pop(), Some(98));
+        assert_eq!(w.len(), 96);
+
+        let (w, _) = work_stealing();
+        w.push(Box::new(1));
+        w.push(Box::new(2));
+        // The remaining boxes are dropped with the deque.
+    }
+
+    #[test]
+    pub fn work_stealing_threads() {
+        let n = 100000;
+        let thieves = 3;
+        let (w, s) = work_stealing();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let mut threads = vec![];
+        for _ in 0..thieves {
+            let s = s.clone();
+            let done = done.clone();
+            threads.push(thread::spawn(move || {
+                let mut got = vec![];
+                loop {
+                    match s.steal() {
+                        Steal::Success(v) => got.push(v),
+                        Steal::Retry => {}
+                        Steal::Empty if done.load(Ordering::SeqCst) => break,
+                        Steal::Empty => thread::yield_now(),
+                    }
+                }
+                got
+            }));
+        }
+
+        // The owner pushes all the values, and pops one after every two pushes.
+        let mut all = vec![];
+        for i in 0..n {
+            w.push(i);
+            if i % 2 == 1 {
+                if let Some(v) = w.pop() {
+                    all.push(v);
+                }
+            }
+        }
+        while let Some(v) = w.pop() {
+            all.push(v);
+        }
+        done.store(true, Ordering::SeqCst);
+
+        for t in threads {
+            all.extend(t.join().unwrap());
+        }
+        all.sort();
+        assert_eq!(all, Vec::from_iter(0..n));
+    }
+}
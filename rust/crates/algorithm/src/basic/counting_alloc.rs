@@ -0,0 +1,344 @@
+/**
+ * The counting allocator, which wraps another allocator (the system one by default) and counts the
+ * allocations, the bytes and the peak of the live bytes, so that the heap memory of the containers
+ * can be measured.
+ *
+ * It counts nothing until it's installed by the binary:
+ *
+ * ```
+ * use hello_world::basic::counting_alloc::CountingAlloc;
+ *
+ * #[global_allocator]
+ * static GLOBAL: CountingAlloc = CountingAlloc::new();
+ * #
+ * # fn main() {}
+ * ```
+ *
+ * The counters are kept per thread, so a `Scope` only sees the allocations of its own thread, and
+ * isn't disturbed by the tests running in parallel. The unit tests of this crate install it.
+ *
+ * Please see: https://doc.rust-lang.org/std/alloc/trait.GlobalAlloc.html
+ */
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt;
+use std::hint::black_box;
+
+#[cfg(test)]
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc::new();
+
+pub struct CountingAlloc<A = System> {
+    inner: A,
+}
+
+impl CountingAlloc {
+    pub const fn new() -> Self {
+        CountingAlloc { inner: System }
+    }
+}
+
+impl<A> CountingAlloc<A> {
+    /// Count the allocations of another allocator.
+    pub const fn wrap(inner: A) -> Self {
+        CountingAlloc { inner }
+    }
+}
+
+impl Default for CountingAlloc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Counters {
+    allocs: usize,
+    deallocs: usize,
+    allocated: usize,
+    freed: usize,
+    // The live bytes can be negative, because the memory allocated by another thread may be freed
+    // by this one.
+    live: isize,
+    peak: isize,
+}
+
+thread_local! {
+    // It's initialized by a constant and has no destructor, so it never allocates by itself and
+    // can be used inside the allocator.
+    static COUNTERS: Cell<Counters> = const {
+        Cell::new(Counters {
+            allocs: 0,
+            deallocs: 0,
+            allocated: 0,
+            freed: 0,
+            live: 0,
+            peak: 0,
+        })
+    };
+}
+
+fn record(f: impl FnOnce(&mut Counters)) {
+    let _ = COUNTERS.try_with(|c| {
+        let mut counters = c.get();
+        f(&mut counters);
+        counters.peak = counters.peak.max(counters.live);
+        c.set(counters);
+    });
+}
+
+fn counters() -> Counters {
+    COUNTERS.with(Cell::get)
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let p = self.inner.alloc(layout);
+        if !p.is_null() {
+            record(|c| {
+                c.allocs += 1;
+                c.allocated += layout.size();
+                c.live += layout.size() as isize;
+            });
+        }
+        p
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let p = self.inner.alloc_zeroed(layout);
+        if !p.is_null() {
+            record(|c| {
+                c.allocs += 1;
+                c.allocated += layout.size();
+                c.live += layout.size() as isize;
+            });
+        }
+        p
+    }
+
+    unsafe fn dealloc(&self, p: *mut u8, layout: Layout) {
+        self.inner.dealloc(p, layout);
+        record(|c| {
+            c.deallocs += 1;
+            c.freed += layout.size();
+            c.live -= layout.size() as isize;
+        });
+    }
+
+    // The reallocation is counted as an allocation of the new block and a deallocation of the old.
+    unsafe fn realloc(&self, p: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let q = self.inner.realloc(p, layout, new_size);
+        if !q.is_null() {
+            record(|c| {
+                c.allocs += 1;
+                c.deallocs += 1;
+                c.allocated += new_size;
+                c.freed += layout.size();
+                c.live += new_size as isize - layout.size() as isize;
+            });
+        }
+        q
+    }
+}
+
+/// The allocations of the current thread in a scope.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The allocations, including the reallocations.
+    pub allocs: usize,
+    /// The deallocations, including the reallocations.
+    pub deallocs: usize,
+    /// The bytes allocated.
+    pub allocated: usize,
+    /// The bytes freed.
+    pub freed: usize,
+    /// The most bytes alive at the same time, over the bytes alive at the start of the scope.
+    pub peak: usize,
+}
+
+impl Stats {
+    /// The bytes allocated but not freed yet.
+    pub fn bytes(&self) -> usize {
+        self.allocated.saturating_sub(self.freed)
+    }
+
+    /// The blocks allocated but not freed yet.
+    pub fn blocks(&self) -> usize {
+        self.allocs.saturating_sub(self.deallocs)
+    }
+}
+
+/// The scope of the measurement, which counts the allocations of the current thread since it's
+/// created. The scopes can be nested, but the peak of the outer one is only right again after the
+/// inner one is dropped.
+pub struct Scope {
+    start: Counters,
+    outer_peak: isize,
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        let start = counters();
+        COUNTERS.with(|c| {
+            let mut counters = c.get();
+            counters.peak = counters.live;
+            c.set(counters);
+        });
+        Scope {
+            start,
+            outer_peak: start.peak,
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        let now = counters();
+        Stats {
+            allocs: now.allocs - self.start.allocs,
+            deallocs: now.deallocs - self.start.deallocs,
+            allocated: now.allocated - self.start.allocated,
+            freed: now.freed - self.start.freed,
+            peak: (now.peak - self.start.live).max(0) as usize,
+        }
+    }
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let outer_peak = self.outer_peak;
+        record(|c| c.peak = c.peak.max(outer_peak));
+    }
+}
+
+/// Run `f` and count its allocations.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Stats) {
+    let scope = Scope::new();
+    let res = f();
+    (res, scope.stats())
+}
+
+/// Whether the counting allocator is the global allocator, otherwise all the stats are zero.
+pub fn is_installed() -> bool {
+    let (_, stats) = measure(|| drop(black_box(Box::new(0u8))));
+    stats.allocs > 0
+}
+
+/// The heap memory held by a container of `entries` entries.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryUsage {
+    pub name: &'static str,
+    pub entries: usize,
+    /// The bytes held by the container after it's built.
+    pub bytes: usize,
+    /// The allocations to build it, including the reallocations.
+    pub allocs: usize,
+    /// The most bytes alive while building it.
+    pub peak: usize,
+    /// The bytes still alive after the container is dropped.
+    pub leaked: usize,
+}
+
+impl MemoryUsage {
+    /// Build the container, and measure it before and after it's dropped.
+    pub fn measure<T>(name: &'static str, entries: usize, build: impl FnOnce() -> T) -> Self {
+        let scope = Scope::new();
+        let container = black_box(build());
+        let built = scope.stats();
+        drop(container);
+        MemoryUsage {
+            name,
+            entries,
+            bytes: built.bytes(),
+            allocs: built.allocs,
+            peak: built.peak,
+            leaked: scope.stats().bytes(),
+        }
+    }
+
+    pub fn bytes_per_entry(&self) -> f64 {
+        self.bytes as f64 / self.entries.max(1) as f64
+    }
+}
+
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<26} {:>8} {:>10} {:>8.1} {:>8} {:>10} {:>8}",
+            self.name,
+            self.entries,
+            self.bytes,
+            self.bytes_per_entry(),
+            self.allocs,
+            self.peak,
+            self.leaked
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    pub fn test_scope() {
+        assert!(is_installed());
+
+        let scope = Scope::new();
+        let v: Vec<u64> = Vec::with_capacity(100);
+        let stats = scope.stats();
+        assert_eq!(stats.allocs, 1);
+        assert_eq!(stats.bytes(), 800);
+        assert_eq!(stats.peak, 800);
+
+        drop(v);
+        let stats = scope.stats();
+        assert_eq!((stats.allocs, stats.deallocs), (1, 1));
+        assert_eq!((stats.allocated, stats.freed), (800, 800));
+        assert_eq!((stats.bytes(), stats.blocks()), (0, 0));
+        assert_eq!(stats.peak, 800);
+    }
+
+    #[test]
+    pub fn test_realloc() {
+        let (v, stats) = measure(|| {
+            let mut v: Vec<u8> = Vec::with_capacity(8);
+            v.extend_from_slice(&[0; 16]);
+            v
+        });
+        // The growth is a reallocation, which frees the old block.
+        assert_eq!(stats.allocs, 2);
+        assert_eq!(stats.deallocs, 1);
+        assert_eq!(stats.bytes(), v.capacity());
+        assert_eq!(stats.blocks(), 1);
+    }
+
+    #[test]
+    pub fn test_nested_scope() {
+        let outer = Scope::new();
+        let big = vec![0u8; 1000];
+        drop(big);
+        let (_, inner) = measure(|| drop(vec![0u8; 10]));
+        assert_eq!(inner.peak, 10);
+        // The inner scope doesn't lower the peak of the outer one.
+        assert_eq!(outer.stats().peak, 1000);
+        assert_eq!(outer.stats().allocs, 2);
+    }
+
+    #[test]
+    pub fn test_other_threads() {
+        let scope = Scope::new();
+        thread::scope(|s| {
+            s.spawn(|| drop(black_box(vec![0u8; 1 << 20])));
+        });
+        // The allocations of the other threads are not counted, except the ones to spawn the
+        // thread itself.
+        assert!(scope.stats().peak < 1 << 20);
+    }
+}
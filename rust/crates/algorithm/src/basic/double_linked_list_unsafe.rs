@@ -20,6 +20,54 @@ struct Node<T> {
     elem: T,
 }
 
+/// The handle of a node, which keeps valid until the node is removed from its list. A node can be
+/// unlinked and linked again through its handle, even into another list, without reallocation.
+pub struct NodeHandle<T>(NonNull<Node<T>>);
+
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeHandle<T> {}
+
+impl<T> PartialEq for NodeHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> Eq for NodeHandle<T> {}
+
+impl<T> Debug for NodeHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("NodeHandle").field(&self.0).finish()
+    }
+}
+
+impl<T> NodeHandle<T> {
+    /// The element of the node, which is borrowed for as long as the list.
+    ///
+    /// # Safety
+    ///
+    /// The node must belong to `_list`, i.e. it's neither removed from it nor moved into another
+    /// list.
+    pub unsafe fn get(self, _list: &LinkedList<T>) -> &T {
+        &(*self.0.as_ptr()).elem
+    }
+
+    /// The mutable element of the node, which is borrowed for as long as the list.
+    ///
+    /// # Safety
+    ///
+    /// The node must belong to `_list`, i.e. it's neither removed from it nor moved into another
+    /// list.
+    pub unsafe fn get_mut(self, _list: &mut LinkedList<T>) -> &mut T {
+        &mut (*self.0.as_ptr()).elem
+    }
+}
+
 pub struct Iter<'a, T> {
     front: Link<T>,
     back: Link<T>,
@@ -44,6 +92,24 @@ pub struct CursorMut<'a, T> {
     index: Option<usize>,
 }
 
+pub struct Cursor<'a, T> {
+    list: &'a LinkedList<T>,
+    cur: Link<T>,
+    index: Option<usize>,
+}
+
+pub struct DrainFilter<'a, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    list: &'a mut LinkedList<T>,
+    // The next node to be checked by the filter.
+    next: Link<T>,
+    filter: F,
+    // Whether the filter has panicked, then it's not called again while dropping.
+    panicked: bool,
+}
+
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
         Self {
@@ -206,6 +272,231 @@ impl<T> LinkedList<T> {
             index: None,
         }
     }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        let (cur, index) = (self.front, self.front.map(|_| 0));
+        CursorMut {
+            list: self,
+            cur,
+            index,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let (cur, index) = (self.back, self.back.map(|_| self.len - 1));
+        CursorMut {
+            list: self,
+            cur,
+            index,
+        }
+    }
+
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self,
+            cur: None,
+            index: None,
+        }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self,
+            cur: self.front,
+            index: self.front.map(|_| 0),
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self,
+            cur: self.back,
+            index: self.back.map(|_| self.len - 1),
+        }
+    }
+
+    /// Move all the elements of `other` to the back of self in O(1), leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        let other = std::mem::take(other);
+        let mut cursor = self.cursor_mut();
+        cursor.splice_before(other);
+    }
+
+    /// Split the list into two at the given index. Returns everything after the given index,
+    /// including the index. Walks from whichever end is closer to `at`.
+    ///
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        assert!(at <= self.len, "Cannot split off at a nonexistent index");
+        if at == 0 {
+            return std::mem::take(self);
+        }
+        if at == self.len {
+            return Self::new();
+        }
+
+        let mut cursor;
+        if at - 1 <= self.len - 1 - (at - 1) {
+            cursor = self.cursor_front_mut();
+            for _ in 0..at - 1 {
+                cursor.move_next();
+            }
+        } else {
+            let steps = self.len - at;
+            cursor = self.cursor_back_mut();
+            for _ in 0..steps {
+                cursor.move_prev();
+            }
+        }
+        cursor.split_after()
+    }
+
+    /// Unlink the node from the list and return its element.
+    ///
+    /// SAFETY: the node must belong to this list.
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        self.detach(node);
+        Box::from_raw(node.as_ptr()).elem
+    }
+
+    /// Push the element to the front and return the handle of its node.
+    pub fn push_front_node(&mut self, elem: T) -> NodeHandle<T> {
+        self.push_front(elem);
+        NodeHandle(self.front.unwrap())
+    }
+
+    /// Push the element to the back and return the handle of its node.
+    pub fn push_back_node(&mut self, elem: T) -> NodeHandle<T> {
+        self.push_back(elem);
+        NodeHandle(self.back.unwrap())
+    }
+
+    pub fn front_node(&self) -> Option<NodeHandle<T>> {
+        self.front.map(NodeHandle)
+    }
+
+    pub fn back_node(&self) -> Option<NodeHandle<T>> {
+        self.back.map(NodeHandle)
+    }
+
+    /// The handle of the node after `node`, or None if it's the back.
+    ///
+    /// # Safety
+    ///
+    /// The node must belong to this list.
+    pub unsafe fn next_node(&self, node: NodeHandle<T>) -> Option<NodeHandle<T>> {
+        (*node.0.as_ptr()).back.map(NodeHandle)
+    }
+
+    /// Insert the element after `at` and return the handle of its node.
+    ///
+    /// # Safety
+    ///
+    /// `at` must belong to this list.
+    pub unsafe fn insert_after_node(&mut self, at: NodeHandle<T>, elem: T) -> NodeHandle<T> {
+        let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+            front: None,
+            back: None,
+            elem,
+        })));
+        self.link_after(at.0, new);
+        NodeHandle(new)
+    }
+
+    /// Remove the node from the list and return its element, the handle is dangling after this.
+    ///
+    /// # Safety
+    ///
+    /// The node must belong to this list, and the handle must not be used any more.
+    pub unsafe fn remove_node(&mut self, node: NodeHandle<T>) -> T {
+        self.unlink(node.0)
+    }
+
+    /// Move the node to the front of the list in O(1).
+    ///
+    /// # Safety
+    ///
+    /// The node must belong to this list.
+    pub unsafe fn move_node_to_front(&mut self, node: NodeHandle<T>) {
+        if self.front != Some(node.0) {
+            self.detach(node.0);
+            self.link_front(node.0);
+        }
+    }
+
+    /// Move the node to the front of `other` in O(1), the handle keeps valid and belongs to `other`
+    /// after this.
+    ///
+    /// # Safety
+    ///
+    /// The node must belong to this list.
+    pub unsafe fn move_node_to_front_of(&mut self, node: NodeHandle<T>, other: &mut Self) {
+        self.detach(node.0);
+        other.link_front(node.0);
+    }
+
+    // SAFETY: the node must belong to this list. It's detached but not freed.
+    unsafe fn detach(&mut self, node: NonNull<Node<T>>) {
+        let prev = (*node.as_ptr()).front.take();
+        let next = (*node.as_ptr()).back.take();
+        match prev {
+            Some(prev) => (*prev.as_ptr()).back = next,
+            None => self.front = next,
+        }
+        match next {
+            Some(next) => (*next.as_ptr()).front = prev,
+            None => self.back = prev,
+        }
+        self.len -= 1;
+    }
+
+    // SAFETY: the node must be detached.
+    unsafe fn link_front(&mut self, node: NonNull<Node<T>>) {
+        (*node.as_ptr()).back = self.front;
+        match self.front {
+            Some(front) => (*front.as_ptr()).front = Some(node),
+            None => self.back = Some(node),
+        }
+        self.front = Some(node);
+        self.len += 1;
+    }
+
+    // SAFETY: `at` must belong to this list, and the node must be detached.
+    unsafe fn link_after(&mut self, at: NonNull<Node<T>>, node: NonNull<Node<T>>) {
+        let next = (*at.as_ptr()).back;
+        (*node.as_ptr()).front = Some(at);
+        (*node.as_ptr()).back = next;
+        (*at.as_ptr()).back = Some(node);
+        match next {
+            Some(next) => (*next.as_ptr()).front = Some(node),
+            None => self.back = Some(node),
+        }
+        self.len += 1;
+    }
+
+    /// Returns an iterator which removes and yields all the elements matching the filter. The
+    /// elements which are not yielded yet when the iterator is dropped are still removed if they
+    /// match the filter.
+    pub fn drain_filter<F>(&mut self, filter: F) -> DrainFilter<'_, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let next = self.front;
+        DrainFilter {
+            list: self,
+            next,
+            filter,
+            panicked: false,
+        }
+    }
+
+    /// Retain only the elements specified by the predicate, in the original order.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.drain_filter(|elem| !f(elem)).for_each(drop);
+    }
 }
 
 impl<T> Drop for LinkedList<T> {
@@ -421,11 +712,139 @@ impl<T> ExactSizeIterator for IntoIter<T> {
     }
 }
 
+impl<'a, T, F> Iterator for DrainFilter<'a, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.next {
+            unsafe {
+                self.next = (*node.as_ptr()).back;
+                self.panicked = true;
+                let matched = (self.filter)(&mut (*node.as_ptr()).elem);
+                self.panicked = false;
+                if matched {
+                    return Some(self.list.unlink(node));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<'a, T, F> Drop for DrainFilter<'a, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    fn drop(&mut self) {
+        // Calling the filter again after it panicked would abort by the double panic, so the rest
+        // of the elements are just kept.
+        if !self.panicked {
+            self.for_each(drop);
+        }
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                self.cur = (*cur.as_ptr()).back;
+                self.index = self.cur.map(|_| self.index.unwrap() + 1);
+            }
+        } else if !self.list.is_empty() {
+            self.cur = self.list.front;
+            self.index = Some(0);
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                self.cur = (*cur.as_ptr()).front;
+                self.index = self.cur.map(|_| self.index.unwrap() - 1);
+            }
+        } else if !self.list.is_empty() {
+            self.cur = self.list.back;
+            self.index = Some(self.list.len - 1);
+        }
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        unsafe { self.cur.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        unsafe {
+            let next = match self.cur {
+                Some(cur) => (*cur.as_ptr()).back,
+                None => self.list.front,
+            };
+            next.map(|node| &(*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        unsafe {
+            let prev = match self.cur {
+                Some(cur) => (*cur.as_ptr()).front,
+                None => self.list.back,
+            };
+            prev.map(|node| &(*node.as_ptr()).elem)
+        }
+    }
+}
+
 impl<'a, T> CursorMut<'a, T> {
     pub fn index(&self) -> Option<usize> {
         self.index
     }
 
+    /// Returns a read-only cursor at the same position, which borrows this cursor.
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self.list,
+            cur: self.cur,
+            index: self.index,
+        }
+    }
+
+    /// Remove the current element and move the cursor to the next one (or the ghost if it was the
+    /// back). Returns None if the cursor is on the ghost.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let cur = self.cur?;
+        unsafe {
+            self.cur = (*cur.as_ptr()).back;
+            if self.cur.is_none() {
+                // We just removed the back, so we are at the ghost now.
+                self.index = None;
+            }
+            Some(self.list.unlink(cur))
+        }
+    }
+
+    /// Insert the element before the current one. If the cursor is on the ghost, the element
+    /// becomes the new back.
+    pub fn insert_before(&mut self, elem: T) {
+        let mut input = LinkedList::new();
+        input.push_back(elem);
+        self.splice_before(input);
+    }
+
+    /// Insert the element after the current one. If the cursor is on the ghost, the element
+    /// becomes the new front.
+    pub fn insert_after(&mut self, elem: T) {
+        let mut input = LinkedList::new();
+        input.push_back(elem);
+        self.splice_after(input);
+    }
+
     pub fn move_next(&mut self) {
         if let Some(cur) = self.cur {
             unsafe {
@@ -759,6 +1178,12 @@ unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
 unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
 unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
 
+unsafe impl<'a, T: Sync> Send for Cursor<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Cursor<'a, T> {}
+
+unsafe impl<'a, T: Send> Send for CursorMut<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for CursorMut<'a, T> {}
+
 #[allow(dead_code)]
 fn assert_properties() {
     fn is_send<T: Send>() {}
@@ -786,12 +1211,23 @@ fn assert_properties() {
         x
     }
 
-    /// ```compile_fail,E0308
-    /// use linked_list::IterMut;
+    /// ```compile_fail
+    /// use hello_world::basic::double_linked_list_unsafe::IterMut;
     ///
     /// fn iter_mut_covariant<'i, 'a, T>(x: IterMut<'i, &'static T>) -> IterMut<'i, &'a T> { x }
     /// ```
     fn iter_mut_invariant() {}
+
+    /// ```compile_fail,E0505
+    /// use hello_world::basic::double_linked_list_unsafe::LinkedList;
+    ///
+    /// let mut list = LinkedList::new();
+    /// let node = list.push_back_node(1);
+    /// let elem = unsafe { node.get(&list) };
+    /// drop(list);
+    /// assert_eq!(*elem, 1);
+    /// ```
+    fn node_handle_borrows_list() {}
 }
 
 #[cfg(test)]
@@ -1131,7 +1567,6 @@ mod test {
             &[10, 7, 1, 8, 2, 3, 4, 5, 6, 9]
         );
 
-        /* remove_current not impl'd
         let mut cursor = m.cursor_mut();
         cursor.move_next();
         cursor.move_prev();
@@ -1146,8 +1581,10 @@ mod test {
         cursor.move_next();
         assert_eq!(cursor.remove_current(), Some(10));
         check_links(&m);
-        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 8, 2, 3, 4, 5, 6]);
-        */
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[1, 8, 2, 3, 4, 5, 6]
+        );
 
         let mut m: LinkedList<u32> = LinkedList::new();
         m.extend([1, 8, 2, 3, 4, 5, 6]);
@@ -1197,4 +1634,239 @@ mod test {
 
         assert_eq!(from_front, re_reved);
     }
+
+    #[test]
+    fn test_cursor_remove_insert() {
+        let mut m: LinkedList<u32> = (1..=5).collect();
+
+        let mut cursor = m.cursor_front_mut();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.index(), Some(0));
+
+        cursor.insert_before(10);
+        cursor.insert_after(20);
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.index(), Some(1));
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[10, 2, 20, 3, 4, 5]);
+
+        let mut cursor = m.cursor_back_mut();
+        assert_eq!(cursor.index(), Some(5));
+        assert_eq!(cursor.remove_current(), Some(5));
+        // Removing the back moves the cursor to the ghost.
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_current(), None);
+
+        // On the ghost, insert_before pushes back and insert_after pushes front.
+        cursor.insert_before(30);
+        cursor.insert_after(40);
+        assert_eq!(cursor.index(), None);
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[40, 10, 2, 20, 3, 4, 30]
+        );
+
+        let mut cursor = m.cursor_front_mut();
+        while cursor.remove_current().is_some() {}
+        assert!(m.is_empty());
+        assert_eq!(m.front(), None);
+        assert_eq!(m.back(), None);
+
+        let mut cursor = m.cursor_front_mut();
+        assert_eq!(cursor.index(), None);
+        cursor.insert_after(1);
+        cursor.insert_before(2);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_cursor_read_only() {
+        let m: LinkedList<u32> = (1..=3).collect();
+
+        let mut cursor = m.cursor();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&1));
+        assert_eq!(cursor.peek_prev(), Some(&3));
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&1));
+        assert_eq!(cursor.index(), Some(0));
+
+        // Two read-only cursors can live at the same time.
+        let mut back = m.cursor_back();
+        assert_eq!(back.current(), Some(&3));
+        assert_eq!(back.index(), Some(2));
+        back.move_prev();
+        assert_eq!(back.current(), Some(&2));
+        assert_eq!(back.peek_prev(), Some(&1));
+        back.move_next();
+        back.move_next();
+        assert_eq!(back.current(), None);
+        assert_eq!(back.index(), None);
+
+        let elem = cursor.current();
+        drop(cursor);
+        assert_eq!(elem, Some(&1));
+
+        let empty: LinkedList<u32> = LinkedList::new();
+        let mut cursor = empty.cursor_front();
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+
+        let mut m: LinkedList<u32> = (1..=3).collect();
+        let mut cursor = m.cursor_mut();
+        cursor.move_prev();
+        assert_eq!(cursor.as_cursor().current(), Some(&3));
+        assert_eq!(cursor.as_cursor().index(), Some(2));
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a: LinkedList<u32> = (1..=3).collect();
+        let mut b: LinkedList<u32> = (4..=6).collect();
+        a.append(&mut b);
+        check_links(&a);
+        assert!(b.is_empty());
+        assert_eq!(a.len(), 6);
+        assert_eq!(a.iter().cloned().collect::<Vec<_>>(), &[1, 2, 3, 4, 5, 6]);
+
+        a.append(&mut b);
+        assert_eq!(a.len(), 6);
+        b.append(&mut a);
+        assert!(a.is_empty());
+        assert_eq!(b.iter().cloned().collect::<Vec<_>>(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(b.back(), Some(&6));
+    }
+
+    #[test]
+    fn test_split_off() {
+        for len in 0..6 {
+            for at in 0..=len {
+                let mut m: LinkedList<u32> = (0..len).collect();
+                let tail = m.split_off(at as usize);
+                check_links(&m);
+                check_links(&tail);
+                assert_eq!(m.len(), at as usize);
+                assert_eq!(tail.len(), (len - at) as usize);
+                assert_eq!(
+                    m.into_iter().collect::<Vec<_>>(),
+                    (0..at).collect::<Vec<_>>()
+                );
+                assert_eq!(
+                    tail.into_iter().collect::<Vec<_>>(),
+                    (at..len).collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_off_out_of_range() {
+        let mut m: LinkedList<u32> = (0..3).collect();
+        m.split_off(4);
+    }
+
+    #[test]
+    fn test_retain_drain_filter() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        m.retain(|x| x % 3 != 0);
+        check_links(&m);
+        assert_eq!(m.len(), 6);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 2, 4, 5, 7, 8]);
+
+        let evens: Vec<_> = m.drain_filter(|x| *x % 2 == 0).collect();
+        assert_eq!(evens, &[2, 4, 8]);
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 5, 7]);
+
+        // The filter can mutate the elements which are kept.
+        let mut it = m.drain_filter(|x| {
+            *x *= 10;
+            *x > 60
+        });
+        assert_eq!(it.next(), Some(70));
+        assert_eq!(it.next(), None);
+        drop(it);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[10, 50]);
+
+        // Dropping the iterator early still removes all the matching elements.
+        let mut m: LinkedList<String> = (0..10).map(|i| i.to_string()).collect();
+        let mut it = m.drain_filter(|s| s != "5");
+        assert_eq!(it.next(), Some(String::from("0")));
+        drop(it);
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &["5"]);
+
+        m.retain(|_| false);
+        assert!(m.is_empty());
+        assert_eq!(m.back(), None);
+    }
+
+    #[test]
+    fn test_drain_filter_panic() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        // The iterator is dropped while unwinding, and the filter would panic again on the rest of
+        // the elements if it were called.
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.drain_filter(|x| {
+                assert!(*x < 4);
+                *x % 2 == 0
+            })
+            .for_each(drop);
+        }));
+        assert!(res.is_err());
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[1, 3, 4, 5, 6, 7, 8, 9]
+        );
+    }
+
+    #[test]
+    fn test_node_handles() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let one = m.push_back_node(1);
+        let two = m.push_back_node(2);
+        let zero = m.push_front_node(0);
+        assert_eq!(m.front_node(), Some(zero));
+        assert_eq!(m.back_node(), Some(two));
+
+        unsafe {
+            assert_eq!(m.next_node(zero), Some(one));
+            assert_eq!(m.next_node(two), None);
+            let three = m.insert_after_node(two, 3);
+            let half = m.insert_after_node(zero, 5);
+            *half.get_mut(&mut m) = 50;
+            check_links(&m);
+            assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[0, 50, 1, 2, 3]);
+
+            m.move_node_to_front(two);
+            m.move_node_to_front(two);
+            check_links(&m);
+            assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[2, 0, 50, 1, 3]);
+
+            assert_eq!(m.remove_node(half), 50);
+            assert_eq!(m.remove_node(three), 3);
+            check_links(&m);
+            assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[2, 0, 1]);
+
+            // The handles keep valid when their nodes are moved into another list.
+            let mut n = LinkedList::new();
+            m.move_node_to_front_of(one, &mut n);
+            m.move_node_to_front_of(two, &mut n);
+            check_links(&m);
+            check_links(&n);
+            assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[0]);
+            assert_eq!(n.iter().cloned().collect::<Vec<_>>(), &[2, 1]);
+            assert_eq!(*one.get(&n), 1);
+            assert_eq!(n.remove_node(one), 1);
+            assert_eq!(n.back_node(), Some(two));
+        }
+    }
 }
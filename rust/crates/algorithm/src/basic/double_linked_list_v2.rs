@@ -51,7 +51,7 @@ impl<T> Node<T> {
 }
 
 // The doubly-linked list with pointers to the first and last nodes in the list.
-struct List<T> {
+pub struct List<T> {
     first: Option<Rc<RefCell<Node<T>>>>,
     last: Option<Rc<RefCell<Node<T>>>>,
 }
@@ -0,0 +1,244 @@
+/**
+ * The gap buffer, which is the classic data structure of text editors. The elements are kept in
+ * one array with a gap at the cursor, so inserting and deleting at the cursor are O(1), and moving
+ * the cursor only moves the elements between the old and the new positions.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Gap_buffer
+ */
+use std::fmt::{self, Debug};
+use std::iter::FromIterator;
+use std::mem::MaybeUninit;
+use std::ptr;
+
+pub struct GapBuffer<T> {
+    buf: Box<[MaybeUninit<T>]>,
+    // The gap is buf[start..end], and the cursor is at `start`.
+    start: usize,
+    end: usize,
+}
+
+impl<T> GapBuffer<T> {
+    pub fn new() -> Self {
+        GapBuffer {
+            buf: Box::new([]),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len() - (self.end - self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The position of the cursor, which is the number of the elements before it.
+    pub fn cursor(&self) -> usize {
+        self.start
+    }
+
+    // The physical index of the i-th element.
+    fn index(&self, i: usize) -> usize {
+        if i < self.start {
+            i
+        } else {
+            i + (self.end - self.start)
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len() {
+            return None;
+        }
+        Some(unsafe { self.buf[self.index(i)].assume_init_ref() })
+    }
+
+    /// Move the cursor to `pos`, the elements in between are moved to the other side of the gap.
+    pub fn move_to(&mut self, pos: usize) {
+        assert!(pos <= self.len(), "The cursor is out of bounds");
+        let base = self.buf.as_mut_ptr();
+        unsafe {
+            if pos < self.start {
+                let n = self.start - pos;
+                ptr::copy(base.add(pos), base.add(self.end - n), n);
+                self.start -= n;
+                self.end -= n;
+            } else if pos > self.start {
+                let n = pos - self.start;
+                ptr::copy(base.add(self.end), base.add(self.start), n);
+                self.start += n;
+                self.end += n;
+            }
+        }
+    }
+
+    // Double the buffer, the gap keeps at the cursor and takes all the new space.
+    fn grow(&mut self) {
+        let old_cap = self.buf.len();
+        let new_cap = (old_cap * 2).max(8);
+        let mut buf: Box<[MaybeUninit<T>]> = (0..new_cap).map(|_| MaybeUninit::uninit()).collect();
+        let tail = old_cap - self.end;
+        unsafe {
+            ptr::copy_nonoverlapping(self.buf.as_ptr(), buf.as_mut_ptr(), self.start);
+            ptr::copy_nonoverlapping(
+                self.buf.as_ptr().add(self.end),
+                buf.as_mut_ptr().add(new_cap - tail),
+                tail,
+            );
+        }
+        self.buf = buf;
+        self.end = new_cap - tail;
+    }
+
+    /// Insert the element before the cursor, and the cursor moves after it.
+    pub fn insert(&mut self, elem: T) {
+        if self.start == self.end {
+            self.grow();
+        }
+        self.buf[self.start].write(elem);
+        self.start += 1;
+    }
+
+    /// Delete the element before the cursor, just like the backspace key.
+    pub fn delete_before(&mut self) -> Option<T> {
+        if self.start == 0 {
+            return None;
+        }
+        self.start -= 1;
+        Some(unsafe { self.buf[self.start].assume_init_read() })
+    }
+
+    /// Delete the element after the cursor, just like the delete key.
+    pub fn delete_after(&mut self) -> Option<T> {
+        if self.end == self.buf.len() {
+            return None;
+        }
+        self.end += 1;
+        Some(unsafe { self.buf[self.end - 1].assume_init_read() })
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        let (before, after) = self.buf.split_at(self.end);
+        before[..self.start]
+            .iter()
+            .chain(after.iter())
+            .map(|e| unsafe { e.assume_init_ref() })
+    }
+}
+
+impl<T> Drop for GapBuffer<T> {
+    fn drop(&mut self) {
+        let (before, after) = self.buf.split_at_mut(self.end);
+        for e in before[..self.start].iter_mut().chain(after.iter_mut()) {
+            unsafe { e.assume_init_drop() };
+        }
+    }
+}
+
+impl<T> Default for GapBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for GapBuffer<T> {
+    /// The cursor is at the end.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut buf = GapBuffer::new();
+        for elem in iter {
+            buf.insert(elem);
+        }
+        buf
+    }
+}
+
+impl<T: Debug> Debug for GapBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl fmt::Display for GapBuffer<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.iter() {
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+
+    #[test]
+    pub fn edit_text() {
+        let mut text: GapBuffer<char> = "hello world".chars().collect();
+        assert_eq!(text.cursor(), 11);
+
+        text.move_to(5);
+        text.insert(',');
+        assert_eq!(text.to_string(), "hello, world");
+
+        assert_eq!(text.delete_after(), Some(' '));
+        assert_eq!(text.delete_before(), Some(','));
+        "~~".chars().for_each(|c| text.insert(c));
+        assert_eq!(text.to_string(), "hello~~world");
+
+        text.move_to(0);
+        assert_eq!(text.delete_before(), None);
+        text.insert('>');
+        text.move_to(text.len());
+        assert_eq!(text.delete_after(), None);
+        text.insert('!');
+        assert_eq!(text.to_string(), ">hello~~world!");
+        assert_eq!(text.get(1), Some(&'h'));
+        assert_eq!(text.get(14), None);
+        assert_eq!(text.iter().next_back(), Some(&'!'));
+    }
+
+    #[test]
+    pub fn random_edits() {
+        let mut buf = GapBuffer::new();
+        let mut expected: Vec<String> = Vec::new();
+        let mut cursor = 0;
+        for _ in 0..20000 {
+            match rand::gen_u32() % 4 {
+                0 | 1 => {
+                    let s = rand::gen_u32().to_string();
+                    buf.insert(s.clone());
+                    expected.insert(cursor, s);
+                    cursor += 1;
+                }
+                2 => {
+                    if rand::gen_bool() {
+                        let e = if cursor > 0 {
+                            cursor -= 1;
+                            Some(expected.remove(cursor))
+                        } else {
+                            None
+                        };
+                        assert_eq!(buf.delete_before(), e);
+                    } else {
+                        let e = if cursor < expected.len() {
+                            Some(expected.remove(cursor))
+                        } else {
+                            None
+                        };
+                        assert_eq!(buf.delete_after(), e);
+                    }
+                }
+                _ => {
+                    cursor = rand::gen_u32() as usize % (expected.len() + 1);
+                    buf.move_to(cursor);
+                }
+            }
+            assert_eq!(buf.len(), expected.len());
+            assert_eq!(buf.cursor(), cursor);
+        }
+        assert!(buf.iter().eq(expected.iter()));
+    }
+}
@@ -1,3 +1,8 @@
+use crate::basic::map::Map;
+use std::iter::Flatten;
+use std::mem;
+use std::slice;
+
 pub trait Hash: Eq {
     fn hash(&self) -> i64;
 }
@@ -12,6 +17,10 @@ struct Tuple<K, V> {
     v: V,
 }
 
+pub struct Iter<'a, K, V> {
+    tuples: Flatten<slice::Iter<'a, Vec<Tuple<K, V>>>>,
+}
+
 fn index<K: Hash>(bucket_num: usize, key: &K) -> usize {
     let hash_code = key.hash();
     let num_i64 = bucket_num as i64;
@@ -87,11 +96,15 @@ where
     }
 
     pub fn get(&self, key: K) -> Option<&V> {
-        let bucket_id = index(self.buckets.capacity(), &key);
+        self.find(&key)
+    }
+
+    fn find(&self, key: &K) -> Option<&V> {
+        let bucket_id = index(self.buckets.capacity(), key);
         let bucket = &self.buckets[bucket_id];
 
         for t in bucket {
-            if t.k == key {
+            if t.k == *key {
                 return Some(&t.v);
             }
         }
@@ -99,12 +112,16 @@ where
     }
 
     pub fn delete(&mut self, key: K) -> Option<V> {
-        let bucket_id = index(self.buckets.capacity(), &key);
+        self.delete_ref(&key)
+    }
+
+    fn delete_ref(&mut self, key: &K) -> Option<V> {
+        let bucket_id = index(self.buckets.capacity(), key);
         let bucket = &mut self.buckets[bucket_id];
 
         let mut key_pos: Option<usize> = None;
         for (pos, tuple) in bucket.iter().enumerate() {
-            if tuple.k == key {
+            if tuple.k == *key {
                 key_pos = Some(pos);
             }
         }
@@ -122,6 +139,21 @@ where
     pub fn is_empty(&self) -> bool {
         self.size == 0
     }
+
+    /// Visit the pairs bucket by bucket.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            tuples: self.buckets.iter().flatten(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tuples.next().map(|t| (&t.k, &t.v))
+    }
 }
 
 impl Hash for String {
@@ -140,6 +172,53 @@ impl Hash for i64 {
     }
 }
 
+impl Hash for i32 {
+    fn hash(&self) -> i64 {
+        *self as i64
+    }
+}
+
+impl<K, V> Map<K, V> for HashTable<K, V>
+where
+    K: Hash,
+    V: Eq,
+{
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let bucket_id = index(self.buckets.capacity(), &k);
+        if let Some(old) = self.get_mut(bucket_id, &k) {
+            return Some(mem::replace(old, v));
+        }
+        self.put(k, v);
+        None
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.delete_ref(k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.find(k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let bucket_id = index(self.buckets.capacity(), k);
+        HashTable::get_mut(self, bucket_id, k)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        HashTable::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,3 +1,8 @@
+use crate::basic::map::Map;
+use std::borrow::Borrow;
+use std::iter;
+use std::mem;
+
 pub trait Hash: Eq {
     fn hash(&self) -> i64;
 }
@@ -21,7 +26,7 @@ pub struct Iter<'a, K: Hash, V> {
     cur: &'a Ptr<K, V>, // The current iterator.
 }
 
-fn to_bucket_idx<K: Hash>(bucket_num: usize, key: &K) -> usize {
+fn to_bucket_idx<K: Hash + ?Sized>(bucket_num: usize, key: &K) -> usize {
     let m = bucket_num as i64;
     ((key.hash() % m + m) % m) as usize
 }
@@ -85,7 +90,6 @@ where
                         val,
                         next: node.next.take(),
                     });
-                    self.size += 1;
                     break;
                 }
                 Some(node) => {
@@ -100,10 +104,20 @@ where
     }
 
     pub fn get(&self, key: K) -> Option<&V> {
-        let h = to_bucket_idx(self.bucket_num(), &key);
+        self.find(&key)
+    }
+
+    /// Get the value by the borrowed form of the key, e.g. `&[u8]` for `Vec<u8>`, whose hash must
+    /// be the same as the key's.
+    pub fn find<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        let h = to_bucket_idx(self.bucket_num(), key);
         let mut ptr = &self.buckets[h];
         while let Some(node) = ptr {
-            if node.key == key {
+            if node.key.borrow() == key {
                 return Some(&node.val);
             }
             ptr = &node.next;
@@ -112,12 +126,20 @@ where
     }
 
     pub fn get_mut_ptr(&mut self, key: K) -> Option<&mut Ptr<K, V>> {
-        let h = to_bucket_idx(self.buckets.capacity(), &key);
+        self.find_ptr(&key)
+    }
+
+    fn find_ptr<Q>(&mut self, key: &Q) -> Option<&mut Ptr<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        let h = to_bucket_idx(self.buckets.capacity(), key);
         let mut cur = &mut self.buckets[h];
         loop {
             match cur {
                 None => return None,
-                Some(node) if node.key == key => return Some(cur),
+                Some(node) if node.key.borrow() == key => return Some(cur),
                 Some(node) => {
                     cur = &mut node.next;
                 }
@@ -126,8 +148,17 @@ where
     }
 
     pub fn remove(&mut self, key: K) -> Option<V> {
+        self.remove_ref(&key)
+    }
+
+    /// Remove the key by its borrowed form like `find`.
+    pub fn remove_ref<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
         let mut ret: Option<V> = None;
-        let ptr = self.get_mut_ptr(key);
+        let ptr = self.find_ptr(key);
         ptr.map(|cur| {
             cur.take().map(|node| {
                 *cur = node.next;
@@ -154,11 +185,13 @@ where
     }
 }
 
-impl<'a, K, V> Iter<'a, K, V>
+impl<'a, K, V> Iterator for Iter<'a, K, V>
 where
     K: Hash,
 {
-    pub fn next(&mut self) -> Option<&'a Node<K, V>> {
+    type Item = &'a Node<K, V>;
+
+    fn next(&mut self) -> Option<&'a Node<K, V>> {
         loop {
             // Exhaust the current bucket.
             if let Some(node) = self.cur {
@@ -193,6 +226,61 @@ impl Hash for String {
     }
 }
 
+impl Hash for [u8] {
+    fn hash(&self) -> i64 {
+        let mut ret: i64 = 0;
+        for c in self {
+            ret = (ret << 5) + ret + (*c as i64);
+        }
+        ret
+    }
+}
+
+// The same hash as the slice, so that the map of `Vec<u8>` can be looked up by `&[u8]`.
+impl Hash for Vec<u8> {
+    fn hash(&self) -> i64 {
+        self.as_slice().hash()
+    }
+}
+
+impl<K: Hash, V> Map<K, V> for HashMap<K, V> {
+    type Iter<'a>
+        = iter::Map<Iter<'a, K, V>, fn(&'a Node<K, V>) -> (&'a K, &'a V)>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        if let Some(Some(node)) = self.find_ptr(&k) {
+            return Some(mem::replace(&mut node.val, v));
+        }
+        self.put(k, v);
+        None
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.remove_ref(k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.find(k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        match self.find_ptr(k) {
+            Some(Some(node)) => Some(&mut node.val),
+            _ => None,
+        }
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        HashMap::iter(self).map(|node| (&node.key, &node.val))
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,6 +293,10 @@ mod tests {
         map.put("DEF".to_string(), 2);
         map.put("HIG".to_string(), 3);
 
+        assert_eq!(map.size(), 3);
+
+        // Replacing the value doesn't change the size.
+        map.put("HIG".to_string(), 3);
         assert_eq!(map.size(), 3);
         assert_eq!(map.get("ABC".to_string()), Some(&1));
         assert_eq!(map.get("DEF".to_string()), Some(&2));
@@ -231,9 +323,8 @@ mod tests {
         }
 
         {
-            let mut iter = map.iter();
             let mut hit = vec![false; n as usize];
-            while let Some(node) = iter.next() {
+            for node in map.iter() {
                 let k = node.key as usize;
                 let v = node.val as usize;
 
@@ -251,9 +342,8 @@ mod tests {
         }
 
         {
-            let mut iter = map.iter();
             let mut hit = vec![false; 100];
-            while let Some(node) = iter.next() {
+            for node in map.iter() {
                 let k = node.key as usize;
                 let v = node.val as usize;
 
@@ -287,4 +377,17 @@ mod tests {
 
         println!("Time elapsed: {} millis", now.elapsed().as_millis());
     }
+
+    #[test]
+    pub fn test_borrowed_keys() {
+        let mut map: HashMap<Vec<u8>, i32> = HashMap::new();
+        for i in 0..100 {
+            map.put(format!("key-{}", i).into_bytes(), i);
+        }
+        assert_eq!(map.find(&b"key-42"[..]), Some(&42));
+        assert_eq!(map.find(&b"key-100"[..]), None);
+        assert_eq!(map.remove_ref(&b"key-42"[..]), Some(42));
+        assert_eq!(map.find(&b"key-42"[..]), None);
+        assert_eq!(map.size(), 99);
+    }
 }
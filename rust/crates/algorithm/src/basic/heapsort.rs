@@ -1,9 +1,12 @@
-fn _fixup(h: &mut [i32], len: usize) {
+use std::cmp::Ordering;
+
+// The heap keeps the element which is `less` than the others at the top.
+fn _fixup<T, F: FnMut(&T, &T) -> bool>(h: &mut [T], len: usize, less: &mut F) {
     let mut c = len; // child index.
     while c > 0 {
         // Parent index.
         let p = (c - 1) >> 1;
-        if h[c] < h[p] {
+        if less(&h[c], &h[p]) {
             h.swap(c, p);
             c = p;
         } else {
@@ -12,18 +15,18 @@ fn _fixup(h: &mut [i32], len: usize) {
     }
 }
 
-fn _fixdown(h: &mut [i32], len: usize) {
+fn _fixdown<T, F: FnMut(&T, &T) -> bool>(h: &mut [T], len: usize, less: &mut F) {
     if len > 1 {
         h.swap(0, len - 1);
 
         let mut p = 0 as usize;
         let mut c = (p << 1) + 1;
         while c < len - 1 {
-            if c + 1 < len - 1 && h[c + 1] < h[c] {
+            if c + 1 < len - 1 && less(&h[c + 1], &h[c]) {
                 c += 1;
             }
 
-            if h[c] < h[p] {
+            if less(&h[c], &h[p]) {
                 h.swap(c, p);
                 p = c;
                 c = (p << 1) + 1;
@@ -36,15 +39,30 @@ fn _fixdown(h: &mut [i32], len: usize) {
 
 pub fn sort(v: &mut [i32]) {
     let mut h = Vec::with_capacity(v.len());
+    let mut less = |a: &i32, b: &i32| a < b;
 
     for i in 0..v.len() {
         h.push(v[i]);
-        _fixup(&mut h, i);
+        _fixup(&mut h, i, &mut less);
     }
 
     for i in 0..v.len() {
         v[i] = h[0];
-        _fixdown(&mut h, v.len() - i);
+        _fixdown(&mut h, v.len() - i, &mut less);
+    }
+}
+
+/// Sort in place by the comparator, which isn't stable. The heap is built in the slice with the
+/// greatest element at the top, and `_fixdown` moves the top to the end of the heap.
+pub fn sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut cmp: F) {
+    let mut greater = |a: &T, b: &T| cmp(a, b) == Ordering::Greater;
+
+    for i in 0..v.len() {
+        _fixup(v, i, &mut greater);
+    }
+
+    for i in 0..v.len() {
+        _fixdown(v, v.len() - i, &mut greater);
     }
 }
 
@@ -74,4 +92,25 @@ mod tests {
         sort(&mut v);
         assert_eq!(v, vec![1, 2, 2, 3, 3, 4, 8]);
     }
+
+    #[test]
+    pub fn test_sort_by() {
+        let mut v: Vec<String> = vec![];
+        sort_by(&mut v, |a, b| a.cmp(b));
+        assert!(v.is_empty());
+
+        v = ["pear", "fig", "apple", "kiwi", "fig"]
+            .map(String::from)
+            .to_vec();
+        sort_by(&mut v, |a, b| a.cmp(b));
+        assert_eq!(v, ["apple", "fig", "fig", "kiwi", "pear"]);
+        sort_by(&mut v, |a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        assert_eq!(v, ["apple", "kiwi", "pear", "fig", "fig"]);
+
+        let mut v: Vec<i32> = (0..1000).map(|i| (i * 7919) % 1000 - 500).collect();
+        let mut expected = v.clone();
+        expected.sort();
+        sort_by(&mut v, |a, b| a.cmp(b));
+        assert_eq!(v, expected);
+    }
 }
@@ -1,7 +1,14 @@
+use std::cmp::Ordering;
+
 pub fn insert_sort<T: Ord>(v: &mut Vec<T>) {
+    insert_sort_by(v, |a, b| a.cmp(b));
+}
+
+/// Sort in place by the comparator, which is stable.
+pub fn insert_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut cmp: F) {
     for i in 1..v.len() {
         for k in (0..i).rev() {
-            if v[k] <= v[k + 1] {
+            if cmp(&v[k], &v[k + 1]) != Ordering::Greater {
                 break;
             } else {
                 v.swap(k, k + 1);
@@ -40,4 +47,13 @@ mod tests {
         insert_sort(&mut v);
         assert_eq!(v, vec![1, 2, 2, 2, 2, 3, 3, 10, 10, 10]);
     }
+
+    #[test]
+    pub fn sort_by_stable() {
+        let mut v = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        insert_sort_by(&mut v, |a, b| a.0.cmp(&b.0));
+        assert_eq!(v, vec![(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+        insert_sort_by(&mut v, |a, b| b.0.cmp(&a.0));
+        assert_eq!(v, vec![(2, 'a'), (2, 'c'), (1, 'b'), (1, 'd')]);
+    }
 }
@@ -66,6 +66,48 @@ fn str_substr(p: &str, s: &str) -> i32 {
     return substr(&p.as_bytes(), &s.as_bytes());
 }
 
+// The length of the longest proper prefix of `s[..=i]` which is also its suffix, for every `i`.
+// Unlike `next`, it's defined for the whole pattern, so the search can go on after a match.
+fn gen_prefix(s: &[u8]) -> Vec<usize> {
+    let mut prefix = vec![0; s.len()];
+    let mut j = 0;
+    for i in 1..s.len() {
+        while j > 0 && s[i] != s[j] {
+            j = prefix[j - 1];
+        }
+        if s[i] == s[j] {
+            j += 1;
+        }
+        prefix[i] = j;
+    }
+    prefix
+}
+
+/// The offsets of all the occurrences of the pattern in the text in the ascending order, including
+/// the overlapped ones. The empty pattern has no occurrence.
+pub fn find_all(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    if pattern.is_empty() {
+        return offsets;
+    }
+
+    let prefix = gen_prefix(pattern);
+    let mut j = 0;
+    for (i, &c) in text.iter().enumerate() {
+        while j > 0 && c != pattern[j] {
+            j = prefix[j - 1];
+        }
+        if c == pattern[j] {
+            j += 1;
+        }
+        if j == pattern.len() {
+            offsets.push(i + 1 - j);
+            j = prefix[j - 1];
+        }
+    }
+    offsets
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +143,33 @@ mod tests {
         assert_eq!(str_substr("abbaa", "babaaaaaaabbaa"), -1);
         assert_eq!(str_substr("aaaabbbabbbaaacccabacaaaabbaaaab", "baaaa"), 26);
     }
+
+    #[test]
+    pub fn test_gen_prefix() {
+        assert_eq!(gen_prefix(b""), vec![]);
+        assert_eq!(gen_prefix(b"aaaab"), vec![0, 1, 2, 3, 0]);
+        assert_eq!(gen_prefix(b"abaabcac"), vec![0, 0, 1, 1, 2, 0, 1, 0]);
+    }
+
+    #[test]
+    pub fn test_find_all() {
+        assert_eq!(find_all(b"aaaa", b"aa"), vec![0, 1, 2]);
+        assert_eq!(find_all(b"abababa", b"aba"), vec![0, 2, 4]);
+        assert_eq!(find_all(b"acabaabaabcacaabc", b"abaabc"), vec![5]);
+        assert_eq!(find_all(b"abc", b"abcd"), vec![]);
+        assert_eq!(find_all(b"abc", b""), vec![]);
+        assert_eq!(find_all(b"", b"a"), vec![]);
+        assert_eq!(find_all(b"a\0b\0a\0b", b"\0b"), vec![1, 5]);
+
+        // Compare with the naive search.
+        let text: Vec<u8> = (0..2000u32)
+            .map(|i| b"ab"[(i * i % 7 % 2) as usize])
+            .collect();
+        for pattern in [&b"a"[..], b"ab", b"abba", b"babab", b"aabaa"] {
+            let expected: Vec<usize> = (0..=text.len() - pattern.len())
+                .filter(|&i| &text[i..i + pattern.len()] == pattern)
+                .collect();
+            assert_eq!(find_all(&text, pattern), expected);
+        }
+    }
 }
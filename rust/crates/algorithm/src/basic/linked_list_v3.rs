@@ -1,54 +1,227 @@
+/**
+ * The persistent (immutable) singly linked list and the two-stack queue. Every update returns a new
+ * version which shares the unchanged nodes with the old one, so all the old versions stay intact.
+ * The nodes are shared by `Rc` by default, or by `Arc` to share the versions between threads.
+ *
+ * Please see: https://rust-unofficial.github.io/too-many-lists/third.html
+ * and Chris Okasaki, Purely Functional Data Structures, 5.2 Queues.
+ */
+use std::fmt::{self, Debug};
+use std::iter::FromIterator;
+use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::Arc;
 
-pub struct List<T> {
-    head: Option<Rc<Node<T>>>,
+/// The kind of the reference counted pointer which shares the nodes.
+pub trait SharedPointer {
+    type Ptr<T>: Deref<Target = T> + Clone;
+
+    fn new<T>(v: T) -> Self::Ptr<T>;
+    fn try_unwrap<T>(p: Self::Ptr<T>) -> Result<T, Self::Ptr<T>>;
+    fn ptr_eq<T>(a: &Self::Ptr<T>, b: &Self::Ptr<T>) -> bool;
+}
+
+pub struct RcK;
+pub struct ArcK;
+
+impl SharedPointer for RcK {
+    type Ptr<T> = Rc<T>;
+
+    fn new<T>(v: T) -> Rc<T> {
+        Rc::new(v)
+    }
+
+    fn try_unwrap<T>(p: Rc<T>) -> Result<T, Rc<T>> {
+        Rc::try_unwrap(p)
+    }
+
+    fn ptr_eq<T>(a: &Rc<T>, b: &Rc<T>) -> bool {
+        Rc::ptr_eq(a, b)
+    }
+}
+
+impl SharedPointer for ArcK {
+    type Ptr<T> = Arc<T>;
+
+    fn new<T>(v: T) -> Arc<T> {
+        Arc::new(v)
+    }
+
+    fn try_unwrap<T>(p: Arc<T>) -> Result<T, Arc<T>> {
+        Arc::try_unwrap(p)
+    }
+
+    fn ptr_eq<T>(a: &Arc<T>, b: &Arc<T>) -> bool {
+        Arc::ptr_eq(a, b)
+    }
+}
+
+pub type ArcList<T> = List<T, ArcK>;
+pub type Stack<T, P = RcK> = List<T, P>;
+
+type Link<T, P> = Option<<P as SharedPointer>::Ptr<Node<T, P>>>;
+
+pub struct List<T, P: SharedPointer = RcK> {
+    head: Link<T, P>,
 }
 
-pub struct Node<T> {
+pub struct Node<T, P: SharedPointer> {
     elem: T,
-    next: Option<Rc<Node<T>>>,
+    // The length of the list starting from this node.
+    len: usize,
+    next: Link<T, P>,
 }
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
+pub struct Iter<'a, T, P: SharedPointer> {
+    next: Option<&'a Node<T, P>>,
 }
 
 impl<T> List<T> {
     pub fn new() -> List<T> {
         List { head: Option::None }
     }
+}
+
+impl<T, P: SharedPointer> List<T, P> {
+    /// The empty list of any pointer kind, such as `ArcList::empty()`.
+    pub fn empty() -> Self {
+        List { head: None }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    pub fn len(&self) -> usize {
+        self.head.as_ref().map_or(0, |node| node.len)
+    }
 
     pub fn head(&self) -> Option<&T> {
         self.head.as_ref().map(|node| &node.elem)
     }
 
-    pub fn prepend(&mut self, elem: T) -> List<T> {
+    /// The new list with `elem` in front of this one, all the nodes of this list are shared.
+    pub fn cons(&self, elem: T) -> Self {
         List {
-            head: Some(Rc::new(Node {
-                elem: elem,
+            head: Some(P::new(Node {
+                elem,
+                len: self.len() + 1,
                 next: self.head.clone(),
             })),
         }
     }
 
-    pub fn tail(&mut self) -> List<T> {
+    pub fn prepend(&self, elem: T) -> Self {
+        self.cons(elem)
+    }
+
+    pub fn tail(&self) -> Self {
         List {
             head: self.head.as_ref().and_then(|node| node.next.clone()),
         }
     }
 
-    pub fn iter<'a>(&'a self) -> Iter<'a, T> {
+    pub fn push(&self, elem: T) -> Self {
+        self.cons(elem)
+    }
+
+    /// Pop the top of the stack, returns it with the rest of the stack.
+    pub fn pop(&self) -> Option<(&T, Self)> {
+        self.head().map(|elem| (elem, self.tail()))
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.head()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
         Iter {
             next: self.head.as_deref(),
         }
     }
+
+    /// Whether both lists are the same version, i.e. they share the same first node.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => P::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    // Build the list from the elements in reversed order, so that `elems[0]` is the head.
+    fn from_rev<I: DoubleEndedIterator<Item = T>>(elems: I, tail: &Self) -> Self {
+        let mut res = tail.clone();
+        for elem in elems.rev() {
+            res = res.cons(elem);
+        }
+        res
+    }
+
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> List<U, P> {
+        let elems: Vec<U> = self.iter().map(f).collect();
+        List::from_rev(elems.into_iter(), &List::empty())
+    }
+}
+
+impl<T: Clone, P: SharedPointer> List<T, P> {
+    pub fn reverse(&self) -> Self {
+        let mut res = List::empty();
+        for elem in self.iter() {
+            res = res.cons(elem.clone());
+        }
+        res
+    }
+
+    /// The elements of this list are copied, and all the nodes of `other` are shared.
+    pub fn append(&self, other: &Self) -> Self {
+        let elems: Vec<T> = self.iter().cloned().collect();
+        List::from_rev(elems.into_iter(), other)
+    }
+
+    /// Only the nodes before the last removed element are copied, the rest of the list is shared.
+    pub fn filter<F: FnMut(&T) -> bool>(&self, mut pred: F) -> Self {
+        let mut copied = Vec::new();
+        // The kept elements after the last removed one.
+        let mut pending: Vec<&T> = Vec::new();
+        // The number of nodes up to the last removed one.
+        let mut removed = None;
+        for (i, elem) in self.iter().enumerate() {
+            if pred(elem) {
+                pending.push(elem);
+            } else {
+                copied.extend(pending.drain(..).cloned());
+                removed = Some(i + 1);
+            }
+        }
+
+        let mut shared = self.clone();
+        for _ in 0..removed.unwrap_or(0) {
+            shared = shared.tail();
+        }
+        List::from_rev(copied.into_iter(), &shared)
+    }
+}
+
+impl<T, P: SharedPointer> Clone for List<T, P> {
+    fn clone(&self) -> Self {
+        List {
+            head: self.head.clone(),
+        }
+    }
 }
 
-impl<T> Drop for List<T> {
+impl<T, P: SharedPointer> Default for List<T, P> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, P: SharedPointer> Drop for List<T, P> {
     fn drop(&mut self) {
         let mut head = self.head.take();
         while let Some(node) = head {
-            if let Ok(mut node) = Rc::try_unwrap(node) {
+            if let Ok(mut node) = P::try_unwrap(node) {
                 head = node.next.take();
             } else {
                 break;
@@ -57,7 +230,7 @@ impl<T> Drop for List<T> {
     }
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, P: SharedPointer> Iterator for Iter<'a, T, P> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -68,9 +241,145 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+impl<'a, T, P: SharedPointer> IntoIterator for &'a List<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, P: SharedPointer> FromIterator<T> for List<T, P> {
+    /// The first element of the iterator becomes the head.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        List::from_rev(elems.into_iter(), &List::empty())
+    }
+}
+
+impl<T: PartialEq, P: SharedPointer> PartialEq for List<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || (self.len() == other.len() && self.iter().eq(other.iter()))
+    }
+}
+
+impl<T: Eq, P: SharedPointer> Eq for List<T, P> {}
+
+impl<T: Debug, P: SharedPointer> Debug for List<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The persistent FIFO queue made of two stacks: the elements are popped from `front` and pushed
+/// onto `rear`. Once `rear` becomes longer than `front`, it's reversed and appended to `front` at
+/// once, so both `push_back` and `pop_front` are amortized O(1) when the versions are used in a
+/// linear way. Unlike the banker's queue, the reversal isn't lazy, so popping the same old version
+/// again and again may repeat it.
+pub struct Queue<T, P: SharedPointer = RcK> {
+    front: List<T, P>,
+    rear: List<T, P>,
+}
+
+impl<T: Clone> Queue<T> {
+    pub fn new() -> Queue<T> {
+        Queue::empty()
+    }
+}
+
+impl<T: Clone, P: SharedPointer> Queue<T, P> {
+    pub fn empty() -> Self {
+        Queue {
+            front: List::empty(),
+            rear: List::empty(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.rear.len()
+    }
+
+    // Keep the invariant |rear| <= |front|, so the queue is empty iff the front is empty.
+    fn check(front: List<T, P>, rear: List<T, P>) -> Self {
+        if rear.len() <= front.len() {
+            Queue { front, rear }
+        } else {
+            Queue {
+                front: front.append(&rear.reverse()),
+                rear: List::empty(),
+            }
+        }
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.front.head()
+    }
+
+    pub fn push_back(&self, elem: T) -> Self {
+        Self::check(self.front.clone(), self.rear.cons(elem))
+    }
+
+    /// Pop the first element, returns it with the rest of the queue.
+    pub fn pop_front(&self) -> Option<(&T, Self)> {
+        let elem = self.front.head()?;
+        Some((elem, Self::check(self.front.tail(), self.rear.clone())))
+    }
+
+    /// The elements from the front to the back.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let rear: Vec<&T> = self.rear.iter().collect();
+        self.front.iter().chain(rear.into_iter().rev())
+    }
+}
+
+impl<T, P: SharedPointer> Clone for Queue<T, P> {
+    fn clone(&self) -> Self {
+        Queue {
+            front: self.front.clone(),
+            rear: self.rear.clone(),
+        }
+    }
+}
+
+impl<T: Clone, P: SharedPointer> Default for Queue<T, P> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T: Clone, P: SharedPointer> FromIterator<T> for Queue<T, P> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Queue {
+            front: iter.into_iter().collect(),
+            rear: List::empty(),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq, P: SharedPointer> PartialEq for Queue<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone + Eq, P: SharedPointer> Eq for Queue<T, P> {}
+
+impl<T: Clone + Debug, P: SharedPointer> Debug for Queue<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic::rand;
+    use std::thread;
 
     #[test]
     pub fn basics() {
@@ -123,4 +432,166 @@ mod tests {
         assert_eq!(Some(&"Hello"), it.next());
         assert_eq!(None, it.next());
     }
+
+    fn to_vec<T: Clone, P: SharedPointer>(list: &List<T, P>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    #[test]
+    pub fn old_versions_intact() {
+        let v1: List<i32> = (1..=3).collect();
+        let v2 = v1.cons(0);
+        let v3 = v1.tail().cons(10);
+        let v4 = v1.reverse();
+        let v5 = v2.append(&v3);
+        let v6 = v5.map(|x| x * 2);
+
+        assert_eq!(to_vec(&v1), vec![1, 2, 3]);
+        assert_eq!(to_vec(&v2), vec![0, 1, 2, 3]);
+        assert_eq!(to_vec(&v3), vec![10, 2, 3]);
+        assert_eq!(to_vec(&v4), vec![3, 2, 1]);
+        assert_eq!(to_vec(&v5), vec![0, 1, 2, 3, 10, 2, 3]);
+        assert_eq!(to_vec(&v6), vec![0, 2, 4, 6, 20, 4, 6]);
+        assert_eq!(v5.len(), 7);
+
+        // The unchanged nodes are shared instead of copied.
+        assert!(v2.tail().ptr_eq(&v1));
+        assert!(v3.tail().ptr_eq(&v1.tail()));
+        let mut shared = v5.clone();
+        for _ in 0..4 {
+            shared = shared.tail();
+        }
+        assert!(shared.ptr_eq(&v3));
+
+        assert_eq!(v1, vec![1, 2, 3].into_iter().collect());
+        assert_ne!(v1, v2);
+        assert_eq!(format!("{:?}", v4), "[3, 2, 1]");
+        assert!(List::<i32>::new().is_empty());
+    }
+
+    #[test]
+    pub fn filter_shares_suffix() {
+        let list: List<i32> = (0..10).collect();
+        let even = list.filter(|x| x % 2 == 0);
+        assert_eq!(to_vec(&even), vec![0, 2, 4, 6, 8]);
+        assert_eq!(to_vec(&list), (0..10).collect::<Vec<_>>());
+
+        let small = list.filter(|&x| x != 3);
+        assert_eq!(to_vec(&small), vec![0, 1, 2, 4, 5, 6, 7, 8, 9]);
+        let mut suffix = small.clone();
+        for _ in 0..3 {
+            suffix = suffix.tail();
+        }
+        let mut orig = list.clone();
+        for _ in 0..4 {
+            orig = orig.tail();
+        }
+        assert!(suffix.ptr_eq(&orig));
+
+        assert!(list.filter(|_| true).ptr_eq(&list));
+        assert!(list.filter(|_| false).is_empty());
+
+        for _ in 0..100 {
+            let v: Vec<u32> = (0..rand::gen_u32() % 20)
+                .map(|_| rand::gen_u32() % 5)
+                .collect();
+            let list: List<u32> = v.iter().cloned().collect();
+            let expected: Vec<u32> = v.iter().cloned().filter(|x| x % 2 == 1).collect();
+            assert_eq!(to_vec(&list.filter(|x| x % 2 == 1)), expected);
+        }
+    }
+
+    #[test]
+    pub fn stack() {
+        let s0: Stack<&str> = Stack::new();
+        let s1 = s0.push("a").push("b");
+        let (top, s2) = s1.pop().unwrap();
+        assert_eq!(*top, "b");
+        assert_eq!(s2.peek(), Some(&"a"));
+        assert_eq!(s1.peek(), Some(&"b"));
+        assert!(s0.pop().is_none());
+    }
+
+    #[test]
+    pub fn arc_list_between_threads() {
+        let base: ArcList<u32> = (0..1000).collect();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let base = base.clone();
+                thread::spawn(move || {
+                    let mine = base.cons(i);
+                    assert!(mine.tail().ptr_eq(&base));
+                    mine.iter().sum::<u32>()
+                })
+            })
+            .collect();
+        for (i, h) in handles.into_iter().enumerate() {
+            assert_eq!(h.join().unwrap(), 499500 + i as u32);
+        }
+        assert_eq!(base.len(), 1000);
+        assert!(ArcList::<u32>::empty().is_empty());
+    }
+
+    #[test]
+    pub fn long_list_drop() {
+        let mut list = List::new();
+        for i in 0..1000000 {
+            list = list.cons(i);
+        }
+        let shared = list.tail();
+        drop(list);
+        assert_eq!(shared.len(), 999999);
+    }
+
+    #[test]
+    pub fn queue() {
+        let q0: Queue<i32> = Queue::new();
+        assert!(q0.pop_front().is_none());
+
+        let q1 = q0.push_back(1).push_back(2).push_back(3);
+        let (x, q2) = q1.pop_front().unwrap();
+        assert_eq!(*x, 1);
+        let q3 = q2.push_back(4);
+        let q4 = q2.push_back(5);
+
+        assert!(q0.is_empty());
+        assert_eq!(q1.iter().cloned().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(q2.iter().cloned().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(q3.iter().cloned().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(q4.iter().cloned().collect::<Vec<_>>(), vec![2, 3, 5]);
+        assert_eq!(q3.len(), 3);
+        assert_eq!(q3.peek(), Some(&2));
+        assert_eq!(q3, vec![2, 3, 4].into_iter().collect());
+        assert_eq!(format!("{:?}", q4), "[2, 3, 5]");
+    }
+
+    #[test]
+    pub fn queue_random() {
+        // Keep all the versions, and compare every new version with a VecDeque.
+        let mut versions: Vec<(Queue<u32>, std::collections::VecDeque<u32>)> =
+            vec![(Queue::new(), Default::default())];
+        for _ in 0..3000 {
+            let i = rand::gen_u32() as usize % versions.len();
+            let (q, mut expected) = versions[i].clone();
+            let next = if rand::gen_u32().is_multiple_of(3) {
+                match q.pop_front() {
+                    Some((x, rest)) => {
+                        assert_eq!(Some(*x), expected.pop_front());
+                        rest
+                    }
+                    None => continue,
+                }
+            } else {
+                let x = rand::gen_u32();
+                expected.push_back(x);
+                q.push_back(x)
+            };
+            assert!(next.iter().eq(expected.iter()));
+            versions.push((next, expected));
+        }
+
+        for (q, expected) in &versions {
+            assert!(q.iter().eq(expected.iter()));
+        }
+    }
 }
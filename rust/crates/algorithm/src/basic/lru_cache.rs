@@ -0,0 +1,526 @@
+/**
+ * The LRU and LFU caches, both of them are O(1) for get and put. The entries are kept in the lists
+ * of `double_linked_list_unsafe`, and the hash map keeps the node handle of every key, so that a
+ * node can be unlinked and moved to the front without walking the list.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Cache_replacement_policies
+ * and the O(1) LFU paper: http://dhruvbird.com/lfu.pdf
+ */
+use crate::basic::double_linked_list_unsafe::{LinkedList, NodeHandle};
+use crate::basic::hash_table_v2::{Hash, HashMap};
+use crate::basic::map::Map;
+
+type EvictFn<K, V> = Box<dyn FnMut(K, V)>;
+
+struct Entry<K, V> {
+    key: K,
+    val: V,
+}
+
+/// The least recently used cache. The list is ordered from the most recently used entry to the
+/// least recently used one, which is evicted once the cache is full.
+pub struct LruCache<K: Hash + Clone, V> {
+    capacity: usize,
+    map: HashMap<K, NodeHandle<Entry<K, V>>>,
+    list: LinkedList<Entry<K, V>>,
+    on_evict: Option<EvictFn<K, V>>,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Hash + Clone,
+{
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "The capacity of LruCache must be positive");
+        LruCache {
+            capacity,
+            map: HashMap::new(),
+            list: LinkedList::new(),
+            on_evict: None,
+        }
+    }
+
+    /// Create the cache whose `on_evict` is called with every entry evicted by `put`.
+    pub fn with_evict<F>(capacity: usize, on_evict: F) -> Self
+    where
+        F: FnMut(K, V) + 'static,
+    {
+        let mut cache = Self::new(capacity);
+        cache.on_evict = Some(Box::new(on_evict));
+        cache
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.get(key.clone()).is_some()
+    }
+
+    /// Get the value and mark the entry as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let node = *self.map.get(key.clone())?;
+        unsafe {
+            self.list.move_node_to_front(node);
+            Some(&node.get(&self.list).val)
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = *self.map.get(key.clone())?;
+        unsafe {
+            self.list.move_node_to_front(node);
+            Some(&mut node.get_mut(&mut self.list).val)
+        }
+    }
+
+    /// Get the value without changing the recency of the entry.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        let node = *self.map.get(key.clone())?;
+        unsafe { Some(&node.get(&self.list).val) }
+    }
+
+    /// The least recently used entry, which is the next one to be evicted.
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.list.back().map(|entry| (&entry.key, &entry.val))
+    }
+
+    /// Insert or update the entry and mark it as the most recently used, returns the old value of
+    /// the key. The least recently used entry is evicted if the cache is full.
+    pub fn put(&mut self, key: K, val: V) -> Option<V> {
+        if let Some(&node) = self.map.get(key.clone()) {
+            unsafe {
+                self.list.move_node_to_front(node);
+                return Some(std::mem::replace(
+                    &mut node.get_mut(&mut self.list).val,
+                    val,
+                ));
+            }
+        }
+
+        if self.len() >= self.capacity {
+            if let Some((k, v)) = self.pop_lru() {
+                if let Some(f) = self.on_evict.as_mut() {
+                    f(k, v);
+                }
+            }
+        }
+
+        let node = self.list.push_front_node(Entry {
+            key: key.clone(),
+            val,
+        });
+        self.map.put(key, node);
+        None
+    }
+
+    /// Remove the least recently used entry, the eviction callback is not called.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let entry = self.list.pop_back()?;
+        self.map.remove(entry.key.clone());
+        Some((entry.key, entry.val))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let node = self.map.remove(key.clone())?;
+        unsafe { Some(self.list.remove_node(node).val) }
+    }
+
+    /// The keys from the most recently used to the least recently used.
+    pub fn keys(&self) -> Vec<&K> {
+        self.list.iter().map(|entry| &entry.key).collect()
+    }
+}
+
+struct LfuEntry<K, V> {
+    key: K,
+    val: V,
+}
+
+// All the entries with the same frequency, from the most recently used to the least recently used.
+struct Bucket<K, V> {
+    freq: u64,
+    entries: LinkedList<LfuEntry<K, V>>,
+}
+
+// Where the entry of a key is, its node is in the entries of the bucket.
+struct Slot<K, V> {
+    bucket: NodeHandle<Bucket<K, V>>,
+    node: NodeHandle<LfuEntry<K, V>>,
+}
+
+impl<K, V> Clone for Slot<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Slot<K, V> {}
+
+/// The least frequently used cache. The buckets are ordered by the increasing frequency, so the
+/// victim is always the least recently used entry of the first bucket, and a hit only moves the
+/// entry into the next bucket. Both of them are O(1).
+pub struct LfuCache<K: Hash + Clone, V> {
+    capacity: usize,
+    len: usize,
+    map: HashMap<K, Slot<K, V>>,
+    buckets: LinkedList<Bucket<K, V>>,
+    on_evict: Option<EvictFn<K, V>>,
+}
+
+impl<K, V> LfuCache<K, V>
+where
+    K: Hash + Clone,
+{
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "The capacity of LfuCache must be positive");
+        LfuCache {
+            capacity,
+            len: 0,
+            map: HashMap::new(),
+            buckets: LinkedList::new(),
+            on_evict: None,
+        }
+    }
+
+    /// Create the cache whose `on_evict` is called with every entry evicted by `put`.
+    pub fn with_evict<F>(capacity: usize, on_evict: F) -> Self
+    where
+        F: FnMut(K, V) + 'static,
+    {
+        let mut cache = Self::new(capacity);
+        cache.on_evict = Some(Box::new(on_evict));
+        cache
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.get(key.clone()).is_some()
+    }
+
+    /// How many times the entry has been put or got.
+    pub fn frequency(&self, key: &K) -> Option<u64> {
+        let slot = *self.map.get(key.clone())?;
+        unsafe { Some(slot.bucket.get(&self.buckets).freq) }
+    }
+
+    fn entry(&self, slot: Slot<K, V>) -> &LfuEntry<K, V> {
+        // The slots in the map always point to the live buckets and entries.
+        unsafe { slot.node.get(&slot.bucket.get(&self.buckets).entries) }
+    }
+
+    fn entry_mut(&mut self, slot: Slot<K, V>) -> &mut LfuEntry<K, V> {
+        unsafe {
+            slot.node
+                .get_mut(&mut slot.bucket.get_mut(&mut self.buckets).entries)
+        }
+    }
+
+    // Move the entry from its bucket into the bucket of the next frequency, and update its slot.
+    fn touch(&mut self, key: &K, slot: Slot<K, V>) -> Slot<K, V> {
+        let Slot { bucket, node } = slot;
+        // The slots in the map always point to the live buckets and entries.
+        let target = unsafe {
+            let freq = bucket.get(&self.buckets).freq;
+            let target = match self.buckets.next_node(bucket) {
+                Some(next) if next.get(&self.buckets).freq == freq + 1 => next,
+                _ => self.buckets.insert_after_node(
+                    bucket,
+                    Bucket {
+                        freq: freq + 1,
+                        entries: LinkedList::new(),
+                    },
+                ),
+            };
+
+            // Both of the buckets are in `self.buckets`, so take the entries out of the old one to
+            // borrow them at the same time. Moving a list doesn't move its nodes.
+            let mut entries = std::mem::take(&mut bucket.get_mut(&mut self.buckets).entries);
+            entries.move_node_to_front_of(node, &mut target.get_mut(&mut self.buckets).entries);
+            if entries.is_empty() {
+                self.buckets.remove_node(bucket);
+            } else {
+                bucket.get_mut(&mut self.buckets).entries = entries;
+            }
+            target
+        };
+
+        let slot = Slot {
+            bucket: target,
+            node,
+        };
+        *self.map.get_mut(key).unwrap() = slot;
+        slot
+    }
+
+    /// Get the value and increase the frequency of the entry.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let slot = *self.map.get(key.clone())?;
+        let slot = self.touch(key, slot);
+        Some(&self.entry(slot).val)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let slot = *self.map.get(key.clone())?;
+        let slot = self.touch(key, slot);
+        Some(&mut self.entry_mut(slot).val)
+    }
+
+    /// Get the value without changing the frequency of the entry.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        let slot = *self.map.get(key.clone())?;
+        Some(&self.entry(slot).val)
+    }
+
+    /// Insert or update the entry and increase its frequency, returns the old value of the key.
+    /// The least frequently used entry is evicted if the cache is full, and the least recently
+    /// used one is chosen among the entries with the same frequency.
+    pub fn put(&mut self, key: K, val: V) -> Option<V> {
+        if let Some(&slot) = self.map.get(key.clone()) {
+            let slot = self.touch(&key, slot);
+            return Some(std::mem::replace(&mut self.entry_mut(slot).val, val));
+        }
+
+        if self.len >= self.capacity {
+            if let Some((k, v)) = self.pop_lfu() {
+                if let Some(f) = self.on_evict.as_mut() {
+                    f(k, v);
+                }
+            }
+        }
+
+        let bucket = match self.buckets.front_node() {
+            Some(front) if unsafe { front.get(&self.buckets).freq } == 1 => front,
+            _ => self.buckets.push_front_node(Bucket {
+                freq: 1,
+                entries: LinkedList::new(),
+            }),
+        };
+        let entries = unsafe { &mut bucket.get_mut(&mut self.buckets).entries };
+        let node = entries.push_front_node(LfuEntry {
+            key: key.clone(),
+            val,
+        });
+        self.map.put(key, Slot { bucket, node });
+        self.len += 1;
+        None
+    }
+
+    // SAFETY: the slot must be of an entry of this cache, and it's not used any more after this.
+    unsafe fn remove_entry(&mut self, slot: Slot<K, V>) -> (K, V) {
+        let entries = &mut slot.bucket.get_mut(&mut self.buckets).entries;
+        let entry = entries.remove_node(slot.node);
+        if entries.is_empty() {
+            self.buckets.remove_node(slot.bucket);
+        }
+        self.len -= 1;
+        (entry.key, entry.val)
+    }
+
+    /// Remove the least frequently used entry, the eviction callback is not called.
+    pub fn pop_lfu(&mut self) -> Option<(K, V)> {
+        let bucket = self.buckets.front_node()?;
+        let node = unsafe { bucket.get(&self.buckets).entries.back_node()? };
+        let (k, v) = unsafe { self.remove_entry(Slot { bucket, node }) };
+        self.map.remove(k.clone());
+        Some((k, v))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = self.map.remove(key.clone())?;
+        unsafe { Some(self.remove_entry(slot).1) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log<K, V> = Rc<RefCell<Vec<(K, V)>>>;
+
+    fn evicted_log<K: 'static, V: 'static>() -> (Log<K, V>, impl FnMut(K, V)) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log2 = log.clone();
+        (log, move |k, v| log2.borrow_mut().push((k, v)))
+    }
+
+    #[test]
+    pub fn lru_eviction_order() {
+        let (log, on_evict) = evicted_log();
+        let mut cache = LruCache::with_evict(3, on_evict);
+        assert!(cache.is_empty());
+
+        assert_eq!(cache.put(1, "a"), None);
+        assert_eq!(cache.put(2, "b"), None);
+        assert_eq!(cache.put(3, "c"), None);
+        assert_eq!(cache.keys(), vec![&3, &2, &1]);
+
+        // Get and put both refresh the entry, but peek does not.
+        assert_eq!(cache.get(&1), Some(&"a"));
+        assert_eq!(cache.put(2, "B"), Some("b"));
+        assert_eq!(cache.peek(&3), Some(&"c"));
+        assert_eq!(cache.keys(), vec![&2, &1, &3]);
+        assert_eq!(cache.peek_lru(), Some((&3, &"c")));
+
+        assert_eq!(cache.put(4, "d"), None);
+        assert_eq!(cache.put(5, "e"), None);
+        assert_eq!(*log.borrow(), vec![(3, "c"), (1, "a")]);
+        assert_eq!(cache.keys(), vec![&5, &4, &2]);
+        assert_eq!(cache.len(), 3);
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.get(&3), None);
+
+        // The explicit removal doesn't call the eviction callback.
+        assert_eq!(cache.pop_lru(), Some((2, "B")));
+        assert_eq!(cache.remove(&5), Some("e"));
+        assert_eq!(cache.remove(&5), None);
+        assert_eq!(cache.keys(), vec![&4]);
+        assert_eq!(log.borrow().len(), 2);
+
+        *cache.get_mut(&4).unwrap() = "D";
+        assert_eq!(cache.pop_lru(), Some((4, "D")));
+        assert_eq!(cache.pop_lru(), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    pub fn lru_random() {
+        // Compare with a naive LRU on a Vec, from the least recently used to the most recently used.
+        let capacity = 16;
+        let mut cache = LruCache::new(capacity);
+        let mut naive: Vec<(i32, u32)> = Vec::new();
+
+        for _ in 0..20000 {
+            let key = (rand::gen_u32() % 40) as i32;
+            let pos = naive.iter().position(|&(k, _)| k == key);
+            if rand::gen_bool() {
+                let expected = pos.map(|i| naive.remove(i)).map(|(k, v)| {
+                    naive.push((k, v));
+                    v
+                });
+                assert_eq!(cache.get(&key).copied(), expected);
+            } else {
+                let val = rand::gen_u32();
+                let old = pos.map(|i| naive.remove(i).1);
+                if old.is_none() && naive.len() == capacity {
+                    naive.remove(0);
+                }
+                naive.push((key, val));
+                assert_eq!(cache.put(key, val), old);
+            }
+
+            let keys: Vec<&i32> = naive.iter().rev().map(|(k, _)| k).collect();
+            assert_eq!(cache.keys(), keys);
+        }
+    }
+
+    #[test]
+    pub fn lfu_eviction_order() {
+        let (log, on_evict) = evicted_log();
+        let mut cache = LfuCache::with_evict(3, on_evict);
+
+        cache.put("a".to_string(), 1);
+        cache.put("b".to_string(), 2);
+        cache.put("c".to_string(), 3);
+        assert_eq!(cache.get(&"a".to_string()), Some(&1));
+        assert_eq!(cache.get(&"a".to_string()), Some(&1));
+        assert_eq!(cache.get(&"b".to_string()), Some(&2));
+        assert_eq!(cache.peek(&"c".to_string()), Some(&3));
+        assert_eq!(cache.frequency(&"a".to_string()), Some(3));
+        assert_eq!(cache.frequency(&"b".to_string()), Some(2));
+        assert_eq!(cache.frequency(&"c".to_string()), Some(1));
+
+        // "c" is the least frequently used one.
+        cache.put("d".to_string(), 4);
+        // "d" and "b" are tied after this, and "b" is the least recently used one.
+        assert_eq!(cache.put("d".to_string(), 40), Some(4));
+        cache.put("e".to_string(), 5);
+        // "e" is the only one with frequency 1.
+        cache.put("f".to_string(), 6);
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                ("c".to_string(), 3),
+                ("b".to_string(), 2),
+                ("e".to_string(), 5)
+            ]
+        );
+
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.pop_lfu(), Some(("f".to_string(), 6)));
+        assert_eq!(cache.pop_lfu(), Some(("d".to_string(), 40)));
+        assert_eq!(cache.remove(&"a".to_string()), Some(1));
+        assert_eq!(cache.pop_lfu(), None);
+        assert!(cache.is_empty());
+        assert_eq!(log.borrow().len(), 3);
+    }
+
+    #[test]
+    pub fn lfu_random() {
+        // Compare with a naive LFU, whose entries are (key, val, freq, last used time).
+        let capacity = 8;
+        let mut cache = LfuCache::new(capacity);
+        let mut naive: Vec<(i32, u32, u64, usize)> = Vec::new();
+
+        for t in 0..20000 {
+            let key = (rand::gen_u32() % 20) as i32;
+            let pos = naive.iter().position(|e| e.0 == key);
+            if rand::gen_bool() {
+                let expected = pos.map(|i| {
+                    naive[i].2 += 1;
+                    naive[i].3 = t;
+                    naive[i].1
+                });
+                assert_eq!(cache.get(&key).copied(), expected);
+            } else {
+                let val = rand::gen_u32();
+                match pos {
+                    Some(i) => {
+                        let old = naive[i].1;
+                        naive[i] = (key, val, naive[i].2 + 1, t);
+                        assert_eq!(cache.put(key, val), Some(old));
+                    }
+                    None => {
+                        if naive.len() == capacity {
+                            let victim = (0..naive.len())
+                                .min_by_key(|&i| (naive[i].2, naive[i].3))
+                                .unwrap();
+                            naive.remove(victim);
+                        }
+                        naive.push((key, val, 1, t));
+                        assert_eq!(cache.put(key, val), None);
+                    }
+                }
+            }
+
+            assert_eq!(cache.len(), naive.len());
+            for e in &naive {
+                assert_eq!(cache.peek(&e.0), Some(&e.1));
+                assert_eq!(cache.frequency(&e.0), Some(e.2));
+            }
+        }
+    }
+}
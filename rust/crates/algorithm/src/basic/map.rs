@@ -0,0 +1,440 @@
+/**
+ * The common interfaces of the key-value maps, so that the hash tables, the search trees and the
+ * skip lists can be swapped with each other and checked by the same conformance tests.
+ *
+ * `Map` is the interface of all the maps, and `OrderedMap` adds the range queries for the maps
+ * which keep the keys sorted, whose `iter` visits the keys in the ascending order.
+ *
+ * The lookups and the iterators lend the plain references into the maps, and every map names its
+ * own iterator as `Map::Iter`. The lookups of `SplayTree` through `get` and `iter` don't splay,
+ * since they only borrow the tree; `get_mut` splays.
+ *
+ * The binary search trees share the in-order iterator `InOrder` and the range walk over their
+ * nodes through `BinaryNode`.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Associative_array
+ */
+use std::ops::{Bound, RangeBounds};
+
+pub trait Map<K, V> {
+    /// The iterator of `iter`.
+    type Iter<'a>: Iterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a,
+        V: 'a;
+
+    /// Insert the pair, and return the old value if the key exists.
+    fn insert(&mut self, k: K, v: V) -> Option<V>;
+
+    /// Remove the key, and return its value.
+    fn remove(&mut self, k: &K) -> Option<V>;
+
+    /// The value of the key. It can't restructure the map, e.g. splay a `SplayTree`, since the
+    /// map is shared while the value is lent out.
+    fn get(&self, k: &K) -> Option<&V>;
+
+    /// The mutable value of the key, which may restructure the map, e.g. splay a `SplayTree`.
+    fn get_mut(&mut self, k: &K) -> Option<&mut V>;
+
+    /// All the pairs, the order is only defined by `OrderedMap`.
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains_key(&self, k: &K) -> bool {
+        self.get(k).is_some()
+    }
+
+    fn get_cloned(&self, k: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.get(k).cloned()
+    }
+
+    /// Visit all the pairs in the order of `iter`.
+    fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        self.iter().for_each(|(k, v)| f(k, v));
+    }
+
+    /// All the pairs in the order of `iter`.
+    fn to_vec(&self) -> Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+}
+
+pub trait OrderedMap<K: Ord, V>: Map<K, V> {
+    /// Visit the pairs whose keys are in the range in the ascending order. The default one scans
+    /// all the pairs, and the maps which can seek to the start of the range override it.
+    fn range_for_each(&self, range: impl RangeBounds<K>, mut f: impl FnMut(&K, &V)) {
+        self.for_each(|k, v| {
+            if range.contains(k) {
+                f(k, v);
+            }
+        });
+    }
+
+    /// The pairs whose keys are in the range in the ascending order.
+    fn range_to_vec(&self, range: impl RangeBounds<K>) -> Vec<(K, V)>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        let mut res = Vec::new();
+        self.range_for_each(range, |k, v| res.push((k.clone(), v.clone())));
+        res
+    }
+}
+
+/// Whether the key is before the start of the range, so are all the keys less than it.
+pub fn before_start<K: Ord>(range: &impl RangeBounds<K>, k: &K) -> bool {
+    match range.start_bound() {
+        Bound::Included(lo) => k < lo,
+        Bound::Excluded(lo) => k <= lo,
+        Bound::Unbounded => false,
+    }
+}
+
+/// Whether the key is after the end of the range, so are all the keys greater than it.
+pub fn after_end<K: Ord>(range: &impl RangeBounds<K>, k: &K) -> bool {
+    match range.end_bound() {
+        Bound::Included(hi) => k > hi,
+        Bound::Excluded(hi) => k >= hi,
+        Bound::Unbounded => false,
+    }
+}
+
+/// The node of the binary search trees, by which the trees share the in-order walks.
+pub trait BinaryNode {
+    type Key;
+    type Val;
+
+    fn key(&self) -> &Self::Key;
+    fn val(&self) -> &Self::Val;
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
+}
+
+/// The in-order iterator of a binary search tree, which keeps the nodes whose keys and right
+/// subtrees are left to visit, so it doesn't recurse on a deep tree.
+pub struct InOrder<'a, N> {
+    stack: Vec<&'a N>,
+}
+
+impl<'a, N: BinaryNode> InOrder<'a, N> {
+    pub fn new(root: Option<&'a N>) -> Self {
+        let mut iter = InOrder { stack: Vec::new() };
+        iter.push_left(root);
+        iter
+    }
+
+    /// The iterator from the first key which isn't before the start of the range.
+    pub fn seek(root: Option<&'a N>, range: &impl RangeBounds<N::Key>) -> Self
+    where
+        N::Key: Ord,
+    {
+        let mut stack = Vec::new();
+        let mut cur = root;
+        while let Some(node) = cur {
+            if before_start(range, node.key()) {
+                cur = node.right();
+            } else {
+                stack.push(node);
+                cur = node.left();
+            }
+        }
+        InOrder { stack }
+    }
+
+    fn push_left(&mut self, mut cur: Option<&'a N>) {
+        while let Some(node) = cur {
+            self.stack.push(node);
+            cur = node.left();
+        }
+    }
+}
+
+impl<'a, N: BinaryNode> Iterator for InOrder<'a, N> {
+    type Item = (&'a N::Key, &'a N::Val);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right());
+        Some((node.key(), node.val()))
+    }
+}
+
+/// Visit the pairs of the tree in the range in order, and skip the subtrees out of the range.
+pub fn range_for_each<N>(
+    root: Option<&N>,
+    range: impl RangeBounds<N::Key>,
+    mut f: impl FnMut(&N::Key, &N::Val),
+) where
+    N: BinaryNode,
+    N::Key: Ord,
+{
+    for (k, v) in InOrder::seek(root, &range).take_while(|(k, _)| !after_end(&range, *k)) {
+        f(k, v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::avl_tree::AVLTree;
+    use crate::basic::b_tree::BTree;
+    use crate::basic::hash_table_v1::HashTable;
+    use crate::basic::hash_table_v2::HashMap;
+    use crate::basic::rand;
+    use crate::basic::rand::SplitMix64;
+    use crate::basic::rb_tree::RBTree;
+    use crate::basic::scapegoat_tree::ScapegoatTree;
+    use crate::basic::simple_tree::SimpleTree;
+    use crate::basic::skiplist::SkipList;
+    use crate::basic::skiplist_unsafe;
+    use crate::basic::splay_tree::SplayTree;
+    use crate::basic::treap::Treap;
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+
+    // Apply the same random operations to the map and the model, and compare all the results. The
+    // `check` compares the whole map with the model every few hundred rounds.
+    fn model_test<V, M>(
+        map: &mut M,
+        rounds: usize,
+        key_range: i32,
+        mut gen_val: impl FnMut() -> V,
+        mut check: impl FnMut(&M, &BTreeMap<i32, V>),
+    ) where
+        V: Clone + Debug + PartialEq,
+        M: Map<i32, V>,
+    {
+        let mut model = BTreeMap::new();
+        for i in 0..rounds {
+            let k = rand::gen_i32() % key_range;
+            match rand::gen_u32() % 8 {
+                0..=3 => {
+                    let v = gen_val();
+                    assert_eq!(map.insert(k, v.clone()), model.insert(k, v));
+                }
+                4 | 5 => assert_eq!(map.remove(&k), model.remove(&k)),
+                6 => assert_eq!(map.get(&k), model.get(&k)),
+                _ => match (map.get_mut(&k), model.get_mut(&k)) {
+                    (Some(v), Some(expected)) => {
+                        assert_eq!(v, expected);
+                        *v = gen_val();
+                        *expected = v.clone();
+                    }
+                    (v, expected) => assert_eq!(v, expected),
+                },
+            }
+            assert_eq!(map.len(), model.len());
+            assert_eq!(map.contains_key(&k), model.contains_key(&k));
+            assert_eq!(map.get_cloned(&k), model.get(&k).cloned());
+            if i % 500 == 0 {
+                check(map, &model);
+            }
+        }
+        check(map, &model);
+
+        let keys: Vec<i32> = model.keys().cloned().collect();
+        for k in keys {
+            assert_eq!(map.remove(&k), model.remove(&k));
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.to_vec(), vec![]);
+    }
+
+    // The conformance test of `Map`, whose pairs may be visited in any order.
+    fn check_map<M: Map<i32, u32>>(mut map: M, rounds: usize, key_range: i32) {
+        let check = |map: &M, model: &BTreeMap<i32, u32>| {
+            let mut all: Vec<(&i32, &u32)> = map.iter().collect();
+            all.sort();
+            assert!(all.into_iter().eq(model.iter()));
+        };
+        model_test(&mut map, rounds, key_range, rand::gen_u32, check);
+    }
+
+    fn gen_bound(key_range: i32) -> Bound<i32> {
+        let k = rand::gen_i32() % key_range;
+        match rand::gen_u32() % 3 {
+            0 => Bound::Included(k),
+            1 => Bound::Excluded(k),
+            _ => Bound::Unbounded,
+        }
+    }
+
+    // The conformance test of `OrderedMap`, which also checks the order and the random ranges.
+    fn check_ordered_map<V, M>(mut map: M, rounds: usize, key_range: i32, gen_val: fn() -> V)
+    where
+        V: Clone + Debug + PartialEq,
+        M: OrderedMap<i32, V>,
+    {
+        let check = |map: &M, model: &BTreeMap<i32, V>| {
+            assert!(map.iter().eq(model.iter()));
+            let all: Vec<(i32, V)> = model.iter().map(|(k, v)| (*k, v.clone())).collect();
+            assert_eq!(map.to_vec(), all);
+
+            for _ in 0..20 {
+                let (mut lo, mut hi) = (gen_bound(key_range), gen_bound(key_range));
+                if let (
+                    Bound::Included(a) | Bound::Excluded(a),
+                    Bound::Included(b) | Bound::Excluded(b),
+                ) = (lo, hi)
+                {
+                    // BTreeMap panics on the reversed range and the empty excluded one.
+                    if a > b {
+                        (lo, hi) = (hi, lo);
+                    }
+                    if a == b {
+                        (lo, hi) = (Bound::Included(a), Bound::Included(b));
+                    }
+                }
+                let expected: Vec<(i32, V)> = model
+                    .range((lo, hi))
+                    .map(|(k, v)| (*k, v.clone()))
+                    .collect();
+                assert_eq!(map.range_to_vec((lo, hi)), expected, "{:?}", (lo, hi));
+            }
+        };
+        model_test(&mut map, rounds, key_range, gen_val, check);
+    }
+
+    fn check_sorted<M: OrderedMap<i32, u32>>(make: impl Fn() -> M) {
+        check_ordered_map(make(), 20000, 1000, rand::gen_u32);
+        check_ordered_map(make(), 20000, 50, rand::gen_u32);
+    }
+
+    #[test]
+    pub fn conformance_hash_table_v1() {
+        check_map(HashTable::new(), 20000, 1000);
+        check_map(HashTable::new(), 20000, 50);
+    }
+
+    #[test]
+    pub fn conformance_hash_table_v2() {
+        check_map(HashMap::new(), 20000, 1000);
+        check_map(HashMap::new(), 20000, 50);
+    }
+
+    #[test]
+    pub fn conformance_simple_tree() {
+        // The tree is a set, which is the map to the unit values.
+        check_ordered_map(SimpleTree::new(), 20000, 1000, || ());
+        check_ordered_map(SimpleTree::new(), 20000, 50, || ());
+    }
+
+    #[test]
+    pub fn conformance_avl_tree() {
+        check_sorted(AVLTree::new);
+    }
+
+    #[test]
+    pub fn conformance_skiplist() {
+        check_sorted(SkipList::new);
+    }
+
+    #[test]
+    pub fn conformance_skiplist_unsafe() {
+        check_sorted(skiplist_unsafe::SkipList::new);
+    }
+
+    #[test]
+    pub fn conformance_rb_tree() {
+        check_sorted(RBTree::new);
+    }
+
+    #[test]
+    pub fn conformance_b_tree() {
+        for order in [3, 4, 5, 16] {
+            check_sorted(|| BTree::with_order(order));
+        }
+    }
+
+    #[test]
+    pub fn conformance_treap() {
+        check_sorted(Treap::new);
+        check_sorted(|| Treap::with_rng(SplitMix64::new(42)));
+    }
+
+    #[test]
+    pub fn conformance_splay_tree() {
+        check_sorted(SplayTree::new);
+    }
+
+    #[test]
+    pub fn conformance_scapegoat_tree() {
+        check_sorted(ScapegoatTree::new);
+        check_sorted(|| ScapegoatTree::with_alpha(0.55));
+    }
+
+    // Any of the maps can be used behind the trait.
+    fn word_count<M: Map<String, usize>>(mut map: M, text: &str) -> Vec<(String, usize)> {
+        for w in text.split_whitespace() {
+            let n = map.get_cloned(&w.to_string()).unwrap_or(0);
+            map.insert(w.to_string(), n + 1);
+        }
+        let mut res = map.to_vec();
+        res.sort();
+        res
+    }
+
+    #[test]
+    pub fn swap_implementations() {
+        let text = "the quick brown fox jumps over the lazy dog the end";
+        let expected = word_count(BTree::new(), text);
+        assert_eq!(expected[0], (String::from("brown"), 1));
+        assert_eq!(expected.last(), Some(&(String::from("the"), 3)));
+        assert_eq!(word_count(HashMap::new(), text), expected);
+        assert_eq!(word_count(AVLTree::new(), text), expected);
+        assert_eq!(word_count(SkipList::new(), text), expected);
+        assert_eq!(word_count(skiplist_unsafe::SkipList::new(), text), expected);
+        assert_eq!(word_count(RBTree::new(), text), expected);
+        assert_eq!(word_count(Treap::new(), text), expected);
+        assert_eq!(word_count(SplayTree::new(), text), expected);
+        assert_eq!(word_count(ScapegoatTree::new(), text), expected);
+    }
+
+    #[test]
+    pub fn range_bounds() {
+        let mut map = RBTree::new();
+        for i in 0..10 {
+            Map::insert(&mut map, i, i);
+        }
+        let keys = |map: &RBTree<i32, i32>, range: (Bound<i32>, Bound<i32>)| -> Vec<i32> {
+            map.range_to_vec(range)
+                .into_iter()
+                .map(|(k, _)| k)
+                .collect()
+        };
+        assert_eq!(
+            keys(&map, (Bound::Included(3), Bound::Excluded(6))),
+            vec![3, 4, 5]
+        );
+        assert_eq!(
+            keys(&map, (Bound::Excluded(3), Bound::Included(6))),
+            vec![4, 5, 6]
+        );
+        assert_eq!(
+            keys(&map, (Bound::Unbounded, Bound::Excluded(2))),
+            vec![0, 1]
+        );
+        assert_eq!(
+            keys(&map, (Bound::Excluded(7), Bound::Unbounded)),
+            vec![8, 9]
+        );
+        assert_eq!(keys(&map, (Bound::Excluded(20), Bound::Unbounded)), vec![]);
+        assert_eq!(map.range_to_vec(2..4), vec![(2, 2), (3, 3)]);
+        assert!(before_start(&(2..4), &1) && !before_start(&(2..4), &2));
+        assert!(after_end(&(2..4), &4) && !after_end(&(2..=4), &4));
+    }
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::iter::FromIterator;
 use std::rc::Rc;
 
 pub struct List<T> {
@@ -38,42 +40,184 @@ impl<T> List<T> {
     pub fn is_empty(&self) -> bool {
         self.head.is_none()
     }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    /// Sort the list in place by the bottom-up merge sort, which is stable and O(n log n). The
+    /// nodes are relinked instead of being copied, so no allocation happens.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(|a, b| a.cmp(b));
+    }
+
+    pub fn sort_by<F>(&mut self, mut cmp: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let n = self.len();
+        let mut width = 1;
+        while width < n {
+            let mut rest = self.head.take();
+            let mut tail = &mut self.head;
+            while rest.is_some() {
+                let a = cut(&mut rest, width);
+                let b = cut(&mut rest, width);
+                *tail = merge_links(a, b, &mut cmp);
+                while let Some(node) = tail {
+                    tail = &mut node_mut(node).next;
+                }
+            }
+            width <<= 1;
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
 }
 
-pub fn merge_lists(a: &mut List<i32>, b: &mut List<i32>) -> List<i32> {
-    let mut res = List::new();
+impl<T> FromIterator<T> for List<T> {
+    /// The first element of the iterator becomes the head.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        let mut tail = &mut list.head;
+        for elem in iter {
+            *tail = Some(Rc::new(Node { elem, next: None }));
+            tail = &mut node_mut(tail.as_mut().unwrap()).next;
+        }
+        list
+    }
+}
 
-    while !a.is_empty() && !b.is_empty() {
-        let a_elem = a.peek().unwrap();
-        let b_elem = b.peek().unwrap();
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(mut node) = head {
+            head = node_mut(&mut node).next.take();
+        }
+    }
+}
+
+// The nodes are never shared between lists, so every node is uniquely owned by its list.
+fn node_mut<T>(node: &mut Rc<Node<T>>) -> &mut Node<T> {
+    Rc::get_mut(node).expect("The list node should not be shared")
+}
 
-        if a_elem < b_elem {
-            res.push_front(a.pop_front().unwrap());
-        } else {
-            res.push_front(b.pop_front().unwrap());
+// Cut the first `n` nodes off `link`, and returns them as a new chain.
+fn cut<T>(link: &mut Link<T>, n: usize) -> Link<T> {
+    let mut head = link.take();
+    let mut cur = &mut head;
+    for _ in 0..n {
+        match cur {
+            Some(node) => cur = &mut node_mut(node).next,
+            None => break,
         }
     }
+    *link = cur.take();
+    head
+}
 
-    while !a.is_empty() {
-        res.push_front(a.pop_front().unwrap());
+// Merge two sorted chains by relinking the nodes. The node of `a` goes first on ties, so the merge
+// is stable.
+fn merge_links<T, F>(mut a: Link<T>, mut b: Link<T>, cmp: &mut F) -> Link<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut head: Link<T> = None;
+    let mut tail = &mut head;
+    loop {
+        let from = match (&a, &b) {
+            (Some(x), Some(y)) => {
+                if cmp(&y.elem, &x.elem) == Ordering::Less {
+                    &mut b
+                } else {
+                    &mut a
+                }
+            }
+            (Some(_), None) => &mut a,
+            (None, _) => &mut b,
+        };
+        match from.take() {
+            None => break,
+            Some(mut node) => {
+                *from = node_mut(&mut node).next.take();
+                *tail = Some(node);
+                tail = &mut node_mut(tail.as_mut().unwrap()).next;
+            }
+        }
     }
+    head
+}
 
-    while !b.is_empty() {
-        res.push_front(b.pop_front().unwrap());
+pub fn merge_lists<T: Ord>(a: &mut List<T>, b: &mut List<T>) -> List<T> {
+    merge_lists_by(a, b, |x, y| x.cmp(y))
+}
+
+/// Merge two sorted lists into a new list, both `a` and `b` are left empty. The nodes are moved
+/// into the result without allocation, and the elements of `a` go first on ties.
+pub fn merge_lists_by<T, F>(a: &mut List<T>, b: &mut List<T>, mut cmp: F) -> List<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    List {
+        head: merge_links(a.head.take(), b.head.take(), &mut cmp),
     }
+}
+
+pub fn merge_k_lists<T: Ord>(lists: Vec<List<T>>) -> List<T> {
+    merge_k_lists_by(lists, |x, y| x.cmp(y))
+}
 
-    let mut reversed = List::new();
-    while !res.is_empty() {
-        reversed.push_front(res.pop_front().unwrap());
+/// Merge k sorted lists by merging them in pairs round by round, which is O(n log k) in total.
+pub fn merge_k_lists_by<T, F>(mut lists: Vec<List<T>>, mut cmp: F) -> List<T>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while lists.len() > 1 {
+        let mut merged = Vec::with_capacity(lists.len().div_ceil(2));
+        let mut it = lists.into_iter();
+        while let Some(mut a) = it.next() {
+            match it.next() {
+                Some(mut b) => merged.push(merge_lists_by(&mut a, &mut b, &mut cmp)),
+                None => merged.push(a),
+            }
+        }
+        lists = merged;
     }
+    lists.pop().unwrap_or_default()
+}
 
-    // TODO free the temporary list res.
-    reversed
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic::rand;
 
     #[test]
     pub fn basics() {
@@ -110,4 +254,81 @@ mod tests {
         assert_eq!(Some(11), res.pop_front());
         assert_eq!(true, res.is_empty());
     }
+
+    fn to_vec<T: Clone>(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    #[test]
+    pub fn merge_generic() {
+        let mut a: List<&str> = vec!["apple", "kiwi", "pear"].into_iter().collect();
+        let mut b: List<&str> = vec!["banana", "fig"].into_iter().collect();
+        let res = merge_lists(&mut a, &mut b);
+        assert_eq!(to_vec(&res), vec!["apple", "banana", "fig", "kiwi", "pear"]);
+        assert!(a.is_empty() && b.is_empty());
+
+        // Merge by the length in the descending order, the elements of `a` go first on ties.
+        let mut a: List<&str> = vec!["ccc", "bb", "a1"].into_iter().collect();
+        let mut b: List<&str> = vec!["ddd", "a2", "b"].into_iter().collect();
+        let res = merge_lists_by(&mut a, &mut b, |x, y| y.len().cmp(&x.len()));
+        assert_eq!(to_vec(&res), vec!["ccc", "ddd", "bb", "a1", "a2", "b"]);
+
+        let mut empty = List::new();
+        let mut b: List<i32> = vec![1, 2].into_iter().collect();
+        assert_eq!(to_vec(&merge_lists(&mut empty, &mut b)), vec![1, 2]);
+    }
+
+    #[test]
+    pub fn sort_random() {
+        for n in [0, 1, 2, 3, 7, 8, 100, 1025] {
+            let v: Vec<i32> = (0..n).map(|_| rand::gen_i32() % 50).collect();
+            let mut list: List<i32> = v.iter().cloned().collect();
+            list.sort();
+
+            let mut expected = v.clone();
+            expected.sort();
+            assert_eq!(to_vec(&list), expected);
+        }
+
+        // The sort is stable.
+        let v: Vec<(u32, usize)> = (0..1000).map(|i| (rand::gen_u32() % 10, i)).collect();
+        let mut list: List<(u32, usize)> = v.iter().cloned().collect();
+        list.sort_by(|x, y| x.0.cmp(&y.0));
+        let mut expected = v.clone();
+        expected.sort_by_key(|x| x.0);
+        assert_eq!(to_vec(&list), expected);
+    }
+
+    #[test]
+    pub fn sort_long_list() {
+        let n = 200_000;
+        let mut list: List<i32> = (0..n).rev().collect();
+        list.sort();
+        assert!(list.iter().cloned().eq(0..n));
+    }
+
+    #[test]
+    pub fn merge_k() {
+        let mut expected = Vec::new();
+        let mut lists = Vec::new();
+        for _ in 0..13 {
+            let mut v: Vec<i32> = (0..rand::gen_u32() % 30)
+                .map(|_| rand::gen_i32() % 100)
+                .collect();
+            v.sort();
+            expected.extend(v.iter().cloned());
+            lists.push(v.into_iter().collect());
+        }
+        expected.sort();
+        assert_eq!(to_vec(&merge_k_lists(lists)), expected);
+
+        assert!(merge_k_lists::<i32>(Vec::new()).is_empty());
+
+        let lists: Vec<List<i32>> = vec![vec![9, 5, 1], vec![8, 2], vec![7]]
+            .into_iter()
+            .map(|v| v.into_iter().collect())
+            .collect();
+        let res = merge_k_lists_by(lists, |x, y| y.cmp(x));
+        assert_eq!(to_vec(&res), vec![9, 8, 7, 5, 2, 1]);
+    }
 }
@@ -1,12 +1,17 @@
+pub mod aho_corasick;
 pub mod avl_tree;
+pub mod b_tree;
 pub mod c_n_m;
 pub mod cmp;
+pub mod concurrent_deque;
+pub mod counting_alloc;
 pub mod deref_trait;
 pub mod disjoint_set;
 pub mod double_linked_list_unsafe;
 pub mod double_linked_list_v1;
 pub mod double_linked_list_v2;
 pub mod double_linked_list_v3;
+pub mod gap_buffer;
 pub mod graph_bfs;
 pub mod graph_dfs;
 pub mod hash_table_v1;
@@ -19,25 +24,36 @@ pub mod linked_list_unsafe;
 pub mod linked_list_v1;
 pub mod linked_list_v2;
 pub mod linked_list_v3;
+pub mod lru_cache;
+pub mod map;
 pub mod map_reduce;
 pub mod merge_linkedlist;
 pub mod msort;
 pub mod multi_thread;
 pub mod prime;
+#[cfg(test)]
+pub mod prop_test;
 pub mod qsort;
 pub mod rabin_karp;
 pub mod rand;
+pub mod rb_tree;
 pub mod ref_cell;
 pub mod ref_deref;
+pub mod ring_buffer;
+pub mod scapegoat_tree;
 pub mod segment_tree;
 pub mod select_sort;
 pub mod simple_tree;
 pub mod skiplist;
 pub mod skiplist_unsafe;
+pub mod splay_tree;
+pub mod suffix_array;
 pub mod sum;
 pub mod test_macros;
 pub mod test_static;
+pub mod treap;
 pub mod trie_tree;
+pub mod vec_deque;
 pub mod visit_binary_tree_safe;
 pub mod visit_binary_tree_unsafe;
 mod explicit_lifetime;
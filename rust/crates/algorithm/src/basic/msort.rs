@@ -1,46 +1,53 @@
-// Merge sort for an integer array.
-fn _msort(v: &mut [i32], l: usize, r: usize) {
+use std::cmp::Ordering;
+
+// Merge sort for an array, which keeps the order of the equal elements.
+fn _msort<T: Clone, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], l: usize, r: usize, cmp: &mut F) {
     if l >= r {
         return;
     }
 
     let m = (l + r) >> 1;
-    _msort(v, l, m);
-    _msort(v, m + 1, r);
+    _msort(v, l, m, cmp);
+    _msort(v, m + 1, r, cmp);
 
     // Temporary array to cache the whole merge sorted elements.
     let mut w = Vec::with_capacity(r + 1 - l);
     let mut i = l;
     let mut j = m + 1;
     while i <= m && j <= r {
-        if v[i] <= v[j] {
-            w.push(v[i]);
+        if cmp(&v[i], &v[j]) != Ordering::Greater {
+            w.push(v[i].clone());
             i += 1;
         } else {
-            w.push(v[j]);
+            w.push(v[j].clone());
             j += 1;
         }
     }
 
     while i <= m {
-        w.push(v[i]);
+        w.push(v[i].clone());
         i += 1;
     }
 
     while j <= r {
-        w.push(v[j]);
+        w.push(v[j].clone());
         j += 1;
     }
 
     // Copy the elements back to the vec
-    for i in 0..(r + 1 - l) {
-        v[l + i] = w[i];
+    for (i, e) in w.into_iter().enumerate() {
+        v[l + i] = e;
     }
 }
 
 pub fn merge_sort(v: &mut [i32]) {
+    merge_sort_by(v, |a, b| a.cmp(b));
+}
+
+/// Sort by the comparator, which is stable: the equal elements keep their order.
+pub fn merge_sort_by<T: Clone, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut cmp: F) {
     if v.len() > 1 {
-        _msort(v, 0, v.len() - 1);
+        _msort(v, 0, v.len() - 1, &mut cmp);
     }
 }
 
@@ -70,4 +77,17 @@ mod tests {
         merge_sort(&mut vec);
         assert_eq!(vec, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
     }
+
+    #[test]
+    pub fn test_msort_by_stable() {
+        let mut vec: Vec<(usize, usize)> = (0..100).map(|i| ((i * 37) % 5, i)).collect();
+        merge_sort_by(&mut vec, |a, b| a.0.cmp(&b.0));
+        for w in vec.windows(2) {
+            assert!(w[0].0 < w[1].0 || (w[0].0 == w[1].0 && w[0].1 < w[1].1));
+        }
+
+        let mut vec = vec!["b", "A", "a", "B"];
+        merge_sort_by(&mut vec, |a, b| a.to_lowercase().cmp(&b.to_lowercase()));
+        assert_eq!(vec, ["A", "a", "b", "B"]);
+    }
 }
@@ -0,0 +1,1465 @@
+/**
+ * The property-based and differential test harness of the containers.
+ *
+ * A test case is a random sequence of operations generated from a seed. The operations are applied
+ * to the container and to its model in std (`Vec`, `VecDeque`, `BTreeMap` or `HashMap`) in
+ * lockstep, and the result of every operation and the observed state after it must be the same.
+ * When a case fails, its sequence is shrunk by removing the operations which are not needed to
+ * reproduce the failure, and the panic message carries the seed and the minimal sequence.
+ *
+ * The seed and the number of the cases can be overridden by the `PROP_SEED` and `PROP_CASES`
+ * environment variables, e.g. to replay a failure with `PROP_SEED=42 PROP_CASES=1`.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Property_testing
+ */
+use crate::basic::map::{Map, OrderedMap};
+use crate::basic::rand::{RandomSource, SplitMix64};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::env;
+use std::fmt::{Debug, Display};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+const DEFAULT_SEED: u64 = 0x9E37_79B9;
+
+/// The generator of the random test data, which is fully determined by its seed.
+pub struct Gen {
+    rng: SplitMix64,
+}
+
+impl Gen {
+    pub fn new(seed: u64) -> Self {
+        Gen {
+            rng: SplitMix64::new(seed),
+        }
+    }
+
+    /// A random number in [0, n).
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "The upper bound must be positive");
+        (self.rng.next_u64() % n as u64) as usize
+    }
+
+    pub fn int_in(&mut self, range: Range<i32>) -> i32 {
+        assert!(!range.is_empty(), "The range must not be empty");
+        let n = (range.end as i64 - range.start as i64) as u64;
+        (range.start as i64 + (self.rng.next_u64() % n) as i64) as i32
+    }
+
+    pub fn u32(&mut self) -> u32 {
+        self.rng.next_u64() as u32
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.rng.next_u64() & 1 != 0
+    }
+
+    /// Pick an index by the weights, e.g. `[3, 1]` returns 0 three times as often as 1.
+    pub fn weighted(&mut self, weights: &[usize]) -> usize {
+        let mut x = self.below(weights.iter().sum());
+        for (i, w) in weights.iter().enumerate() {
+            if x < *w {
+                return i;
+            }
+            x -= w;
+        }
+        unreachable!()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub cases: usize,
+    /// The max length of the generated sequences.
+    pub max_len: usize,
+    /// The seed of the first case, and the i-th case uses `seed + i`.
+    pub seed: u64,
+}
+
+impl Config {
+    pub fn with_max_len(max_len: usize) -> Self {
+        Config {
+            max_len,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let var = |name| env::var(name).ok().and_then(|v| v.parse().ok());
+        Config {
+            cases: var("PROP_CASES").map_or(100, |n| n as usize),
+            max_len: 200,
+            seed: var("PROP_SEED").unwrap_or(DEFAULT_SEED),
+        }
+    }
+}
+
+/// The failing case after shrinking.
+#[derive(Debug)]
+pub struct Failure<T> {
+    pub seed: u64,
+    /// The length of the sequence before shrinking.
+    pub original_len: usize,
+    pub shrunk: Vec<T>,
+    pub message: String,
+}
+
+// Run the property, and turn the panic into the error as well.
+fn run<T>(prop: &impl Fn(&[T]) -> Result<(), String>, input: &[T]) -> Result<(), String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| prop(input))) {
+        Ok(res) => res,
+        Err(payload) => Err(match payload.downcast::<String>() {
+            Ok(s) => *s,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(s) => s.to_string(),
+                Err(_) => String::from("panicked"),
+            },
+        }),
+    }
+}
+
+/// Remove the chunks of the failing sequence as long as it still fails, from the halves down to
+/// the single elements, which is the simplified delta debugging.
+pub fn shrink<T: Clone>(
+    mut input: Vec<T>,
+    mut message: String,
+    prop: &impl Fn(&[T]) -> Result<(), String>,
+) -> (Vec<T>, String) {
+    let mut chunk = input.len() / 2;
+    while chunk > 0 {
+        let mut removed = false;
+        let mut i = 0;
+        while i < input.len() {
+            let mut candidate = input.clone();
+            candidate.drain(i..(i + chunk).min(input.len()));
+            match run(prop, &candidate) {
+                Err(m) => {
+                    input = candidate;
+                    message = m;
+                    removed = true;
+                }
+                Ok(()) => i += chunk,
+            }
+        }
+        if !removed {
+            chunk /= 2;
+        }
+    }
+    (input, message)
+}
+
+/// Run the property against the random sequences, and return the first failure after shrinking.
+pub fn find_failure<T: Clone>(
+    config: &Config,
+    gen_elem: impl Fn(&mut Gen) -> T,
+    prop: impl Fn(&[T]) -> Result<(), String>,
+) -> Option<Failure<T>> {
+    for case in 0..config.cases {
+        let seed = config.seed.wrapping_add(case as u64);
+        let mut gen = Gen::new(seed);
+        let len = gen.below(config.max_len + 1);
+        let input: Vec<T> = (0..len).map(|_| gen_elem(&mut gen)).collect();
+        if let Err(message) = run(&prop, &input) {
+            let (shrunk, message) = shrink(input, message, &prop);
+            return Some(Failure {
+                seed,
+                original_len: len,
+                shrunk,
+                message,
+            });
+        }
+    }
+    None
+}
+
+/// Check the property, and panic with the minimal failing sequence and its seed.
+pub fn check<T: Clone + Debug>(
+    config: &Config,
+    gen_elem: impl Fn(&mut Gen) -> T,
+    prop: impl Fn(&[T]) -> Result<(), String>,
+) {
+    if let Some(f) = find_failure(config, gen_elem, prop) {
+        panic!(
+            "The property fails with PROP_SEED={}, shrunk from {} to {} elements: {:?}\n{}",
+            f.seed,
+            f.original_len,
+            f.shrunk.len(),
+            f.shrunk,
+            f.message
+        );
+    }
+}
+
+pub fn ensure_eq<T: PartialEq + Debug>(
+    actual: T,
+    expected: T,
+    what: impl Display,
+) -> Result<(), String> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected {:?}, but got {:?}",
+            what, expected, actual
+        ))
+    }
+}
+
+/// The reference implementation of a container, whose behavior defines the correct one.
+pub trait Model<Op> {
+    type Output: PartialEq + Debug;
+    type State: PartialEq + Debug;
+
+    fn apply(&mut self, op: &Op) -> Self::Output;
+
+    fn state(&self) -> Self::State;
+}
+
+/// Apply the operations to the container and the model in lockstep, and compare the output of
+/// every operation and the state after it.
+pub fn run_model<S, M, Op>(
+    ops: &[Op],
+    sut: &mut S,
+    model: &mut M,
+    apply: impl Fn(&mut S, &Op) -> M::Output,
+    state: impl Fn(&S) -> M::State,
+) -> Result<(), String>
+where
+    M: Model<Op>,
+    Op: Debug,
+{
+    for (i, op) in ops.iter().enumerate() {
+        ensure_eq(
+            apply(sut, op),
+            model.apply(op),
+            format_args!("#{} {:?}", i, op),
+        )?;
+        ensure_eq(
+            state(sut),
+            model.state(),
+            format_args!("after #{} {:?}", i, op),
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub enum StackOp {
+    Push(i32),
+    Pop,
+    Peek,
+}
+
+pub fn gen_stack_op(gen: &mut Gen) -> StackOp {
+    match gen.weighted(&[3, 2, 1]) {
+        0 => StackOp::Push(gen.int_in(0..100)),
+        1 => StackOp::Pop,
+        _ => StackOp::Peek,
+    }
+}
+
+/// The stack is the end of the vector, and its state is listed from the top.
+impl Model<StackOp> for Vec<i32> {
+    type Output = Option<i32>;
+    type State = Vec<i32>;
+
+    fn apply(&mut self, op: &StackOp) -> Option<i32> {
+        match op {
+            StackOp::Push(x) => {
+                self.push(*x);
+                None
+            }
+            StackOp::Pop => self.pop(),
+            StackOp::Peek => self.last().copied(),
+        }
+    }
+
+    fn state(&self) -> Vec<i32> {
+        self.iter().rev().copied().collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum DequeOp {
+    PushFront(i32),
+    PushBack(i32),
+    PopFront,
+    PopBack,
+}
+
+/// Generate the deque operations, but only the ones whose kinds are allowed, in the order of
+/// push front, push back, pop front and pop back.
+pub fn gen_deque_op(gen: &mut Gen, allowed: [bool; 4]) -> DequeOp {
+    let weights: Vec<usize> = [3, 3, 2, 2]
+        .iter()
+        .zip(allowed)
+        .map(|(w, a)| if a { *w } else { 0 })
+        .collect();
+    match gen.weighted(&weights) {
+        0 => DequeOp::PushFront(gen.int_in(0..100)),
+        1 => DequeOp::PushBack(gen.int_in(0..100)),
+        2 => DequeOp::PopFront,
+        _ => DequeOp::PopBack,
+    }
+}
+
+impl Model<DequeOp> for VecDeque<i32> {
+    type Output = Option<i32>;
+    type State = Vec<i32>;
+
+    fn apply(&mut self, op: &DequeOp) -> Option<i32> {
+        match op {
+            DequeOp::PushFront(x) => {
+                self.push_front(*x);
+                None
+            }
+            DequeOp::PushBack(x) => {
+                self.push_back(*x);
+                None
+            }
+            DequeOp::PopFront => self.pop_front(),
+            DequeOp::PopBack => self.pop_back(),
+        }
+    }
+
+    fn state(&self) -> Vec<i32> {
+        self.iter().copied().collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum MapOp {
+    Insert(i32, u32),
+    Remove(i32),
+    Get(i32),
+    Range(i32, i32),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MapOutput {
+    Value(Option<u32>),
+    Pairs(Vec<(i32, u32)>),
+}
+
+/// Generate the map operations on the keys in [0, key_range), and the range queries only if
+/// `ordered`.
+pub fn gen_map_op(gen: &mut Gen, key_range: i32, ordered: bool) -> MapOp {
+    let k = gen.int_in(0..key_range);
+    match gen.weighted(&[4, 2, 2, if ordered { 1 } else { 0 }]) {
+        0 => MapOp::Insert(k, gen.u32()),
+        1 => MapOp::Remove(k),
+        2 => MapOp::Get(k),
+        _ => MapOp::Range(k, k + gen.int_in(0..key_range / 4 + 1)),
+    }
+}
+
+/// Apply the operation to any `Map`, and the range queries need `apply_ordered_map`.
+pub fn apply_map<M: Map<i32, u32>>(map: &mut M, op: &MapOp) -> MapOutput {
+    MapOutput::Value(match op {
+        MapOp::Insert(k, v) => map.insert(*k, *v),
+        MapOp::Remove(k) => map.remove(k),
+        MapOp::Get(k) => map.get_cloned(k),
+        MapOp::Range(..) => panic!("The range query needs the ordered map"),
+    })
+}
+
+pub fn apply_ordered_map<M: OrderedMap<i32, u32>>(map: &mut M, op: &MapOp) -> MapOutput {
+    match op {
+        MapOp::Range(lo, hi) => MapOutput::Pairs(map.range_to_vec(*lo..*hi)),
+        _ => apply_map(map, op),
+    }
+}
+
+/// The pairs of any `Map` in the order of the keys.
+pub fn sorted_pairs<M: Map<i32, u32>>(map: &M) -> Vec<(i32, u32)> {
+    let mut pairs = map.to_vec();
+    pairs.sort_unstable();
+    pairs
+}
+
+impl Model<MapOp> for BTreeMap<i32, u32> {
+    type Output = MapOutput;
+    type State = Vec<(i32, u32)>;
+
+    fn apply(&mut self, op: &MapOp) -> MapOutput {
+        match op {
+            MapOp::Insert(k, v) => MapOutput::Value(self.insert(*k, *v)),
+            MapOp::Remove(k) => MapOutput::Value(self.remove(k)),
+            MapOp::Get(k) => MapOutput::Value(self.get(k).copied()),
+            MapOp::Range(lo, hi) => {
+                MapOutput::Pairs(self.range(*lo..*hi).map(|(k, v)| (*k, *v)).collect())
+            }
+        }
+    }
+
+    fn state(&self) -> Vec<(i32, u32)> {
+        self.iter().map(|(k, v)| (*k, *v)).collect()
+    }
+}
+
+/// The model of the unordered maps, which has no range queries.
+impl Model<MapOp> for HashMap<i32, u32> {
+    type Output = MapOutput;
+    type State = Vec<(i32, u32)>;
+
+    fn apply(&mut self, op: &MapOp) -> MapOutput {
+        MapOutput::Value(match op {
+            MapOp::Insert(k, v) => self.insert(*k, *v),
+            MapOp::Remove(k) => self.remove(k),
+            MapOp::Get(k) => self.get(k).copied(),
+            MapOp::Range(..) => panic!("HashMap has no range queries"),
+        })
+    }
+
+    fn state(&self) -> Vec<(i32, u32)> {
+        let mut pairs: Vec<(i32, u32)> = self.iter().map(|(k, v)| (*k, *v)).collect();
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+/// Check the ordered map against `BTreeMap`.
+pub fn check_ordered_map<M: OrderedMap<i32, u32>>(make: impl Fn() -> M) {
+    for key_range in [16, 1000] {
+        check(
+            &Config::default(),
+            |gen| gen_map_op(gen, key_range, true),
+            |ops| {
+                let mut model = BTreeMap::new();
+                run_model(ops, &mut make(), &mut model, apply_ordered_map, M::to_vec)
+            },
+        );
+    }
+}
+
+/// Check the unordered map against `HashMap`.
+pub fn check_map<M: Map<i32, u32>>(make: impl Fn() -> M) {
+    for key_range in [16, 1000] {
+        check(
+            &Config::default(),
+            |gen| gen_map_op(gen, key_range, false),
+            |ops| {
+                let mut model = HashMap::new();
+                run_model(ops, &mut make(), &mut model, apply_map, sorted_pairs)
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::avl_tree::AVLTree;
+    use crate::basic::b_tree::BTree;
+    use crate::basic::concurrent_deque::{work_stealing, MsQueue, MutexDeque, Steal};
+    use crate::basic::double_linked_list_unsafe::LinkedList;
+    use crate::basic::gap_buffer::GapBuffer;
+    use crate::basic::hash_table_v1::HashTable;
+    use crate::basic::hash_table_v2;
+    use crate::basic::lru_cache::{LfuCache, LruCache};
+    use crate::basic::rb_tree::RBTree;
+    use crate::basic::scapegoat_tree::ScapegoatTree;
+    use crate::basic::skiplist::SkipList;
+    use crate::basic::skiplist_unsafe;
+    use crate::basic::splay_tree::SplayTree;
+    use crate::basic::treap::Treap;
+    use crate::basic::trie_tree::TrieTree;
+    use crate::basic::visit_binary_tree_safe::Tree;
+    use crate::basic::{double_linked_list_v1, double_linked_list_v2, double_linked_list_v3};
+    use crate::basic::{linked_list_unsafe, linked_list_v1, linked_list_v2, linked_list_v3};
+    use crate::basic::{ring_buffer, vec_deque, visit_binary_tree_unsafe};
+
+    #[test]
+    pub fn test_gen() {
+        let mut a = Gen::new(7);
+        let mut b = Gen::new(7);
+        for _ in 0..1000 {
+            let x = a.int_in(-5..5);
+            assert!((-5..5).contains(&x));
+            assert_eq!(x, b.int_in(-5..5));
+            assert!(a.below(3) < 3);
+            assert_ne!(a.weighted(&[1, 0, 1]), 1);
+            b.below(3);
+            b.weighted(&[1, 0, 1]);
+        }
+    }
+
+    #[test]
+    pub fn test_shrink() {
+        // The buggy stack drops the pushed element whenever it already holds three elements.
+        let prop = |ops: &[StackOp]| {
+            let mut sut: Vec<i32> = Vec::new();
+            let apply = |s: &mut Vec<i32>, op: &StackOp| match op {
+                StackOp::Push(x) if s.len() == 3 => {
+                    let _ = x;
+                    None
+                }
+                _ => s.apply(op),
+            };
+            run_model(ops, &mut sut, &mut Vec::new(), apply, Vec::state)
+        };
+        let failure = find_failure(&Config::default(), gen_stack_op, prop).unwrap();
+        assert!(failure.original_len >= 4);
+        assert_eq!(failure.shrunk.len(), 4);
+        assert!(failure
+            .shrunk
+            .iter()
+            .all(|op| matches!(op, StackOp::Push(_))));
+        assert!(failure.message.contains("after #3"));
+
+        // The same seed finds the same failure.
+        let config = Config {
+            cases: 1,
+            ..Config::default()
+        };
+        let config = Config {
+            seed: failure.seed,
+            ..config
+        };
+        let again = find_failure(&config, gen_stack_op, prop).unwrap();
+        assert_eq!(again.original_len, failure.original_len);
+
+        // The panics are failures as well.
+        let failure = find_failure(&Config::default(), gen_stack_op, |ops| {
+            assert!(ops.len() < 10, "too long");
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(failure.shrunk.len(), 10);
+        assert!(failure.message.contains("too long"));
+    }
+
+    #[test]
+    #[should_panic(expected = "PROP_SEED")]
+    pub fn test_check_panics() {
+        check(
+            &Config::default(),
+            |gen| gen.below(10),
+            |xs| ensure_eq(xs.contains(&9), false, "no nine"),
+        );
+    }
+
+    #[test]
+    pub fn prop_linked_list_v1() {
+        // The list can't be iterated, so only the outputs are compared.
+        check(&Config::default(), gen_stack_op, |ops| {
+            let mut sut = linked_list_v1::List::new();
+            let mut model = Vec::new();
+            for (i, op) in ops.iter().enumerate() {
+                let out = match op {
+                    StackOp::Push(x) => {
+                        sut.push(*x);
+                        None
+                    }
+                    StackOp::Pop => sut.pop(),
+                    // The list has no peek, so pop and push it back.
+                    StackOp::Peek => sut.pop().inspect(|x| sut.push(*x)),
+                };
+                ensure_eq(out, model.apply(op), format_args!("#{} {:?}", i, op))?;
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    pub fn prop_linked_list_v2() {
+        check(&Config::default(), gen_stack_op, |ops| {
+            let apply = |s: &mut linked_list_v2::List<i32>, op: &StackOp| match op {
+                StackOp::Push(x) => {
+                    s.push(*x);
+                    None
+                }
+                StackOp::Pop => s.pop(),
+                StackOp::Peek => s.peek().copied(),
+            };
+            let state = |s: &linked_list_v2::List<i32>| s.iter().copied().collect();
+            run_model(
+                ops,
+                &mut linked_list_v2::List::new(),
+                &mut Vec::new(),
+                apply,
+                state,
+            )
+        });
+    }
+
+    #[test]
+    pub fn prop_linked_list_v3() {
+        // The persistent list is checked with all its old versions, which must never change.
+        check(&Config::default(), gen_stack_op, |ops| {
+            let mut list = linked_list_v3::List::new();
+            let mut model: Vec<i32> = Vec::new();
+            let mut versions = Vec::new();
+            for (i, op) in ops.iter().enumerate() {
+                let out = match op {
+                    StackOp::Push(x) => {
+                        list = list.push(*x);
+                        None
+                    }
+                    StackOp::Pop => {
+                        let (x, tail) = match list.pop() {
+                            None => (None, list.clone()),
+                            Some((x, tail)) => (Some(*x), tail),
+                        };
+                        list = tail;
+                        x
+                    }
+                    StackOp::Peek => list.peek().copied(),
+                };
+                ensure_eq(out, model.apply(op), format_args!("#{} {:?}", i, op))?;
+                versions.push((list.clone(), model.state()));
+            }
+            for (i, (list, expected)) in versions.iter().enumerate() {
+                let actual: Vec<i32> = list.iter().copied().collect();
+                ensure_eq(&actual, expected, format_args!("version #{}", i))?;
+                ensure_eq(list.len(), expected.len(), format_args!("len of #{}", i))?;
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    pub fn prop_linked_list_unsafe() {
+        let gen = |gen: &mut Gen| gen_deque_op(gen, [true, true, true, false]);
+        check(&Config::default(), gen, |ops| {
+            let apply = |s: &mut linked_list_unsafe::List<i32>, op: &DequeOp| match op {
+                DequeOp::PushFront(x) => {
+                    s.push_front(*x);
+                    None
+                }
+                DequeOp::PushBack(x) => {
+                    s.push_back(*x);
+                    None
+                }
+                _ => s.pop(),
+            };
+            let state = |s: &linked_list_unsafe::List<i32>| s.iter().copied().collect();
+            let mut sut = linked_list_unsafe::List::new();
+            run_model(ops, &mut sut, &mut VecDeque::new(), apply, state)
+        });
+    }
+
+    #[test]
+    pub fn prop_double_linked_list_v1() {
+        check(
+            &Config::default(),
+            |gen| gen_deque_op(gen, [true; 4]),
+            |ops| {
+                let apply = |s: &mut double_linked_list_v1::List<i32>, op: &DequeOp| {
+                    let out = match op {
+                        DequeOp::PushFront(x) => {
+                            s.push_front(*x);
+                            None
+                        }
+                        DequeOp::PushBack(x) => {
+                            s.push_back(*x);
+                            None
+                        }
+                        DequeOp::PopFront => s.pop_front(),
+                        DequeOp::PopBack => s.pop_back(),
+                    };
+                    // The ends are the only observable state without consuming the list.
+                    let ends = (s.peek_front().map(|x| *x), s.peek_back().map(|x| *x));
+                    (out, ends)
+                };
+                let mut sut = double_linked_list_v1::List::new();
+                let mut model = VecDeque::new();
+                for (i, op) in ops.iter().enumerate() {
+                    let (out, ends) = apply(&mut sut, op);
+                    ensure_eq(out, model.apply(op), format_args!("#{} {:?}", i, op))?;
+                    let expected = (model.front().copied(), model.back().copied());
+                    ensure_eq(ends, expected, format_args!("ends after #{}", i))?;
+                }
+                ensure_eq(sut.into_iter().collect(), model.state(), "all")
+            },
+        );
+    }
+
+    #[test]
+    pub fn prop_double_linked_list_v2() {
+        // The list can only append, and print itself in the format of the vector.
+        check(
+            &Config::default(),
+            |gen| gen.int_in(0..100),
+            |elems| {
+                let mut sut = double_linked_list_v2::List::new();
+                let mut model = Vec::new();
+                for (i, x) in elems.iter().enumerate() {
+                    sut.append(*x);
+                    model.push(*x);
+                    ensure_eq(
+                        sut.to_string(),
+                        format!("{:?}", model),
+                        format_args!("after #{}", i),
+                    )?;
+                }
+                Ok(())
+            },
+        );
+    }
+
+    #[test]
+    pub fn prop_double_linked_list_v3() {
+        check(
+            &Config::default(),
+            |gen| gen_deque_op(gen, [true, false, false, false]),
+            |ops| {
+                let apply = |s: &mut double_linked_list_v3::List<i32>, op: &DequeOp| {
+                    if let DequeOp::PushFront(x) = op {
+                        s.push_front(*x);
+                    }
+                    None
+                };
+                let state = |s: &double_linked_list_v3::List<i32>| {
+                    assert_eq!(s.len(), s.iter().count());
+                    assert!(s
+                        .iter()
+                        .rev()
+                        .eq(s.iter().collect::<Vec<_>>().into_iter().rev()));
+                    s.iter().copied().collect()
+                };
+                let mut sut = double_linked_list_v3::List::new();
+                run_model(ops, &mut sut, &mut VecDeque::new(), apply, state)
+            },
+        );
+    }
+
+    fn apply_deque<S>(
+        push_front: fn(&mut S, i32),
+        push_back: fn(&mut S, i32),
+        pop_front: fn(&mut S) -> Option<i32>,
+        pop_back: fn(&mut S) -> Option<i32>,
+    ) -> impl Fn(&mut S, &DequeOp) -> Option<i32> {
+        move |s, op| match op {
+            DequeOp::PushFront(x) => {
+                push_front(s, *x);
+                None
+            }
+            DequeOp::PushBack(x) => {
+                push_back(s, *x);
+                None
+            }
+            DequeOp::PopFront => pop_front(s),
+            DequeOp::PopBack => pop_back(s),
+        }
+    }
+
+    #[test]
+    pub fn prop_double_linked_list_unsafe() {
+        check(
+            &Config::default(),
+            |gen| gen_deque_op(gen, [true; 4]),
+            |ops| {
+                let apply = apply_deque(
+                    LinkedList::push_front,
+                    LinkedList::push_back,
+                    LinkedList::pop_front,
+                    LinkedList::pop_back,
+                );
+                let state = |s: &LinkedList<i32>| {
+                    assert_eq!(s.len(), s.iter().count());
+                    s.iter().copied().collect()
+                };
+                run_model(
+                    ops,
+                    &mut LinkedList::new(),
+                    &mut VecDeque::new(),
+                    apply,
+                    state,
+                )
+            },
+        );
+    }
+
+    #[test]
+    pub fn prop_vec_deque() {
+        check(
+            &Config::default(),
+            |gen| gen_deque_op(gen, [true; 4]),
+            |ops| {
+                let apply = apply_deque(
+                    vec_deque::VecDeque::push_front,
+                    vec_deque::VecDeque::push_back,
+                    vec_deque::VecDeque::pop_front,
+                    vec_deque::VecDeque::pop_back,
+                );
+                let state = |s: &vec_deque::VecDeque<i32>| {
+                    assert!((0..s.len()).map(|i| s[i]).eq(s.iter().copied()));
+                    s.iter().copied().collect()
+                };
+                let mut sut = vec_deque::VecDeque::with_capacity(1);
+                run_model(ops, &mut sut, &mut VecDeque::new(), apply, state)
+            },
+        );
+    }
+
+    #[test]
+    pub fn prop_mutex_deque() {
+        check(
+            &Config::default(),
+            |gen| gen_deque_op(gen, [true; 4]),
+            |ops| {
+                let apply = apply_deque(
+                    |s: &mut MutexDeque<i32>, x| s.push_front(x),
+                    |s: &mut MutexDeque<i32>, x| s.push_back(x),
+                    |s: &mut MutexDeque<i32>| s.pop_front(),
+                    |s: &mut MutexDeque<i32>| s.pop_back(),
+                );
+                // Only the ends and the length are observable.
+                let state = |s: &MutexDeque<i32>| (s.len(), s.peek_front(), s.peek_back());
+                let mut sut = MutexDeque::new();
+                let mut model = VecDeque::new();
+                for (i, op) in ops.iter().enumerate() {
+                    ensure_eq(apply(&mut sut, op), model.apply(op), format_args!("#{}", i))?;
+                    let expected = (model.len(), model.front().copied(), model.back().copied());
+                    ensure_eq(state(&sut), expected, format_args!("after #{}", i))?;
+                }
+                Ok(())
+            },
+        );
+    }
+
+    // The model of the ring buffer is the bounded queue, which gives the value back when it's full.
+    struct Bounded {
+        capacity: usize,
+        elems: VecDeque<i32>,
+    }
+
+    impl Model<DequeOp> for Bounded {
+        type Output = Option<i32>;
+        type State = (usize, Option<i32>, bool);
+
+        fn apply(&mut self, op: &DequeOp) -> Option<i32> {
+            match op {
+                DequeOp::PushBack(x) if self.elems.len() == self.capacity => Some(*x),
+                DequeOp::PushBack(x) => {
+                    self.elems.push_back(*x);
+                    None
+                }
+                DequeOp::PopFront => self.elems.pop_front(),
+                _ => unreachable!(),
+            }
+        }
+
+        fn state(&self) -> (usize, Option<i32>, bool) {
+            let len = self.elems.len();
+            (len, self.elems.front().copied(), len == self.capacity)
+        }
+    }
+
+    #[test]
+    pub fn prop_ring_buffer() {
+        type Spsc = (ring_buffer::Producer<i32>, ring_buffer::Consumer<i32>);
+        for capacity in [1, 3, 8] {
+            check(
+                &Config::default(),
+                |gen| gen_deque_op(gen, [false, true, true, false]),
+                |ops| {
+                    let apply = |(p, c): &mut Spsc, op: &DequeOp| match op {
+                        DequeOp::PushBack(x) => p.push(*x).err(),
+                        DequeOp::PopFront => c.pop(),
+                        _ => unreachable!(),
+                    };
+                    // Only the front, the length and the fullness are observable.
+                    let state = |(p, c): &Spsc| {
+                        assert_eq!(p.len(), c.len());
+                        (c.len(), c.peek().copied(), p.is_full())
+                    };
+                    let mut model = Bounded {
+                        capacity,
+                        elems: VecDeque::new(),
+                    };
+                    let mut sut = ring_buffer::ring_buffer(capacity);
+                    run_model(ops, &mut sut, &mut model, apply, state)
+                },
+            );
+        }
+    }
+
+    #[test]
+    pub fn prop_ms_queue() {
+        // The concurrency is covered by the tests of the queue, and this checks the FIFO order.
+        check(
+            &Config::default(),
+            |gen| gen_deque_op(gen, [false, true, true, false]),
+            |ops| {
+                let apply = apply_deque(
+                    |_, _| unreachable!(),
+                    |s: &mut MsQueue<i32>, x| s.push(x),
+                    |s: &mut MsQueue<i32>| s.pop(),
+                    |_| unreachable!(),
+                );
+                let mut sut = MsQueue::new();
+                let mut model = VecDeque::new();
+                for (i, op) in ops.iter().enumerate() {
+                    ensure_eq(apply(&mut sut, op), model.apply(op), format_args!("#{}", i))?;
+                    ensure_eq(
+                        sut.is_empty(),
+                        model.is_empty(),
+                        format_args!("after #{}", i),
+                    )?;
+                }
+                Ok(())
+            },
+        );
+    }
+
+    #[test]
+    pub fn prop_work_stealing() {
+        // The owner pushes and pops at the back, and the thief steals from the front. A steal
+        // without any race never retries.
+        check(
+            &Config::default(),
+            |gen| gen_deque_op(gen, [false, true, true, true]),
+            |ops| {
+                let (worker, stealer) = work_stealing();
+                let mut model = VecDeque::new();
+                for (i, op) in ops.iter().enumerate() {
+                    let out = match op {
+                        DequeOp::PushBack(x) => {
+                            worker.push(*x);
+                            None
+                        }
+                        DequeOp::PopBack => worker.pop(),
+                        DequeOp::PopFront => match stealer.steal() {
+                            Steal::Success(x) => Some(x),
+                            Steal::Empty => None,
+                            Steal::Retry => return Err(format!("#{} retried", i)),
+                        },
+                        _ => unreachable!(),
+                    };
+                    ensure_eq(out, model.apply(op), format_args!("#{} {:?}", i, op))?;
+                    ensure_eq(worker.len(), model.len(), format_args!("after #{}", i))?;
+                }
+                Ok(())
+            },
+        );
+    }
+
+    #[derive(Clone, Debug)]
+    enum EditOp {
+        Insert(i32),
+        DeleteBefore,
+        DeleteAfter,
+        // The position is taken modulo the length plus one.
+        MoveTo(usize),
+    }
+
+    // The model of the gap buffer is the vector with the cursor.
+    struct Editor {
+        elems: Vec<i32>,
+        cursor: usize,
+    }
+
+    impl Model<EditOp> for Editor {
+        type Output = Option<i32>;
+        type State = (Vec<i32>, usize);
+
+        fn apply(&mut self, op: &EditOp) -> Option<i32> {
+            match op {
+                EditOp::Insert(x) => {
+                    self.elems.insert(self.cursor, *x);
+                    self.cursor += 1;
+                    None
+                }
+                EditOp::DeleteBefore if self.cursor > 0 => {
+                    self.cursor -= 1;
+                    Some(self.elems.remove(self.cursor))
+                }
+                EditOp::DeleteAfter if self.cursor < self.elems.len() => {
+                    Some(self.elems.remove(self.cursor))
+                }
+                EditOp::MoveTo(pos) => {
+                    self.cursor = pos % (self.elems.len() + 1);
+                    None
+                }
+                _ => None,
+            }
+        }
+
+        fn state(&self) -> (Vec<i32>, usize) {
+            (self.elems.clone(), self.cursor)
+        }
+    }
+
+    #[test]
+    pub fn prop_gap_buffer() {
+        let gen = |gen: &mut Gen| match gen.weighted(&[4, 2, 2, 1]) {
+            0 => EditOp::Insert(gen.int_in(0..100)),
+            1 => EditOp::DeleteBefore,
+            2 => EditOp::DeleteAfter,
+            _ => EditOp::MoveTo(gen.below(1000)),
+        };
+        check(&Config::default(), gen, |ops| {
+            let apply = |s: &mut GapBuffer<i32>, op: &EditOp| match op {
+                EditOp::Insert(x) => {
+                    s.insert(*x);
+                    None
+                }
+                EditOp::DeleteBefore => s.delete_before(),
+                EditOp::DeleteAfter => s.delete_after(),
+                EditOp::MoveTo(pos) => {
+                    s.move_to(pos % (s.len() + 1));
+                    None
+                }
+            };
+            let state = |s: &GapBuffer<i32>| (s.iter().copied().collect(), s.cursor());
+            let mut model = Editor {
+                elems: Vec::new(),
+                cursor: 0,
+            };
+            run_model(ops, &mut GapBuffer::new(), &mut model, apply, state)
+        });
+    }
+
+    // The model of the LRU cache is the vector from the most recently used entry.
+    struct Lru {
+        capacity: usize,
+        entries: Vec<(i32, u32)>,
+    }
+
+    impl Model<MapOp> for Lru {
+        type Output = MapOutput;
+        type State = Vec<(i32, u32)>;
+
+        fn apply(&mut self, op: &MapOp) -> MapOutput {
+            let pos = |k: &i32| self.entries.iter().position(|e| e.0 == *k);
+            MapOutput::Value(match op {
+                MapOp::Insert(k, v) => match pos(k) {
+                    Some(i) => {
+                        let (_, old) = self.entries.remove(i);
+                        self.entries.insert(0, (*k, *v));
+                        Some(old)
+                    }
+                    None => {
+                        self.entries.truncate(self.capacity - 1);
+                        self.entries.insert(0, (*k, *v));
+                        None
+                    }
+                },
+                MapOp::Remove(k) => pos(k).map(|i| self.entries.remove(i).1),
+                MapOp::Get(k) => pos(k).map(|i| {
+                    let e = self.entries.remove(i);
+                    self.entries.insert(0, e);
+                    e.1
+                }),
+                MapOp::Range(..) => unreachable!(),
+            })
+        }
+
+        fn state(&self) -> Vec<(i32, u32)> {
+            self.entries.clone()
+        }
+    }
+
+    #[test]
+    pub fn prop_lru_cache() {
+        for capacity in [1, 3, 8] {
+            check(
+                &Config::default(),
+                |gen| gen_map_op(gen, 16, false),
+                |ops| {
+                    let apply = |s: &mut LruCache<i32, u32>, op: &MapOp| {
+                        MapOutput::Value(match op {
+                            MapOp::Insert(k, v) => s.put(*k, *v),
+                            MapOp::Remove(k) => s.remove(k),
+                            MapOp::Get(k) => s.get(k).copied(),
+                            MapOp::Range(..) => unreachable!(),
+                        })
+                    };
+                    let state = |s: &LruCache<i32, u32>| {
+                        s.keys()
+                            .into_iter()
+                            .map(|k| (*k, *s.peek(k).unwrap()))
+                            .collect()
+                    };
+                    let mut model = Lru {
+                        capacity,
+                        entries: Vec::new(),
+                    };
+                    run_model(ops, &mut LruCache::new(capacity), &mut model, apply, state)
+                },
+            );
+        }
+    }
+
+    // The model of the LFU cache is the entries of (key, value, frequency, last used time), and the
+    // victim is the least frequently used one, then the least recently used one.
+    struct Lfu {
+        capacity: usize,
+        time: usize,
+        entries: Vec<(i32, u32, u64, usize)>,
+    }
+
+    impl Model<MapOp> for Lfu {
+        type Output = MapOutput;
+        type State = Vec<(i32, u32, u64)>;
+
+        fn apply(&mut self, op: &MapOp) -> MapOutput {
+            self.time += 1;
+            let t = self.time;
+            let pos = |k: &i32| self.entries.iter().position(|e| e.0 == *k);
+            MapOutput::Value(match op {
+                MapOp::Insert(k, v) => match pos(k) {
+                    Some(i) => {
+                        let e = &mut self.entries[i];
+                        let old = e.1;
+                        *e = (*k, *v, e.2 + 1, t);
+                        Some(old)
+                    }
+                    None => {
+                        if self.entries.len() == self.capacity {
+                            let victim = (0..self.entries.len())
+                                .min_by_key(|&i| (self.entries[i].2, self.entries[i].3))
+                                .unwrap();
+                            self.entries.remove(victim);
+                        }
+                        self.entries.push((*k, *v, 1, t));
+                        None
+                    }
+                },
+                MapOp::Remove(k) => pos(k).map(|i| self.entries.remove(i).1),
+                MapOp::Get(k) => pos(k).map(|i| {
+                    let e = &mut self.entries[i];
+                    e.2 += 1;
+                    e.3 = t;
+                    e.1
+                }),
+                MapOp::Range(..) => unreachable!(),
+            })
+        }
+
+        fn state(&self) -> Vec<(i32, u32, u64)> {
+            let mut state: Vec<_> = self.entries.iter().map(|e| (e.0, e.1, e.2)).collect();
+            state.sort();
+            state
+        }
+    }
+
+    #[test]
+    pub fn prop_lfu_cache() {
+        for capacity in [1, 3, 8] {
+            check(
+                &Config::default(),
+                |gen| gen_map_op(gen, 16, false),
+                |ops| {
+                    let apply = |s: &mut LfuCache<i32, u32>, op: &MapOp| {
+                        MapOutput::Value(match op {
+                            MapOp::Insert(k, v) => s.put(*k, *v),
+                            MapOp::Remove(k) => s.remove(k),
+                            MapOp::Get(k) => s.get(k).copied(),
+                            MapOp::Range(..) => unreachable!(),
+                        })
+                    };
+                    // The cache can't be iterated, so all the keys of the ops are peeked.
+                    let state = |s: &LfuCache<i32, u32>| {
+                        let state: Vec<_> = (0..16)
+                            .filter_map(|k| Some((k, *s.peek(&k)?, s.frequency(&k)?)))
+                            .collect();
+                        assert_eq!(state.len(), s.len());
+                        state
+                    };
+                    let mut model = Lfu {
+                        capacity,
+                        time: 0,
+                        entries: Vec::new(),
+                    };
+                    run_model(ops, &mut LfuCache::new(capacity), &mut model, apply, state)
+                },
+            );
+        }
+    }
+
+    #[test]
+    pub fn prop_hash_tables() {
+        check_map(HashTable::new);
+        check_map(hash_table_v2::HashMap::new);
+    }
+
+    #[test]
+    pub fn prop_ordered_maps() {
+        check_ordered_map(AVLTree::new);
+        check_ordered_map(RBTree::new);
+        check_ordered_map(BTree::new);
+        check_ordered_map(|| BTree::with_order(3));
+        check_ordered_map(Treap::new);
+        check_ordered_map(SplayTree::new);
+        check_ordered_map(ScapegoatTree::new);
+        check_ordered_map(SkipList::new);
+        check_ordered_map(skiplist_unsafe::SkipList::new);
+    }
+
+    #[test]
+    pub fn prop_simple_tree() {
+        // The tree is a set, so it's checked against the keys of the model.
+        use crate::basic::simple_tree::SimpleTree;
+        check(
+            &Config::default(),
+            |gen| gen_map_op(gen, 64, true),
+            |ops| {
+                let apply = |s: &mut SimpleTree<i32>, op: &MapOp| {
+                    let found = |x: Option<()>| MapOutput::Value(x.map(|_| 0));
+                    match op {
+                        MapOp::Insert(k, _) => found(Map::insert(s, *k, ())),
+                        MapOp::Remove(k) => found(Map::remove(s, k)),
+                        MapOp::Get(k) => found(s.get_cloned(k)),
+                        MapOp::Range(lo, hi) => MapOutput::Pairs(
+                            s.range_to_vec(*lo..*hi)
+                                .iter()
+                                .map(|(k, _)| (*k, 0))
+                                .collect(),
+                        ),
+                    }
+                };
+                let state = |s: &SimpleTree<i32>| s.iter().map(|k| (*k, 0)).collect();
+                // The values of the model are always 0 as well.
+                let ops: Vec<MapOp> = ops
+                    .iter()
+                    .map(|op| match op {
+                        MapOp::Insert(k, _) => MapOp::Insert(*k, 0),
+                        op => op.clone(),
+                    })
+                    .collect();
+                run_model(
+                    &ops,
+                    &mut SimpleTree::new(),
+                    &mut BTreeMap::new(),
+                    apply,
+                    state,
+                )
+            },
+        );
+    }
+
+    #[derive(Clone, Debug)]
+    enum WordOp {
+        Add(String),
+        Delete(String),
+        Find(String),
+    }
+
+    // All the words of "a" and "b" up to 3 letters, including the empty one.
+    fn small_words() -> Vec<String> {
+        let mut words = vec![String::new()];
+        for i in 0.. {
+            if words[i].len() == 3 {
+                break;
+            }
+            let w = words[i].clone();
+            words.push(w.clone() + "a");
+            words.push(w + "b");
+        }
+        words
+    }
+
+    // The model of the trie is the multiset of the words.
+    impl Model<WordOp> for BTreeMap<String, usize> {
+        type Output = Option<bool>;
+        type State = Vec<bool>;
+
+        fn apply(&mut self, op: &WordOp) -> Option<bool> {
+            match op {
+                WordOp::Add(w) => {
+                    *self.entry(w.clone()).or_insert(0) += 1;
+                    None
+                }
+                WordOp::Delete(w) => Some(match self.get_mut(w) {
+                    Some(n) if *n > 1 => {
+                        *n -= 1;
+                        true
+                    }
+                    Some(_) => self.remove(w).is_some(),
+                    None => false,
+                }),
+                WordOp::Find(w) => Some(self.contains_key(w)),
+            }
+        }
+
+        fn state(&self) -> Vec<bool> {
+            small_words().iter().map(|w| self.contains_key(w)).collect()
+        }
+    }
+
+    #[test]
+    pub fn prop_trie_tree() {
+        let words = small_words();
+        let gen = |gen: &mut Gen| {
+            let w = words[gen.below(words.len())].clone();
+            match gen.weighted(&[3, 2, 2]) {
+                0 => WordOp::Add(w),
+                1 => WordOp::Delete(w),
+                _ => WordOp::Find(w),
+            }
+        };
+        check(&Config::default(), gen, |ops| {
+            let apply = |s: &mut TrieTree, op: &WordOp| match op {
+                WordOp::Add(w) => {
+                    s.add(w);
+                    None
+                }
+                WordOp::Delete(w) => Some(s.delete(w)),
+                WordOp::Find(w) => Some(s.find(w)),
+            };
+            let state = |s: &TrieTree| words.iter().map(|w| s.find(w)).collect();
+            run_model(
+                ops,
+                &mut TrieTree::new(),
+                &mut BTreeMap::new(),
+                apply,
+                state,
+            )
+        });
+    }
+
+    // The node i of the tree has the value i, and it's added as a child of the node `.0`.
+    #[derive(Clone, Debug)]
+    enum TreeOp {
+        Root,
+        Left(usize, usize),
+        Right(usize, usize),
+    }
+
+    // The model of the binary tree is the children of every node.
+    struct Arena {
+        children: Vec<[Option<usize>; 2]>,
+    }
+
+    impl Arena {
+        // Visit the subtree in the pre-order, in-order or post-order for `order` of 0, 1 or 2.
+        fn visit(&self, i: usize, order: usize, out: &mut Vec<usize>) {
+            let [l, r] = self.children[i];
+            if order == 0 {
+                out.push(i);
+            }
+            if let Some(l) = l {
+                self.visit(l, order, out);
+            }
+            if order == 1 {
+                out.push(i);
+            }
+            if let Some(r) = r {
+                self.visit(r, order, out);
+            }
+            if order == 2 {
+                out.push(i);
+            }
+        }
+    }
+
+    impl Model<TreeOp> for Arena {
+        type Output = ();
+        type State = [Vec<usize>; 3];
+
+        fn apply(&mut self, op: &TreeOp) {
+            match *op {
+                TreeOp::Root => {}
+                TreeOp::Left(p, c) => self.children[p][0] = Some(c),
+                TreeOp::Right(p, c) => self.children[p][1] = Some(c),
+            }
+            self.children.push([None, None]);
+        }
+
+        fn state(&self) -> [Vec<usize>; 3] {
+            [0, 1, 2].map(|order| {
+                let mut out = Vec::new();
+                if !self.children.is_empty() {
+                    self.visit(0, order, &mut out);
+                }
+                out
+            })
+        }
+    }
+
+    #[test]
+    pub fn prop_visit_binary_tree_unsafe() {
+        // Each element picks the parent and the side of the next node, which takes the other side
+        // if the picked one is taken, or is dropped if both are.
+        check(
+            &Config::default(),
+            |gen| (gen.below(64), gen.bool()),
+            |picks| {
+                let mut taken: Vec<[bool; 2]> = Vec::new();
+                let mut ops = Vec::new();
+                for &(p, left) in picks {
+                    let c = taken.len();
+                    if c == 0 {
+                        ops.push(TreeOp::Root);
+                        taken.push([false; 2]);
+                        continue;
+                    }
+                    let p = p % c;
+                    let side = [!left as usize, left as usize]
+                        .into_iter()
+                        .find(|&side| !taken[p][side]);
+                    if let Some(side) = side {
+                        taken[p][side] = true;
+                        taken.push([false; 2]);
+                        ops.push(if side == 0 {
+                            TreeOp::Left(p, c)
+                        } else {
+                            TreeOp::Right(p, c)
+                        });
+                    }
+                }
+
+                type UnsafeTree = visit_binary_tree_unsafe::Tree<usize>;
+                let apply = |s: &mut UnsafeTree, op: &TreeOp| {
+                    match *op {
+                        TreeOp::Root => s.init_root(0),
+                        TreeOp::Left(p, c) => s.add_left_child(p, c),
+                        TreeOp::Right(p, c) => s.add_right_child(p, c),
+                    }
+                    // The Morris traversals agree with the recursive ones, so the threads written
+                    // by the first one are removed.
+                    let mut pre = Vec::new();
+                    s.morris_preorder(|x| pre.push(*x));
+                    let mut inorder = Vec::new();
+                    s.morris_inorder(|x| inorder.push(*x));
+                    assert!(pre.iter().eq(s.pre_visit()));
+                    assert!(inorder.iter().eq(s.inorder_visit()));
+                };
+                let state = |s: &UnsafeTree| {
+                    [s.pre_visit(), s.inorder_visit(), s.post_visit()]
+                        .map(|order| order.into_iter().copied().collect())
+                };
+                let mut model = Arena {
+                    children: Vec::new(),
+                };
+                run_model(&ops, &mut UnsafeTree::new(), &mut model, apply, state)
+            },
+        );
+    }
+
+    #[test]
+    pub fn prop_binary_tree_round_trips() {
+        // Each element says whether the node of the level order array is present, and the
+        // values are the distinct indexes.
+        check(&Config::with_max_len(60), Gen::bool, |present| {
+            let arr: Vec<Option<usize>> = (0..present.len())
+                .map(|i| present[i].then_some(i))
+                .collect();
+            let tree = Tree::from_level_order(&arr);
+
+            let copy = Tree::deserialize(&tree.serialize())?;
+            ensure_eq(copy.same_as(&tree), true, "serialize")?;
+            let copy = Tree::from_paren_str(&tree.to_paren_string())?;
+            ensure_eq(copy.same_as(&tree), true, "paren string")?;
+            let copy = Tree::from_level_order(&tree.to_level_order());
+            ensure_eq(copy.same_as(&tree), true, "level order")?;
+
+            let pre: Vec<usize> = tree.pre_iter().copied().collect();
+            let inorder: Vec<usize> = tree.inorder_iter().copied().collect();
+            let post: Vec<usize> = tree.post_iter().copied().collect();
+            ensure_eq(inorder.len(), tree.level_iter().count(), "size")?;
+            if !pre.is_empty() {
+                let copy = Tree::from_pre_in(&pre, &inorder).ok_or("from_pre_in")?;
+                ensure_eq(copy.same_as(&tree), true, "pre and in")?;
+                let copy = Tree::from_post_in(&post, &inorder).ok_or("from_post_in")?;
+                ensure_eq(copy.same_as(&tree), true, "post and in")?;
+            }
+            Ok(())
+        });
+    }
+}
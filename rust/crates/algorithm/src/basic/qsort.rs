@@ -1,32 +1,97 @@
-fn _sort_(v: &mut [i32], l: usize, r: usize) {
-    let mut i = l;
-    let x = v[r];
-    for j in l..r {
-        if v[j] <= x {
-            v.swap(i, j);
-            i += 1;
+use crate::basic::rand::{RandomSource, SplitMix64};
+use std::cmp::Ordering;
+
+const SEED: u64 = 0x5EED;
+
+// Move the median of three random elements of `v[l..r]` to `v[l]` as the pivot. The fixed
+// positions like the first, the middle and the last ones are defeated by the order which the
+// partition leaves behind in a sorted input, and the recursion gets quadratic again.
+fn choose_pivot<T, F: FnMut(&T, &T) -> Ordering>(
+    v: &mut [T],
+    l: usize,
+    r: usize,
+    cmp: &mut F,
+    rng: &mut SplitMix64,
+) {
+    let mut pick = || l + (rng.next_u64() % (r - l) as u64) as usize;
+    let (a, b, c) = (pick(), pick(), pick());
+    let ab = cmp(&v[a], &v[b]) == Ordering::Less;
+    let bc = cmp(&v[b], &v[c]) == Ordering::Less;
+    let ac = cmp(&v[a], &v[c]) == Ordering::Less;
+    let m = if ab == bc {
+        b
+    } else if ab == ac {
+        c
+    } else {
+        a
+    };
+    v.swap(l, m);
+}
+
+// The 3-way partition of `v[l..r]` by the pivot `v[l]`, returns `(lt, gt)` where `v[l..lt]` are
+// less than the pivot, `v[lt..gt]` are equal to it and `v[gt..r]` are greater. The equal keys are
+// done at once, so the input of all the same keys takes only one pass.
+fn partition<T, F: FnMut(&T, &T) -> Ordering>(
+    v: &mut [T],
+    l: usize,
+    r: usize,
+    cmp: &mut F,
+) -> (usize, usize) {
+    // `v[lt]` is always equal to the pivot, since `v[lt..i]` are all equal to it.
+    let (mut lt, mut i, mut gt) = (l, l + 1, r);
+    while i < gt {
+        match cmp(&v[i], &v[lt]) {
+            Ordering::Less => {
+                v.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                gt -= 1;
+                v.swap(i, gt);
+            }
+            Ordering::Equal => i += 1,
         }
     }
-    v.swap(i, r);
+    (lt, gt)
+}
 
-    if l < i {
-        _sort_(v, l, i - 1);
-    }
-    if i < r {
-        _sort_(v, i + 1, r);
+// Sort `v[l..r]`. It recurses into the smaller side and loops on the larger one, so the depth of
+// the recursion is at most log(n) whatever the input is.
+fn _sort_<T, F: FnMut(&T, &T) -> Ordering>(
+    v: &mut [T],
+    mut l: usize,
+    mut r: usize,
+    cmp: &mut F,
+    rng: &mut SplitMix64,
+) {
+    while r - l > 1 {
+        choose_pivot(v, l, r, cmp, rng);
+        let (lt, gt) = partition(v, l, r, cmp);
+        if lt - l < r - gt {
+            _sort_(v, l, lt, cmp, rng);
+            l = gt;
+        } else {
+            _sort_(v, gt, r, cmp, rng);
+            r = lt;
+        }
     }
 }
 
 pub fn qsort(v: &mut [i32]) {
-    if v.len() <= 1 {
-        return;
-    }
-    _sort_(v, 0, v.len() - 1);
+    qsort_by(v, |a, b| a.cmp(b));
+}
+
+/// Sort in place by the comparator, which isn't stable.
+pub fn qsort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut cmp: F) {
+    // The seed is fixed, so the comparisons of the same input are reproducible.
+    let mut rng = SplitMix64::new(SEED);
+    _sort_(v, 0, v.len(), &mut cmp, &mut rng);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::qsort;
+    use super::{qsort, qsort_by};
 
     #[test]
     pub fn test_qsort() {
@@ -50,4 +115,37 @@ mod tests {
         qsort(&mut vec);
         assert_eq!(vec, []);
     }
+
+    #[test]
+    pub fn test_qsort_by() {
+        let mut vec = vec![(2, 'a'), (1, 'b'), (3, 'c'), (1, 'd')];
+        qsort_by(&mut vec, |a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        assert_eq!(vec, [(3, 'c'), (2, 'a'), (1, 'b'), (1, 'd')]);
+
+        let mut vec = vec!["ccc", "a", "bb", ""];
+        qsort_by(&mut vec, |a, b| a.len().cmp(&b.len()));
+        assert_eq!(vec, ["", "a", "bb", "ccc"]);
+    }
+
+    #[test]
+    pub fn test_qsort_large() {
+        // They recursed n deep and overflowed the stack before.
+        let n = 1_000_000;
+        let mut vec: Vec<i32> = (0..n).collect();
+        qsort(&mut vec);
+        assert!(vec.iter().copied().eq(0..n));
+
+        vec.reverse();
+        qsort(&mut vec);
+        assert!(vec.iter().copied().eq(0..n));
+
+        let mut vec = vec![7; n as usize];
+        qsort(&mut vec);
+        assert!(vec.iter().all(|&x| x == 7));
+
+        let mut vec: Vec<i32> = (0..n).map(|i| i % 3).collect();
+        qsort(&mut vec);
+        assert!(vec.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(vec.iter().filter(|&&x| x == 1).count(), n as usize / 3);
+    }
 }
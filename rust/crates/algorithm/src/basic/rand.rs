@@ -21,6 +21,46 @@ pub fn shuffle<T>(v: &mut Vec<T>) {
     }
 }
 
+/// The source of the random numbers, which can be injected into the randomized data structures,
+/// so that they are reproducible by the seed and don't depend on the global state of libc.
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+}
+
+/// The source which can be created from a seed, e.g. to fork an independent stream from another.
+pub trait SeedableSource: RandomSource {
+    fn from_seed(seed: u64) -> Self;
+}
+
+/// The SplitMix64 generator, which is tiny and fast, and good enough for the randomized algorithms.
+/// Please see: https://prng.di.unimi.it/splitmix64.c
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl SeedableSource for SplitMix64 {
+    fn from_seed(seed: u64) -> Self {
+        SplitMix64::new(seed)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,4 +99,18 @@ mod tests {
 
         assert_eq!(hit, vec![true; 1000_0]);
     }
+
+    #[test]
+    pub fn test_split_mix64() {
+        // The first outputs of the reference implementation with seed 1234567.
+        let mut rng = SplitMix64::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+
+        let a: Vec<u64> = (0..100).map(|_| rng.next_u64()).collect();
+        let mut other = SplitMix64::new(1234567);
+        other.next_u64();
+        other.next_u64();
+        assert!(a.iter().all(|x| *x == other.next_u64()));
+    }
 }
@@ -0,0 +1,453 @@
+/**
+ * The left-leaning red-black tree, which is a binary search tree equivalent to the 2-3 tree: a red
+ * link glues a node to its parent as one 3-node, and red links always lean left. It needs fewer
+ * rotations than the AVL tree on insert and delete, so it suits the write-heavy workloads.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Left-leaning_red%E2%80%93black_tree
+ */
+use crate::basic::map::{range_for_each, BinaryNode, InOrder, Map, OrderedMap};
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::RangeBounds;
+
+pub struct RBTree<K, V> {
+    size: usize,
+    root: Link<K, V>,
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+pub struct Node<K, V> {
+    k: K,
+    v: V,
+    // Whether the link from the parent is red.
+    red: bool,
+    l: Link<K, V>,
+    r: Link<K, V>,
+}
+
+pub type Iter<'a, K, V> = InOrder<'a, Node<K, V>>;
+
+fn is_red<K, V>(link: &Link<K, V>) -> bool {
+    link.as_ref().is_some_and(|node| node.red)
+}
+
+/**
+ * Turn the right-leaning red link to lean left:
+ *       (h)                  (x)
+ *      /   \\               //   \
+ *           (x)    =>     (h)
+ *          /   \         /   \
+ */
+fn rotate_left<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut x = h.r.take().unwrap();
+    h.r = x.l.take();
+    x.red = h.red;
+    h.red = true;
+    x.l = Some(h);
+    x
+}
+
+/**
+ * Turn the left-leaning red link to lean right:
+ *         (h)             (x)
+ *        //   \          /   \\
+ *      (x)         =>          (h)
+ *     /   \                   /   \
+ */
+fn rotate_right<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut x = h.l.take().unwrap();
+    h.l = x.r.take();
+    x.red = h.red;
+    h.red = true;
+    x.r = Some(h);
+    x
+}
+
+// Split the temporary 4-node, or combine the node and its children into a 4-node.
+fn flip_colors<K, V>(h: &mut Node<K, V>) {
+    h.red = !h.red;
+    for child in [&mut h.l, &mut h.r].into_iter().flatten() {
+        child.red = !child.red;
+    }
+}
+
+// Restore the invariants on the way up.
+fn fix_up<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    if is_red(&h.r) && !is_red(&h.l) {
+        h = rotate_left(h);
+    }
+    if is_red(&h.l) && is_red(&h.l.as_ref().unwrap().l) {
+        h = rotate_right(h);
+    }
+    if is_red(&h.l) && is_red(&h.r) {
+        flip_colors(&mut h);
+    }
+    h
+}
+
+// Make h.l or one of its children red, so that the deletion never ends at a 2-node.
+fn move_red_left<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    flip_colors(&mut h);
+    if is_red(&h.r.as_ref().unwrap().l) {
+        h.r = Some(rotate_right(h.r.take().unwrap()));
+        h = rotate_left(h);
+        flip_colors(&mut h);
+    }
+    h
+}
+
+// Make h.r or one of its children red.
+fn move_red_right<K, V>(mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    flip_colors(&mut h);
+    if is_red(&h.l.as_ref().unwrap().l) {
+        h = rotate_right(h);
+        flip_colors(&mut h);
+    }
+    h
+}
+
+fn insert<K: Ord, V>(link: Link<K, V>, k: K, v: V, old: &mut Option<V>) -> Box<Node<K, V>> {
+    let mut h = match link {
+        None => {
+            return Box::new(Node {
+                k,
+                v,
+                red: true,
+                l: None,
+                r: None,
+            })
+        }
+        Some(h) => h,
+    };
+
+    match Ord::cmp(&k, &h.k) {
+        Ordering::Less => h.l = Some(insert(h.l.take(), k, v, old)),
+        Ordering::Greater => h.r = Some(insert(h.r.take(), k, v, old)),
+        Ordering::Equal => *old = Some(mem::replace(&mut h.v, v)),
+    }
+    fix_up(h)
+}
+
+// Delete the min node of the subtree, and return the rest of the subtree and the min node.
+fn delete_min<K, V>(mut h: Box<Node<K, V>>) -> (Link<K, V>, Box<Node<K, V>>) {
+    // The left-leaning tree has no right child if there's no left child.
+    if h.l.is_none() {
+        return (None, h);
+    }
+    if !is_red(&h.l) && !is_red(&h.l.as_ref().unwrap().l) {
+        h = move_red_left(h);
+    }
+    let (l, min) = delete_min(h.l.take().unwrap());
+    h.l = l;
+    (Some(fix_up(h)), min)
+}
+
+// The key must be in the subtree.
+fn delete<K: Ord, V>(mut h: Box<Node<K, V>>, k: &K, out: &mut Option<V>) -> Link<K, V> {
+    if *k < h.k {
+        if !is_red(&h.l) && !is_red(&h.l.as_ref().unwrap().l) {
+            h = move_red_left(h);
+        }
+        h.l = delete(h.l.take().unwrap(), k, out);
+    } else {
+        if is_red(&h.l) {
+            h = rotate_right(h);
+        }
+        if *k == h.k && h.r.is_none() {
+            *out = Some(h.v);
+            return None;
+        }
+        if !is_red(&h.r) && !is_red(&h.r.as_ref().unwrap().l) {
+            h = move_red_right(h);
+        }
+        if *k == h.k {
+            // Replace the node with its successor.
+            let (r, min) = delete_min(h.r.take().unwrap());
+            h.r = r;
+            let min = *min;
+            h.k = min.k;
+            *out = Some(mem::replace(&mut h.v, min.v));
+        } else {
+            h.r = delete(h.r.take().unwrap(), k, out);
+        }
+    }
+    Some(fix_up(h))
+}
+
+impl<K, V> RBTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        RBTree {
+            size: 0,
+            root: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Insert the pair, and return the old value if the key exists.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let mut old = None;
+        let mut root = insert(self.root.take(), k, v, &mut old);
+        root.red = false;
+        self.root = Some(root);
+        if old.is_none() {
+            self.size += 1;
+        }
+        old
+    }
+
+    fn find(&self, k: &K) -> Option<&Node<K, V>> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            match Ord::cmp(k, &node.k) {
+                Ordering::Less => cur = &node.l,
+                Ordering::Greater => cur = &node.r,
+                Ordering::Equal => return Some(node),
+            }
+        }
+        None
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.find(k).map(|node| &node.v)
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let mut cur = &mut self.root;
+        while let Some(node) = cur {
+            match Ord::cmp(k, &node.k) {
+                Ordering::Less => cur = &mut node.l,
+                Ordering::Greater => cur = &mut node.r,
+                Ordering::Equal => return Some(&mut node.v),
+            }
+        }
+        None
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.find(k).is_some()
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        if !self.contains_key(k) {
+            return None;
+        }
+
+        let mut root = self.root.take().unwrap();
+        if !is_red(&root.l) && !is_red(&root.r) {
+            root.red = true;
+        }
+        let mut out = None;
+        self.root = delete(root, k, &mut out);
+        if let Some(root) = self.root.as_mut() {
+            root.red = false;
+        }
+        self.size -= 1;
+        out
+    }
+
+    pub fn min(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(l) = node.l.as_deref() {
+            node = l;
+        }
+        Some((&node.k, &node.v))
+    }
+
+    pub fn max(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(r) = node.r.as_deref() {
+            node = r;
+        }
+        Some((&node.k, &node.v))
+    }
+
+    /// The number of the black links from the root to any leaf.
+    pub fn black_height(&self) -> usize {
+        let mut height = 0;
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            if !node.red {
+                height += 1;
+            }
+            cur = &node.l;
+        }
+        height
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        InOrder::new(self.root.as_deref())
+    }
+}
+
+impl<K: Ord, V> Default for RBTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Val = V;
+
+    fn key(&self) -> &K {
+        &self.k
+    }
+
+    fn val(&self) -> &V {
+        &self.v
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.l.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.r.as_deref()
+    }
+}
+
+fn internal_drop<K, V>(link: &mut Link<K, V>) {
+    if let Some(node) = link {
+        internal_drop(&mut node.l);
+        internal_drop(&mut node.r);
+        link.take();
+    }
+}
+
+impl<K, V> Drop for RBTree<K, V> {
+    fn drop(&mut self) {
+        internal_drop(&mut self.root);
+    }
+}
+
+impl<K: Ord, V> Map<K, V> for RBTree<K, V> {
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        RBTree::insert(self, k, v)
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        RBTree::remove(self, k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        RBTree::get(self, k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        RBTree::get_mut(self, k)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        RBTree::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for RBTree<K, V> {
+    fn range_for_each(&self, range: impl RangeBounds<K>, f: impl FnMut(&K, &V)) {
+        range_for_each(self.root.as_deref(), range, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+
+    // Check the red-black invariants and the order, and return the black height.
+    fn verify<K: Ord, V>(link: &Link<K, V>, lo: Option<&K>, hi: Option<&K>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                assert!(lo.is_none_or(|lo| *lo < node.k));
+                assert!(hi.is_none_or(|hi| node.k < *hi));
+                assert!(!is_red(&node.r), "The red link leans right");
+                if node.red {
+                    assert!(!is_red(&node.l), "Two red links in a row");
+                }
+                let l = verify(&node.l, lo, Some(&node.k));
+                let r = verify(&node.r, Some(&node.k), hi);
+                assert_eq!(l, r, "The black heights are different");
+                l + if node.red { 0 } else { 1 }
+            }
+        }
+    }
+
+    #[test]
+    pub fn basics() {
+        let mut tree = RBTree::new();
+        assert_eq!(tree.min(), None);
+        assert_eq!(tree.remove(&1), None);
+
+        for i in 0..10 {
+            assert_eq!(tree.insert(i, i * 10), None);
+        }
+        assert_eq!(tree.insert(3, 33), Some(30));
+        assert_eq!(tree.len(), 10);
+        assert_eq!(tree.get(&3), Some(&33));
+        *tree.get_mut(&4).unwrap() += 1;
+        assert_eq!(tree.get(&4), Some(&41));
+        assert_eq!(tree.get(&10), None);
+        assert_eq!(tree.min(), Some((&0, &0)));
+        assert_eq!(tree.max(), Some((&9, &90)));
+
+        assert_eq!(tree.remove(&0), Some(0));
+        assert_eq!(tree.remove(&0), None);
+        assert_eq!(tree.remove(&5), Some(50));
+        assert_eq!(
+            tree.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 6, 7, 8, 9]
+        );
+        assert_eq!(verify(&tree.root, None, None), tree.black_height());
+    }
+
+    #[test]
+    pub fn sequential_is_balanced() {
+        let mut tree = RBTree::new();
+        let n = 1 << 16;
+        for i in 0..n {
+            tree.insert(i, ());
+        }
+        // The height is at most 2 * log2(n).
+        let black = verify(&tree.root, None, None);
+        assert!(black <= 16);
+        for i in (0..n).step_by(2) {
+            assert_eq!(tree.remove(&i), Some(()));
+        }
+        verify(&tree.root, None, None);
+        assert_eq!(tree.len(), n as usize / 2);
+    }
+
+    #[test]
+    pub fn random_keep_invariants() {
+        let mut tree = RBTree::new();
+        for _ in 0..5000 {
+            let k = rand::gen_i32() % 300;
+            if rand::gen_bool() {
+                tree.insert(k, k);
+            } else {
+                tree.remove(&k);
+            }
+            verify(&tree.root, None, None);
+        }
+    }
+}
@@ -0,0 +1,216 @@
+/**
+ * The fixed-capacity ring buffer for a single producer and a single consumer (SPSC). The producer
+ * only writes the tail and the consumer only writes the head, so neither side needs a lock.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Circular_buffer
+ */
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Ring<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Both of them keep increasing, and the slot of the n-th element is `n % capacity`.
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        let cap = self.capacity();
+        for i in 0..tail.wrapping_sub(head) {
+            unsafe { (*self.slots[head.wrapping_add(i) % cap].get()).assume_init_drop() };
+        }
+    }
+}
+
+/// The producer side, which can be moved to another thread but not shared.
+pub struct Producer<T> {
+    ring: Arc<Ring<T>>,
+    _not_sync: PhantomData<*const ()>,
+}
+
+/// The consumer side, which can be moved to another thread but not shared.
+pub struct Consumer<T> {
+    ring: Arc<Ring<T>>,
+    _not_sync: PhantomData<*const ()>,
+}
+
+unsafe impl<T: Send> Send for Producer<T> {}
+unsafe impl<T: Send> Send for Consumer<T> {}
+
+/// Create the ring buffer which holds at most `capacity` elements.
+pub fn ring_buffer<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(
+        capacity > 0,
+        "The capacity of the ring buffer must be positive"
+    );
+    let ring = Arc::new(Ring {
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (
+        Producer {
+            ring: ring.clone(),
+            _not_sync: PhantomData,
+        },
+        Consumer {
+            ring,
+            _not_sync: PhantomData,
+        },
+    )
+}
+
+impl<T> Producer<T> {
+    /// Push the value, or give it back if the buffer is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        let head = ring.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) == ring.capacity() {
+            return Err(value);
+        }
+
+        unsafe { (*ring.slots[tail % ring.capacity()].get()).write(value) };
+        // Publish the slot to the consumer.
+        ring.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.ring.len() == self.ring.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+}
+
+impl<T> Consumer<T> {
+    pub fn pop(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        let tail = ring.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        let value = unsafe { (*ring.slots[head % ring.capacity()].get()).assume_init_read() };
+        // Give the slot back to the producer.
+        ring.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        let tail = ring.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        Some(unsafe { (*ring.slots[head % ring.capacity()].get()).assume_init_ref() })
+    }
+
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    pub fn basics() {
+        let (mut p, mut c) = ring_buffer(3);
+        assert_eq!(c.pop(), None);
+
+        assert_eq!(p.push(1), Ok(()));
+        assert_eq!(p.push(2), Ok(()));
+        assert_eq!(p.push(3), Ok(()));
+        assert!(p.is_full());
+        assert_eq!(p.push(4), Err(4));
+
+        assert_eq!(c.peek(), Some(&1));
+        assert_eq!(c.pop(), Some(1));
+        assert_eq!(p.push(4), Ok(()));
+        assert_eq!(c.len(), 3);
+        assert_eq!(c.pop(), Some(2));
+        assert_eq!(c.pop(), Some(3));
+        assert_eq!(c.pop(), Some(4));
+        assert_eq!(c.pop(), None);
+        assert!(c.is_empty());
+
+        // The elements left in the buffer are dropped with it.
+        let (mut p, _c) = ring_buffer(4);
+        p.push(String::from("left")).unwrap();
+    }
+
+    #[test]
+    pub fn spsc_threads() {
+        let n: u64 = 10_000;
+        let (mut p, mut c) = ring_buffer(64);
+
+        let producer = thread::spawn(move || {
+            for i in 0..n {
+                let mut v = i;
+                while let Err(back) = p.push(v) {
+                    v = back;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        // The consumer sees all the values in the pushed order.
+        let mut expected = 0;
+        while expected < n {
+            match c.pop() {
+                Some(v) => {
+                    assert_eq!(v, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+        assert!(c.is_empty());
+    }
+}
@@ -0,0 +1,376 @@
+/**
+ * The scapegoat tree, which keeps no balance information in the nodes. When an insertion goes
+ * deeper than log(n) with base 1/alpha, some ancestor of the new node must be unbalanced, i.e. one
+ * of its subtrees has more than alpha of its nodes, and that ancestor (the scapegoat) is rebuilt
+ * into a perfectly balanced subtree. The whole tree is rebuilt after enough deletions.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Scapegoat_tree
+ */
+use crate::basic::map::{range_for_each, BinaryNode, InOrder, Map, OrderedMap};
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::RangeBounds;
+
+const DEFAULT_ALPHA: f64 = 2.0 / 3.0;
+
+pub struct ScapegoatTree<K, V> {
+    alpha: f64,
+    size: usize,
+    // The max size since the last rebuilding of the whole tree.
+    max_size: usize,
+    root: Link<K, V>,
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+pub struct Node<K, V> {
+    k: K,
+    v: V,
+    l: Link<K, V>,
+    r: Link<K, V>,
+}
+
+pub type Iter<'a, K, V> = InOrder<'a, Node<K, V>>;
+
+fn count<K, V>(link: &Link<K, V>) -> usize {
+    let mut n = 0;
+    let mut stack: Vec<&Node<K, V>> = link.iter().map(|node| node.as_ref()).collect();
+    while let Some(node) = stack.pop() {
+        n += 1;
+        stack.extend([&node.l, &node.r].into_iter().flatten().map(|n| n.as_ref()));
+    }
+    n
+}
+
+// Rebuild the subtree into a perfectly balanced one, the nodes are reused.
+fn rebuild<K, V>(link: &mut Link<K, V>) {
+    let mut nodes = Vec::new();
+    let mut stack = Vec::new();
+    let mut cur = link.take();
+    loop {
+        while let Some(mut node) = cur {
+            cur = node.l.take();
+            stack.push(node);
+        }
+        match stack.pop() {
+            None => break,
+            Some(mut node) => {
+                cur = node.r.take();
+                nodes.push(Some(node));
+            }
+        }
+    }
+    *link = build(&mut nodes);
+}
+
+fn build<K, V>(nodes: &mut [Option<Box<Node<K, V>>>]) -> Link<K, V> {
+    if nodes.is_empty() {
+        return None;
+    }
+    let (left, rest) = nodes.split_at_mut(nodes.len() / 2);
+    let (mid, right) = rest.split_first_mut().unwrap();
+    let mut node = mid.take().unwrap();
+    node.l = build(left);
+    node.r = build(right);
+    Some(node)
+}
+
+// Insert the new key, and return the size of the subtree if the new node is too deep and the
+// scapegoat is not found yet.
+fn insert<K: Ord, V>(
+    link: &mut Link<K, V>,
+    k: K,
+    v: V,
+    depth: usize,
+    max_depth: usize,
+    alpha: f64,
+) -> Option<usize> {
+    let node = match link {
+        None => {
+            *link = Some(Box::new(Node {
+                k,
+                v,
+                l: None,
+                r: None,
+            }));
+            return if depth > max_depth { Some(1) } else { None };
+        }
+        Some(node) => node,
+    };
+
+    let (child, sibling) = if k < node.k {
+        (&mut node.l, &node.r)
+    } else {
+        (&mut node.r, &node.l)
+    };
+    let child_size = insert(child, k, v, depth + 1, max_depth, alpha)?;
+    let size = child_size + count(sibling) + 1;
+    if child_size as f64 > alpha * size as f64 {
+        rebuild(link);
+        return None;
+    }
+    Some(size)
+}
+
+impl<K, V> ScapegoatTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::with_alpha(DEFAULT_ALPHA)
+    }
+
+    /// The smaller alpha keeps the tree lower, but rebuilds more often.
+    pub fn with_alpha(alpha: f64) -> Self {
+        assert!(
+            (0.5..1.0).contains(&alpha),
+            "The alpha of the scapegoat tree must be in [0.5, 1)"
+        );
+        ScapegoatTree {
+            alpha,
+            size: 0,
+            max_size: 0,
+            root: None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // The max depth of the node (the root is 0) in the tree of n nodes.
+    fn max_depth(&self, n: usize) -> usize {
+        ((n as f64).ln() / (1.0 / self.alpha).ln()).floor() as usize
+    }
+
+    /// Insert the pair, and return false if the key exists.
+    pub fn insert(&mut self, key: K, val: V) -> bool {
+        if self.find(&key).is_some() {
+            return false;
+        }
+
+        let max_depth = self.max_depth(self.size + 1);
+        insert(&mut self.root, key, val, 0, max_depth, self.alpha);
+        self.size += 1;
+        self.max_size = self.max_size.max(self.size);
+        true
+    }
+
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.find(&key)
+    }
+
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.find_mut(&key)
+    }
+
+    fn find(&self, key: &K) -> Option<&V> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            match Ord::cmp(key, &node.k) {
+                Ordering::Less => cur = &node.l,
+                Ordering::Greater => cur = &node.r,
+                Ordering::Equal => return Some(&node.v),
+            }
+        }
+        None
+    }
+
+    fn find_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut cur = &mut self.root;
+        while let Some(node) = cur {
+            match Ord::cmp(key, &node.k) {
+                Ordering::Less => cur = &mut node.l,
+                Ordering::Greater => cur = &mut node.r,
+                Ordering::Equal => return Some(&mut node.v),
+            }
+        }
+        None
+    }
+
+    pub fn delete(&mut self, key: K) -> Option<V> {
+        self.remove_key(&key)
+    }
+
+    fn remove_key(&mut self, key: &K) -> Option<V> {
+        let mut cur = &mut self.root;
+        while cur.as_ref().is_some_and(|node| node.k != *key) {
+            let node = cur.as_mut().unwrap();
+            cur = if *key < node.k {
+                &mut node.l
+            } else {
+                &mut node.r
+            };
+        }
+
+        let mut node = cur.take()?;
+        *cur = match (node.l.take(), node.r.take()) {
+            (None, r) => r,
+            (l, None) => l,
+            (l, r) => {
+                // Replace the node with its successor.
+                let mut r = r;
+                let mut min = &mut r;
+                while min.as_ref().unwrap().l.is_some() {
+                    min = &mut min.as_mut().unwrap().l;
+                }
+                let mut succ = min.take().unwrap();
+                *min = succ.r.take();
+                succ.l = l;
+                succ.r = r;
+                Some(succ)
+            }
+        };
+
+        self.size -= 1;
+        if (self.size as f64) < self.alpha * self.max_size as f64 {
+            rebuild(&mut self.root);
+            self.max_size = self.size;
+        }
+        Some(node.v)
+    }
+
+    pub fn height(&self) -> usize {
+        fn height<K, V>(link: &Link<K, V>) -> usize {
+            link.as_ref()
+                .map_or(0, |node| height(&node.l).max(height(&node.r)) + 1)
+        }
+        height(&self.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        InOrder::new(self.root.as_deref())
+    }
+}
+
+impl<K: Ord, V> Default for ScapegoatTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Val = V;
+
+    fn key(&self) -> &K {
+        &self.k
+    }
+
+    fn val(&self) -> &V {
+        &self.v
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.l.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.r.as_deref()
+    }
+}
+
+impl<K: Ord, V> Map<K, V> for ScapegoatTree<K, V> {
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        match self.find_mut(&k) {
+            Some(old) => Some(mem::replace(old, v)),
+            None => {
+                ScapegoatTree::insert(self, k, v);
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.remove_key(k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.find(k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.find_mut(k)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        ScapegoatTree::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for ScapegoatTree<K, V> {
+    fn range_for_each(&self, range: impl RangeBounds<K>, f: impl FnMut(&K, &V)) {
+        range_for_each(self.root.as_deref(), range, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+
+    // Check the order of the keys and the height bound.
+    fn verify<K: Ord, V>(tree: &ScapegoatTree<K, V>) {
+        let keys: Vec<&K> = tree.iter().map(|(k, _)| k).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(keys.len(), tree.size());
+        if tree.size() > 0 {
+            assert!(tree.height() <= tree.max_depth(tree.max_size) + 1);
+        }
+    }
+
+    #[test]
+    pub fn test_basics() {
+        let mut tree = ScapegoatTree::new();
+        assert_eq!(tree.get(1), None);
+        assert_eq!(tree.delete(1), None);
+
+        // The sorted insertion keeps rebuilding the subtrees.
+        let max = 100_000;
+        for i in 0..max {
+            assert!(tree.insert(i, i));
+        }
+        verify(&tree);
+        assert!(tree.height() <= 30);
+
+        assert!(!tree.insert(5, 0));
+        assert_eq!(tree.get(5), Some(&5));
+        *tree.get_mut(5).unwrap() += 1;
+        assert_eq!(tree.get(5), Some(&6));
+        assert_eq!(tree.delete(5), Some(6));
+        assert_eq!(tree.delete(5), None);
+
+        // Deleting most of the keys rebuilds the whole tree.
+        for i in 0..max - 10 {
+            tree.delete(i);
+        }
+        verify(&tree);
+        assert!(tree.height() <= 4);
+        assert!(tree.iter().map(|(k, _)| *k).eq(max - 10..max));
+    }
+
+    #[test]
+    pub fn test_verify_tree() {
+        for alpha in [0.5, DEFAULT_ALPHA, 0.9] {
+            let mut tree = ScapegoatTree::with_alpha(alpha);
+            for _ in 0..1000 {
+                let x = rand::gen_i32() % 500;
+                if rand::gen_u32().is_multiple_of(3) {
+                    tree.delete(x);
+                } else {
+                    tree.insert(x, x);
+                }
+                verify(&tree);
+            }
+        }
+    }
+}
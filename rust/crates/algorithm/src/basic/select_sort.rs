@@ -1,10 +1,20 @@
+use std::cmp::Ordering;
+
 pub fn select_sort<T>(v: &mut [T])
 where
     T: Ord,
+{
+    select_sort_by(v, |a, b| a.cmp(b));
+}
+
+/// Sort in place by the comparator, which isn't stable.
+pub fn select_sort_by<T, F>(v: &mut [T], mut cmp: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
 {
     for i in 0..v.len() {
         for j in i + 1..v.len() {
-            if v[j] < v[i] {
+            if cmp(&v[j], &v[i]) == Ordering::Less {
                 v.swap(i, j);
             }
         }
@@ -47,6 +57,17 @@ mod tests {
         assert_eq!(v, ["", "A", "B"]);
     }
 
+    #[test]
+    pub fn test_sort_by() {
+        let mut v = vec!["E", "FFF", "KK", "EE", "EA"];
+        select_sort_by(&mut v, |a, b| b.cmp(a));
+        assert_eq!(v, ["KK", "FFF", "EE", "EA", "E"]);
+
+        let mut v = vec![3.5, -1.0, 2.25, 0.0];
+        select_sort_by(&mut v, |a: &f64, b| a.total_cmp(b));
+        assert_eq!(v, [-1.0, 0.0, 2.25, 3.5]);
+    }
+
     #[test]
     pub fn test_rng_float_sort() {
         let now = Instant::now();
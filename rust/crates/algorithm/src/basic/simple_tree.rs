@@ -1,52 +1,80 @@
+use crate::basic::map::{after_end, before_start, Map, OrderedMap};
 use std::cmp;
+use std::iter;
+use std::ops::{Bound, RangeBounds};
+
 pub struct SimpleTree<T: Ord> {
-    size: usize,
     root: Link<T>,
 }
 
 type Link<T> = Option<Box<Node<T>>>;
 
+/// The in-order iterator, which can also be consumed from the back.
 pub struct Iter<'a, T: Ord> {
-    stack: Vec<&'a Node<T>>,
+    front: Vec<&'a Node<T>>,
+    back: Vec<&'a Node<T>>,
+    // The number of the elements which are not visited yet, both stacks stop when it's 0.
+    len: usize,
 }
 
 struct Node<T: Ord> {
     elem: T,
+    // The number of the nodes in the subtree rooted at this node.
+    size: usize,
     l: Link<T>,
     r: Link<T>,
 }
 
+fn size_of<T: Ord>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
 impl<T> SimpleTree<T>
 where
     T: Ord,
 {
     pub fn new() -> Self {
-        SimpleTree {
-            size: 0,
-            root: None,
+        SimpleTree { root: None }
+    }
+
+    fn contains(&self, elem: &T) -> bool {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            if *elem < node.elem {
+                cur = &node.l;
+            } else if *elem > node.elem {
+                cur = &node.r;
+            } else {
+                return true;
+            }
         }
+        false
     }
 
     pub fn insert(&mut self, elem: T) {
+        // Check first, so that the sizes on the path can be updated on the way down.
+        if self.contains(&elem) {
+            return;
+        }
+
         let mut cur = &mut self.root;
         loop {
             match cur {
                 None => {
                     *cur = Some(Box::new(Node {
                         elem,
+                        size: 1,
                         l: None,
                         r: None,
                     }));
-                    self.size += 1;
                     break;
                 }
                 Some(node) => {
+                    node.size += 1;
                     if elem < node.elem {
                         cur = &mut node.l;
-                    } else if elem > node.elem {
-                        cur = &mut node.r;
                     } else {
-                        break;
+                        cur = &mut node.r;
                     }
                 }
             }
@@ -54,28 +82,28 @@ where
     }
 
     // Take the precursor of the p's elem, which is similar to the Option.take().
-    fn take_prec(p: &mut Option<Box<Node<T>>>) -> Option<T> {
+    // The right child of the left child of p must exist.
+    fn take_prec(p: &mut Link<T>) -> T {
         // The parent of the right most child in the left-side of target node.
         let mut cur = &mut p.as_deref_mut().unwrap().l;
 
-        // cur will be the parent of the right most child.
-        while !cur.as_deref().unwrap().r.is_none()
-            && !cur.as_deref().unwrap().r.as_deref().unwrap().r.is_none()
-        {
-            cur = &mut cur.as_deref_mut().unwrap().r;
+        // cur will be the parent of the right most child, and every subtree on the way loses one.
+        while cur.as_deref().unwrap().r.as_deref().unwrap().r.is_some() {
+            let node = cur.as_deref_mut().unwrap();
+            node.size -= 1;
+            cur = &mut node.r;
         }
 
         // Left tree of the right most child.
-        let r_most = &mut cur.as_deref_mut().unwrap().r;
-        let l_subtree = r_most.as_deref_mut().unwrap().l.take();
-        r_most.take().map(|r_most_node| {
-            *r_most = l_subtree;
-            r_most_node.elem
-        })
+        let parent = cur.as_deref_mut().unwrap();
+        parent.size -= 1;
+        let r_most = parent.r.take().unwrap();
+        parent.r = r_most.l;
+        r_most.elem
     }
 
     // Delete help methods
-    fn delete_node(p: &mut Option<Box<Node<T>>>) {
+    fn delete_node(p: &mut Link<T>) {
         // Case#1: Handle the nil-left child and nil-right child.
         {
             match p {
@@ -94,46 +122,46 @@ where
 
         // Case#2: The right child of the left child is NULL.
         {
-            let l = &mut p.as_mut().unwrap().l;
-            if l.as_deref().unwrap().r.is_none() {
-                l.take().map(|node| {
-                    p.as_mut().unwrap().elem = node.elem;
-                });
+            let node = p.as_deref_mut().unwrap();
+            if node.l.as_deref().unwrap().r.is_none() {
+                let l = node.l.take().unwrap();
+                node.elem = l.elem;
+                node.l = l.l;
+                node.size -= 1;
                 return;
             }
         }
 
         // Case#3: Replace the target node with its precursor.
         {
-            SimpleTree::take_prec(p).map(|elem| {
-                p.as_mut().unwrap().elem = elem;
-            });
+            let elem = SimpleTree::take_prec(p);
+            let node = p.as_deref_mut().unwrap();
+            node.elem = elem;
+            node.size -= 1;
         }
     }
 
-    fn inter_delete(ptr: &mut Option<Box<Node<T>>>, elem: T) -> bool {
-        match ptr {
-            None => false,
-            Some(node) => {
-                if elem < node.elem {
-                    return SimpleTree::inter_delete(&mut node.l, elem);
-                } else if elem == node.elem {
-                    SimpleTree::delete_node(ptr);
-                    return true;
-                } else {
-                    return SimpleTree::inter_delete(&mut node.r, elem);
-                }
-            }
-        }
+    pub fn delete(&mut self, elem: T) -> bool {
+        self.delete_ref(&elem)
     }
 
-    pub fn delete(&mut self, elem: T) -> bool {
-        if SimpleTree::inter_delete(&mut self.root, elem) {
-            self.size -= 1;
-            return true;
-        } else {
+    fn delete_ref(&mut self, elem: &T) -> bool {
+        if !self.contains(elem) {
             return false;
         }
+
+        let mut cur = &mut self.root;
+        while cur.as_deref().unwrap().elem != *elem {
+            let node = cur.as_deref_mut().unwrap();
+            node.size -= 1;
+            if *elem < node.elem {
+                cur = &mut node.l;
+            } else {
+                cur = &mut node.r;
+            }
+        }
+        SimpleTree::delete_node(cur);
+        true
     }
 
     pub fn find(&self, elem: T) -> Option<&T> {
@@ -199,63 +227,162 @@ where
 
     // Get the precursor element of the given element.
     pub fn prec(&self, elem: T) -> Option<&T> {
-        let mut iter = self.iter();
-        let mut prec = None;
-        while let Some(cur) = iter.next() {
-            if *cur < elem {
-                prec = Some(cur);
-            }
+        match self.rank(elem) {
+            0 => None,
+            r => self.select(r - 1),
         }
-        prec
     }
 
     // Get the succeed element of the given element.
     pub fn succ(&self, elem: T) -> Option<&T> {
-        let mut iter = self.iter();
-        while let Some(cur) = iter.next() {
-            if *cur > elem {
-                return Some(cur);
+        self.select(self.count_le(&elem))
+    }
+
+    /// The k-th smallest element, k starts from 0.
+    pub fn select(&self, mut k: usize) -> Option<&T> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            let l = size_of(&node.l);
+            if k < l {
+                cur = &node.l;
+            } else if k == l {
+                return Some(&node.elem);
+            } else {
+                k -= l + 1;
+                cur = &node.r;
             }
         }
-        return None;
+        None
     }
 
-    pub fn iter<'a>(&'a self) -> Iter<'a, T> {
-        let mut stack = Vec::new();
+    /// The number of the elements less than the given element, which is also the index of the
+    /// element if it's in the tree.
+    pub fn rank(&self, elem: T) -> usize {
+        self.count_lt(&elem)
+    }
+
+    // The number of the elements less than the given element.
+    fn count_lt(&self, elem: &T) -> usize {
         let mut cur = &self.root;
+        let mut rank = 0;
+        while let Some(node) = cur {
+            if *elem <= node.elem {
+                cur = &node.l;
+            } else {
+                rank += size_of(&node.l) + 1;
+                cur = &node.r;
+            }
+        }
+        rank
+    }
 
-        while cur.is_some() {
-            stack.push(cur.as_deref().unwrap());
-            cur = &cur.as_deref().unwrap().l;
+    // The number of the elements less than or equal to the given element.
+    fn count_le(&self, elem: &T) -> usize {
+        let mut cur = &self.root;
+        let mut count = 0;
+        while let Some(node) = cur {
+            if *elem < node.elem {
+                cur = &node.l;
+            } else {
+                count += size_of(&node.l) + 1;
+                cur = &node.r;
+            }
         }
+        count
+    }
 
-        Iter { stack }
+    /// The number of the elements in [lo, hi].
+    pub fn count_in_range(&self, lo: T, hi: T) -> usize {
+        if lo > hi {
+            return 0;
+        }
+        self.count_le(&hi) - self.rank(lo)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        push_left(&mut front, &self.root);
+        push_right(&mut back, &self.root);
+        Iter {
+            front,
+            back,
+            len: self.size(),
+        }
+    }
+
+    /// Iterate the elements in [lo, hi] in order.
+    pub fn range(&self, lo: T, hi: T) -> Iter<'_, T> {
+        self.range_iter(&(lo..=hi))
+    }
+
+    fn range_iter(&self, range: &impl RangeBounds<T>) -> Iter<'_, T> {
+        // The top of the stack is the first element in the range, and the rest are its ancestors
+        // which are greater than it, so it's the state after the visited ones.
+        let mut front = Vec::new();
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            if !before_start(range, &node.elem) {
+                front.push(node.as_ref());
+                cur = &node.l;
+            } else {
+                cur = &node.r;
+            }
+        }
+
+        let mut back = Vec::new();
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            if !after_end(range, &node.elem) {
+                back.push(node.as_ref());
+                cur = &node.r;
+            } else {
+                cur = &node.l;
+            }
+        }
+
+        // The number of the elements before the range and up to the end of the range.
+        let skipped = match range.start_bound() {
+            Bound::Included(lo) => self.count_lt(lo),
+            Bound::Excluded(lo) => self.count_le(lo),
+            Bound::Unbounded => 0,
+        };
+        let through = match range.end_bound() {
+            Bound::Included(hi) => self.count_le(hi),
+            Bound::Excluded(hi) => self.count_lt(hi),
+            Bound::Unbounded => self.size(),
+        };
+        let len = through.saturating_sub(skipped);
+        Iter { front, back, len }
     }
 
     pub fn size(&self) -> usize {
-        self.size
+        size_of(&self.root)
     }
 }
 
-impl<'a, T> Iter<'a, T>
+impl<T> Default for SimpleTree<T>
 where
     T: Ord,
 {
-    fn next(&mut self) -> Option<&'a T> {
-        match self.stack.pop() {
-            None => return None,
-            Some(node) => {
-                if node.r.is_some() {
-                    // Push node.r and all left children of node.r into the stack.
-                    let mut cur = &node.r;
-                    while cur.is_some() {
-                        self.stack.push(cur.as_deref().unwrap());
-                        cur = &cur.as_deref().unwrap().l;
-                    }
-                }
-                return Some(&node.elem);
-            }
-        };
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Push the node and all its left children into the stack.
+fn push_left<'a, T: Ord>(stack: &mut Vec<&'a Node<T>>, mut cur: &'a Link<T>) {
+    while let Some(node) = cur {
+        stack.push(node);
+        cur = &node.l;
+    }
+}
+
+// Push the node and all its right children into the stack.
+fn push_right<'a, T: Ord>(stack: &mut Vec<&'a Node<T>>, mut cur: &'a Link<T>) {
+    while let Some(node) = cur {
+        stack.push(node);
+        cur = &node.r;
     }
 }
 
@@ -266,10 +393,37 @@ where
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.next()
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        push_left(&mut self.front, &node.r);
+        self.len -= 1;
+        Some(&node.elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T>
+where
+    T: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        push_right(&mut self.back, &node.l);
+        self.len -= 1;
+        Some(&node.elem)
     }
 }
 
+impl<'a, T> ExactSizeIterator for Iter<'a, T> where T: Ord {}
+
 fn internal_drop<T: Ord>(ptr: &mut Option<Box<Node<T>>>) {
     match ptr {
         None => (),
@@ -287,13 +441,56 @@ where
 {
     fn drop(&mut self) {
         internal_drop(&mut self.root);
-        self.size = 0;
+    }
+}
+
+// The tree is a set, which is the map from the elements to the unit values.
+impl<T: Ord> Map<T, ()> for SimpleTree<T> {
+    type Iter<'a>
+        = iter::Map<Iter<'a, T>, fn(&'a T) -> (&'a T, &'a ())>
+    where
+        T: 'a;
+
+    fn insert(&mut self, k: T, _: ()) -> Option<()> {
+        if self.contains(&k) {
+            return Some(());
+        }
+        SimpleTree::insert(self, k);
+        None
+    }
+
+    fn remove(&mut self, k: &T) -> Option<()> {
+        self.delete_ref(k).then_some(())
+    }
+
+    fn get(&self, k: &T) -> Option<&()> {
+        self.contains(k).then_some(&())
+    }
+
+    // The unit is zero-sized, so leaking its box doesn't allocate.
+    fn get_mut(&mut self, k: &T) -> Option<&mut ()> {
+        self.contains(k).then(|| Box::leak(Box::new(())))
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        SimpleTree::iter(self).map(|e| (e, &()))
+    }
+
+    fn len(&self) -> usize {
+        self.size()
+    }
+}
+
+impl<T: Ord> OrderedMap<T, ()> for SimpleTree<T> {
+    fn range_for_each(&self, range: impl RangeBounds<T>, mut f: impl FnMut(&T, &())) {
+        self.range_iter(&range).for_each(|e| f(e, &()));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic::rand;
 
     #[test]
     pub fn basics() {
@@ -515,4 +712,103 @@ mod tests {
 
         drop(tree);
     }
+
+    // Check the subtree sizes of all the nodes, and return the size of the tree.
+    fn check_sizes<T: Ord>(link: &Link<T>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                let size = check_sizes(&node.l) + check_sizes(&node.r) + 1;
+                assert_eq!(node.size, size);
+                size
+            }
+        }
+    }
+
+    #[test]
+    pub fn order_statistics() {
+        let mut tree = SimpleTree::new();
+        for i in [6, 3, 8, 1, 4, 7, 9] {
+            tree.insert(i);
+        }
+
+        assert_eq!(tree.select(0), Some(&1));
+        assert_eq!(tree.select(3), Some(&6));
+        assert_eq!(tree.select(6), Some(&9));
+        assert_eq!(tree.select(7), None);
+        assert_eq!(tree.rank(1), 0);
+        assert_eq!(tree.rank(5), 3);
+        assert_eq!(tree.rank(6), 3);
+        assert_eq!(tree.rank(100), 7);
+        assert_eq!(tree.count_in_range(3, 7), 4);
+        assert_eq!(tree.count_in_range(5, 5), 0);
+        assert_eq!(tree.count_in_range(7, 3), 0);
+        assert_eq!(
+            tree.range(2, 8).collect::<Vec<_>>(),
+            vec![&3, &4, &6, &7, &8]
+        );
+        assert_eq!(
+            tree.range(2, 8).rev().collect::<Vec<_>>(),
+            vec![&8, &7, &6, &4, &3]
+        );
+        assert_eq!(tree.range(10, 20).next(), None);
+
+        let mut iter = tree.iter();
+        assert_eq!(iter.len(), 7);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.next_back(), Some(&8));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&4, &6, &7]);
+
+        // The left child of the deleted node has no right child but a left one.
+        assert!(tree.delete(3));
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            vec![&1, &4, &6, &7, &8, &9]
+        );
+        assert!(tree.delete(6));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![&1, &4, &7, &8, &9]);
+        assert_eq!(check_sizes(&tree.root), 5);
+    }
+
+    #[test]
+    pub fn random_order_statistics() {
+        let mut tree = SimpleTree::new();
+        let mut expected: Vec<i32> = Vec::new();
+        for _ in 0..3000 {
+            let x = rand::gen_i32() % 500;
+            if rand::gen_u32() % 3 == 0 {
+                let found = expected.binary_search(&x);
+                assert_eq!(tree.delete(x), found.is_ok());
+                if let Ok(i) = found {
+                    expected.remove(i);
+                }
+            } else {
+                tree.insert(x);
+                if let Err(i) = expected.binary_search(&x) {
+                    expected.insert(i, x);
+                }
+            }
+            assert_eq!(tree.size(), expected.len());
+
+            let k = rand::gen_u32() as usize % (expected.len() + 1);
+            assert_eq!(tree.select(k), expected.get(k));
+            let y = rand::gen_i32() % 500;
+            let rank = expected.partition_point(|e| *e < y);
+            assert_eq!(tree.rank(y), rank);
+            assert_eq!(tree.prec(y), rank.checked_sub(1).map(|i| &expected[i]));
+            let le = expected.partition_point(|e| *e <= y);
+            assert_eq!(tree.succ(y), expected.get(le));
+
+            let (lo, hi) = (rand::gen_i32() % 500, rand::gen_i32() % 500);
+            let in_range: Vec<&i32> = expected.iter().filter(|e| lo <= **e && **e <= hi).collect();
+            assert_eq!(tree.count_in_range(lo, hi), in_range.len());
+            assert_eq!(tree.range(lo, hi).collect::<Vec<_>>(), in_range);
+            assert!(tree.range(lo, hi).rev().eq(in_range.iter().rev().copied()));
+        }
+        assert_eq!(check_sizes(&tree.root), expected.len());
+        assert!(tree.iter().eq(expected.iter()));
+        assert!(tree.iter().rev().eq(expected.iter().rev()));
+    }
 }
@@ -1,7 +1,11 @@
+use crate::basic::map::{after_end, before_start, Map, OrderedMap};
 use crate::basic::rand;
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::fmt::Display;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
 use std::rc::Rc;
 
 pub struct SkipList<K, V> {
@@ -22,8 +26,37 @@ pub struct Entry<K, V> {
     v: V,
 }
 
-pub struct Iter<K, V> {
+/// The iterator of the cloned entries. It borrows the list even though it holds its own `Rc` of
+/// the nodes, so the list can't be changed while iterating, e.g. by the value lent by
+/// `Map::get_mut`:
+///
+/// ```compile_fail,E0502
+/// use hello_world::basic::map::Map;
+/// use hello_world::basic::skiplist::SkipList;
+///
+/// let mut list = SkipList::new();
+/// list.put(1, vec![1]);
+/// let mut it = list.iter();
+/// let v = Map::get_mut(&mut list, &1).unwrap();
+/// it.next();
+/// v.push(2);
+/// ```
+pub struct Iter<'a, K, V> {
     cur: Link<K, V>,
+    _list: PhantomData<&'a SkipList<K, V>>,
+}
+
+/// The iterator of `Map::iter`, which lends the pairs without cloning them.
+pub struct Refs<'a, K, V> {
+    cur: Option<&'a Node<K, V>>,
+}
+
+// Lend the node for as long as its list is borrowed. It's sound because the nodes are only borrowed
+// mutably by the methods on `&mut SkipList`, and the only `Rc` of them out of this module is held by
+// `Iter`, which borrows the list too. So the node is neither changed nor freed while the list is
+// shared, and no `Ref` of it is alive while the list is borrowed mutably.
+unsafe fn lend<'a, K, V>(node: &Rc<RefCell<Node<K, V>>>) -> &'a Node<K, V> {
+    &*node.as_ptr()
 }
 
 impl<K, V> Node<K, V> {
@@ -107,8 +140,13 @@ where
         let new_node = Node::new(k, v, new_level);
 
         let mut ptr = self.head.clone();
-        for i in (0..std::cmp::min(self.level, new_level)).rev() {
+        // Descend from the top level even if the new node is lower, otherwise the search of its
+        // levels starts from the head and walks through the whole list.
+        for i in (0..self.level).rev() {
             ptr = self.get_prec(ptr, i, &new_node.borrow().key());
+            if i >= new_level {
+                continue;
+            }
 
             ptr.as_ref().map(|pre_node| {
                 let mut pre_mut_ref = pre_node.borrow_mut();
@@ -141,6 +179,11 @@ where
     }
 
     pub fn get(&self, k: K) -> Option<V> {
+        self.find(&k).map(|node| node.borrow().val().clone())
+    }
+
+    // Find the first node of the key.
+    fn find(&self, k: &K) -> Link<K, V> {
         let mut ptr = self.head.clone();
         for i in (0..self.level).rev() {
             // Iterate to find the correct key in the leve-i.
@@ -151,14 +194,15 @@ where
                         break;
                     }
                     Some(next) => {
-                        match Ord::cmp(&k, next.borrow().key()) {
+                        let ord = Ord::cmp(k, next.borrow().key());
+                        match ord {
                             Ordering::Less => {
                                 // Cann't find the key in current level! Let's just goto the next level.
                                 break;
                             }
                             Ordering::Equal => {
                                 // Find the correct key value in currect level.
-                                return Some(next.borrow().val().clone());
+                                return Some(next);
                             }
                             Ordering::Greater => {
                                 // Iterate to the next key in current level.
@@ -171,10 +215,14 @@ where
         }
 
         // The target key is greater than all of the keys in the collection.
-        return None;
+        None
     }
 
     pub fn delete(&mut self, k: K) -> Option<V> {
+        self.delete_ref(&k)
+    }
+
+    fn delete_ref(&mut self, k: &K) -> Option<V> {
         let mut ptr = self.head.clone();
         for i in (0..self.level).rev() {
             // Iterate to find the correct key in the leve-i.
@@ -187,7 +235,7 @@ where
                     }
                     Some(next) => {
                         let mut next_mut_ref = next.borrow_mut();
-                        match Ord::cmp(&k, next_mut_ref.key()) {
+                        match Ord::cmp(k, next_mut_ref.key()) {
                             Ordering::Less => {
                                 // Cann't find the key in current level! Let's just goto the next level.
                                 break;
@@ -221,19 +269,20 @@ where
         self.size
     }
 
-    pub fn peek_front(&self) -> Link<K, V> {
+    fn peek_front(&self) -> Link<K, V> {
         match self.head.clone() {
             None => None,
             Some(head_node) => head_node.borrow().forward[0].clone(),
         }
     }
 
-    pub fn iter(&self) -> Iter<K, V> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
         return Iter {
             cur: match self.head.clone() {
                 None => None,
                 Some(head_node) => head_node.borrow().forward[0].clone(),
             },
+            _list: PhantomData,
         };
     }
 }
@@ -263,7 +312,7 @@ where
 // TODO Implement a generic Iterator without any cloning ?
 // TODO To be frank, I still don't get the correct approach to handle the lifetime issues
 // TODO by implementing this iterator with zero-copy.
-impl<K, V> Iterator for Iter<K, V>
+impl<K, V> Iterator for Iter<'_, K, V>
 where
     K: Clone,
     V: Clone,
@@ -283,6 +332,91 @@ where
     }
 }
 
+impl<'a, K, V> Iterator for Refs<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cur.take().map(|node| {
+            self.cur = node.forward[0].as_ref().map(|next| unsafe { lend(next) });
+            (node.key(), node.val())
+        })
+    }
+}
+
+// The inherent `put` keeps the duplicated keys, while the map replaces the value of the key.
+impl<K, V> Map<K, V> for SkipList<K, V>
+where
+    K: Ord,
+    V: Clone,
+{
+    type Iter<'a>
+        = Refs<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        if let Some(node) = self.find(&k) {
+            let mut node = node.borrow_mut();
+            return Some(mem::replace(&mut node.entry.as_mut().unwrap().v, v));
+        }
+        self.put(k, v);
+        None
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.delete_ref(k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.find(k).map(|node| unsafe { lend(&node) }.val())
+    }
+
+    // No `Ref` of the nodes outlives the methods, and `Iter` borrows the list, so the list being
+    // borrowed mutably means no node is borrowed.
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.find(k)
+            .map(|node| unsafe { &mut (*node.as_ptr()).entry.as_mut().unwrap().v })
+    }
+
+    fn iter(&self) -> Refs<'_, K, V> {
+        Refs {
+            cur: self.peek_front().map(|node| unsafe { lend(&node) }),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K, V> OrderedMap<K, V> for SkipList<K, V>
+where
+    K: Ord,
+    V: Clone,
+{
+    fn range_for_each(&self, range: impl RangeBounds<K>, mut f: impl FnMut(&K, &V)) {
+        // Go down the levels to the last node before the start of the range.
+        let mut ptr = self.head.clone();
+        if let Bound::Included(lo) | Bound::Excluded(lo) = range.start_bound() {
+            for i in (0..self.level).rev() {
+                ptr = self.get_prec(ptr, i, lo);
+            }
+        }
+
+        let mut ptr = ptr.and_then(|node| node.borrow().forward[0].clone());
+        while let Some(node) = ptr {
+            let node = node.borrow();
+            if after_end(&range, node.key()) {
+                break;
+            }
+            if !before_start(&range, node.key()) {
+                f(node.key(), node.val());
+            }
+            ptr = node.forward[0].clone();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,10 +1,28 @@
+/**
+ * The unsafe skip list, whose nodes are linked by the raw pointers instead of
+ * `Option<Rc<RefCell<..>>>`, so the values can be lent out by reference and there is no
+ * reference counting or borrow checking at runtime.
+ *
+ * Every node is owned by the level-0 list: it's allocated by `Box::into_raw` when inserted, and
+ * freed by `Box::from_raw` when deleted or when the list is dropped.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Skip_list
+ */
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
 use std::ptr;
 
+use crate::basic::map::{after_end, before_start, Map, OrderedMap};
 use crate::basic::rand;
 
+const MAX_LEVEL: usize = 16;
+
 pub struct SkipList<K, V> {
     size: usize,
     level: usize,
+    // The forward pointers of the head, whose length is always MAX_LEVEL.
     head: Vec<*mut Node<K, V>>,
 }
 
@@ -18,6 +36,11 @@ pub struct Entry<K, V> {
     v: V,
 }
 
+pub struct Iter<'a, K, V> {
+    cur: *mut Node<K, V>,
+    _marker: PhantomData<&'a Node<K, V>>,
+}
+
 impl<K, V> Node<K, V> {
     pub fn new(level: usize, k: K, v: V) -> Self {
         Node {
@@ -35,6 +58,19 @@ impl<K, V> Node<K, V> {
     }
 }
 
+// The slot of the level-i forward pointer of the node, and the null node is the head.
+unsafe fn slot_of<K, V>(
+    head: *mut *mut Node<K, V>,
+    node: *mut Node<K, V>,
+    i: usize,
+) -> *mut *mut Node<K, V> {
+    if node.is_null() {
+        head.add(i)
+    } else {
+        (*node).next.as_mut_ptr().add(i)
+    }
+}
+
 impl<K, V> SkipList<K, V>
 where
     K: Ord,
@@ -43,67 +79,280 @@ where
         SkipList {
             size: 0,
             level: 1,
-            head: (0..1).map(|_| std::ptr::null_mut()).collect(),
+            head: (0..MAX_LEVEL).map(|_| ptr::null_mut()).collect(),
         }
     }
 
     fn rand_level(&self) -> usize {
         let mut level = 1;
-        while rand::gen_bool() {
+        while level < MAX_LEVEL && rand::gen_bool() {
             level += 1;
         }
-        return level;
+        level
     }
 
-    pub fn add(&mut self, k: K, v: V) {
-        unsafe {
-            let mut ptr = self.head[self.level - 1];
-            let new_node = Node::new(self.rand_level(), k, v);
-            let min_level = std::cmp::min(new_node.next.len(), self.level);
-
-            for level in (0..min_level).rev() {
-                // Find the correct ptr to link the new node.
-                while !(*ptr).next[level].is_null() {
-                    let next = Box::from_raw((*ptr).next[level]);
-                    if new_node.key() < next.key() {
-                        break;
-                    } else {
-                        ptr = next.next[level];
-                    }
+    // The slots pointing to the first node not less than k in each level.
+    unsafe fn find_slots<Q>(&mut self, k: &Q) -> [*mut *mut Node<K, V>; MAX_LEVEL]
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // All the slots of the head come from the same pointer, so that taking one doesn't
+        // invalidate the others.
+        let head = self.head.as_mut_ptr();
+        let mut slots = [ptr::null_mut(); MAX_LEVEL];
+        let mut prev: *mut Node<K, V> = ptr::null_mut();
+        for i in (0..MAX_LEVEL).rev() {
+            let mut slot = slot_of(head, prev, i);
+            while !(*slot).is_null() && (**slot).key().borrow() < k {
+                prev = *slot;
+                slot = slot_of(head, prev, i);
+            }
+            slots[i] = slot;
+        }
+        slots
+    }
+
+    // The first node not less than k, or the null pointer.
+    fn lower_bound<Q>(&self, k: &Q) -> *mut Node<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut next = &self.head;
+        for i in (0..self.level).rev() {
+            // The nodes are only freed by `&mut self`, so they're valid while `self` is borrowed.
+            unsafe {
+                while !next[i].is_null() && (*next[i]).key().borrow() < k {
+                    next = &(*next[i]).next;
                 }
+            }
+        }
+        next[0]
+    }
+
+    /// Insert the pair, and return the old value if the key exists.
+    pub fn add(&mut self, k: K, v: V) -> Option<V> {
+        unsafe {
+            let slots = self.find_slots(&k);
+            let found = *slots[0];
+            if !found.is_null() && *(*found).key() == k {
+                let entry = (*found).entry.as_mut().unwrap();
+                return Some(mem::replace(&mut entry.v, v));
+            }
 
-                //*((*ptr).next[level]) = new_node;
+            let level = self.rand_level();
+            let node = Box::into_raw(Box::new(Node::new(level, k, v)));
+            for (i, slot) in slots.iter().enumerate().take(level) {
+                *(*node).next.as_mut_ptr().add(i) = **slot;
+                **slot = node;
             }
+            self.level = self.level.max(level);
         }
+        self.size += 1;
+        None
     }
 
-    pub fn delete(&mut self, k: K, v: V) {
-        todo!()
+    pub fn get(&self, k: K) -> Option<&V> {
+        self.find(&k)
     }
 
-    pub fn get(&self, k: K) -> Option<V> {
+    /// Get the value by the borrowed form of the key, e.g. `&[u8]` for `Vec<u8>`.
+    pub fn find<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = self.lower_bound(k);
         unsafe {
-            let mut ptr = self.head[self.level - 1];
-            for level in (0..self.level).rev() {
-                while !(*ptr).next[level].is_null() {
-                    let next = Box::from_raw((*ptr).next[level]);
-                    if &k < next.key() {
-                        break;
-                    } else if &k == next.key() {
-                        return Some((*next).entry.unwrap().v);
-                    } else {
-                        ptr = (*ptr).next[level];
-                    }
-                }
+            if !node.is_null() && (*node).key().borrow() == k {
+                return Some((*node).val());
             }
         }
-        return None;
+        None
+    }
+
+    pub fn get_mut(&mut self, k: K) -> Option<&mut V> {
+        self.find_mut(&k)
+    }
+
+    fn find_mut(&mut self, k: &K) -> Option<&mut V> {
+        let node = self.lower_bound(k);
+        unsafe {
+            if !node.is_null() && (*node).key() == k {
+                return Some(&mut (*node).entry.as_mut().unwrap().v);
+            }
+        }
+        None
+    }
+
+    pub fn delete(&mut self, k: K) -> Option<V> {
+        self.delete_ref(&k)
+    }
+
+    /// Delete the key by its borrowed form like `find`.
+    pub fn delete_ref<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        unsafe {
+            let slots = self.find_slots(k);
+            let found = *slots[0];
+            if found.is_null() || (*found).key().borrow() != k {
+                return None;
+            }
+
+            // Unlink the node from all its levels, then it's owned here.
+            for (i, slot) in slots.iter().enumerate().take((*found).next.len()) {
+                **slot = *(*found).next.as_ptr().add(i);
+            }
+            while self.level > 1 && self.head[self.level - 1].is_null() {
+                self.level -= 1;
+            }
+            self.size -= 1;
+            let node = Box::from_raw(found);
+            node.entry.map(|entry| entry.v)
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            cur: self.head[0],
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Ord, V> Default for SkipList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur.is_null() {
+            return None;
+        }
+        // The list is borrowed for 'a, so the node can't be freed.
+        unsafe {
+            let node = &*self.cur;
+            self.cur = node.next[0];
+            Some((node.key(), node.val()))
+        }
+    }
+}
+
+impl<K, V> Drop for SkipList<K, V> {
+    fn drop(&mut self) {
+        let mut cur = self.head[0];
+        while !cur.is_null() {
+            let node = unsafe { Box::from_raw(cur) };
+            cur = node.next[0];
+        }
+    }
+}
+
+impl<K: Ord, V> Map<K, V> for SkipList<K, V> {
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.add(k, v)
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.delete_ref(k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.find(k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.find_mut(k)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        SkipList::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for SkipList<K, V> {
+    fn range_for_each(&self, range: impl RangeBounds<K>, mut f: impl FnMut(&K, &V)) {
+        let cur = match range.start_bound() {
+            Bound::Included(lo) | Bound::Excluded(lo) => self.lower_bound(lo),
+            Bound::Unbounded => self.head[0],
+        };
+        let iter = Iter {
+            cur,
+            _marker: PhantomData,
+        };
+        for (k, v) in iter
+            .skip_while(|(k, _)| before_start(&range, *k))
+            .take_while(|(k, _)| !after_end(&range, *k))
+        {
+            f(k, v);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
-    pub fn basic() {}
+    pub fn basic() {
+        let mut list = SkipList::new();
+        assert_eq!(list.get(1), None);
+        assert_eq!(list.delete(1), None);
+
+        for i in (0..100).rev() {
+            assert_eq!(list.add(i, i * 10), None);
+        }
+        assert_eq!(list.size(), 100);
+        assert_eq!(list.add(5, 0), Some(50));
+        assert_eq!(list.get(5), Some(&0));
+        *list.get_mut(6).unwrap() += 1;
+        assert_eq!(list.get(6), Some(&61));
+        assert_eq!(list.get(100), None);
+
+        assert_eq!(list.delete(5), Some(0));
+        assert_eq!(list.delete(5), None);
+        assert_eq!(list.size(), 99);
+        assert!(list
+            .iter()
+            .map(|(k, _)| *k)
+            .eq((0..100).filter(|i| *i != 5)));
+
+        for i in 0..100 {
+            list.delete(i);
+        }
+        assert_eq!(list.size(), 0);
+        assert_eq!(list.level, 1);
+        assert_eq!(list.iter().next(), None);
+    }
+
+    #[test]
+    pub fn test_drop() {
+        // Dropping the list frees all the nodes and their entries.
+        let mut list = SkipList::new();
+        for i in 0..10000 {
+            list.add(i, i.to_string());
+        }
+        drop(list);
+    }
 }
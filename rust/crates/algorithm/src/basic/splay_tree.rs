@@ -0,0 +1,430 @@
+/**
+ * The splay tree, which moves the accessed node to the root by rotations on every access. It keeps
+ * no balance information, but any sequence of m operations costs O(m * log(n)) in total, and the
+ * frequently accessed keys stay near the root, which suits the skewed reads.
+ *
+ * The splay is done top-down without the recursion, because the tree can be as deep as a linked
+ * list, e.g. after inserting the sorted keys.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Splay_tree
+ */
+use crate::basic::map::{range_for_each, BinaryNode, InOrder, Map, OrderedMap};
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::RangeBounds;
+
+pub struct SplayTree<K, V> {
+    size: usize,
+    root: Link<K, V>,
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+pub struct Node<K, V> {
+    k: K,
+    v: V,
+    l: Link<K, V>,
+    r: Link<K, V>,
+}
+
+pub type Iter<'a, K, V> = InOrder<'a, Node<K, V>>;
+
+/**
+ * Splay the node of `k`, or the last node on the search path if `k` is not in the tree, to the root.
+ * The nodes on the path are split into the left tree (less than `k`) and the right tree (greater
+ * than `k`), and the zig-zig case is rotated before being split:
+ *
+ * ```text
+ *          (A)                    (B)            left: ..
+ *          /          =>            \\     =>    right: (B) -> (A)
+ *        (B)                        (A)          root: (C)
+ *        /
+ *      (C)
+ * ```
+ */
+fn splay<K: Ord, V>(root: &mut Link<K, V>, k: &K) {
+    let mut t = match root.take() {
+        None => return,
+        Some(t) => t,
+    };
+
+    // Each node in `lefts` becomes the right child of the previous one, and the nodes in `rights`
+    // become the left children, which are the holes left by the splitting.
+    let mut lefts: Vec<Box<Node<K, V>>> = Vec::new();
+    let mut rights: Vec<Box<Node<K, V>>> = Vec::new();
+    loop {
+        match Ord::cmp(k, &t.k) {
+            Ordering::Less => {
+                let mut l = match t.l.take() {
+                    None => break,
+                    Some(l) => l,
+                };
+                if *k < l.k {
+                    // Rotate right.
+                    t.l = l.r.take();
+                    l.r = Some(t);
+                    t = l;
+                    match t.l.take() {
+                        None => break,
+                        Some(next) => l = next,
+                    }
+                }
+                rights.push(t);
+                t = l;
+            }
+            Ordering::Greater => {
+                let mut r = match t.r.take() {
+                    None => break,
+                    Some(r) => r,
+                };
+                if *k > r.k {
+                    // Rotate left.
+                    t.r = r.l.take();
+                    r.l = Some(t);
+                    t = r;
+                    match t.r.take() {
+                        None => break,
+                        Some(next) => r = next,
+                    }
+                }
+                lefts.push(t);
+                t = r;
+            }
+            Ordering::Equal => break,
+        }
+    }
+
+    // Reassemble: the deepest node of each side takes the subtree of the new root.
+    let mut l = t.l.take();
+    while let Some(mut node) = lefts.pop() {
+        node.r = l;
+        l = Some(node);
+    }
+    let mut r = t.r.take();
+    while let Some(mut node) = rights.pop() {
+        node.l = r;
+        r = Some(node);
+    }
+    t.l = l;
+    t.r = r;
+    *root = Some(t);
+}
+
+impl<K, V> SplayTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        SplayTree {
+            size: 0,
+            root: None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Splay the key, and return whether it's at the root.
+    fn access(&mut self, k: &K) -> bool {
+        splay(&mut self.root, k);
+        self.root.as_ref().is_some_and(|node| node.k == *k)
+    }
+
+    /// Insert the pair, and return false if the key exists. The key becomes the root anyway.
+    pub fn insert(&mut self, key: K, val: V) -> bool {
+        if self.access(&key) {
+            return false;
+        }
+
+        let mut node = Box::new(Node {
+            k: key,
+            v: val,
+            l: None,
+            r: None,
+        });
+        // The root is the neighbour of the new key after the splay.
+        if let Some(mut root) = self.root.take() {
+            if node.k < root.k {
+                node.l = root.l.take();
+                node.r = Some(root);
+            } else {
+                node.r = root.r.take();
+                node.l = Some(root);
+            }
+        }
+        self.root = Some(node);
+        self.size += 1;
+        true
+    }
+
+    /// Get the value and move the key to the root, so it takes `&mut self`. `Map::get` doesn't
+    /// splay, and is picked over this one on a shared tree when `Map` is in scope.
+    pub fn get(&mut self, key: K) -> Option<&V> {
+        if !self.access(&key) {
+            return None;
+        }
+        self.root.as_ref().map(|node| &node.v)
+    }
+
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        if !self.access(&key) {
+            return None;
+        }
+        self.root.as_mut().map(|node| &mut node.v)
+    }
+
+    /// Get the value without changing the shape of the tree.
+    pub fn peek(&self, key: K) -> Option<&V> {
+        self.find(&key)
+    }
+
+    fn find(&self, key: &K) -> Option<&V> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            match Ord::cmp(key, &node.k) {
+                Ordering::Less => cur = &node.l,
+                Ordering::Greater => cur = &node.r,
+                Ordering::Equal => return Some(&node.v),
+            }
+        }
+        None
+    }
+
+    pub fn delete(&mut self, key: K) -> Option<V> {
+        self.remove_key(&key)
+    }
+
+    fn remove_key(&mut self, key: &K) -> Option<V> {
+        if !self.access(key) {
+            return None;
+        }
+
+        let root = *self.root.take().unwrap();
+        self.root = match root.l {
+            None => root.r,
+            Some(l) => {
+                // The max of the left subtree becomes its root, which has no right child.
+                let mut l = Some(l);
+                splay(&mut l, key);
+                l.as_mut().unwrap().r = root.r;
+                l
+            }
+        };
+        self.size -= 1;
+        Some(root.v)
+    }
+
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut level: Vec<&Node<K, V>> = self.root.iter().map(|node| node.as_ref()).collect();
+        while !level.is_empty() {
+            height += 1;
+            level = level
+                .iter()
+                .flat_map(|node| [&node.l, &node.r])
+                .flatten()
+                .map(|node| node.as_ref())
+                .collect();
+        }
+        height
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        InOrder::new(self.root.as_deref())
+    }
+}
+
+impl<K: Ord, V> Default for SplayTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Val = V;
+
+    fn key(&self) -> &K {
+        &self.k
+    }
+
+    fn val(&self) -> &V {
+        &self.v
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.l.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.r.as_deref()
+    }
+}
+
+// Drop the nodes one by one, since the recursive drop may overflow the stack on a deep tree.
+impl<K, V> Drop for SplayTree<K, V> {
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.l.take());
+            stack.extend(node.r.take());
+        }
+    }
+}
+
+// The lookup of the map doesn't splay, since it only borrows the tree.
+impl<K: Ord, V> Map<K, V> for SplayTree<K, V> {
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        if self.access(&k) {
+            let root = self.root.as_mut().unwrap();
+            return Some(mem::replace(&mut root.v, v));
+        }
+        SplayTree::insert(self, k, v);
+        None
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        self.remove_key(k)
+    }
+
+    // The shared lookup can't splay, see `Map::get`.
+    fn get(&self, k: &K) -> Option<&V> {
+        self.find(k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        if !self.access(k) {
+            return None;
+        }
+        self.root.as_mut().map(|node| &mut node.v)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        SplayTree::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+impl<K: Ord, V> OrderedMap<K, V> for SplayTree<K, V> {
+    fn range_for_each(&self, range: impl RangeBounds<K>, f: impl FnMut(&K, &V)) {
+        range_for_each(self.root.as_deref(), range, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Not `Map`, whose `get` on `&self` would be picked over the splaying one.
+    use super::SplayTree;
+    use crate::basic::rand;
+
+    // Check the order of the keys, and return the number of the nodes.
+    fn verify<K: Ord, V>(tree: &SplayTree<K, V>) -> usize {
+        let keys: Vec<&K> = tree.iter().map(|(k, _)| k).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(keys.len(), tree.size());
+        keys.len()
+    }
+
+    #[test]
+    pub fn test_basics() {
+        let mut tree = SplayTree::new();
+        assert_eq!(tree.get(1), None);
+        assert_eq!(tree.delete(1), None);
+
+        for i in 0..100 {
+            assert!(tree.insert(i, i * 10));
+            assert_eq!(tree.root.as_ref().unwrap().k, i);
+        }
+        assert!(!tree.insert(5, 0));
+        assert_eq!(tree.get(5), Some(&50));
+        assert_eq!(tree.root.as_ref().unwrap().k, 5);
+        *tree.get_mut(6).unwrap() += 1;
+        assert_eq!(tree.peek(6), Some(&61));
+        assert_eq!(tree.peek(100), None);
+        assert_eq!(tree.delete(5), Some(50));
+        assert_eq!(tree.delete(5), None);
+        assert_eq!(verify(&tree), 99);
+    }
+
+    #[test]
+    pub fn test_skewed_reads() {
+        // The sorted insertion makes a linked list.
+        let n = 100_000;
+        let mut tree = SplayTree::new();
+        for i in 0..n {
+            tree.insert(i, i);
+        }
+        assert_eq!(tree.height(), n as usize);
+
+        // Accessing the deepest key halves the depth of the path.
+        assert_eq!(tree.get(0), Some(&0));
+        assert!(tree.height() < n as usize / 2 + 2);
+
+        // The hot keys stay near the root.
+        for _ in 0..1000 {
+            let k = rand::gen_u32() % 8;
+            assert_eq!(tree.get(k), Some(&k));
+        }
+        let mut depth = 0;
+        let mut cur = &tree.root;
+        while let Some(node) = cur {
+            if node.k < 8 {
+                break;
+            }
+            depth += 1;
+            cur = &node.l;
+        }
+        assert!(depth < 8);
+    }
+
+    #[test]
+    pub fn test_map_lookups() {
+        use crate::basic::map::Map;
+
+        let mut tree = SplayTree::new();
+        for i in 0..100 {
+            tree.insert(i, i);
+        }
+        // The shared lookup keeps the shape, and the mutable one splays.
+        assert_eq!(Map::get(&tree, &0), Some(&0));
+        assert_eq!(tree.height(), 100);
+        *Map::get_mut(&mut tree, &0).unwrap() = 10;
+        assert_eq!(
+            tree.root.as_ref().map(|node| (node.k, node.v)),
+            Some((0, 10))
+        );
+        assert!(tree.height() < 52);
+        assert_eq!(Map::get_mut(&mut tree, &100), None);
+    }
+
+    #[test]
+    pub fn test_verify_tree() {
+        let max = 1000;
+        let mut tree = SplayTree::new();
+
+        for _ in 0..max {
+            let x = rand::gen_i32() % 500;
+            match rand::gen_u32() % 3 {
+                0 => {
+                    tree.insert(x, x);
+                }
+                1 => {
+                    tree.delete(x);
+                }
+                _ => {
+                    assert!(tree.get(x).is_none_or(|v| *v == x));
+                }
+            }
+            verify(&tree);
+        }
+    }
+}
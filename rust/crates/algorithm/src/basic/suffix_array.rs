@@ -0,0 +1,324 @@
+/**
+ * Suffix array built by prefix doubling, and the LCP array built by Kasai's algorithm.
+ * Please see: https://en.wikipedia.org/wiki/Suffix_array and https://en.wikipedia.org/wiki/LCP_array
+ */
+pub struct SuffixArray<'a> {
+    text: &'a [u8],
+    sa: Vec<usize>,
+    lcp: Vec<usize>,
+}
+
+/// Sort all the suffixes of `s` by prefix doubling: in the k-th round every suffix is ranked by its
+/// first 2^k symbols, using the ranks of the previous round as the (first, second) sort keys.
+fn build_sa(s: &[u32]) -> Vec<usize> {
+    let n = s.len();
+    let mut sa: Vec<usize> = (0..n).collect();
+    if n <= 1 {
+        return sa;
+    }
+
+    let mut rank: Vec<usize> = s.iter().map(|&c| c as usize).collect();
+    let mut tmp = vec![0; n];
+    let mut k = 1;
+    loop {
+        // The suffix shorter than k sorts before all the others which share the same first half.
+        let key = |i: usize| (rank[i], if i + k < n { rank[i + k] + 1 } else { 0 });
+        sa.sort_by_key(|&i| key(i));
+
+        tmp[sa[0]] = 0;
+        for i in 1..n {
+            tmp[sa[i]] = tmp[sa[i - 1]] + if key(sa[i - 1]) < key(sa[i]) { 1 } else { 0 };
+        }
+        std::mem::swap(&mut rank, &mut tmp);
+
+        if rank[sa[n - 1]] == n - 1 {
+            // All the ranks are distinct, so the order will never change again.
+            break;
+        }
+        k *= 2;
+    }
+
+    sa
+}
+
+/// Kasai's algorithm: lcp[i] is the length of the longest common prefix between the suffixes
+/// sa[i - 1] and sa[i], and lcp[0] is always 0.
+fn build_lcp(s: &[u32], sa: &[usize]) -> Vec<usize> {
+    let n = s.len();
+    let mut rank = vec![0; n];
+    for i in 0..n {
+        rank[sa[i]] = i;
+    }
+
+    let mut lcp = vec![0; n];
+    let mut h = 0;
+    for i in 0..n {
+        if rank[i] == 0 {
+            h = 0;
+            continue;
+        }
+
+        let j = sa[rank[i] - 1];
+        while i + h < n && j + h < n && s[i + h] == s[j + h] {
+            h += 1;
+        }
+        lcp[rank[i]] = h;
+
+        // The suffix i + 1 shares at least h - 1 symbols with its predecessor.
+        h = h.saturating_sub(1);
+    }
+
+    lcp
+}
+
+/// The first index in [lo, hi) which does not satisfy `pred`, assuming `pred` is monotone.
+fn partition_point<F: Fn(usize) -> bool>(mut lo: usize, mut hi: usize, pred: F) -> usize {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+impl<'a> SuffixArray<'a> {
+    pub fn new(text: &'a [u8]) -> Self {
+        let s: Vec<u32> = text.iter().map(|&c| c as u32).collect();
+        let sa = build_sa(&s);
+        let lcp = build_lcp(&s, &sa);
+        SuffixArray { text, sa, lcp }
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn sa(&self) -> &[usize] {
+        &self.sa
+    }
+
+    pub fn lcp(&self) -> &[usize] {
+        &self.lcp
+    }
+
+    fn prefix(&self, i: usize, m: usize) -> &[u8] {
+        let start = self.sa[i];
+        &self.text[start..self.text.len().min(start + m)]
+    }
+
+    /// The range [lo, hi) in the suffix array whose suffixes all start with `p`.
+    fn equal_range(&self, p: &[u8]) -> (usize, usize) {
+        let m = p.len();
+        let n = self.sa.len();
+        let lo = partition_point(0, n, |i| self.prefix(i, m) < p);
+        let hi = partition_point(lo, n, |i| self.prefix(i, m) <= p);
+        (lo, hi)
+    }
+
+    /// Count the occurrences of `p` in the text, the empty pattern never matches.
+    pub fn count(&self, p: &[u8]) -> usize {
+        if p.is_empty() {
+            return 0;
+        }
+        let (lo, hi) = self.equal_range(p);
+        hi - lo
+    }
+
+    /// All the positions where `p` occurs in the text, in ascending order.
+    pub fn find_all(&self, p: &[u8]) -> Vec<usize> {
+        if p.is_empty() {
+            return Vec::new();
+        }
+        let (lo, hi) = self.equal_range(p);
+        let mut res: Vec<usize> = self.sa[lo..hi].to_vec();
+        res.sort();
+        res
+    }
+
+    /// The longest substring which occurs at least twice (occurrences may overlap), None if there
+    /// is no repeated substring at all.
+    pub fn longest_repeated_substring(&self) -> Option<&'a [u8]> {
+        let mut best = 0;
+        for i in 1..self.lcp.len() {
+            if self.lcp[i] > self.lcp[best] {
+                best = i;
+            }
+        }
+
+        if self.lcp.is_empty() || self.lcp[best] == 0 {
+            return None;
+        }
+        let start = self.sa[best];
+        Some(&self.text[start..start + self.lcp[best]])
+    }
+}
+
+/// The longest common substring of `a` and `b`, found by building the suffix array of `a#b` where
+/// `#` is a separator which is out of the byte alphabet, so no common prefix can cross it.
+pub fn longest_common_substring<'a>(a: &'a [u8], b: &[u8]) -> Option<&'a [u8]> {
+    let mut s: Vec<u32> = Vec::with_capacity(a.len() + b.len() + 1);
+    s.extend(a.iter().map(|&c| c as u32));
+    s.push(256);
+    s.extend(b.iter().map(|&c| c as u32));
+
+    let sa = build_sa(&s);
+    let lcp = build_lcp(&s, &sa);
+
+    let mut best: Option<(usize, usize)> = None;
+    for i in 1..s.len() {
+        // Only the adjacent suffixes which come from different texts are interesting.
+        if (sa[i - 1] < a.len()) == (sa[i] < a.len()) {
+            continue;
+        }
+        if lcp[i] > best.map_or(0, |(_, len)| len) {
+            best = Some((sa[i - 1].min(sa[i]), lcp[i]));
+        }
+    }
+
+    best.map(|(start, len)| &a[start..start + len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+
+    fn naive_sa(s: &[u8]) -> Vec<usize> {
+        let mut sa: Vec<usize> = (0..s.len()).collect();
+        sa.sort_by(|&a, &b| s[a..].cmp(&s[b..]));
+        sa
+    }
+
+    fn naive_lcp(s: &[u8], sa: &[usize]) -> Vec<usize> {
+        let mut lcp = vec![0; sa.len()];
+        for i in 1..sa.len() {
+            let (a, b) = (&s[sa[i - 1]..], &s[sa[i]..]);
+            lcp[i] = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+        }
+        lcp
+    }
+
+    fn naive_find_all(s: &[u8], p: &[u8]) -> Vec<usize> {
+        if p.is_empty() || p.len() > s.len() {
+            return Vec::new();
+        }
+        (0..s.len() - p.len() + 1)
+            .filter(|&i| &s[i..i + p.len()] == p)
+            .collect()
+    }
+
+    fn naive_lrs(s: &[u8]) -> usize {
+        for len in (1..s.len()).rev() {
+            for i in 0..s.len() - len + 1 {
+                if naive_find_all(s, &s[i..i + len]).len() >= 2 {
+                    return len;
+                }
+            }
+        }
+        0
+    }
+
+    fn naive_lcs(a: &[u8], b: &[u8]) -> usize {
+        let mut best = 0;
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                let len = a[i..]
+                    .iter()
+                    .zip(b[j..].iter())
+                    .take_while(|(x, y)| x == y)
+                    .count();
+                best = best.max(len);
+            }
+        }
+        best
+    }
+
+    fn rand_text(max_len: u32, alphabet: u8) -> Vec<u8> {
+        let len = rand::gen_u32() % max_len;
+        (0..len)
+            .map(|_| b'a' + (rand::gen_u32() % alphabet as u32) as u8)
+            .collect()
+    }
+
+    #[test]
+    pub fn basics() {
+        let sa = SuffixArray::new(b"banana");
+        assert_eq!(sa.sa(), &[5, 3, 1, 0, 4, 2]);
+        assert_eq!(sa.lcp(), &[0, 1, 3, 0, 0, 2]);
+
+        assert_eq!(sa.count(b"ana"), 2);
+        assert_eq!(sa.count(b"an"), 2);
+        assert_eq!(sa.count(b"a"), 3);
+        assert_eq!(sa.count(b"banana"), 1);
+        assert_eq!(sa.count(b"bananas"), 0);
+        assert_eq!(sa.count(b"c"), 0);
+        assert_eq!(sa.count(b""), 0);
+        assert_eq!(sa.find_all(b"ana"), vec![1, 3]);
+
+        assert_eq!(sa.longest_repeated_substring(), Some(&b"ana"[..]));
+        assert_eq!(
+            longest_common_substring(b"xabcdy", b"zzbcdzz"),
+            Some(&b"bcd"[..])
+        );
+    }
+
+    #[test]
+    pub fn corner_cases() {
+        let empty = SuffixArray::new(b"");
+        assert!(empty.is_empty());
+        assert_eq!(empty.sa(), &[] as &[usize]);
+        assert_eq!(empty.count(b"a"), 0);
+        assert_eq!(empty.longest_repeated_substring(), None);
+
+        let single = SuffixArray::new(b"a");
+        assert_eq!(single.sa(), &[0]);
+        assert_eq!(single.count(b"a"), 1);
+        assert_eq!(single.longest_repeated_substring(), None);
+
+        let same = SuffixArray::new(b"aaaa");
+        assert_eq!(same.sa(), &[3, 2, 1, 0]);
+        assert_eq!(same.lcp(), &[0, 1, 2, 3]);
+        assert_eq!(same.count(b"aa"), 3);
+        assert_eq!(same.longest_repeated_substring(), Some(&b"aaa"[..]));
+
+        assert_eq!(longest_common_substring(b"", b"abc"), None);
+        assert_eq!(longest_common_substring(b"abc", b"def"), None);
+        assert_eq!(longest_common_substring(b"abc", b"abc"), Some(&b"abc"[..]));
+    }
+
+    #[test]
+    pub fn test_verify_with_naive() {
+        for _ in 0..200 {
+            let text = rand_text(64, 3);
+            let sa = SuffixArray::new(&text);
+            assert_eq!(sa.sa(), naive_sa(&text).as_slice());
+            assert_eq!(sa.lcp(), naive_lcp(&text, sa.sa()).as_slice());
+
+            for _ in 0..10 {
+                let p = rand_text(5, 3);
+                assert_eq!(sa.count(&p), naive_find_all(&text, &p).len());
+                assert_eq!(sa.find_all(&p), naive_find_all(&text, &p));
+            }
+
+            let lrs = sa.longest_repeated_substring();
+            assert_eq!(lrs.map_or(0, |s| s.len()), naive_lrs(&text));
+            if let Some(s) = lrs {
+                assert!(naive_find_all(&text, s).len() >= 2);
+            }
+
+            let other = rand_text(64, 3);
+            let lcs = longest_common_substring(&text, &other);
+            assert_eq!(lcs.map_or(0, |s| s.len()), naive_lcs(&text, &other));
+            if let Some(s) = lcs {
+                assert!(!naive_find_all(&other, s).is_empty());
+            }
+        }
+    }
+}
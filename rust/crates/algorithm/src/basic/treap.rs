@@ -0,0 +1,415 @@
+/**
+ * The treap, which is a binary search tree by the keys and a max-heap by the random priorities at
+ * the same time. Its shape is the same as the tree built by inserting the keys in the order of
+ * their priorities, so the expected height is O(log(n)) for any input.
+ *
+ * All the updates are done by `split` and `merge`, and the priorities come from the injected
+ * `RandomSource`, so the shape is reproducible by the seed.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Treap
+ */
+use crate::basic::map::{range_for_each, BinaryNode, InOrder, Map, OrderedMap};
+use crate::basic::rand::{RandomSource, SeedableSource, SplitMix64};
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::RangeBounds;
+
+const DEFAULT_SEED: u64 = 0x5EED;
+
+pub struct Treap<K, V, R = SplitMix64> {
+    rng: R,
+    root: Link<K, V>,
+}
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+pub struct Node<K, V> {
+    k: K,
+    v: V,
+    prio: u64,
+    // The number of the nodes in the subtree.
+    size: usize,
+    l: Link<K, V>,
+    r: Link<K, V>,
+}
+
+pub type Iter<'a, K, V> = InOrder<'a, Node<K, V>>;
+
+fn size_of<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+impl<K, V> Node<K, V> {
+    fn update(&mut self) {
+        self.size = size_of(&self.l) + size_of(&self.r) + 1;
+    }
+}
+
+// Split the tree into the keys less than `k` and the rest.
+fn split<K: Ord, V>(link: Link<K, V>, k: &K) -> (Link<K, V>, Link<K, V>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if node.k < *k {
+                let (l, r) = split(node.r.take(), k);
+                node.r = l;
+                node.update();
+                (Some(node), r)
+            } else {
+                let (l, r) = split(node.l.take(), k);
+                node.l = r;
+                node.update();
+                (l, Some(node))
+            }
+        }
+    }
+}
+
+// Merge two trees, all the keys in `a` must be less than the keys in `b`.
+fn merge<K, V>(a: Link<K, V>, b: Link<K, V>) -> Link<K, V> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.prio > b.prio {
+                a.r = merge(a.r.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.l = merge(Some(a), b.l.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+fn remove<K: Ord, V>(link: &mut Link<K, V>, k: &K) -> Option<V> {
+    let node = link.as_mut()?;
+    let v = match Ord::cmp(k, &node.k) {
+        Ordering::Less => remove(&mut node.l, k),
+        Ordering::Greater => remove(&mut node.r, k),
+        Ordering::Equal => {
+            let node = *link.take().unwrap();
+            *link = merge(node.l, node.r);
+            return Some(node.v);
+        }
+    };
+    if v.is_some() {
+        node.size -= 1;
+    }
+    v
+}
+
+impl<K, V> Treap<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::with_rng(SplitMix64::new(DEFAULT_SEED))
+    }
+}
+
+impl<K, V, R> Treap<K, V, R>
+where
+    K: Ord,
+    R: RandomSource,
+{
+    /// Create the treap which takes the priorities from `rng`.
+    pub fn with_rng(rng: R) -> Self {
+        Treap { rng, root: None }
+    }
+
+    pub fn size(&self) -> usize {
+        size_of(&self.root)
+    }
+
+    /// Insert the pair, and return false if the key exists.
+    pub fn insert(&mut self, key: K, val: V) -> bool {
+        if self.find(&key).is_some() {
+            return false;
+        }
+
+        let node = Box::new(Node {
+            k: key,
+            v: val,
+            prio: self.rng.next_u64(),
+            size: 1,
+            l: None,
+            r: None,
+        });
+        let (l, r) = split(self.root.take(), &node.k);
+        self.root = merge(merge(l, Some(node)), r);
+        true
+    }
+
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.find(&key)
+    }
+
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.find_mut(&key)
+    }
+
+    pub fn delete(&mut self, key: K) -> Option<V> {
+        remove(&mut self.root, &key)
+    }
+
+    fn find(&self, key: &K) -> Option<&V> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            match Ord::cmp(key, &node.k) {
+                Ordering::Less => cur = &node.l,
+                Ordering::Greater => cur = &node.r,
+                Ordering::Equal => return Some(&node.v),
+            }
+        }
+        None
+    }
+
+    fn find_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut cur = &mut self.root;
+        while let Some(node) = cur {
+            match Ord::cmp(key, &node.k) {
+                Ordering::Less => cur = &mut node.l,
+                Ordering::Greater => cur = &mut node.r,
+                Ordering::Equal => return Some(&mut node.v),
+            }
+        }
+        None
+    }
+
+    /// Move the keys no less than `key` into the returned treap. Its source is seeded from this
+    /// one, so the two halves don't draw the same priorities for their later inserts.
+    pub fn split_off(&mut self, key: &K) -> Self
+    where
+        R: SeedableSource,
+    {
+        let (l, r) = split(self.root.take(), key);
+        self.root = l;
+        Treap {
+            rng: R::from_seed(self.rng.next_u64()),
+            root: r,
+        }
+    }
+
+    /// Move all the keys of `other` into this treap, which must be greater than the keys here.
+    pub fn merge(&mut self, mut other: Treap<K, V, R>) {
+        if let (Some(max), Some(min)) = (self.max(), other.min()) {
+            assert!(
+                max.0 < min.0,
+                "The keys to merge must be greater than the existing keys"
+            );
+        }
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    pub fn min(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(l) = node.l.as_deref() {
+            node = l;
+        }
+        Some((&node.k, &node.v))
+    }
+
+    pub fn max(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(r) = node.r.as_deref() {
+            node = r;
+        }
+        Some((&node.k, &node.v))
+    }
+
+    /// The k-th smallest pair, k starts from 0.
+    pub fn select(&self, mut k: usize) -> Option<(&K, &V)> {
+        let mut cur = &self.root;
+        while let Some(node) = cur {
+            let l = size_of(&node.l);
+            match Ord::cmp(&k, &l) {
+                Ordering::Less => cur = &node.l,
+                Ordering::Equal => return Some((&node.k, &node.v)),
+                Ordering::Greater => {
+                    k -= l + 1;
+                    cur = &node.r;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn height(&self) -> usize {
+        fn height<K, V>(link: &Link<K, V>) -> usize {
+            link.as_ref()
+                .map_or(0, |node| height(&node.l).max(height(&node.r)) + 1)
+        }
+        height(&self.root)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        InOrder::new(self.root.as_deref())
+    }
+}
+
+impl<K: Ord, V> Default for Treap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Val = V;
+
+    fn key(&self) -> &K {
+        &self.k
+    }
+
+    fn val(&self) -> &V {
+        &self.v
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.l.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.r.as_deref()
+    }
+}
+
+impl<K: Ord, V, R: RandomSource> Map<K, V> for Treap<K, V, R> {
+    type Iter<'a>
+        = Iter<'a, K, V>
+    where
+        Self: 'a;
+
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        match self.find_mut(&k) {
+            Some(old) => Some(mem::replace(old, v)),
+            None => {
+                Treap::insert(self, k, v);
+                None
+            }
+        }
+    }
+
+    fn remove(&mut self, k: &K) -> Option<V> {
+        remove(&mut self.root, k)
+    }
+
+    fn get(&self, k: &K) -> Option<&V> {
+        self.find(k)
+    }
+
+    fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        self.find_mut(k)
+    }
+
+    fn iter(&self) -> Iter<'_, K, V> {
+        Treap::iter(self)
+    }
+
+    fn len(&self) -> usize {
+        self.size()
+    }
+}
+
+impl<K: Ord, V, R: RandomSource> OrderedMap<K, V> for Treap<K, V, R> {
+    fn range_for_each(&self, range: impl RangeBounds<K>, f: impl FnMut(&K, &V)) {
+        range_for_each(self.root.as_deref(), range, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::rand;
+
+    // Check the order of the keys, the heap of the priorities and the sizes.
+    fn verify<K: Ord, V>(link: &Link<K, V>, lo: Option<&K>, hi: Option<&K>) -> usize {
+        match link {
+            None => 0,
+            Some(node) => {
+                assert!(lo.is_none_or(|lo| *lo < node.k));
+                assert!(hi.is_none_or(|hi| node.k < *hi));
+                for child in [&node.l, &node.r].into_iter().flatten() {
+                    assert!(child.prio <= node.prio);
+                }
+                let size = verify(&node.l, lo, Some(&node.k)) + verify(&node.r, Some(&node.k), hi);
+                assert_eq!(node.size, size + 1);
+                node.size
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_basics() {
+        let mut tree = Treap::new();
+        for i in 0..100 {
+            assert!(tree.insert(i, i * 10));
+        }
+        assert!(!tree.insert(5, 0));
+        assert_eq!(tree.get(5), Some(&50));
+        assert_eq!(tree.get(100), None);
+        *tree.get_mut(6).unwrap() += 1;
+        assert_eq!(tree.get(6), Some(&61));
+        assert_eq!(tree.size(), 100);
+        assert_eq!(tree.select(42), Some((&42, &420)));
+        assert_eq!(tree.min(), Some((&0, &0)));
+        assert_eq!(tree.max(), Some((&99, &990)));
+        assert_eq!(tree.delete(5), Some(50));
+        assert_eq!(tree.delete(5), None);
+        assert_eq!(tree.size(), 99);
+        verify(&tree.root, None, None);
+
+        let mut right = tree.split_off(&50);
+        assert!(tree.iter().map(|(k, _)| *k).eq((0..50).filter(|i| *i != 5)));
+        assert!(right.iter().map(|(k, _)| *k).eq(50..100));
+        let (a, b): (Vec<u64>, Vec<u64>) = (0..8)
+            .map(|_| (tree.rng.next_u64(), right.rng.next_u64()))
+            .unzip();
+        assert_ne!(a, b);
+        assert!(right.insert(200, 0));
+        tree.merge(right);
+        assert_eq!(tree.size(), 100);
+        assert_eq!(tree.max(), Some((&200, &0)));
+        verify(&tree.root, None, None);
+    }
+
+    #[test]
+    pub fn test_same_seed_same_shape() {
+        let shape = |seed| {
+            let mut tree = Treap::with_rng(SplitMix64::new(seed));
+            (0..1000).for_each(|i| {
+                tree.insert(i, ());
+            });
+            let mut prios = Vec::new();
+            let mut stack: Vec<&Node<i32, ()>> = tree.root.iter().map(|n| n.as_ref()).collect();
+            while let Some(node) = stack.pop() {
+                prios.push(node.prio);
+                stack.extend([&node.l, &node.r].into_iter().flatten().map(|n| n.as_ref()));
+            }
+            (tree.height(), prios)
+        };
+        assert_eq!(shape(7), shape(7));
+        assert_ne!(shape(7).1, shape(8).1);
+        // The expected height of the sorted input is about 2 * ln(n).
+        assert!(shape(7).0 < 40);
+    }
+
+    #[test]
+    pub fn test_verify_tree() {
+        let max = 1000;
+        let mut tree = Treap::with_rng(SplitMix64::new(rand::gen_u32() as u64));
+
+        for _ in 0..max {
+            let x = rand::gen_i32() % 500;
+            if rand::gen_bool() {
+                tree.insert(x, x);
+            } else {
+                tree.delete(x);
+            }
+            verify(&tree.root, None, None);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-struct TrieTree {
+pub(crate) struct TrieTree {
     root: Link,
 }
 
@@ -45,11 +45,11 @@ impl Node {
 }
 
 impl TrieTree {
-    fn new() -> Self {
+    pub fn new() -> Self {
         TrieTree { root: Node::new() }
     }
 
-    fn add(&mut self, s: &str) {
+    pub fn add(&mut self, s: &str) {
         let mut ptr = &mut self.root;
         let bytes = s.as_bytes();
 
@@ -63,7 +63,7 @@ impl TrieTree {
         ptr.as_mut().unwrap().inc();
     }
 
-    fn delete(&mut self, s: &str) -> bool {
+    pub fn delete(&mut self, s: &str) -> bool {
         let mut ptr = &mut self.root;
         let bytes = s.as_bytes();
 
@@ -77,7 +77,7 @@ impl TrieTree {
         ptr.as_mut().unwrap().dec()
     }
 
-    fn find(&self, s: &str) -> bool {
+    pub fn find(&self, s: &str) -> bool {
         let mut ptr = &self.root;
         let bytes = s.as_bytes();
 
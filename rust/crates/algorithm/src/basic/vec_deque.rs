@@ -0,0 +1,461 @@
+/**
+ * The double-ended queue on a growable ring buffer. The elements live in one contiguous
+ * allocation and wrap around its end, so both ends are amortized O(1) without chasing pointers.
+ *
+ * Please see: https://en.wikipedia.org/wiki/Circular_buffer
+ */
+use std::fmt::{self, Debug};
+use std::iter::FromIterator;
+use std::mem::MaybeUninit;
+use std::ops::{Index, IndexMut};
+use std::slice;
+
+pub struct VecDeque<T> {
+    buf: Box<[MaybeUninit<T>]>,
+    // The physical index of the first element.
+    head: usize,
+    len: usize,
+}
+
+pub struct Iter<'a, T> {
+    a: slice::Iter<'a, T>,
+    b: slice::Iter<'a, T>,
+}
+
+pub struct IterMut<'a, T> {
+    a: slice::IterMut<'a, T>,
+    b: slice::IterMut<'a, T>,
+}
+
+pub struct IntoIter<T> {
+    deque: VecDeque<T>,
+}
+
+fn alloc<T>(cap: usize) -> Box<[MaybeUninit<T>]> {
+    (0..cap).map(|_| MaybeUninit::uninit()).collect()
+}
+
+impl<T> VecDeque<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(cap: usize) -> Self {
+        VecDeque {
+            buf: alloc(cap),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    // The physical index of the i-th element, `i` can be up to the capacity.
+    fn wrap(&self, i: usize) -> usize {
+        let idx = self.head + i;
+        if idx >= self.capacity() {
+            idx - self.capacity()
+        } else {
+            idx
+        }
+    }
+
+    // Move all the elements into a buffer of the double capacity, starting at index 0.
+    fn grow(&mut self) {
+        let new_cap = (self.capacity() * 2).max(4);
+        let mut buf = alloc(new_cap);
+        let (a, b) = self.as_slices();
+        let (a_len, b_len) = (a.len(), b.len());
+        unsafe {
+            std::ptr::copy_nonoverlapping(a.as_ptr(), buf.as_mut_ptr() as *mut T, a_len);
+            std::ptr::copy_nonoverlapping(
+                b.as_ptr(),
+                (buf.as_mut_ptr() as *mut T).add(a_len),
+                b_len,
+            );
+        }
+        // The elements are moved, and the old buffer of `MaybeUninit` drops nothing.
+        self.buf = buf;
+        self.head = 0;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+        let idx = self.wrap(self.len);
+        self.buf[idx].write(elem);
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+        self.head = self.wrap(self.capacity() - 1);
+        self.buf[self.head].write(elem);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let elem = unsafe { self.buf[self.head].assume_init_read() };
+        self.head = self.wrap(1);
+        self.len -= 1;
+        Some(elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let idx = self.wrap(self.len);
+        Some(unsafe { self.buf[idx].assume_init_read() })
+    }
+
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len {
+            return None;
+        }
+        Some(unsafe { self.buf[self.wrap(i)].assume_init_ref() })
+    }
+
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        if i >= self.len {
+            return None;
+        }
+        let idx = self.wrap(i);
+        Some(unsafe { self.buf[idx].assume_init_mut() })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_back().is_some() {}
+        self.head = 0;
+    }
+
+    /// The elements in order are the first slice followed by the second one, and the second one is
+    /// not empty only if the elements wrap around the end of the buffer.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let first = self.len.min(self.capacity() - self.head);
+        unsafe {
+            let base = self.buf.as_ptr() as *const T;
+            (
+                slice::from_raw_parts(base.add(self.head), first),
+                slice::from_raw_parts(base, self.len - first),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let first = self.len.min(self.capacity() - self.head);
+        unsafe {
+            let base = self.buf.as_mut_ptr() as *mut T;
+            (
+                slice::from_raw_parts_mut(base.add(self.head), first),
+                slice::from_raw_parts_mut(base, self.len - first),
+            )
+        }
+    }
+
+    /// Rotate the buffer in place so that all the elements are in one slice.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.head + self.len > self.capacity() {
+            // Rotating the `MaybeUninit` slots only moves the bits, the uninitialized gap included.
+            self.buf.rotate_left(self.head);
+            self.head = 0;
+        }
+        self.as_mut_slices().0
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (a, b) = self.as_slices();
+        Iter {
+            a: a.iter(),
+            b: b.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (a, b) = self.as_mut_slices();
+        IterMut {
+            a: a.iter_mut(),
+            b: b.iter_mut(),
+        }
+    }
+}
+
+impl<T> Drop for VecDeque<T> {
+    fn drop(&mut self) {
+        let (a, b) = self.as_mut_slices();
+        unsafe {
+            std::ptr::drop_in_place(a);
+            std::ptr::drop_in_place(b);
+        }
+    }
+}
+
+impl<T> Default for VecDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for VecDeque<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        self.get(i).expect("Out of bounds access")
+    }
+}
+
+impl<T> IndexMut<usize> for VecDeque<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        self.get_mut(i).expect("Out of bounds access")
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.a.next().or_else(|| self.b.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.a.len() + self.b.len();
+        (n, Some(n))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.b.next_back().or_else(|| self.a.next_back())
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.a.next().or_else(|| self.b.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.a.len() + self.b.len();
+        (n, Some(n))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.b.next_back().or_else(|| self.a.next_back())
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.deque.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.deque.len, Some(self.deque.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.deque.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for VecDeque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { deque: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a VecDeque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> Extend<T> for VecDeque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for VecDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = VecDeque::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<T: PartialEq> PartialEq for VecDeque<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for VecDeque<T> {}
+
+impl<T: Debug> Debug for VecDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basic::double_linked_list_unsafe::LinkedList;
+    use crate::basic::rand;
+
+    #[test]
+    pub fn basics() {
+        let mut d = VecDeque::new();
+        assert_eq!(d.pop_front(), None);
+        assert_eq!(d.pop_back(), None);
+
+        d.push_back(2);
+        d.push_back(3);
+        d.push_front(1);
+        d.push_front(0);
+        // Wrapped around the end of the buffer.
+        assert_eq!(d.capacity(), 4);
+        assert_eq!(d.as_slices(), (&[0, 1][..], &[2, 3][..]));
+        assert_eq!(d[0], 0);
+        assert_eq!(d[3], 3);
+        assert_eq!(d.get(4), None);
+        assert_eq!(d.front(), Some(&0));
+        assert_eq!(d.back(), Some(&3));
+
+        d[1] = 10;
+        d.push_back(4);
+        assert_eq!(d.iter().cloned().collect::<Vec<_>>(), vec![0, 10, 2, 3, 4]);
+        assert_eq!(
+            d.iter().rev().cloned().collect::<Vec<_>>(),
+            vec![4, 3, 2, 10, 0]
+        );
+        assert_eq!(d.iter().len(), 5);
+
+        for x in d.iter_mut() {
+            *x *= 2;
+        }
+        assert_eq!(d.pop_front(), Some(0));
+        assert_eq!(d.pop_back(), Some(8));
+        assert_eq!(format!("{:?}", d), "[20, 4, 6]");
+        assert_eq!(d.into_iter().rev().collect::<Vec<_>>(), vec![6, 4, 20]);
+    }
+
+    #[test]
+    pub fn make_contiguous() {
+        let mut d: VecDeque<String> = VecDeque::with_capacity(8);
+        for i in 0..6 {
+            d.push_back(i.to_string());
+        }
+        for _ in 0..4 {
+            d.pop_front();
+        }
+        for i in 6..10 {
+            d.push_back(i.to_string());
+        }
+        assert!(!d.as_slices().1.is_empty());
+
+        let expected: Vec<String> = (4..10).map(|i| i.to_string()).collect();
+        assert_eq!(d.make_contiguous(), &expected[..]);
+        assert!(d.as_slices().1.is_empty());
+        assert_eq!(d, expected.into_iter().collect());
+
+        d.clear();
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    pub fn random_ops() {
+        let mut d = VecDeque::new();
+        let mut expected = std::collections::VecDeque::new();
+        for _ in 0..100000 {
+            let v = rand::gen_i32();
+            match rand::gen_u32() % 5 {
+                0 => {
+                    d.push_front(v);
+                    expected.push_front(v);
+                }
+                1 => {
+                    d.push_back(v);
+                    expected.push_back(v);
+                }
+                2 => assert_eq!(d.pop_front(), expected.pop_front()),
+                3 => assert_eq!(d.pop_back(), expected.pop_back()),
+                _ => {
+                    if !expected.is_empty() {
+                        let i = rand::gen_u32() as usize % expected.len();
+                        assert_eq!(d[i], expected[i]);
+                    }
+                }
+            }
+            assert_eq!(d.len(), expected.len());
+        }
+        assert!(d.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    pub fn fifo_against_linked_list() {
+        // A FIFO workload which keeps about 1000 elements in the queue, the timing of both is
+        // compared by the bench binary.
+        let n = 10_000;
+
+        let mut d = VecDeque::new();
+        let mut list = LinkedList::new();
+        for i in 0..n {
+            d.push_back(i);
+            list.push_back(i);
+            if i >= 1000 {
+                assert_eq!(d.pop_front(), list.pop_front());
+            }
+        }
+        assert_eq!(d.len(), list.len());
+        assert!(d.iter().eq(list.iter()));
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::str::FromStr;
+
 pub struct Tree<T: Eq> {
     root: Link<T>,
 }
@@ -10,6 +14,31 @@ pub struct Node<T: Eq> {
 
 type Link<T> = Option<Box<Node<T>>>;
 
+pub struct PreIter<'a, T: Eq> {
+    stack: Vec<&'a Node<T>>,
+}
+
+pub struct InIter<'a, T: Eq> {
+    stack: Vec<&'a Node<T>>,
+    cur: Option<&'a Node<T>>,
+}
+
+pub struct PostIter<'a, T: Eq> {
+    // The flag tells whether both children of the node have been pushed.
+    stack: Vec<(&'a Node<T>, bool)>,
+}
+
+pub struct LevelIter<'a, T: Eq> {
+    queue: VecDeque<&'a Node<T>>,
+}
+
+pub struct ZigzagIter<'a, T: Eq> {
+    // The current level from left to right.
+    level: Vec<&'a Node<T>>,
+    pos: usize,
+    left_to_right: bool,
+}
+
 trait TreeLink<T: Eq> {
     fn find_mut(&mut self, elem: &T) -> Option<&mut Link<T>>;
 
@@ -128,6 +157,557 @@ where
         self.root.post_visit(&mut order);
         order
     }
+
+    pub fn pre_iter(&self) -> PreIter<'_, T> {
+        PreIter {
+            stack: self.root.as_deref().into_iter().collect(),
+        }
+    }
+
+    pub fn inorder_iter(&self) -> InIter<'_, T> {
+        InIter {
+            stack: Vec::new(),
+            cur: self.root.as_deref(),
+        }
+    }
+
+    pub fn post_iter(&self) -> PostIter<'_, T> {
+        PostIter {
+            stack: self
+                .root
+                .as_deref()
+                .map(|n| (n, false))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Breadth first search, from the top level to the bottom and from left to right.
+    pub fn level_iter(&self) -> LevelIter<'_, T> {
+        LevelIter {
+            queue: self.root.as_deref().into_iter().collect(),
+        }
+    }
+
+    /// Level order too, but the direction alternates level by level, starting from left to right.
+    pub fn zigzag_iter(&self) -> ZigzagIter<'_, T> {
+        ZigzagIter {
+            level: self.root.as_deref().into_iter().collect(),
+            pos: 0,
+            left_to_right: true,
+        }
+    }
+
+    /// The elements grouped by levels, each level from left to right.
+    pub fn levels(&self) -> Vec<Vec<&T>> {
+        let mut res = Vec::new();
+        let mut level: Vec<&Node<T>> = self.root.as_deref().into_iter().collect();
+        while !level.is_empty() {
+            res.push(level.iter().map(|n| &n.elem).collect());
+            level = level.iter().flat_map(|n| n.children()).collect();
+        }
+        res
+    }
+}
+
+impl<T: Eq> Node<T> {
+    fn new(elem: T, left: Link<T>, right: Link<T>) -> Link<T> {
+        Some(Box::new(Node { elem, left, right }))
+    }
+
+    fn children(&self) -> impl Iterator<Item = &Node<T>> {
+        self.left
+            .as_deref()
+            .into_iter()
+            .chain(self.right.as_deref())
+    }
+}
+
+// The cursor over a traversal sequence, which is consumed from the front or from the back.
+struct Seq<'a, T> {
+    elems: &'a [T],
+    pos: usize,
+    rev: bool,
+}
+
+impl<'a, T> Seq<'a, T> {
+    fn new(elems: &'a [T], rev: bool) -> Self {
+        Seq { elems, pos: 0, rev }
+    }
+
+    fn peek(&self) -> Option<&'a T> {
+        if self.pos >= self.elems.len() {
+            return None;
+        }
+        if self.rev {
+            Some(&self.elems[self.elems.len() - 1 - self.pos])
+        } else {
+            Some(&self.elems[self.pos])
+        }
+    }
+}
+
+impl<T> Tree<T>
+where
+    T: Eq + Clone,
+{
+    /// Rebuild the tree from the preorder and inorder sequences of the distinct elements. Returns
+    /// None if the sequences don't come from the same tree.
+    pub fn from_pre_in(pre: &[T], inorder: &[T]) -> Option<Self> {
+        let tree = Self::rebuild(Seq::new(pre, false), Seq::new(inorder, false), false)?;
+        if tree.pre_iter().eq(pre.iter()) {
+            Some(tree)
+        } else {
+            None
+        }
+    }
+
+    /// Rebuild the tree from the postorder and inorder sequences of the distinct elements.
+    pub fn from_post_in(post: &[T], inorder: &[T]) -> Option<Self> {
+        let tree = Self::rebuild(Seq::new(post, true), Seq::new(inorder, true), true)?;
+        if tree.post_iter().eq(post.iter()) {
+            Some(tree)
+        } else {
+            None
+        }
+    }
+
+    // The root comes first in the preorder, and the left subtree ends at the root in the inorder.
+    // The postorder is the mirror when both sequences are read backward, so the right subtree is
+    // built before the left one. Both sequences are consumed once, which is O(n).
+    fn rebuild(mut order: Seq<T>, mut inorder: Seq<T>, mirror: bool) -> Option<Self> {
+        if order.elems.len() != inorder.elems.len() {
+            return None;
+        }
+        let root = Self::rebuild_link(&mut order, &mut inorder, None, mirror)?;
+        if order.peek().is_some() || inorder.peek().is_some() {
+            return None;
+        }
+        Some(Tree { root })
+    }
+
+    fn rebuild_link(
+        order: &mut Seq<T>,
+        inorder: &mut Seq<T>,
+        stop: Option<&T>,
+        mirror: bool,
+    ) -> Option<Link<T>> {
+        let elem = match order.peek() {
+            None => return Some(None),
+            Some(_) if inorder.peek() == stop => return Some(None),
+            Some(elem) => elem,
+        };
+        order.pos += 1;
+
+        let first = Self::rebuild_link(order, inorder, Some(elem), mirror)?;
+        if inorder.peek() != Some(elem) {
+            return None;
+        }
+        inorder.pos += 1;
+        let second = Self::rebuild_link(order, inorder, stop, mirror)?;
+
+        if mirror {
+            Some(Node::new(elem.clone(), second, first))
+        } else {
+            Some(Node::new(elem.clone(), first, second))
+        }
+    }
+
+    /// Build the tree from the level order array, where None is the missing child. Just like the
+    /// LeetCode format, only the present nodes have their children in the array, e.g.
+    /// `[1, None, 2, 3]` is the root 1 with the right child 2, whose left child is 3.
+    pub fn from_level_order(arr: &[Option<T>]) -> Self {
+        // children[i] is the indexes of the left and right children of arr[i].
+        let mut children = vec![(None, None); arr.len()];
+        let mut next = 1;
+        for (i, elem) in arr.iter().enumerate() {
+            if elem.is_none() || (i > 0 && i >= next) {
+                continue;
+            }
+            children[i] = (Some(next), Some(next + 1));
+            next += 2;
+        }
+
+        Tree {
+            root: Self::level_link(arr, &children, Some(0)),
+        }
+    }
+
+    fn level_link(
+        arr: &[Option<T>],
+        children: &[(Option<usize>, Option<usize>)],
+        idx: Option<usize>,
+    ) -> Link<T> {
+        let i = idx?;
+        let elem = arr.get(i)?.clone()?;
+        let (l, r) = children[i];
+        Node::new(
+            elem,
+            Self::level_link(arr, children, l),
+            Self::level_link(arr, children, r),
+        )
+    }
+}
+
+// The height of the subtree and the longest path (in edges) inside it.
+fn diameter_of<T: Eq>(link: &Link<T>) -> (usize, usize) {
+    match link {
+        None => (0, 0),
+        Some(node) => {
+            let (lh, ld) = diameter_of(&node.left);
+            let (rh, rd) = diameter_of(&node.right);
+            (lh.max(rh) + 1, ld.max(rd).max(lh + rh))
+        }
+    }
+}
+
+// The max sum of the downward path starting at the node, and updates the best path through it.
+// The sums are i128, which can't overflow by adding up fewer than 2^64 values of i64.
+fn max_gain<T: Eq + Copy + Into<i64>>(link: &Link<T>, best: &mut i128) -> i128 {
+    match link {
+        None => 0,
+        Some(node) => {
+            let l = max_gain(&node.left, best).max(0);
+            let r = max_gain(&node.right, best).max(0);
+            let v = node.elem.into() as i128;
+            *best = (*best).max(v + l + r);
+            v + l.max(r)
+        }
+    }
+}
+
+// Returns the LCA if both are found in the subtree, otherwise the found one of `a` and `b`.
+fn lca_of<'a, T: Eq>(link: &'a Link<T>, a: &T, b: &T) -> (Option<&'a T>, bool, bool) {
+    match link {
+        None => (None, false, false),
+        Some(node) => {
+            let (l, la, lb) = lca_of(&node.left, a, b);
+            if l.is_some() {
+                return (l, true, true);
+            }
+            let (r, ra, rb) = lca_of(&node.right, a, b);
+            if r.is_some() {
+                return (r, true, true);
+            }
+            let has_a = la || ra || node.elem == *a;
+            let has_b = lb || rb || node.elem == *b;
+            let lca = if has_a && has_b {
+                Some(&node.elem)
+            } else {
+                None
+            };
+            (lca, has_a, has_b)
+        }
+    }
+}
+
+// The height of the subtree, or None if it's not balanced.
+fn balanced_height<T: Eq>(link: &Link<T>) -> Option<usize> {
+    match link {
+        None => Some(0),
+        Some(node) => {
+            let l = balanced_height(&node.left)?;
+            let r = balanced_height(&node.right)?;
+            if l.abs_diff(r) > 1 {
+                None
+            } else {
+                Some(l.max(r) + 1)
+            }
+        }
+    }
+}
+
+fn same_tree<T: Eq>(a: &Link<T>, b: &Link<T>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(x), Some(y)) => {
+            x.elem == y.elem && same_tree(&x.left, &y.left) && same_tree(&x.right, &y.right)
+        }
+        _ => false,
+    }
+}
+
+fn contains_tree<T: Eq>(link: &Link<T>, sub: &Link<T>) -> bool {
+    if same_tree(link, sub) {
+        return true;
+    }
+    match link {
+        None => false,
+        Some(node) => contains_tree(&node.left, sub) || contains_tree(&node.right, sub),
+    }
+}
+
+fn mirror_of<T: Eq>(link: &mut Link<T>) {
+    if let Some(node) = link {
+        std::mem::swap(&mut node.left, &mut node.right);
+        mirror_of(&mut node.left);
+        mirror_of(&mut node.right);
+    }
+}
+
+fn write_paren<T: Eq + Display>(link: &Link<T>, out: &mut String) {
+    if let Some(node) = link {
+        out.push_str(&node.elem.to_string());
+        if node.left.is_some() || node.right.is_some() {
+            out.push('(');
+            write_paren(&node.left, out);
+            out.push(')');
+        }
+        if node.right.is_some() {
+            out.push('(');
+            write_paren(&node.right, out);
+            out.push(')');
+        }
+    }
+}
+
+fn write_pretty<T: Eq + Display>(link: &Link<T>, prefix: &str, last: bool, out: &mut String) {
+    out.push_str(prefix);
+    out.push_str(if last { "└── " } else { "├── " });
+    let node = match link {
+        None => {
+            out.push_str("·\n");
+            return;
+        }
+        Some(node) => node,
+    };
+    out.push_str(&format!("{}\n", node.elem));
+
+    let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+    write_children(node, &prefix, out);
+}
+
+// The left child goes first, and a missing child is printed as `·` if its sibling is present.
+fn write_children<T: Eq + Display>(node: &Node<T>, prefix: &str, out: &mut String) {
+    if node.left.is_some() || node.right.is_some() {
+        write_pretty(&node.left, prefix, false, out);
+        write_pretty(&node.right, prefix, true, out);
+    }
+}
+
+// The parser of the parenthesized text, such as `1(2()(4))(3)`.
+struct ParenParser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ParenParser<'a> {
+    fn parse<T: Eq + FromStr>(&mut self) -> Result<Link<T>, String> {
+        let start = self.pos;
+        while self.pos < self.s.len() && self.s[self.pos] != b'(' && self.s[self.pos] != b')' {
+            self.pos += 1;
+        }
+        let token = std::str::from_utf8(&self.s[start..self.pos])
+            .unwrap()
+            .trim();
+        if token.is_empty() {
+            return Ok(None);
+        }
+        let elem = token
+            .parse::<T>()
+            .map_err(|_| format!("Invalid element {:?} at {}", token, start))?;
+
+        let left = self.child()?;
+        let right = self.child()?;
+        Ok(Node::new(elem, left.unwrap_or(None), right.unwrap_or(None)))
+    }
+
+    // Parse the optional `(subtree)`.
+    fn child<T: Eq + FromStr>(&mut self) -> Result<Option<Link<T>>, String> {
+        if self.s.get(self.pos) != Some(&b'(') {
+            return Ok(None);
+        }
+        self.pos += 1;
+        let link = self.parse()?;
+        if self.s.get(self.pos) != Some(&b')') {
+            return Err(format!("Expect ')' at {}", self.pos));
+        }
+        self.pos += 1;
+        Ok(Some(link))
+    }
+}
+
+/// The structural queries recurse once per level, so they need the stack in proportion to the
+/// height of the tree. A deep degenerate tree, such as a chain of many thousands of nodes, may
+/// overflow the stack of a thread.
+impl<T> Tree<T>
+where
+    T: Eq,
+{
+    /// The number of edges on the longest path between any two nodes.
+    pub fn diameter(&self) -> usize {
+        diameter_of(&self.root).1
+    }
+
+    /// The max sum of the path between any two nodes, which is None for the empty tree. The sum
+    /// saturates at `i64::MAX` if it overflows i64.
+    pub fn max_path_sum(&self) -> Option<i64>
+    where
+        T: Copy + Into<i64>,
+    {
+        self.root.as_ref()?;
+        let mut best = i128::MIN;
+        max_gain(&self.root, &mut best);
+        // The best is at least the value of a node, so it can only overflow upward.
+        Some(i64::try_from(best).unwrap_or(i64::MAX))
+    }
+
+    /// The lowest common ancestor of `a` and `b`, which is None unless both are in the tree.
+    pub fn lca(&self, a: &T, b: &T) -> Option<&T> {
+        lca_of(&self.root, a, b).0
+    }
+
+    /// Whether the heights of the two subtrees of every node differ by at most one.
+    pub fn is_balanced(&self) -> bool {
+        balanced_height(&self.root).is_some()
+    }
+
+    /// Whether the inorder sequence is strictly increasing.
+    pub fn is_bst(&self) -> bool
+    where
+        T: Ord,
+    {
+        let mut it = self.inorder_iter();
+        let mut prev = match it.next() {
+            None => return true,
+            Some(elem) => elem,
+        };
+        for elem in it {
+            if *prev >= *elem {
+                return false;
+            }
+            prev = elem;
+        }
+        true
+    }
+
+    /// Swap the left and right children of every node.
+    pub fn mirror(&mut self) {
+        mirror_of(&mut self.root);
+    }
+
+    /// Whether `other` is the same as a subtree of this tree, which is all the descendants of a
+    /// node. The empty tree is a subtree of any tree.
+    pub fn is_subtree(&self, other: &Tree<T>) -> bool {
+        contains_tree(&self.root, &other.root)
+    }
+
+    /// Whether both trees have the same shape and elements.
+    pub fn same_as(&self, other: &Tree<T>) -> bool {
+        same_tree(&self.root, &other.root)
+    }
+
+    /// The level order array which is accepted by `from_level_order`, without the trailing None.
+    pub fn to_level_order(&self) -> Vec<Option<T>>
+    where
+        T: Clone,
+    {
+        let mut res = Vec::new();
+        let mut queue: VecDeque<Option<&Node<T>>> = VecDeque::new();
+        queue.push_back(self.root.as_deref());
+        while let Some(link) = queue.pop_front() {
+            match link {
+                None => res.push(None),
+                Some(node) => {
+                    res.push(Some(node.elem.clone()));
+                    queue.push_back(node.left.as_deref());
+                    queue.push_back(node.right.as_deref());
+                }
+            }
+        }
+        while let Some(None) = res.last() {
+            res.pop();
+        }
+        res
+    }
+
+    /// Serialize into the level order text such as `[1,null,2,3]`.
+    pub fn serialize(&self) -> String
+    where
+        T: Clone + Display,
+    {
+        let items: Vec<String> = self
+            .to_level_order()
+            .iter()
+            .map(|e| match e {
+                None => String::from("null"),
+                Some(v) => v.to_string(),
+            })
+            .collect();
+        format!("[{}]", items.join(","))
+    }
+
+    /// Deserialize the level order text produced by `serialize`.
+    pub fn deserialize(s: &str) -> Result<Self, String>
+    where
+        T: Clone + FromStr,
+    {
+        let body = s
+            .trim()
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| format!("Expect [...] but got {:?}", s))?;
+        if body.trim().is_empty() {
+            return Ok(Tree::new());
+        }
+
+        let mut arr = Vec::new();
+        for item in body.split(',') {
+            let item = item.trim();
+            if item == "null" {
+                arr.push(None);
+            } else {
+                let elem = item
+                    .parse::<T>()
+                    .map_err(|_| format!("Invalid element {:?}", item))?;
+                arr.push(Some(elem));
+            }
+        }
+        Ok(Self::from_level_order(&arr))
+    }
+
+    /// The parenthesized text where each node is followed by `(left)(right)`, e.g. `1(2()(4))(3)`.
+    /// The empty parentheses are kept only for the missing left child of a right child.
+    pub fn to_paren_string(&self) -> String
+    where
+        T: Display,
+    {
+        let mut out = String::new();
+        write_paren(&self.root, &mut out);
+        out
+    }
+
+    pub fn from_paren_str(s: &str) -> Result<Self, String>
+    where
+        T: FromStr,
+    {
+        let mut parser = ParenParser {
+            s: s.as_bytes(),
+            pos: 0,
+        };
+        let root = parser.parse()?;
+        if parser.pos != s.len() {
+            return Err(format!(
+                "Unexpected {:?} at {}",
+                &s[parser.pos..],
+                parser.pos
+            ));
+        }
+        Ok(Tree { root })
+    }
+
+    /// Draw the shape of the tree, the left child is printed above the right one.
+    pub fn pretty(&self) -> String
+    where
+        T: Display,
+    {
+        let mut out = String::new();
+        if let Some(root) = &self.root {
+            out.push_str(&format!("{}\n", root.elem));
+            write_children(root, "", &mut out);
+        }
+        out
+    }
 }
 
 impl<T> Drop for Tree<T>
@@ -139,9 +719,92 @@ where
     }
 }
 
+impl<'a, T: Eq> Iterator for PreIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if let Some(right) = node.right.as_deref() {
+            self.stack.push(right);
+        }
+        if let Some(left) = node.left.as_deref() {
+            self.stack.push(left);
+        }
+        Some(&node.elem)
+    }
+}
+
+impl<'a, T: Eq> Iterator for InIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.cur {
+            self.stack.push(node);
+            self.cur = node.left.as_deref();
+        }
+        let node = self.stack.pop()?;
+        self.cur = node.right.as_deref();
+        Some(&node.elem)
+    }
+}
+
+impl<'a, T: Eq> Iterator for PostIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, expanded) = self.stack.pop()?;
+            if expanded {
+                return Some(&node.elem);
+            }
+            self.stack.push((node, true));
+            if let Some(right) = node.right.as_deref() {
+                self.stack.push((right, false));
+            }
+            if let Some(left) = node.left.as_deref() {
+                self.stack.push((left, false));
+            }
+        }
+    }
+}
+
+impl<'a, T: Eq> Iterator for LevelIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.children());
+        Some(&node.elem)
+    }
+}
+
+impl<'a, T: Eq> Iterator for ZigzagIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.level.len() {
+            self.level = self.level.iter().flat_map(|n| n.children()).collect();
+            self.pos = 0;
+            self.left_to_right = !self.left_to_right;
+        }
+        if self.level.is_empty() {
+            return None;
+        }
+
+        let i = if self.left_to_right {
+            self.pos
+        } else {
+            self.level.len() - 1 - self.pos
+        };
+        self.pos += 1;
+        Some(&self.level[i].elem)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::basic::rand;
 
     #[test]
     pub fn basics() {
@@ -231,4 +894,226 @@ mod tests {
             assert_eq!(tree.post_visit(), vec![&10, &9, &8, &7, &6, &5]);
         }
     }
+
+    fn sample() -> Tree<i32> {
+        //        10
+        //      /    \
+        //     5      20
+        //    / \    /  \
+        //   3   8  13   25
+        //           \
+        //            15
+        Tree::from_level_order(&[
+            Some(10),
+            Some(5),
+            Some(20),
+            Some(3),
+            Some(8),
+            Some(13),
+            Some(25),
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(15),
+        ])
+    }
+
+    #[test]
+    pub fn iterators() {
+        let tree = sample();
+        assert!(tree.pre_iter().eq(tree.pre_visit()));
+        assert!(tree.inorder_iter().eq(tree.inorder_visit()));
+        assert!(tree.post_iter().eq(tree.post_visit()));
+        assert_eq!(
+            tree.inorder_iter().collect::<Vec<_>>(),
+            vec![&3, &5, &8, &10, &13, &15, &20, &25]
+        );
+        assert_eq!(
+            tree.level_iter().collect::<Vec<_>>(),
+            vec![&10, &5, &20, &3, &8, &13, &25, &15]
+        );
+        assert_eq!(
+            tree.zigzag_iter().collect::<Vec<_>>(),
+            vec![&10, &20, &5, &3, &8, &13, &25, &15]
+        );
+        assert_eq!(
+            tree.levels(),
+            vec![vec![&10], vec![&5, &20], vec![&3, &8, &13, &25], vec![&15]]
+        );
+
+        // The iterators are lazy.
+        assert_eq!(tree.post_iter().next(), Some(&3));
+
+        let empty: Tree<i32> = Tree::new();
+        assert_eq!(empty.pre_iter().next(), None);
+        assert_eq!(empty.inorder_iter().next(), None);
+        assert_eq!(empty.post_iter().next(), None);
+        assert_eq!(empty.level_iter().next(), None);
+        assert_eq!(empty.zigzag_iter().next(), None);
+        assert!(empty.levels().is_empty());
+    }
+
+    #[test]
+    pub fn build_from_level_order() {
+        let tree = Tree::from_level_order(&[Some(1), None, Some(2), Some(3)]);
+        assert_eq!(tree.pre_visit(), vec![&1, &2, &3]);
+        assert_eq!(tree.inorder_visit(), vec![&1, &3, &2]);
+
+        assert!(Tree::<i32>::from_level_order(&[]).pre_visit().is_empty());
+        assert!(Tree::<i32>::from_level_order(&[None])
+            .pre_visit()
+            .is_empty());
+    }
+
+    // Build a random tree with the distinct elements 0..n.
+    fn random_tree(n: i32) -> Tree<i32> {
+        let mut tree = Tree::new();
+        if n == 0 {
+            return tree;
+        }
+        tree.init_root(0);
+        let mut free: Vec<(i32, bool)> = vec![(0, true), (0, false)];
+        for i in 1..n {
+            let k = rand::gen_u32() as usize % free.len();
+            let (parent, left) = free.swap_remove(k);
+            if left {
+                tree.add_left_child(parent, i);
+            } else {
+                tree.add_right_child(parent, i);
+            }
+            free.push((i, true));
+            free.push((i, false));
+        }
+        tree
+    }
+
+    #[test]
+    pub fn rebuild_from_traversals() {
+        for n in 0..50 {
+            let tree = random_tree(n);
+            let pre: Vec<i32> = tree.pre_iter().cloned().collect();
+            let ino: Vec<i32> = tree.inorder_iter().cloned().collect();
+            let post: Vec<i32> = tree.post_iter().cloned().collect();
+
+            let a = Tree::from_pre_in(&pre, &ino).unwrap();
+            assert_eq!(a.levels(), tree.levels());
+            let b = Tree::from_post_in(&post, &ino).unwrap();
+            assert_eq!(b.levels(), tree.levels());
+        }
+
+        assert!(Tree::from_pre_in(&[1, 2], &[1]).is_none());
+        assert!(Tree::from_pre_in(&[1, 3, 2], &[3, 1, 2]).is_some());
+        assert!(Tree::from_pre_in(&[1, 2, 3], &[3, 1, 2]).is_none());
+        assert!(Tree::from_pre_in(&[1, 2, 3], &[3, 4, 2]).is_none());
+        assert!(Tree::from_post_in(&[1, 2, 3], &[2, 3, 1]).is_none());
+    }
+
+    #[test]
+    pub fn serialize_and_deserialize() {
+        let tree = sample();
+        let s = tree.serialize();
+        assert_eq!(s, "[10,5,20,3,8,13,25,null,null,null,null,null,15]");
+        let back: Tree<i32> = Tree::deserialize(&s).unwrap();
+        assert!(back.same_as(&tree));
+
+        let p = tree.to_paren_string();
+        assert_eq!(p, "10(5(3)(8))(20(13()(15))(25))");
+        let back: Tree<i32> = Tree::from_paren_str(&p).unwrap();
+        assert!(back.same_as(&tree));
+
+        let right_only = Tree::from_level_order(&[Some(-1), None, Some(2)]);
+        assert_eq!(right_only.to_paren_string(), "-1()(2)");
+        assert_eq!(right_only.serialize(), "[-1,null,2]");
+        assert!(Tree::from_paren_str("-1()(2)")
+            .unwrap()
+            .same_as(&right_only));
+
+        let empty: Tree<i32> = Tree::deserialize("[]").unwrap();
+        assert_eq!(empty.serialize(), "[]");
+        assert_eq!(empty.to_paren_string(), "");
+        assert!(Tree::<i32>::from_paren_str("").unwrap().same_as(&empty));
+
+        assert!(Tree::<i32>::deserialize("[1,x]").is_err());
+        assert!(Tree::<i32>::deserialize("1,2").is_err());
+        assert!(Tree::<i32>::from_paren_str("1(2").is_err());
+        assert!(Tree::<i32>::from_paren_str("1(2))").is_err());
+        assert!(Tree::<i32>::from_paren_str("a(2)").is_err());
+
+        for n in 0..30 {
+            let tree = random_tree(n);
+            let back: Tree<i32> = Tree::deserialize(&tree.serialize()).unwrap();
+            assert!(back.same_as(&tree));
+            let back: Tree<i32> = Tree::from_paren_str(&tree.to_paren_string()).unwrap();
+            assert!(back.same_as(&tree));
+        }
+    }
+
+    #[test]
+    pub fn structural_queries() {
+        let tree = sample();
+        // 3 -> 5 -> 10 -> 20 -> 13 -> 15
+        assert_eq!(tree.diameter(), 5);
+        assert_eq!(tree.max_path_sum(), Some(15 + 13 + 20 + 25));
+        assert_eq!(tree.lca(&3, &8), Some(&5));
+        assert_eq!(tree.lca(&3, &15), Some(&10));
+        assert_eq!(tree.lca(&15, &13), Some(&13));
+        assert_eq!(tree.lca(&15, &99), None);
+        assert!(tree.is_balanced());
+        assert!(tree.is_bst());
+
+        let neg =
+            Tree::from_level_order(&[Some(-10), Some(9), Some(20), None, None, Some(15), Some(7)]);
+        assert_eq!(neg.max_path_sum(), Some(42));
+        assert!(!neg.is_bst());
+        assert!(Tree::<i32>::new().max_path_sum().is_none());
+
+        let big = Tree::from_level_order(&[Some(i64::MAX), Some(i64::MAX), Some(i64::MIN)]);
+        assert_eq!(big.max_path_sum(), Some(i64::MAX));
+        let small = Tree::from_level_order(&[Some(i64::MIN), Some(i64::MIN), Some(i64::MIN)]);
+        assert_eq!(small.max_path_sum(), Some(i64::MIN));
+        let mixed = Tree::from_level_order(&[Some(-1), Some(i64::MAX), Some(i64::MAX)]);
+        assert_eq!(mixed.max_path_sum(), Some(i64::MAX));
+        let exact = Tree::from_level_order(&[Some(-2), Some(i64::MAX), Some(1)]);
+        assert_eq!(exact.max_path_sum(), Some(i64::MAX));
+
+        let chain = Tree::from_level_order(&[Some(1), None, Some(2), None, Some(3)]);
+        assert!(!chain.is_balanced());
+        assert_eq!(chain.diameter(), 2);
+
+        let sub = Tree::from_level_order(&[Some(20), Some(13), Some(25), None, Some(15)]);
+        assert!(tree.is_subtree(&sub));
+        assert!(tree.is_subtree(&Tree::new()));
+        let not_sub = Tree::from_level_order(&[Some(20), Some(13), Some(25)]);
+        assert!(!tree.is_subtree(&not_sub));
+
+        let mut mirrored = sample();
+        mirrored.mirror();
+        assert_eq!(
+            mirrored.inorder_iter().collect::<Vec<_>>(),
+            vec![&25, &20, &15, &13, &10, &8, &5, &3]
+        );
+        assert!(!mirrored.is_bst());
+        mirrored.mirror();
+        assert!(mirrored.same_as(&tree));
+    }
+
+    #[test]
+    pub fn pretty_print() {
+        let tree = sample();
+        let expected = "\
+10
+├── 5
+│   ├── 3
+│   └── 8
+└── 20
+    ├── 13
+    │   ├── ·
+    │   └── 15
+    └── 25
+";
+        assert_eq!(tree.pretty(), expected);
+        assert_eq!(Tree::<i32>::new().pretty(), "");
+    }
 }
@@ -1,6 +1,6 @@
 use std::ptr;
 
-struct Tree<T: Eq> {
+pub(crate) struct Tree<T: Eq> {
     root: Link<T>,
 }
 
@@ -96,9 +96,8 @@ where
         if !self.is_null() {
             unsafe {
                 (*self).left.drop();
-                (*self).left = ptr::null_mut();
                 (*self).right.drop();
-                (*self).right = ptr::null_mut();
+                drop(Box::from_raw(self));
             }
         }
     }
@@ -153,6 +152,79 @@ where
         self.root.post_visit(&mut order);
         return order;
     }
+
+    /// Morris traversal takes O(1) extra space instead of the stack: the rightmost node of the
+    /// left subtree is threaded back to the current node, so we can climb back after visiting the
+    /// left subtree. It writes the threads into the nodes, so it needs `&mut self`, and all the
+    /// threads are removed again when the traversal finishes, even if `visit` panics.
+    pub fn morris_inorder(&mut self, visit: impl FnMut(&T)) {
+        self.morris(false, visit)
+    }
+
+    pub fn morris_preorder(&mut self, visit: impl FnMut(&T)) {
+        self.morris(true, visit)
+    }
+
+    fn morris(&mut self, preorder: bool, mut visit: impl FnMut(&T)) {
+        let mut walk = Morris {
+            cur: self.root,
+            _tree: self,
+        };
+        unsafe {
+            while let Some((node, first, last)) = walk.step() {
+                if (preorder && first) || (!preorder && last) {
+                    visit(&(*node).elem);
+                }
+            }
+        }
+    }
+}
+
+// The state of a Morris traversal. If it's dropped before the end, e.g. by a panic of the visitor,
+// it walks the rest of the tree to remove the threads left behind.
+struct Morris<'a, T: Eq> {
+    cur: Link<T>,
+    _tree: &'a mut Tree<T>,
+}
+
+impl<T: Eq> Morris<'_, T> {
+    // Move to the next node, and tell whether it's reached for the first time, which is the visit
+    // of the preorder, and whether for the last time, which is the visit of the inorder. A node
+    // without the left subtree is reached only once, so it's both of them.
+    unsafe fn step(&mut self) -> Option<(Link<T>, bool, bool)> {
+        let cur = self.cur;
+        if cur.is_null() {
+            return None;
+        }
+        if (*cur).left.is_null() {
+            self.cur = (*cur).right;
+            return Some((cur, true, true));
+        }
+
+        // The inorder predecessor of cur.
+        let mut prev = (*cur).left;
+        while !(*prev).right.is_null() && (*prev).right != cur {
+            prev = (*prev).right;
+        }
+
+        if (*prev).right.is_null() {
+            // The first time to reach cur, thread it and go down to the left.
+            (*prev).right = cur;
+            self.cur = (*cur).left;
+            Some((cur, true, false))
+        } else {
+            // Back from the left subtree by the thread, remove it.
+            (*prev).right = ptr::null_mut();
+            self.cur = (*cur).right;
+            Some((cur, false, true))
+        }
+    }
+}
+
+impl<T: Eq> Drop for Morris<'_, T> {
+    fn drop(&mut self) {
+        unsafe { while self.step().is_some() {} }
+    }
 }
 
 impl<T> Drop for Tree<T>
@@ -189,6 +261,69 @@ mod tests {
         assert_eq!(tree.post_visit(), vec![&3, &8, &5, &11, &12, &13, &20, &10]);
     }
 
+    fn morris_order(tree: &mut Tree<i32>, preorder: bool) -> Vec<i32> {
+        let mut order = Vec::new();
+        if preorder {
+            tree.morris_preorder(|&e| order.push(e));
+        } else {
+            tree.morris_inorder(|&e| order.push(e));
+        }
+        order
+    }
+
+    #[test]
+    pub fn morris() {
+        let mut tree = Tree::new();
+        assert!(morris_order(&mut tree, false).is_empty());
+
+        tree.init_root(10);
+        tree.add_left_child(10, 5);
+        tree.add_right_child(10, 20);
+        tree.add_left_child(5, 3);
+        tree.add_right_child(5, 8);
+        tree.add_left_child(20, 13);
+        tree.add_left_child(13, 12);
+        tree.add_right_child(3, 4);
+
+        let inorder: Vec<i32> = tree.inorder_visit().into_iter().copied().collect();
+        let preorder: Vec<i32> = tree.pre_visit().into_iter().copied().collect();
+        assert_eq!(morris_order(&mut tree, false), inorder);
+        assert_eq!(morris_order(&mut tree, true), preorder);
+        // The threads have been removed, so the tree is still the same.
+        assert_eq!(
+            morris_order(&mut tree, false),
+            vec![3, 4, 5, 8, 10, 12, 13, 20]
+        );
+        assert_eq!(tree.post_visit(), vec![&4, &3, &8, &5, &12, &13, &20, &10]);
+    }
+
+    #[test]
+    pub fn morris_panic() {
+        let mut tree = Tree::new();
+        tree.init_root(10);
+        tree.add_left_child(10, 5);
+        tree.add_right_child(10, 20);
+        tree.add_left_child(5, 3);
+        tree.add_right_child(5, 8);
+        tree.add_right_child(3, 4);
+
+        // The visitor panics deep in the left subtree, where the threads of 10, 5 and 3 are all
+        // in place, and they are removed while unwinding.
+        for preorder in [false, true] {
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let visit = |&e: &i32| assert_ne!(e, 4);
+                if preorder {
+                    tree.morris_preorder(visit);
+                } else {
+                    tree.morris_inorder(visit);
+                }
+            }));
+            assert!(res.is_err());
+            assert_eq!(tree.pre_visit(), vec![&10, &5, &3, &4, &8, &20]);
+            assert_eq!(tree.inorder_visit(), vec![&3, &4, &5, &8, &10, &20]);
+        }
+    }
+
     #[test]
     pub fn empty_tree() {
         let tree: Tree<i32> = Tree::new();
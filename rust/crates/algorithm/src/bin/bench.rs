@@ -0,0 +1,1058 @@
+/**
+ * The benchmark of the linked lists, the maps and the sorts in `basic`, which runs the same
+ * workloads against all the implementations and prints the throughput and the memory usage of
+ * each one, with the std containers as the baselines.
+ *
+ * It only depends on std: the time is measured by `Instant`, and the memory by `CountingAlloc`,
+ * which is installed as the global allocator.
+ *
+ * Usage: cargo run --release --bin bench -- [options], and see `--help` for the options.
+ */
+use std::collections::{btree_map, hash_map, BTreeMap, HashMap, LinkedList};
+use std::env;
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hello_world::basic::counting_alloc::{CountingAlloc, MemoryUsage, Scope};
+use hello_world::basic::map::Map;
+use hello_world::basic::rand::{RandomSource, SplitMix64};
+use hello_world::basic::{
+    avl_tree, b_tree, concurrent_deque, double_linked_list_unsafe, double_linked_list_v1,
+    double_linked_list_v2, double_linked_list_v3, gap_buffer, hash_table_v1, hash_table_v2,
+    heapsort, insert_sort, linked_list_unsafe, linked_list_v1, linked_list_v2, linked_list_v3,
+    lru_cache, merge_linkedlist, msort, qsort, rb_tree, ring_buffer, scapegoat_tree, select_sort,
+    simple_tree, skiplist, skiplist_unsafe, splay_tree, treap, vec_deque,
+};
+
+const USAGE: &str = "\
+Usage: bench [options]
+
+Options:
+  --n <N>          The number of elements of the list and map workloads (default 100000).
+  --sort-n <N>     The number of elements to sort, the O(n^2) sorts are slow (default 10000).
+  --mix <I:L:D>    The weights of the inserts, lookups and deletes of the maps (default 50:40:10).
+  --keys <DIST>    The keys of the maps: uniform, sequential or skewed (default uniform).
+  --order <ORDER>  The input of the sorts: random, sorted, reversed, few-unique or all (default all).
+  --group <GROUP>  Only run the lists, the maps or the sorts.
+  --filter <S>     Only run the implementations whose name contains S.
+  --seed <SEED>    The seed of the keys and the inputs (default 42).
+  --csv            Print CSV instead of the table.
+  --memory         Only print the memory usage of all the containers with n entries.
+";
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc::new();
+
+// The time and the memory of one workload, which is built first and then run.
+struct Sample {
+    build: Duration,
+    run: Duration,
+    // The bytes held by the container after it's built.
+    retained: usize,
+    // The peak and the allocations of the build.
+    build_peak: usize,
+    build_allocs: usize,
+    // The most bytes allocated at the same time since the start, over the bytes before it.
+    peak: usize,
+    allocs: usize,
+}
+
+fn measure<S>(build: impl FnOnce() -> S, run: impl FnOnce(S)) -> Sample {
+    let scope = Scope::new();
+    let start = Instant::now();
+    let state = black_box(build());
+    let built = Instant::now();
+    let build_stats = scope.stats();
+    run(state);
+    let end = Instant::now();
+    let stats = scope.stats();
+
+    Sample {
+        build: built - start,
+        run: end - built,
+        retained: build_stats.bytes(),
+        build_peak: build_stats.peak,
+        build_allocs: build_stats.allocs,
+        peak: stats.peak,
+        allocs: stats.allocs,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Group {
+    Lists,
+    Maps,
+    Sorts,
+}
+
+#[derive(Clone, Copy)]
+enum KeyDist {
+    Uniform,
+    Sequential,
+    Skewed,
+}
+
+#[derive(Clone, Copy)]
+enum Order {
+    Random,
+    Sorted,
+    Reversed,
+    FewUnique,
+}
+
+const ORDERS: [Order; 4] = [
+    Order::Random,
+    Order::Sorted,
+    Order::Reversed,
+    Order::FewUnique,
+];
+
+impl Order {
+    fn name(self) -> &'static str {
+        match self {
+            Order::Random => "random",
+            Order::Sorted => "sorted",
+            Order::Reversed => "reversed",
+            Order::FewUnique => "few-unique",
+        }
+    }
+}
+
+struct Options {
+    n: usize,
+    sort_n: usize,
+    // The weights of the inserts, lookups and deletes.
+    mix: [u64; 3],
+    keys: KeyDist,
+    orders: Vec<Order>,
+    group: Option<Group>,
+    filter: Option<String>,
+    seed: u64,
+    csv: bool,
+    memory: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut opts = Options {
+        n: 100_000,
+        sort_n: 10_000,
+        mix: [50, 40, 10],
+        keys: KeyDist::Uniform,
+        orders: ORDERS.to_vec(),
+        group: None,
+        filter: None,
+        seed: 42,
+        csv: false,
+        memory: false,
+    };
+
+    while let Some(arg) = args.next() {
+        if arg == "--csv" || arg == "--memory" {
+            opts.csv |= arg == "--csv";
+            opts.memory |= arg == "--memory";
+            continue;
+        }
+        if arg == "--help" || arg == "-h" {
+            return Err(String::new());
+        }
+        let val = args
+            .next()
+            .ok_or_else(|| format!("missing the value of {}", arg))?;
+        let number = |s: &str| {
+            s.parse::<u64>()
+                .map_err(|_| format!("invalid number for {}: {}", arg, s))
+        };
+        match arg.as_str() {
+            "--n" => opts.n = number(&val)? as usize,
+            "--sort-n" => opts.sort_n = number(&val)? as usize,
+            "--seed" => opts.seed = number(&val)?,
+            "--mix" => {
+                let parts = val.split(':').map(number).collect::<Result<Vec<_>, _>>()?;
+                if parts.len() != 3 || parts.iter().sum::<u64>() == 0 {
+                    return Err(format!("invalid mix: {}", val));
+                }
+                opts.mix = [parts[0], parts[1], parts[2]];
+            }
+            "--keys" => {
+                opts.keys = match val.as_str() {
+                    "uniform" => KeyDist::Uniform,
+                    "sequential" => KeyDist::Sequential,
+                    "skewed" => KeyDist::Skewed,
+                    _ => return Err(format!("unknown key distribution: {}", val)),
+                }
+            }
+            "--order" => {
+                opts.orders = match val.as_str() {
+                    "all" => ORDERS.to_vec(),
+                    _ => match ORDERS.iter().find(|o| o.name() == val) {
+                        Some(o) => vec![*o],
+                        None => return Err(format!("unknown order: {}", val)),
+                    },
+                }
+            }
+            "--group" => {
+                opts.group = match val.as_str() {
+                    "lists" => Some(Group::Lists),
+                    "maps" => Some(Group::Maps),
+                    "sorts" => Some(Group::Sorts),
+                    _ => return Err(format!("unknown group: {}", val)),
+                }
+            }
+            "--filter" => opts.filter = Some(val),
+            _ => return Err(format!("unknown option: {}", arg)),
+        }
+    }
+    Ok(opts)
+}
+
+struct Row {
+    group: &'static str,
+    name: &'static str,
+    workload: String,
+    n: usize,
+    ops: usize,
+    elapsed: Duration,
+    retained: usize,
+    peak: usize,
+    allocs: usize,
+}
+
+impl Row {
+    fn mops(&self) -> f64 {
+        self.ops as f64 / self.elapsed.as_secs_f64().max(1e-9) / 1e6
+    }
+
+    fn bytes_per_entry(&self) -> f64 {
+        self.retained as f64 / self.n.max(1) as f64
+    }
+}
+
+struct Report {
+    opts: Options,
+    rows: Vec<Row>,
+}
+
+impl Report {
+    fn enabled(&self, group: Group, name: &str) -> bool {
+        self.opts.group.is_none_or(|g| g == group)
+            && self.opts.filter.as_ref().is_none_or(|f| name.contains(f))
+    }
+
+    fn push(&mut self, row: Row) {
+        // Print the rows of the table as they come, because the slow ones take a while.
+        if !self.opts.csv {
+            println!("{}", format_row(&row));
+        }
+        self.rows.push(row);
+    }
+}
+
+const HEADER: [&str; 11] = [
+    "group", "impl", "workload", "n", "ops", "ms", "Mops/s", "bytes", "B/entry", "peak", "allocs",
+];
+
+fn format_row(r: &Row) -> String {
+    format!(
+        "{:<6} {:<26} {:<18} {:>8} {:>8} {:>9.2} {:>8.2} {:>11} {:>8.1} {:>11} {:>8}",
+        r.group,
+        r.name,
+        r.workload,
+        r.n,
+        r.ops,
+        r.elapsed.as_secs_f64() * 1e3,
+        r.mops(),
+        r.retained,
+        r.bytes_per_entry(),
+        r.peak,
+        r.allocs
+    )
+}
+
+fn to_csv(rows: &[Row]) -> String {
+    let mut out = HEADER.join(",");
+    out.push('\n');
+    for r in rows {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{:.3},{:.3},{},{:.1},{},{}",
+            r.group,
+            r.name,
+            r.workload,
+            r.n,
+            r.ops,
+            r.elapsed.as_secs_f64() * 1e3,
+            r.mops(),
+            r.retained,
+            r.bytes_per_entry(),
+            r.peak,
+            r.allocs
+        );
+    }
+    out
+}
+
+// The list is built by n pushes and then emptied by n pops, unless it has no pop.
+fn bench_list<S>(
+    report: &mut Report,
+    name: &'static str,
+    workload: &str,
+    n: usize,
+    build: impl FnOnce(usize) -> S,
+    run: impl FnOnce(S),
+) {
+    if !report.enabled(Group::Lists, name) {
+        return;
+    }
+    let s = measure(|| build(n), run);
+    report.push(Row {
+        group: "lists",
+        name,
+        workload: workload.to_string(),
+        n,
+        ops: 2 * n,
+        elapsed: s.build + s.run,
+        retained: s.retained,
+        peak: s.peak,
+        allocs: s.allocs,
+    });
+}
+
+fn pop_all(mut pop: impl FnMut() -> Option<i32>) {
+    let mut sum = 0i64;
+    while let Some(v) = pop() {
+        sum += v as i64;
+    }
+    black_box(sum);
+}
+
+fn bench_lists(report: &mut Report) {
+    let n = report.opts.n;
+
+    bench_list(
+        report,
+        "linked_list_v1",
+        "stack",
+        n,
+        |n| {
+            let mut list = linked_list_v1::List::new();
+            (0..n as i32).for_each(|i| list.push(i));
+            list
+        },
+        |mut list| pop_all(move || list.pop()),
+    );
+    bench_list(
+        report,
+        "linked_list_v2",
+        "stack",
+        n,
+        |n| {
+            let mut list = linked_list_v2::List::new();
+            (0..n as i32).for_each(|i| list.push(i));
+            list
+        },
+        |mut list| pop_all(move || list.pop()),
+    );
+    bench_list(
+        report,
+        "linked_list_v3",
+        "persistent stack",
+        n,
+        |n| (0..n as i32).fold(linked_list_v3::List::new(), |list, i| list.push(i)),
+        |mut list| {
+            pop_all(move || {
+                let v = list.head().cloned();
+                list = list.tail();
+                v
+            })
+        },
+    );
+    bench_list(
+        report,
+        "linked_list_unsafe",
+        "queue",
+        n,
+        |n| {
+            let mut list = linked_list_unsafe::List::new();
+            (0..n as i32).for_each(|i| list.push_back(i));
+            list
+        },
+        |mut list| pop_all(move || list.pop()),
+    );
+    bench_list(
+        report,
+        "merge_linkedlist",
+        "stack",
+        n,
+        |n| {
+            let mut list = merge_linkedlist::List::new();
+            (0..n as i32).for_each(|i| list.push_front(i));
+            list
+        },
+        |mut list| pop_all(move || list.pop_front()),
+    );
+    bench_list(
+        report,
+        "double_linked_list_v1",
+        "queue",
+        n,
+        |n| {
+            let mut list = double_linked_list_v1::List::new();
+            (0..n as i32).for_each(|i| list.push_back(i));
+            list
+        },
+        |mut list| pop_all(move || list.pop_front()),
+    );
+    // The append walks the whole list recursively, so it's quadratic and only run on a few.
+    bench_list(
+        report,
+        "double_linked_list_v2",
+        "append+drop",
+        n.min(2_000),
+        |n| {
+            let mut list = double_linked_list_v2::List::new();
+            (0..n as i32).for_each(|i| list.append(i));
+            list
+        },
+        drop,
+    );
+    // It has no pop, so the list is iterated and dropped instead.
+    bench_list(
+        report,
+        "double_linked_list_v3",
+        "push+iter",
+        n,
+        |n| {
+            let mut list = double_linked_list_v3::List::new();
+            (0..n as i32).for_each(|i| list.push_front(i));
+            list
+        },
+        |list| {
+            black_box(list.iter().map(|v| *v as i64).sum::<i64>());
+        },
+    );
+    bench_list(
+        report,
+        "double_linked_list_unsafe",
+        "queue",
+        n,
+        |n| {
+            let mut list = double_linked_list_unsafe::LinkedList::new();
+            (0..n as i32).for_each(|i| list.push_back(i));
+            list
+        },
+        |mut list| pop_all(move || list.pop_front()),
+    );
+    bench_list(
+        report,
+        "vec_deque",
+        "queue",
+        n,
+        |n| {
+            let mut deque = vec_deque::VecDeque::new();
+            (0..n as i32).for_each(|i| deque.push_back(i));
+            deque
+        },
+        |mut deque| pop_all(move || deque.pop_front()),
+    );
+    // The producer thread pushes while the current thread pops, through a buffer of 64 slots.
+    bench_list(
+        report,
+        "ring_buffer",
+        "spsc",
+        n,
+        |_| ring_buffer::ring_buffer(64),
+        |(mut p, mut c)| {
+            let producer = thread::spawn(move || {
+                for i in 0..n as i32 {
+                    let mut v = i;
+                    while let Err(back) = p.push(v) {
+                        v = back;
+                        thread::yield_now();
+                    }
+                }
+            });
+            let mut left = n;
+            pop_all(move || {
+                while left > 0 {
+                    if let Some(v) = c.pop() {
+                        left -= 1;
+                        return Some(v);
+                    }
+                    thread::yield_now();
+                }
+                None
+            });
+            producer.join().unwrap();
+        },
+    );
+    bench_list(
+        report,
+        "std::LinkedList",
+        "queue",
+        n,
+        |n| {
+            let mut list = LinkedList::new();
+            (0..n as i32).for_each(|i| list.push_back(i));
+            list
+        },
+        |mut list| pop_all(move || list.pop_front()),
+    );
+}
+
+// The std maps as the baselines, wrapped to implement `Map`.
+struct Std<M>(M);
+
+impl Map<i32, u32> for Std<BTreeMap<i32, u32>> {
+    type Iter<'a> = btree_map::Iter<'a, i32, u32>;
+
+    fn insert(&mut self, k: i32, v: u32) -> Option<u32> {
+        self.0.insert(k, v)
+    }
+
+    fn remove(&mut self, k: &i32) -> Option<u32> {
+        self.0.remove(k)
+    }
+
+    fn get(&self, k: &i32) -> Option<&u32> {
+        self.0.get(k)
+    }
+
+    fn get_mut(&mut self, k: &i32) -> Option<&mut u32> {
+        self.0.get_mut(k)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.0.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl Map<i32, u32> for Std<HashMap<i32, u32>> {
+    type Iter<'a> = hash_map::Iter<'a, i32, u32>;
+
+    fn insert(&mut self, k: i32, v: u32) -> Option<u32> {
+        self.0.insert(k, v)
+    }
+
+    fn remove(&mut self, k: &i32) -> Option<u32> {
+        self.0.remove(k)
+    }
+
+    fn get(&self, k: &i32) -> Option<&u32> {
+        self.0.get(k)
+    }
+
+    fn get_mut(&mut self, k: &i32) -> Option<&mut u32> {
+        self.0.get_mut(k)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.0.iter()
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MapOp {
+    Insert(i32),
+    Get(i32),
+    Remove(i32),
+}
+
+// The keys are drawn from [0, 2n), so about half of the lookups and the deletes hit.
+struct Keys {
+    dist: KeyDist,
+    rng: SplitMix64,
+    space: u64,
+    next: u64,
+}
+
+impl Keys {
+    fn next(&mut self) -> i32 {
+        let k = match self.dist {
+            KeyDist::Uniform => self.rng.next_u64() % self.space,
+            KeyDist::Sequential => {
+                self.next += 1;
+                (self.next - 1) % self.space
+            }
+            // Most of the keys are small: the cube of a uniform number in [0, 1).
+            KeyDist::Skewed => {
+                let u = (self.rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+                (u * u * u * self.space as f64) as u64
+            }
+        };
+        k as i32
+    }
+}
+
+// The keys to load and the mixed operations after that, generated before the timing.
+struct MapWorkload {
+    load: Vec<i32>,
+    ops: Vec<MapOp>,
+    mix: String,
+}
+
+fn map_workload(opts: &Options) -> MapWorkload {
+    let mut keys = Keys {
+        dist: opts.keys,
+        rng: SplitMix64::new(opts.seed),
+        space: (2 * opts.n).max(1) as u64,
+        next: 0,
+    };
+    let load = (0..opts.n).map(|_| keys.next()).collect();
+    let [insert, lookup, delete] = opts.mix;
+    let mut rng = SplitMix64::new(opts.seed ^ 0xA5A5);
+    let ops = (0..opts.n)
+        .map(|_| {
+            let r = rng.next_u64() % (insert + lookup + delete);
+            let k = keys.next();
+            if r < insert {
+                MapOp::Insert(k)
+            } else if r < insert + lookup {
+                MapOp::Get(k)
+            } else {
+                MapOp::Remove(k)
+            }
+        })
+        .collect();
+    MapWorkload {
+        load,
+        ops,
+        mix: format!("mix {}:{}:{}", insert, lookup, delete),
+    }
+}
+
+fn bench_map<M: Map<i32, u32>>(
+    report: &mut Report,
+    work: &MapWorkload,
+    name: &'static str,
+    make: impl FnOnce() -> M,
+) {
+    if !report.enabled(Group::Maps, name) {
+        return;
+    }
+    let mut len = 0;
+    let s = measure(
+        || {
+            let mut map = make();
+            for &k in &work.load {
+                map.insert(k, k as u32);
+            }
+            map
+        },
+        |mut map| {
+            len = map.len();
+            let mut hits = 0usize;
+            for op in &work.ops {
+                let hit = match *op {
+                    MapOp::Insert(k) => map.insert(k, k as u32).is_some(),
+                    MapOp::Get(k) => black_box(map.get(&k)).is_some(),
+                    MapOp::Remove(k) => map.remove(&k).is_some(),
+                };
+                hits += hit as usize;
+            }
+            black_box(hits);
+        },
+    );
+    report.push(Row {
+        group: "maps",
+        name,
+        workload: "load".to_string(),
+        n: len,
+        ops: work.load.len(),
+        elapsed: s.build,
+        retained: s.retained,
+        peak: s.build_peak,
+        allocs: s.build_allocs,
+    });
+    report.push(Row {
+        group: "maps",
+        name,
+        workload: work.mix.clone(),
+        n: len,
+        ops: work.ops.len(),
+        elapsed: s.run,
+        retained: s.retained,
+        peak: s.peak,
+        allocs: s.allocs - s.build_allocs,
+    });
+}
+
+fn bench_maps(report: &mut Report) {
+    let work = map_workload(&report.opts);
+    bench_map(
+        report,
+        &work,
+        "hash_table_v1",
+        hash_table_v1::HashTable::new,
+    );
+    bench_map(report, &work, "hash_table_v2", hash_table_v2::HashMap::new);
+    bench_map(report, &work, "skiplist", skiplist::SkipList::new);
+    bench_map(
+        report,
+        &work,
+        "skiplist_unsafe",
+        skiplist_unsafe::SkipList::new,
+    );
+    bench_map(report, &work, "avl_tree", avl_tree::AVLTree::new);
+    bench_map(report, &work, "rb_tree", rb_tree::RBTree::new);
+    bench_map(report, &work, "b_tree", b_tree::BTree::new);
+    bench_map(report, &work, "treap", treap::Treap::new);
+    bench_map(report, &work, "splay_tree", splay_tree::SplayTree::new);
+    bench_map(
+        report,
+        &work,
+        "scapegoat_tree",
+        scapegoat_tree::ScapegoatTree::new,
+    );
+    bench_map(report, &work, "std::BTreeMap", || Std(BTreeMap::new()));
+    bench_map(report, &work, "std::HashMap", || Std(HashMap::new()));
+}
+
+type SortFn = fn(&mut Vec<i32>);
+
+fn sort_input(order: Order, n: usize, seed: u64) -> Vec<i32> {
+    let mut rng = SplitMix64::new(seed);
+    match order {
+        Order::Random => (0..n).map(|_| rng.next_u64() as i32).collect(),
+        Order::Sorted => (0..n as i32).collect(),
+        Order::Reversed => (0..n as i32).rev().collect(),
+        Order::FewUnique => (0..n).map(|_| (rng.next_u64() % 8) as i32).collect(),
+    }
+}
+
+fn bench_sort(
+    report: &mut Report,
+    input: &[i32],
+    expected: &[i32],
+    order: Order,
+    name: &'static str,
+    sort: SortFn,
+) {
+    if !report.enabled(Group::Sorts, name) {
+        return;
+    }
+    // The input is copied before the timing, and only the sort itself is measured.
+    let mut v = input.to_vec();
+    let s = measure(|| (), |_| sort(&mut v));
+    assert_eq!(
+        v,
+        expected,
+        "{} is wrong on the {} input",
+        name,
+        order.name()
+    );
+    report.push(Row {
+        group: "sorts",
+        name,
+        workload: order.name().to_string(),
+        n: input.len(),
+        ops: input.len(),
+        elapsed: s.run,
+        retained: 0,
+        peak: s.peak,
+        allocs: s.allocs,
+    });
+}
+
+fn bench_sorts(report: &mut Report) {
+    let sorts: [(&'static str, SortFn); 7] = [
+        ("insert_sort", insert_sort::insert_sort),
+        ("select_sort", |v| select_sort::select_sort(v)),
+        ("qsort", |v| qsort::qsort(v)),
+        ("heapsort", |v| heapsort::sort(v)),
+        ("msort", |v| msort::merge_sort(v)),
+        ("std::sort", |v| v.sort()),
+        ("std::sort_unstable", |v| v.sort_unstable()),
+    ];
+    for order in report.opts.orders.clone() {
+        let input = sort_input(order, report.opts.sort_n, report.opts.seed);
+        let mut expected = input.clone();
+        expected.sort_unstable();
+        for (name, sort) in sorts {
+            bench_sort(report, &input, &expected, order, name, sort);
+        }
+    }
+}
+
+fn map_usage<M: Map<i32, i32>>(name: &'static str, keys: &[i32], make: fn() -> M) -> MemoryUsage {
+    MemoryUsage::measure(name, keys.len(), || {
+        let mut map = make();
+        for &k in keys {
+            map.insert(k, k);
+        }
+        map
+    })
+}
+
+/// The memory usage of all the containers in `basic` holding `n` i32s, or `n` pairs of i32s for
+/// the maps, which are inserted in a shuffled order.
+fn memory_usage(n: usize) -> Vec<MemoryUsage> {
+    let mut keys: Vec<i32> = (0..n as i32).collect();
+    let mut rng = SplitMix64::new(42);
+    for i in (1..keys.len()).rev() {
+        keys.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+    let keys = &keys;
+
+    vec![
+        MemoryUsage::measure("linked_list_v1", n, || {
+            let mut list = linked_list_v1::List::new();
+            keys.iter().for_each(|&k| list.push(k));
+            list
+        }),
+        MemoryUsage::measure("linked_list_v2", n, || {
+            let mut list = linked_list_v2::List::new();
+            keys.iter().for_each(|&k| list.push(k));
+            list
+        }),
+        MemoryUsage::measure("linked_list_v3", n, || {
+            keys.iter()
+                .fold(linked_list_v3::List::new(), |list, &k| list.push(k))
+        }),
+        MemoryUsage::measure("linked_list_unsafe", n, || {
+            let mut list = linked_list_unsafe::List::new();
+            keys.iter().for_each(|&k| list.push_back(k));
+            list
+        }),
+        MemoryUsage::measure("merge_linkedlist", n, || {
+            keys.iter()
+                .copied()
+                .collect::<merge_linkedlist::List<i32>>()
+        }),
+        MemoryUsage::measure("double_linked_list_v1", n, || {
+            let mut list = double_linked_list_v1::List::new();
+            keys.iter().for_each(|&k| list.push_back(k));
+            list
+        }),
+        MemoryUsage::measure("double_linked_list_v2", n, || {
+            let mut list = double_linked_list_v2::List::new();
+            keys.iter().for_each(|&k| list.append(k));
+            list
+        }),
+        MemoryUsage::measure("double_linked_list_v3", n, || {
+            let mut list = double_linked_list_v3::List::new();
+            keys.iter().for_each(|&k| list.push_front(k));
+            list
+        }),
+        MemoryUsage::measure("double_linked_list_unsafe", n, || {
+            let mut list = double_linked_list_unsafe::LinkedList::new();
+            keys.iter().for_each(|&k| list.push_back(k));
+            list
+        }),
+        MemoryUsage::measure("vec_deque", n, || {
+            let mut deque = vec_deque::VecDeque::new();
+            keys.iter().for_each(|&k| deque.push_back(k));
+            deque
+        }),
+        MemoryUsage::measure("mutex_deque", n, || {
+            let deque = concurrent_deque::MutexDeque::new();
+            keys.iter().for_each(|&k| deque.push_back(k));
+            deque
+        }),
+        MemoryUsage::measure("gap_buffer", n, || {
+            let mut buf = gap_buffer::GapBuffer::new();
+            keys.iter().for_each(|&k| buf.insert(k));
+            buf
+        }),
+        MemoryUsage::measure("lru_cache", n, || {
+            let mut cache = lru_cache::LruCache::new(n.max(1));
+            keys.iter().for_each(|&k| {
+                cache.put(k, k);
+            });
+            cache
+        }),
+        MemoryUsage::measure("lfu_cache", n, || {
+            let mut cache = lru_cache::LfuCache::new(n.max(1));
+            keys.iter().for_each(|&k| {
+                cache.put(k, k);
+            });
+            cache
+        }),
+        map_usage("hash_table_v1", keys, hash_table_v1::HashTable::new),
+        map_usage("hash_table_v2", keys, hash_table_v2::HashMap::new),
+        map_usage("skiplist", keys, skiplist::SkipList::new),
+        map_usage("skiplist_unsafe", keys, skiplist_unsafe::SkipList::new),
+        map_usage("avl_tree", keys, avl_tree::AVLTree::new),
+        map_usage("rb_tree", keys, rb_tree::RBTree::new),
+        map_usage("b_tree", keys, b_tree::BTree::new),
+        map_usage("treap", keys, treap::Treap::new),
+        map_usage("splay_tree", keys, splay_tree::SplayTree::new),
+        map_usage("scapegoat_tree", keys, scapegoat_tree::ScapegoatTree::new),
+        MemoryUsage::measure("simple_tree", n, || {
+            let mut tree = simple_tree::SimpleTree::new();
+            keys.iter().for_each(|&k| tree.insert(k));
+            tree
+        }),
+    ]
+}
+
+fn print_memory_usage(n: usize, csv: bool) {
+    // Some lists are built by the quadratic appends, so they're measured on a thread with a big
+    // stack too, whose allocations are counted by the thread itself.
+    let usages = thread::Builder::new()
+        .stack_size(1 << 30)
+        .spawn(move || memory_usage(n))
+        .unwrap()
+        .join()
+        .unwrap();
+    if csv {
+        println!("impl,entries,bytes,B/entry,allocs,peak,leaked");
+        for u in usages {
+            println!(
+                "{},{},{},{:.1},{},{},{}",
+                u.name,
+                u.entries,
+                u.bytes,
+                u.bytes_per_entry(),
+                u.allocs,
+                u.peak,
+                u.leaked
+            );
+        }
+    } else {
+        println!(
+            "{:<26} {:>8} {:>10} {:>8} {:>8} {:>10} {:>8}",
+            "impl", "entries", "bytes", "B/entry", "allocs", "peak", "leaked"
+        );
+        usages.iter().for_each(|u| println!("{}", u));
+    }
+}
+
+fn main() {
+    let opts = match parse_args(env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(err) => {
+            if err.is_empty() {
+                print!("{}", USAGE);
+                return;
+            }
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+    if opts.memory {
+        print_memory_usage(opts.n, opts.csv);
+        return;
+    }
+    let csv = opts.csv;
+    let mut report = Report { opts, rows: vec![] };
+    if !csv {
+        println!(
+            "{:<6} {:<26} {:<18} {:>8} {:>8} {:>9} {:>8} {:>11} {:>8} {:>11} {:>8}",
+            HEADER[0],
+            HEADER[1],
+            HEADER[2],
+            HEADER[3],
+            HEADER[4],
+            HEADER[5],
+            HEADER[6],
+            HEADER[7],
+            HEADER[8],
+            HEADER[9],
+            HEADER[10]
+        );
+    }
+
+    // Some lists drop their nodes recursively, and the qsort recurses as deep as the sorted input,
+    // so they run on a thread with a big stack.
+    let report = thread::Builder::new()
+        .stack_size(1 << 30)
+        .spawn(move || {
+            bench_lists(&mut report);
+            bench_maps(&mut report);
+            bench_sorts(&mut report);
+            report
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    if csv {
+        print!("{}", to_csv(&report.rows));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage_of(name: &str, usages: &[MemoryUsage]) -> MemoryUsage {
+        usages.iter().find(|u| u.name == name).unwrap().clone()
+    }
+
+    #[test]
+    pub fn test_no_leaks() {
+        for usage in memory_usage(1000) {
+            assert_eq!(usage.leaked, 0, "{}", usage);
+            assert!(usage.bytes > 0, "{}", usage);
+            assert!(usage.peak >= usage.bytes, "{}", usage);
+        }
+        for usage in memory_usage(0) {
+            assert_eq!(usage.leaked, 0, "{}", usage);
+        }
+    }
+
+    // The allocation regression tests, which fail if a container allocates more blocks or holds
+    // more bytes per entry than the bounds. The bytes are of the 64-bit targets, and only bounded
+    // from above, so that a smaller layout of the nodes doesn't fail them.
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    pub fn test_allocation_regression() {
+        let n = 1000;
+        let usages = memory_usage(n);
+
+        // The nodes are allocated one by one, so is the entry of the persistent list.
+        let node_lists = [
+            ("linked_list_v1", 16),
+            ("linked_list_v2", 16),
+            ("linked_list_v3", 40),
+            ("linked_list_unsafe", 16),
+            ("merge_linkedlist", 32),
+            ("double_linked_list_v1", 48),
+            ("double_linked_list_v2", 48),
+            ("double_linked_list_v3", 24),
+            ("double_linked_list_unsafe", 24),
+            ("mutex_deque", 48),
+            ("avl_tree", 32),
+            ("rb_tree", 32),
+            ("treap", 40),
+            ("simple_tree", 32),
+        ];
+        for (name, bytes_per_entry) in node_lists {
+            let usage = usage_of(name, &usages);
+            assert_eq!(usage.allocs, n, "{}", usage);
+            assert!(usage.bytes <= n * bytes_per_entry, "{}", usage);
+        }
+
+        // The rest allocate in blocks or in the random levels, which are bounded.
+        let bounded = [
+            ("vec_deque", 20, 4.1),
+            ("gap_buffer", 20, 4.1),
+            ("lru_cache", 4 * n, 56.0),
+            ("lfu_cache", 4 * n, 64.0),
+            ("hash_table_v1", 2 * n, 88.0),
+            ("hash_table_v2", 3 * n, 24.0),
+            ("skiplist", 3 * n, 88.0),
+            ("skiplist_unsafe", 3 * n, 64.0),
+            ("b_tree", n, 40.0),
+            // The splay tree allocates the path while it splays.
+            ("splay_tree", 8 * n, 24.0),
+            ("scapegoat_tree", 2 * n, 24.0),
+        ];
+        for (name, allocs, bytes_per_entry) in bounded {
+            let usage = usage_of(name, &usages);
+            assert!(usage.allocs <= allocs, "{}", usage);
+            assert!(usage.bytes_per_entry() <= bytes_per_entry, "{}", usage);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod algo;
+pub mod basic;
+pub mod design_pattern;
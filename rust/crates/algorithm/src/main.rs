@@ -1,7 +1,3 @@
-pub mod algo;
-pub mod basic;
-pub mod design_pattern;
-
 fn main() {
     println!("Hello world");
 }
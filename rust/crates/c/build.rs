@@ -4,12 +4,49 @@ use std::env;
 
 fn main() {
     let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
-    let mut config: cbindgen::Config = Default::default();
-    config.header = Some(
-        String::from("// Licensed under the Apache License, Version 2.0 (the \"License\")"));
-    config.language = cbindgen::Language::C;
+    let config = cbindgen::Config {
+        header: Some(String::from(
+            "// Licensed under the Apache License, Version 2.0 (the \"License\")",
+        )),
+        language: cbindgen::Language::C,
+        usize_is_size_t: true,
+        ..Default::default()
+    };
+
+    // The C++ header declares the same API in the namespace `c::ffi`, and ends with the RAII
+    // wrappers of the handles in `src/wrapper.hpp.in`, which are written by hand on top of it.
+    let mut cxx = config.clone();
+    cxx.language = cbindgen::Language::Cxx;
+    cxx.namespaces = Some(vec![String::from("c"), String::from("ffi")]);
+    cxx.pragma_once = true;
+    cxx.trailer = Some(std::fs::read_to_string("src/wrapper.hpp.in").unwrap());
 
     cbindgen::generate_with_config(&crate_dir, config)
         .unwrap()
         .write_to_file("src/c.h");
-}
\ No newline at end of file
+    cbindgen::generate_with_config(&crate_dir, cxx)
+        .unwrap()
+        .write_to_file("src/c.hpp");
+
+    // The C harness of the unit tests, which includes the header generated above. It's only linked
+    // by the tests, so the metadata which would link it into the libraries isn't emitted.
+    cc::Build::new()
+        .file("src/harness.c")
+        .include("src")
+        .warnings(true)
+        .cargo_metadata(false)
+        .compile("c_harness");
+    println!("cargo:rustc-link-search=native={}", env::var("OUT_DIR").unwrap());
+
+    // Once any file is listed, cargo only reruns this script for the listed ones, so all the Rust
+    // sources read by cbindgen are listed too. The headers are written into `src`, so the directory
+    // itself can't be listed, or the script would run on every build.
+    for entry in std::fs::read_dir("src").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+    println!("cargo:rerun-if-changed=src/wrapper.hpp.in");
+    println!("cargo:rerun-if-changed=src/harness.c");
+}
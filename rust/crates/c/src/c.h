@@ -2,9 +2,47 @@
 
 #include <stdarg.h>
 #include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * The value returned by `job_state` on the error, which is none of the states.
+ */
+#define STATE_INVALID -1
+
+/**
+ * The result of the C APIs which can fail.
+ *
+ */
+typedef enum ErrorCode {
+  ErrorCode_Ok = 0,
+  /**
+   * The key isn't in the map.
+   */
+  ErrorCode_NotFound = 1,
+  /**
+   * A required pointer is null.
+   */
+  ErrorCode_NullPointer = 2,
+  /**
+   * The string isn't valid UTF-8.
+   */
+  ErrorCode_InvalidUtf8 = 3,
+  /**
+   * The argument is out of its range.
+   */
+  ErrorCode_InvalidArgument = 4,
+  /**
+   * Rust panicked, which is a bug of this library.
+   */
+  ErrorCode_Panic = 5,
+  /**
+   * The job can't move from its state to the requested one.
+   */
+  ErrorCode_InvalidTransition = 6,
+} ErrorCode;
+
 typedef enum State {
   New,
   Init,
@@ -12,15 +50,563 @@ typedef enum State {
   Done,
 } State;
 
+/**
+ * The Aho–Corasick automaton of a set of patterns, which can search many texts.
+ */
+typedef struct AhoCorasick AhoCorasick;
+
+typedef struct ByteAvlTree ByteAvlTree;
+
+typedef struct ByteHashMap ByteHashMap;
+
+typedef struct ByteSkipList ByteSkipList;
+
+typedef struct Job Job;
+
+/**
+ * The snapshot of the pairs of a map, in the ascending order of the keys for the skip list and the
+ * AVL tree, and in any order for the hash map.
+ */
+typedef struct MapIter MapIter;
+
+/**
+ * The string slice of `len` bytes, which needn't be NUL-terminated and may contain NUL. The `str`
+ * may be NULL only if `len` is zero.
+ */
 typedef struct CString {
   const char *str;
   uint32_t len;
 } CString;
 
+/**
+ * The callback of the transitions, which is called with the user data, the job and the transition.
+ */
+typedef void (*JobCallback)(void *user_data, struct Job *job, enum State from, enum State to);
+
+/**
+ * The transition of a job, which happened `micros` microseconds after the job was created.
+ */
+typedef struct Transition {
+  enum State from;
+  enum State to;
+  uint64_t micros;
+} Transition;
+
+/**
+ * The visitor of `*_for_each`, which is called with the user data and every pair in the order of
+ * the iterator, and returns false to stop. The key and the value are only valid in the call.
+ */
+typedef bool (*MapVisitor)(void *user_data,
+                           const uint8_t *key,
+                           size_t key_len,
+                           const uint8_t *val,
+                           size_t val_len);
+
+/**
+ * The occurrence of the pattern at the index `pattern` at `text[offset..offset + len]`.
+ */
+typedef struct SearchMatch {
+  size_t pattern;
+  size_t offset;
+  size_t len;
+} SearchMatch;
+
+/**
+ * The comparator of the sorts, which is the same as the one of `qsort`.
+ */
+typedef int (*Comparator)(const void *a, const void *b);
+
 void print_hello_from_rust(void);
 
 int32_t state_to_i32(enum State state);
 
+/**
+ * The length in bytes of the NUL-terminated UTF-8 string, or -1 on the error.
+ *
+ * # Safety
+ *
+ * The `str` must be NULL or point to a NUL-terminated string, which is readable up to its NUL.
+ */
 int32_t c_str_len(const char *str);
 
+/**
+ * The length in bytes of the UTF-8 string slice, which is its `len`, or -1 on the error.
+ *
+ * # Safety
+ *
+ * The `cstr` must be NULL or point to a readable `CString`, whose `str` points to `len` readable
+ * bytes unless `len` is zero.
+ */
 int32_t cstring_len(const struct CString *cstr);
+
+/**
+ * The code of the last error of the thread, or `ErrorCode_Ok` if there's none.
+ */
+enum ErrorCode last_error_code(void);
+
+/**
+ * The message of the last error of the thread, or NULL if there's none. It's owned by the thread,
+ * and valid until the next failure or `last_error_clear` on the same thread.
+ */
+const char *last_error_message(void);
+
+void last_error_clear(void);
+
+/**
+ * The name of the state, which is a static NUL-terminated string, or NULL if it's not a state.
+ */
+const char *state_name(int state);
+
+/**
+ * Create a job in the state `New`, which must be freed by `job_free`.
+ */
+struct Job *job_new(void);
+
+/**
+ * Free the job and its history.
+ *
+ * # Safety
+ *
+ * The job must be NULL or come from `job_new`, and be freed exactly once. It must not be used
+ * after this, and not be freed by its callbacks.
+ */
+void job_free(struct Job *job);
+
+/**
+ * The state of the job, or `STATE_INVALID` if the job is NULL.
+ *
+ * # Safety
+ *
+ * The job must be NULL or a live handle from `job_new`.
+ */
+int job_state(const struct Job *job);
+
+/**
+ * Move the job to the state `to` and call the callbacks, or fail without any change if the
+ * transition is illegal.
+ *
+ * # Safety
+ *
+ * The job must be NULL or a live handle from `job_new`, and the callbacks must be safe to call
+ * with their user data. They must not free the job.
+ */
+enum ErrorCode job_transition(struct Job *job, int to);
+
+/**
+ * Register the callback, which is called with `user_data` after every later transition of the
+ * job until it's freed.
+ *
+ * # Safety
+ *
+ * The job must be NULL or a live handle from `job_new`. The `user_data` is only passed to the
+ * callback, so it must be valid for the callback for as long as the job lives.
+ */
+enum ErrorCode job_on_transition(struct Job *job, JobCallback callback, void *user_data);
+
+/**
+ * Copy the first `cap` transitions of the job in their order into `history`, and set `count` to
+ * the number of all of them.
+ *
+ * # Safety
+ *
+ * The job must be NULL or a live handle from `job_new`. The `history` must be NULL or point to
+ * `cap` writable transitions, and `count` must be NULL or writable.
+ */
+enum ErrorCode job_history(const struct Job *job,
+                           struct Transition *history,
+                           size_t cap,
+                           size_t *count);
+
+struct ByteSkipList *skiplist_new(void);
+
+/**
+ * Free the map and all its pairs.
+ *
+ * # Safety
+ *
+ * The map must be NULL or come from `skiplist_new`, and be freed exactly once. It must not be used
+ * after this, and not be freed while it's visited by `skiplist_for_each`.
+ */
+void skiplist_free(struct ByteSkipList *map);
+
+/**
+ * Insert the pair, or replace the value if the key exists. Both of them are copied.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `skiplist_new`. The `key` and `val` must be NULL or
+ * point to `key_len` and `val_len` readable bytes.
+ */
+enum ErrorCode skiplist_put(struct ByteSkipList *map,
+                            const uint8_t *key,
+                            size_t key_len,
+                            const uint8_t *val,
+                            size_t val_len);
+
+/**
+ * Point `val` at the value of the key and set `val_len` to its length, or fail with
+ * `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `skiplist_new`. The `key` must be NULL or point to
+ * `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+ */
+enum ErrorCode skiplist_get(const struct ByteSkipList *map,
+                            const uint8_t *key,
+                            size_t key_len,
+                            const uint8_t **val,
+                            size_t *val_len);
+
+/**
+ * Remove the key, or fail with `ErrorCode_NotFound`.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `skiplist_new`. The `key` must be NULL or point to
+ * `key_len` readable bytes.
+ */
+enum ErrorCode skiplist_delete(struct ByteSkipList *map, const uint8_t *key, size_t key_len);
+
+/**
+ * The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `skiplist_new`.
+ */
+size_t skiplist_len(const struct ByteSkipList *map);
+
+/**
+ * Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+ * error.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `skiplist_new`.
+ */
+struct MapIter *skiplist_iter(const struct ByteSkipList *map);
+
+/**
+ * Call the visitor with every pair until it returns false.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `skiplist_new`. The map is borrowed while it's visited, so
+ * the visitor must neither change nor free it by `skiplist_put`, `skiplist_delete` or `skiplist_free`. Use
+ * `skiplist_iter` instead to change the map while iterating.
+ */
+enum ErrorCode skiplist_for_each(const struct ByteSkipList *map,
+                                 MapVisitor visitor,
+                                 void *user_data);
+
+struct ByteAvlTree *avl_tree_new(void);
+
+/**
+ * Free the map and all its pairs.
+ *
+ * # Safety
+ *
+ * The map must be NULL or come from `avl_tree_new`, and be freed exactly once. It must not be used
+ * after this, and not be freed while it's visited by `avl_tree_for_each`.
+ */
+void avl_tree_free(struct ByteAvlTree *map);
+
+/**
+ * Insert the pair, or replace the value if the key exists. Both of them are copied.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `avl_tree_new`. The `key` and `val` must be NULL or
+ * point to `key_len` and `val_len` readable bytes.
+ */
+enum ErrorCode avl_tree_put(struct ByteAvlTree *map,
+                            const uint8_t *key,
+                            size_t key_len,
+                            const uint8_t *val,
+                            size_t val_len);
+
+/**
+ * Point `val` at the value of the key and set `val_len` to its length, or fail with
+ * `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `avl_tree_new`. The `key` must be NULL or point to
+ * `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+ */
+enum ErrorCode avl_tree_get(const struct ByteAvlTree *map,
+                            const uint8_t *key,
+                            size_t key_len,
+                            const uint8_t **val,
+                            size_t *val_len);
+
+/**
+ * Remove the key, or fail with `ErrorCode_NotFound`.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `avl_tree_new`. The `key` must be NULL or point to
+ * `key_len` readable bytes.
+ */
+enum ErrorCode avl_tree_delete(struct ByteAvlTree *map, const uint8_t *key, size_t key_len);
+
+/**
+ * The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `avl_tree_new`.
+ */
+size_t avl_tree_len(const struct ByteAvlTree *map);
+
+/**
+ * Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+ * error.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `avl_tree_new`.
+ */
+struct MapIter *avl_tree_iter(const struct ByteAvlTree *map);
+
+/**
+ * Call the visitor with every pair until it returns false.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `avl_tree_new`. The map is borrowed while it's visited, so
+ * the visitor must neither change nor free it by `avl_tree_put`, `avl_tree_delete` or `avl_tree_free`. Use
+ * `avl_tree_iter` instead to change the map while iterating.
+ */
+enum ErrorCode avl_tree_for_each(const struct ByteAvlTree *map,
+                                 MapVisitor visitor,
+                                 void *user_data);
+
+struct ByteHashMap *hash_map_new(void);
+
+/**
+ * Free the map and all its pairs.
+ *
+ * # Safety
+ *
+ * The map must be NULL or come from `hash_map_new`, and be freed exactly once. It must not be used
+ * after this, and not be freed while it's visited by `hash_map_for_each`.
+ */
+void hash_map_free(struct ByteHashMap *map);
+
+/**
+ * Insert the pair, or replace the value if the key exists. Both of them are copied.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `hash_map_new`. The `key` and `val` must be NULL or
+ * point to `key_len` and `val_len` readable bytes.
+ */
+enum ErrorCode hash_map_put(struct ByteHashMap *map,
+                            const uint8_t *key,
+                            size_t key_len,
+                            const uint8_t *val,
+                            size_t val_len);
+
+/**
+ * Point `val` at the value of the key and set `val_len` to its length, or fail with
+ * `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `hash_map_new`. The `key` must be NULL or point to
+ * `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+ */
+enum ErrorCode hash_map_get(const struct ByteHashMap *map,
+                            const uint8_t *key,
+                            size_t key_len,
+                            const uint8_t **val,
+                            size_t *val_len);
+
+/**
+ * Remove the key, or fail with `ErrorCode_NotFound`.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `hash_map_new`. The `key` must be NULL or point to
+ * `key_len` readable bytes.
+ */
+enum ErrorCode hash_map_delete(struct ByteHashMap *map, const uint8_t *key, size_t key_len);
+
+/**
+ * The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `hash_map_new`.
+ */
+size_t hash_map_len(const struct ByteHashMap *map);
+
+/**
+ * Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+ * error.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `hash_map_new`.
+ */
+struct MapIter *hash_map_iter(const struct ByteHashMap *map);
+
+/**
+ * Call the visitor with every pair until it returns false.
+ *
+ * # Safety
+ *
+ * The map must be NULL or a live handle from `hash_map_new`. The map is borrowed while it's visited, so
+ * the visitor must neither change nor free it by `hash_map_put`, `hash_map_delete` or `hash_map_free`. Use
+ * `hash_map_iter` instead to change the map while iterating.
+ */
+enum ErrorCode hash_map_for_each(const struct ByteHashMap *map,
+                                 MapVisitor visitor,
+                                 void *user_data);
+
+/**
+ * Move to the next pair and return true, or return false at the end or on the error. The key and
+ * the value point into the iterator, and they're valid until it's freed.
+ *
+ * # Safety
+ *
+ * The iterator must be NULL or a live handle from `*_iter`, and `key`, `key_len`, `val` and
+ * `val_len` must be NULL or writable.
+ */
+bool map_iter_next(struct MapIter *iter,
+                   const uint8_t **key,
+                   size_t *key_len,
+                   const uint8_t **val,
+                   size_t *val_len);
+
+/**
+ * Free the iterator and its snapshot.
+ *
+ * # Safety
+ *
+ * The iterator must be NULL or come from `*_iter`, and be freed exactly once.
+ */
+void map_iter_free(struct MapIter *iter);
+
+/**
+ * Find the offsets of all the occurrences of the pattern in the text by KMP, in the ascending
+ * order and including the overlapped ones.
+ *
+ * # Safety
+ *
+ * The `text` and `pattern` must be NULL or point to `text_len` and `pattern_len` readable bytes.
+ * The `offsets` must be NULL or point to `cap` writable offsets, and `count` must be NULL or
+ * writable.
+ */
+enum ErrorCode kmp_search(const uint8_t *text,
+                          size_t text_len,
+                          const uint8_t *pattern,
+                          size_t pattern_len,
+                          size_t *offsets,
+                          size_t cap,
+                          size_t *count);
+
+/**
+ * Build the automaton of `n` patterns, where the pattern `i` is `patterns[i]` of `lens[i]` bytes,
+ * or return NULL on the error. The patterns are copied, and identified by their indexes.
+ *
+ * # Safety
+ *
+ * Unless `n` is zero, `patterns` and `lens` must point to `n` readable items, and every
+ * `patterns[i]` must be NULL or point to `lens[i]` readable bytes.
+ */
+struct AhoCorasick *aho_corasick_new(const uint8_t *const *patterns, const size_t *lens, size_t n);
+
+/**
+ * Free the automaton.
+ *
+ * # Safety
+ *
+ * The `ac` must be NULL or come from `aho_corasick_new`, and be freed exactly once. It must not be
+ * used after this.
+ */
+void aho_corasick_free(struct AhoCorasick *ac);
+
+/**
+ * The number of the patterns, or 0 if the automaton is NULL, which is also kept as the last
+ * error.
+ *
+ * # Safety
+ *
+ * The `ac` must be NULL or a live handle from `aho_corasick_new`.
+ */
+size_t aho_corasick_pattern_count(const struct AhoCorasick *ac);
+
+/**
+ * Find all the occurrences of the patterns in the text, in the ascending order of their ends, and
+ * the longer patterns first for the same end.
+ *
+ * # Safety
+ *
+ * The `ac` must be NULL or a live handle from `aho_corasick_new`, and `text` must be NULL or point
+ * to `text_len` readable bytes. The `matches` must be NULL or point to `cap` writable matches, and
+ * `count` must be NULL or writable.
+ */
+enum ErrorCode aho_corasick_search(const struct AhoCorasick *ac,
+                                   const uint8_t *text,
+                                   size_t text_len,
+                                   struct SearchMatch *matches,
+                                   size_t cap,
+                                   size_t *count);
+
+/**
+ * Sort by the quick sort, which isn't stable.
+ *
+ * # Safety
+ *
+ * The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+ * the comparator must be safe to call with any two pointers to the elements in the array. It must
+ * not change the array, which is read by the sort while it's called.
+ */
+enum ErrorCode sort_quick(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/**
+ * Sort by the heap sort, which isn't stable.
+ *
+ * # Safety
+ *
+ * The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+ * the comparator must be safe to call with any two pointers to the elements in the array. It must
+ * not change the array, which is read by the sort while it's called.
+ */
+enum ErrorCode sort_heap(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/**
+ * Sort by the merge sort, which is stable.
+ *
+ * # Safety
+ *
+ * The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+ * the comparator must be safe to call with any two pointers to the elements in the array. It must
+ * not change the array, which is read by the sort while it's called.
+ */
+enum ErrorCode sort_merge(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/**
+ * Sort by the insertion sort, which is stable and O(n^2).
+ *
+ * # Safety
+ *
+ * The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+ * the comparator must be safe to call with any two pointers to the elements in the array. It must
+ * not change the array, which is read by the sort while it's called.
+ */
+enum ErrorCode sort_insertion(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/**
+ * Sort by the selection sort, which isn't stable and is O(n^2).
+ *
+ * # Safety
+ *
+ * The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+ * the comparator must be safe to call with any two pointers to the elements in the array. It must
+ * not change the array, which is read by the sort while it's called.
+ */
+enum ErrorCode sort_selection(void *base, size_t nmemb, size_t size, Comparator compar);
@@ -0,0 +1,895 @@
+// Licensed under the Apache License, Version 2.0 (the "License")
+
+#pragma once
+
+#include <cstdarg>
+#include <cstddef>
+#include <cstdint>
+#include <cstdlib>
+#include <ostream>
+#include <new>
+
+namespace c {
+namespace ffi {
+
+/// The value returned by `job_state` on the error, which is none of the states.
+constexpr static const int STATE_INVALID = -1;
+
+/// The result of the C APIs which can fail.
+///
+enum class ErrorCode {
+  ErrorCode_Ok = 0,
+  /// The key isn't in the map.
+  ErrorCode_NotFound = 1,
+  /// A required pointer is null.
+  ErrorCode_NullPointer = 2,
+  /// The string isn't valid UTF-8.
+  ErrorCode_InvalidUtf8 = 3,
+  /// The argument is out of its range.
+  ErrorCode_InvalidArgument = 4,
+  /// Rust panicked, which is a bug of this library.
+  ErrorCode_Panic = 5,
+  /// The job can't move from its state to the requested one.
+  ErrorCode_InvalidTransition = 6,
+};
+
+enum class State {
+  New,
+  Init,
+  Running,
+  Done,
+};
+
+/// The Aho–Corasick automaton of a set of patterns, which can search many texts.
+struct AhoCorasick;
+
+struct ByteAvlTree;
+
+struct ByteHashMap;
+
+struct ByteSkipList;
+
+struct Job;
+
+/// The snapshot of the pairs of a map, in the ascending order of the keys for the skip list and the
+/// AVL tree, and in any order for the hash map.
+struct MapIter;
+
+/// The string slice of `len` bytes, which needn't be NUL-terminated and may contain NUL. The `str`
+/// may be NULL only if `len` is zero.
+struct CString {
+  const char *str;
+  uint32_t len;
+};
+
+/// The callback of the transitions, which is called with the user data, the job and the transition.
+using JobCallback = void(*)(void *user_data, Job *job, State from, State to);
+
+/// The transition of a job, which happened `micros` microseconds after the job was created.
+struct Transition {
+  State from;
+  State to;
+  uint64_t micros;
+};
+
+/// The visitor of `*_for_each`, which is called with the user data and every pair in the order of
+/// the iterator, and returns false to stop. The key and the value are only valid in the call.
+using MapVisitor = bool(*)(void *user_data,
+                           const uint8_t *key,
+                           size_t key_len,
+                           const uint8_t *val,
+                           size_t val_len);
+
+/// The occurrence of the pattern at the index `pattern` at `text[offset..offset + len]`.
+struct SearchMatch {
+  size_t pattern;
+  size_t offset;
+  size_t len;
+};
+
+/// The comparator of the sorts, which is the same as the one of `qsort`.
+using Comparator = int(*)(const void *a, const void *b);
+
+extern "C" {
+
+void print_hello_from_rust();
+
+int32_t state_to_i32(State state);
+
+/// The length in bytes of the NUL-terminated UTF-8 string, or -1 on the error.
+///
+/// # Safety
+///
+/// The `str` must be NULL or point to a NUL-terminated string, which is readable up to its NUL.
+int32_t c_str_len(const char *str);
+
+/// The length in bytes of the UTF-8 string slice, which is its `len`, or -1 on the error.
+///
+/// # Safety
+///
+/// The `cstr` must be NULL or point to a readable `CString`, whose `str` points to `len` readable
+/// bytes unless `len` is zero.
+int32_t cstring_len(const CString *cstr);
+
+/// The code of the last error of the thread, or `ErrorCode_Ok` if there's none.
+ErrorCode last_error_code();
+
+/// The message of the last error of the thread, or NULL if there's none. It's owned by the thread,
+/// and valid until the next failure or `last_error_clear` on the same thread.
+const char *last_error_message();
+
+void last_error_clear();
+
+/// The name of the state, which is a static NUL-terminated string, or NULL if it's not a state.
+const char *state_name(int state);
+
+/// Create a job in the state `New`, which must be freed by `job_free`.
+Job *job_new();
+
+/// Free the job and its history.
+///
+/// # Safety
+///
+/// The job must be NULL or come from `job_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed by its callbacks.
+void job_free(Job *job);
+
+/// The state of the job, or `STATE_INVALID` if the job is NULL.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`.
+int job_state(const Job *job);
+
+/// Move the job to the state `to` and call the callbacks, or fail without any change if the
+/// transition is illegal.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`, and the callbacks must be safe to call
+/// with their user data. They must not free the job.
+ErrorCode job_transition(Job *job, int to);
+
+/// Register the callback, which is called with `user_data` after every later transition of the
+/// job until it's freed.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`. The `user_data` is only passed to the
+/// callback, so it must be valid for the callback for as long as the job lives.
+ErrorCode job_on_transition(Job *job, JobCallback callback, void *user_data);
+
+/// Copy the first `cap` transitions of the job in their order into `history`, and set `count` to
+/// the number of all of them.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`. The `history` must be NULL or point to
+/// `cap` writable transitions, and `count` must be NULL or writable.
+ErrorCode job_history(const Job *job, Transition *history, size_t cap, size_t *count);
+
+ByteSkipList *skiplist_new();
+
+/// Free the map and all its pairs.
+///
+/// # Safety
+///
+/// The map must be NULL or come from `skiplist_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed while it's visited by `skiplist_for_each`.
+void skiplist_free(ByteSkipList *map);
+
+/// Insert the pair, or replace the value if the key exists. Both of them are copied.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The `key` and `val` must be NULL or
+/// point to `key_len` and `val_len` readable bytes.
+ErrorCode skiplist_put(ByteSkipList *map,
+                       const uint8_t *key,
+                       size_t key_len,
+                       const uint8_t *val,
+                       size_t val_len);
+
+/// Point `val` at the value of the key and set `val_len` to its length, or fail with
+/// `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+ErrorCode skiplist_get(const ByteSkipList *map,
+                       const uint8_t *key,
+                       size_t key_len,
+                       const uint8_t **val,
+                       size_t *val_len);
+
+/// Remove the key, or fail with `ErrorCode_NotFound`.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes.
+ErrorCode skiplist_delete(ByteSkipList *map, const uint8_t *key, size_t key_len);
+
+/// The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`.
+size_t skiplist_len(const ByteSkipList *map);
+
+/// Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+/// error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`.
+MapIter *skiplist_iter(const ByteSkipList *map);
+
+/// Call the visitor with every pair until it returns false.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The map is borrowed while it's visited, so
+/// the visitor must neither change nor free it by `skiplist_put`, `skiplist_delete` or `skiplist_free`. Use
+/// `skiplist_iter` instead to change the map while iterating.
+ErrorCode skiplist_for_each(const ByteSkipList *map,
+                            MapVisitor visitor,
+                            void *user_data);
+
+ByteAvlTree *avl_tree_new();
+
+/// Free the map and all its pairs.
+///
+/// # Safety
+///
+/// The map must be NULL or come from `avl_tree_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed while it's visited by `avl_tree_for_each`.
+void avl_tree_free(ByteAvlTree *map);
+
+/// Insert the pair, or replace the value if the key exists. Both of them are copied.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The `key` and `val` must be NULL or
+/// point to `key_len` and `val_len` readable bytes.
+ErrorCode avl_tree_put(ByteAvlTree *map,
+                       const uint8_t *key,
+                       size_t key_len,
+                       const uint8_t *val,
+                       size_t val_len);
+
+/// Point `val` at the value of the key and set `val_len` to its length, or fail with
+/// `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+ErrorCode avl_tree_get(const ByteAvlTree *map,
+                       const uint8_t *key,
+                       size_t key_len,
+                       const uint8_t **val,
+                       size_t *val_len);
+
+/// Remove the key, or fail with `ErrorCode_NotFound`.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes.
+ErrorCode avl_tree_delete(ByteAvlTree *map, const uint8_t *key, size_t key_len);
+
+/// The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`.
+size_t avl_tree_len(const ByteAvlTree *map);
+
+/// Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+/// error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`.
+MapIter *avl_tree_iter(const ByteAvlTree *map);
+
+/// Call the visitor with every pair until it returns false.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The map is borrowed while it's visited, so
+/// the visitor must neither change nor free it by `avl_tree_put`, `avl_tree_delete` or `avl_tree_free`. Use
+/// `avl_tree_iter` instead to change the map while iterating.
+ErrorCode avl_tree_for_each(const ByteAvlTree *map,
+                            MapVisitor visitor,
+                            void *user_data);
+
+ByteHashMap *hash_map_new();
+
+/// Free the map and all its pairs.
+///
+/// # Safety
+///
+/// The map must be NULL or come from `hash_map_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed while it's visited by `hash_map_for_each`.
+void hash_map_free(ByteHashMap *map);
+
+/// Insert the pair, or replace the value if the key exists. Both of them are copied.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The `key` and `val` must be NULL or
+/// point to `key_len` and `val_len` readable bytes.
+ErrorCode hash_map_put(ByteHashMap *map,
+                       const uint8_t *key,
+                       size_t key_len,
+                       const uint8_t *val,
+                       size_t val_len);
+
+/// Point `val` at the value of the key and set `val_len` to its length, or fail with
+/// `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+ErrorCode hash_map_get(const ByteHashMap *map,
+                       const uint8_t *key,
+                       size_t key_len,
+                       const uint8_t **val,
+                       size_t *val_len);
+
+/// Remove the key, or fail with `ErrorCode_NotFound`.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes.
+ErrorCode hash_map_delete(ByteHashMap *map, const uint8_t *key, size_t key_len);
+
+/// The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`.
+size_t hash_map_len(const ByteHashMap *map);
+
+/// Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+/// error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`.
+MapIter *hash_map_iter(const ByteHashMap *map);
+
+/// Call the visitor with every pair until it returns false.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The map is borrowed while it's visited, so
+/// the visitor must neither change nor free it by `hash_map_put`, `hash_map_delete` or `hash_map_free`. Use
+/// `hash_map_iter` instead to change the map while iterating.
+ErrorCode hash_map_for_each(const ByteHashMap *map,
+                            MapVisitor visitor,
+                            void *user_data);
+
+/// Move to the next pair and return true, or return false at the end or on the error. The key and
+/// the value point into the iterator, and they're valid until it's freed.
+///
+/// # Safety
+///
+/// The iterator must be NULL or a live handle from `*_iter`, and `key`, `key_len`, `val` and
+/// `val_len` must be NULL or writable.
+bool map_iter_next(MapIter *iter,
+                   const uint8_t **key,
+                   size_t *key_len,
+                   const uint8_t **val,
+                   size_t *val_len);
+
+/// Free the iterator and its snapshot.
+///
+/// # Safety
+///
+/// The iterator must be NULL or come from `*_iter`, and be freed exactly once.
+void map_iter_free(MapIter *iter);
+
+/// Find the offsets of all the occurrences of the pattern in the text by KMP, in the ascending
+/// order and including the overlapped ones.
+///
+/// # Safety
+///
+/// The `text` and `pattern` must be NULL or point to `text_len` and `pattern_len` readable bytes.
+/// The `offsets` must be NULL or point to `cap` writable offsets, and `count` must be NULL or
+/// writable.
+ErrorCode kmp_search(const uint8_t *text,
+                     size_t text_len,
+                     const uint8_t *pattern,
+                     size_t pattern_len,
+                     size_t *offsets,
+                     size_t cap,
+                     size_t *count);
+
+/// Build the automaton of `n` patterns, where the pattern `i` is `patterns[i]` of `lens[i]` bytes,
+/// or return NULL on the error. The patterns are copied, and identified by their indexes.
+///
+/// # Safety
+///
+/// Unless `n` is zero, `patterns` and `lens` must point to `n` readable items, and every
+/// `patterns[i]` must be NULL or point to `lens[i]` readable bytes.
+AhoCorasick *aho_corasick_new(const uint8_t *const *patterns, const size_t *lens, size_t n);
+
+/// Free the automaton.
+///
+/// # Safety
+///
+/// The `ac` must be NULL or come from `aho_corasick_new`, and be freed exactly once. It must not be
+/// used after this.
+void aho_corasick_free(AhoCorasick *ac);
+
+/// The number of the patterns, or 0 if the automaton is NULL, which is also kept as the last
+/// error.
+///
+/// # Safety
+///
+/// The `ac` must be NULL or a live handle from `aho_corasick_new`.
+size_t aho_corasick_pattern_count(const AhoCorasick *ac);
+
+/// Find all the occurrences of the patterns in the text, in the ascending order of their ends, and
+/// the longer patterns first for the same end.
+///
+/// # Safety
+///
+/// The `ac` must be NULL or a live handle from `aho_corasick_new`, and `text` must be NULL or point
+/// to `text_len` readable bytes. The `matches` must be NULL or point to `cap` writable matches, and
+/// `count` must be NULL or writable.
+ErrorCode aho_corasick_search(const AhoCorasick *ac,
+                              const uint8_t *text,
+                              size_t text_len,
+                              SearchMatch *matches,
+                              size_t cap,
+                              size_t *count);
+
+/// Sort by the quick sort, which isn't stable.
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+ErrorCode sort_quick(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/// Sort by the heap sort, which isn't stable.
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+ErrorCode sort_heap(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/// Sort by the merge sort, which is stable.
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+ErrorCode sort_merge(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/// Sort by the insertion sort, which is stable and O(n^2).
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+ErrorCode sort_insertion(void *base, size_t nmemb, size_t size, Comparator compar);
+
+/// Sort by the selection sort, which isn't stable and is O(n^2).
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+ErrorCode sort_selection(void *base, size_t nmemb, size_t size, Comparator compar);
+
+}  // extern "C"
+
+}  // namespace ffi
+}  // namespace c
+
+
+// The C++17 wrappers of the C API above, which are appended to c.hpp by build.rs.
+//
+// Every handle is owned by a move-only class which frees it on destruction, and the failed calls
+// throw the exception of their `ErrorCode` with the message of the last error. A moved-from object
+// only supports the destruction and the assignment, and any other call throws `NullPointerError`.
+
+#include <deque>
+#include <exception>
+#include <functional>
+#include <memory>
+#include <optional>
+#include <stdexcept>
+#include <string>
+#include <string_view>
+#include <type_traits>
+#include <utility>
+#include <vector>
+
+namespace c {
+
+using ErrorCode = ffi::ErrorCode;
+using State = ffi::State;
+using Transition = ffi::Transition;
+using SearchMatch = ffi::SearchMatch;
+
+class Error : public std::runtime_error {
+ public:
+  Error(ErrorCode code, const std::string &message) : std::runtime_error(message), code_(code) {}
+
+  ErrorCode code() const noexcept { return code_; }
+
+ private:
+  ErrorCode code_;
+};
+
+template <ErrorCode Code>
+class ErrorOf : public Error {
+ public:
+  explicit ErrorOf(const std::string &message) : Error(Code, message) {}
+};
+
+using NotFoundError = ErrorOf<ErrorCode::ErrorCode_NotFound>;
+using NullPointerError = ErrorOf<ErrorCode::ErrorCode_NullPointer>;
+using InvalidUtf8Error = ErrorOf<ErrorCode::ErrorCode_InvalidUtf8>;
+using InvalidArgumentError = ErrorOf<ErrorCode::ErrorCode_InvalidArgument>;
+using PanicError = ErrorOf<ErrorCode::ErrorCode_Panic>;
+using InvalidTransitionError = ErrorOf<ErrorCode::ErrorCode_InvalidTransition>;
+
+/// Throw the exception of the code, with the message of the last error of the thread.
+[[noreturn]] inline void throw_error(ErrorCode code) {
+  const char *last = ffi::last_error_message();
+  std::string message = last != nullptr ? last : "unknown error";
+  switch (code) {
+    case ErrorCode::ErrorCode_NotFound:
+      throw NotFoundError(message);
+    case ErrorCode::ErrorCode_NullPointer:
+      throw NullPointerError(message);
+    case ErrorCode::ErrorCode_InvalidUtf8:
+      throw InvalidUtf8Error(message);
+    case ErrorCode::ErrorCode_InvalidArgument:
+      throw InvalidArgumentError(message);
+    case ErrorCode::ErrorCode_Panic:
+      throw PanicError(message);
+    case ErrorCode::ErrorCode_InvalidTransition:
+      throw InvalidTransitionError(message);
+    default:
+      throw Error(code, message);
+  }
+}
+
+inline void check(ErrorCode code) {
+  if (code != ErrorCode::ErrorCode_Ok) {
+    throw_error(code);
+  }
+}
+
+namespace detail {
+
+inline const uint8_t *bytes(std::string_view s) {
+  return reinterpret_cast<const uint8_t *>(s.data());
+}
+
+inline std::string_view view(const uint8_t *p, size_t len) {
+  return {reinterpret_cast<const char *>(p), len};
+}
+
+// Call the C API which fills a buffer like `snprintf` twice: for the count, then for the items.
+template <typename T, typename F>
+std::vector<T> collect(F &&fill) {
+  size_t count = 0;
+  check(fill(nullptr, 0, &count));
+  std::vector<T> items(count);
+  check(fill(items.data(), items.size(), &count));
+  items.resize(count < items.size() ? count : items.size());
+  return items;
+}
+
+}  // namespace detail
+
+/// The owner of a handle, which frees it by `Free` on destruction.
+template <typename T, void (*Free)(T *)>
+class Handle {
+ public:
+  /// Take the handle, or throw the last error if it's NULL.
+  explicit Handle(T *raw) : raw_(raw) {
+    if (raw_ == nullptr) {
+      throw_error(ffi::last_error_code());
+    }
+  }
+
+  Handle(const Handle &) = delete;
+  Handle &operator=(const Handle &) = delete;
+
+  Handle(Handle &&other) noexcept : raw_(std::exchange(other.raw_, nullptr)) {}
+
+  Handle &operator=(Handle &&other) noexcept {
+    if (this != &other) {
+      reset();
+      raw_ = std::exchange(other.raw_, nullptr);
+    }
+    return *this;
+  }
+
+  ~Handle() { reset(); }
+
+  T *raw() const noexcept { return raw_; }
+
+  /// Give up the ownership, and the caller must free the handle.
+  T *release() noexcept { return std::exchange(raw_, nullptr); }
+
+  explicit operator bool() const noexcept { return raw_ != nullptr; }
+
+ protected:
+  // The handle of the C APIs which can't report the errors.
+  T *live() const {
+    if (raw_ == nullptr) {
+      throw NullPointerError("the handle has been moved");
+    }
+    return raw_;
+  }
+
+ private:
+  void reset() noexcept {
+    if (raw_ != nullptr) {
+      Free(raw_);
+      raw_ = nullptr;
+    }
+  }
+
+  T *raw_;
+};
+
+/// The snapshot of the pairs of a map.
+class MapIter : public Handle<ffi::MapIter, ffi::map_iter_free> {
+ public:
+  using Pair = std::pair<std::string_view, std::string_view>;
+
+  explicit MapIter(ffi::MapIter *raw) : Handle(raw) {}
+
+  /// The next pair, which is valid until the iterator is destroyed, or nullopt at the end.
+  std::optional<Pair> next() {
+    const uint8_t *key, *val;
+    size_t key_len, val_len;
+    if (!ffi::map_iter_next(live(), &key, &key_len, &val, &val_len)) {
+      return std::nullopt;
+    }
+    return Pair(detail::view(key, key_len), detail::view(val, val_len));
+  }
+};
+
+/// The byte-string map of the C API named by `Ops`.
+template <typename Ops>
+class BasicMap : public Handle<typename Ops::Raw, Ops::free> {
+ public:
+  BasicMap() : Handle<typename Ops::Raw, Ops::free>(Ops::create()) {}
+
+  void put(std::string_view key, std::string_view val) {
+    check(Ops::put(this->raw(), detail::bytes(key), key.size(), detail::bytes(val), val.size()));
+  }
+
+  /// The value of the key, which is valid until the map is changed, or nullopt if it's missing.
+  std::optional<std::string_view> get(std::string_view key) const {
+    const uint8_t *val;
+    size_t val_len;
+    ErrorCode code = Ops::get(this->raw(), detail::bytes(key), key.size(), &val, &val_len);
+    if (code == ErrorCode::ErrorCode_NotFound) {
+      return std::nullopt;
+    }
+    check(code);
+    return detail::view(val, val_len);
+  }
+
+  /// Remove the key, and return false if it's missing.
+  bool erase(std::string_view key) {
+    ErrorCode code = Ops::erase(this->raw(), detail::bytes(key), key.size());
+    if (code == ErrorCode::ErrorCode_NotFound) {
+      return false;
+    }
+    check(code);
+    return true;
+  }
+
+  size_t size() const { return Ops::len(this->live()); }
+
+  MapIter iter() const { return MapIter(Ops::iter(this->raw())); }
+
+  /// Call `f(key, val)` with every pair until it returns false, if it returns a bool. The exception
+  /// thrown by `f` stops the iteration, and is rethrown here.
+  template <typename F>
+  void for_each(F &&f) const {
+    Visit<F> visit{f, nullptr};
+    check(Ops::for_each(this->raw(), &BasicMap::visitor<F>, &visit));
+    if (visit.error) {
+      std::rethrow_exception(visit.error);
+    }
+  }
+
+ private:
+  template <typename F>
+  struct Visit {
+    F &f;
+    std::exception_ptr error;
+  };
+
+  template <typename F>
+  static bool visitor(void *user_data, const uint8_t *key, size_t key_len, const uint8_t *val,
+                      size_t val_len) noexcept {
+    auto *visit = static_cast<Visit<F> *>(user_data);
+    std::string_view k = detail::view(key, key_len), v = detail::view(val, val_len);
+    try {
+      if constexpr (std::is_void_v<std::invoke_result_t<F &, std::string_view, std::string_view>>) {
+        visit->f(k, v);
+        return true;
+      } else {
+        return static_cast<bool>(visit->f(k, v));
+      }
+    } catch (...) {
+      visit->error = std::current_exception();
+      return false;
+    }
+  }
+};
+
+namespace detail {
+
+struct SkipListOps {
+  using Raw = ffi::ByteSkipList;
+  static constexpr auto create = ffi::skiplist_new;
+  static constexpr auto free = ffi::skiplist_free;
+  static constexpr auto put = ffi::skiplist_put;
+  static constexpr auto get = ffi::skiplist_get;
+  static constexpr auto erase = ffi::skiplist_delete;
+  static constexpr auto len = ffi::skiplist_len;
+  static constexpr auto iter = ffi::skiplist_iter;
+  static constexpr auto for_each = ffi::skiplist_for_each;
+};
+
+struct AvlTreeOps {
+  using Raw = ffi::ByteAvlTree;
+  static constexpr auto create = ffi::avl_tree_new;
+  static constexpr auto free = ffi::avl_tree_free;
+  static constexpr auto put = ffi::avl_tree_put;
+  static constexpr auto get = ffi::avl_tree_get;
+  static constexpr auto erase = ffi::avl_tree_delete;
+  static constexpr auto len = ffi::avl_tree_len;
+  static constexpr auto iter = ffi::avl_tree_iter;
+  static constexpr auto for_each = ffi::avl_tree_for_each;
+};
+
+struct HashMapOps {
+  using Raw = ffi::ByteHashMap;
+  static constexpr auto create = ffi::hash_map_new;
+  static constexpr auto free = ffi::hash_map_free;
+  static constexpr auto put = ffi::hash_map_put;
+  static constexpr auto get = ffi::hash_map_get;
+  static constexpr auto erase = ffi::hash_map_delete;
+  static constexpr auto len = ffi::hash_map_len;
+  static constexpr auto iter = ffi::hash_map_iter;
+  static constexpr auto for_each = ffi::hash_map_for_each;
+};
+
+}  // namespace detail
+
+using SkipList = BasicMap<detail::SkipListOps>;
+using AvlTree = BasicMap<detail::AvlTreeOps>;
+using HashMap = BasicMap<detail::HashMapOps>;
+
+/// The Aho–Corasick automaton of the patterns, which must not be empty.
+class AhoCorasick : public Handle<ffi::AhoCorasick, ffi::aho_corasick_free> {
+ public:
+  explicit AhoCorasick(const std::vector<std::string_view> &patterns) : Handle(create(patterns)) {}
+
+  size_t pattern_count() const { return ffi::aho_corasick_pattern_count(live()); }
+
+  std::vector<SearchMatch> search(std::string_view text) const {
+    return detail::collect<SearchMatch>([&](SearchMatch *out, size_t cap, size_t *count) {
+      return ffi::aho_corasick_search(raw(), detail::bytes(text), text.size(), out, cap, count);
+    });
+  }
+
+ private:
+  static ffi::AhoCorasick *create(const std::vector<std::string_view> &patterns) {
+    std::vector<const uint8_t *> ptrs;
+    std::vector<size_t> lens;
+    for (std::string_view p : patterns) {
+      ptrs.push_back(detail::bytes(p));
+      lens.push_back(p.size());
+    }
+    return ffi::aho_corasick_new(ptrs.data(), lens.data(), patterns.size());
+  }
+};
+
+/// The offsets of all the occurrences of the pattern, which must not be empty, by KMP.
+inline std::vector<size_t> kmp_search(std::string_view text, std::string_view pattern) {
+  return detail::collect<size_t>([&](size_t *out, size_t cap, size_t *count) {
+    return ffi::kmp_search(detail::bytes(text), text.size(), detail::bytes(pattern),
+                           pattern.size(), out, cap, count);
+  });
+}
+
+inline const char *state_name(State state) {
+  const char *name = ffi::state_name(static_cast<int>(state));
+  if (name == nullptr) {
+    throw_error(ffi::last_error_code());
+  }
+  return name;
+}
+
+/// The job, whose callbacks are `std::function`s. The exception thrown by a callback skips the
+/// rest of them, and is rethrown by `transition`.
+class Job : public Handle<ffi::Job, ffi::job_free> {
+ public:
+  using Callback = std::function<void(State from, State to)>;
+
+  Job() : Handle(ffi::job_new()), callbacks_(std::make_unique<Callbacks>()) {}
+
+  State state() const {
+    int state = ffi::job_state(live());
+    if (state == ffi::STATE_INVALID) {
+      throw_error(ffi::last_error_code());
+    }
+    return static_cast<State>(state);
+  }
+
+  void transition(State to) {
+    check(ffi::job_transition(raw(), static_cast<int>(to)));
+    if (std::exception_ptr error = std::exchange(callbacks_->error, nullptr)) {
+      std::rethrow_exception(error);
+    }
+  }
+
+  void on_transition(Callback callback) {
+    // The callbacks are only registered to the job once, and called by `dispatch` in their order.
+    ffi::Job *job = live();
+    if (callbacks_->fns.empty()) {
+      check(ffi::job_on_transition(job, &Job::dispatch, callbacks_.get()));
+    }
+    callbacks_->fns.push_back(std::move(callback));
+  }
+
+  std::vector<Transition> history() const {
+    return detail::collect<Transition>([&](Transition *out, size_t cap, size_t *count) {
+      return ffi::job_history(raw(), out, cap, count);
+    });
+  }
+
+ private:
+  // The callbacks stay at the same address when the job is moved, and the deque keeps them there
+  // when more are registered by a callback.
+  struct Callbacks {
+    std::deque<Callback> fns;
+    std::exception_ptr error;
+  };
+
+  static void dispatch(void *user_data, ffi::Job *, State from, State to) noexcept {
+    auto *callbacks = static_cast<Callbacks *>(user_data);
+    // The callbacks registered by this transition are only called by the later ones, as in C.
+    size_t n = callbacks->fns.size();
+    for (size_t i = 0; i < n && !callbacks->error; i++) {
+      try {
+        callbacks->fns[i](from, to);
+      } catch (...) {
+        callbacks->error = std::current_exception();
+      }
+    }
+  }
+
+  std::unique_ptr<Callbacks> callbacks_;
+};
+
+}  // namespace c
@@ -0,0 +1,183 @@
+// The errors of the C APIs. A failed call returns its `ErrorCode` (or -1, NULL or false when it
+// returns something else), and keeps the code and the message as the last error of the thread,
+// which stays there until the next failure or `last_error_clear`.
+//
+// No panic unwinds into C: the body of every C API runs in `catch_unwind`, and a panic is
+// reported as `ErrorCode_Panic` with its message.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+//
+// C API: 'ErrorCode'
+//
+
+/// The result of the C APIs which can fail.
+///
+/// cbindgen:prefix-with-name
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    Ok = 0,
+    /// The key isn't in the map.
+    NotFound = 1,
+    /// A required pointer is null.
+    NullPointer = 2,
+    /// The string isn't valid UTF-8.
+    InvalidUtf8 = 3,
+    /// The argument is out of its range.
+    InvalidArgument = 4,
+    /// Rust panicked, which is a bug of this library.
+    Panic = 5,
+    /// The job can't move from its state to the requested one.
+    InvalidTransition = 6,
+}
+
+pub(crate) struct Error {
+    code: ErrorCode,
+    message: String,
+}
+
+impl Error {
+    pub(crate) fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Error {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn null(arg: &str) -> Self {
+        Error::new(ErrorCode::NullPointer, format!("`{}` is null", arg))
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(ErrorCode, CString)>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(err: Error) -> ErrorCode {
+    // The message can't contain NUL in C, so it's cut at the first one.
+    let mut bytes = err.message.into_bytes();
+    bytes.truncate(bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len()));
+    let message = CString::new(bytes).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some((err.code, message)));
+    err.code
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(s) => format!("panic: {}", s),
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(s) => format!("panic: {}", s),
+            Err(_) => String::from("panic"),
+        },
+    }
+}
+
+/// Run the body of a C API which returns a value, and return `on_err` if it fails or panics.
+pub(crate) fn catch<T>(on_err: T, f: impl FnOnce() -> Result<T, Error>) -> T {
+    let err = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(v)) => return v,
+        Ok(Err(err)) => err,
+        Err(payload) => Error::new(ErrorCode::Panic, panic_message(payload)),
+    };
+    set_last_error(err);
+    on_err
+}
+
+/// Run the body of a C API which returns the `ErrorCode`.
+pub(crate) fn status(f: impl FnOnce() -> Result<(), Error>) -> ErrorCode {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => ErrorCode::Ok,
+        Ok(Err(err)) => set_last_error(err),
+        Err(payload) => set_last_error(Error::new(ErrorCode::Panic, panic_message(payload))),
+    }
+}
+
+//
+// C API: 'last_error_*'
+//
+
+/// The code of the last error of the thread, or `ErrorCode_Ok` if there's none.
+#[no_mangle]
+pub extern "C" fn last_error_code() -> ErrorCode {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ErrorCode::Ok, |(code, _)| *code))
+}
+
+/// The message of the last error of the thread, or NULL if there's none. It's owned by the thread,
+/// and valid until the next failure or `last_error_clear` on the same thread.
+#[no_mangle]
+pub extern "C" fn last_error_message() -> *const c_char {
+    LAST_ERROR.with(|e| {
+        e.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |(_, message)| message.as_ptr())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn last_error_clear() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn message() -> String {
+        let p = last_error_message();
+        assert!(!p.is_null());
+        unsafe { CStr::from_ptr(p) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    pub fn test_last_error() {
+        last_error_clear();
+        assert_eq!(last_error_code(), ErrorCode::Ok);
+        assert!(last_error_message().is_null());
+
+        assert_eq!(status(|| Ok(())), ErrorCode::Ok);
+        assert_eq!(last_error_code(), ErrorCode::Ok);
+        assert_eq!(status(|| Err(Error::null("key"))), ErrorCode::NullPointer);
+        assert_eq!(last_error_code(), ErrorCode::NullPointer);
+        assert_eq!(message(), "`key` is null");
+
+        // The success doesn't clear the last error.
+        assert_eq!(catch(-1, || Ok(7)), 7);
+        assert_eq!(last_error_code(), ErrorCode::NullPointer);
+        let err = || Err(Error::new(ErrorCode::InvalidUtf8, "bad\0bytes"));
+        assert_eq!(catch(-1, err), -1);
+        assert_eq!(message(), "bad");
+
+        last_error_clear();
+        assert_eq!(last_error_code(), ErrorCode::Ok);
+    }
+
+    #[test]
+    pub fn test_catch_panic() {
+        let code = status(|| {
+            let v: Vec<i32> = vec![];
+            let _ = v[1];
+            Ok(())
+        });
+        assert_eq!(code, ErrorCode::Panic);
+        assert!(message().starts_with("panic: index out of bounds"));
+
+        assert!(catch(ptr::null::<u8>(), || panic!("boom")).is_null());
+        assert_eq!(last_error_code(), ErrorCode::Panic);
+        assert_eq!(message(), "panic: boom");
+    }
+
+    #[test]
+    pub fn test_per_thread() {
+        status(|| Err(Error::null("map")));
+        std::thread::spawn(|| assert_eq!(last_error_code(), ErrorCode::Ok))
+            .join()
+            .unwrap();
+        assert_eq!(last_error_code(), ErrorCode::NullPointer);
+    }
+}
@@ -0,0 +1,410 @@
+// The C harness of the unit tests in lib.rs, which is compiled by build.rs. Every test returns
+// zero, or the line of the first failed check.
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "c.h"
+
+#define CHECK(cond)                                                              \
+    do {                                                                         \
+        if (!(cond)) {                                                           \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            return __LINE__;                                                     \
+        }                                                                        \
+    } while (0)
+
+#define BYTES(s) (const uint8_t *)(s), strlen(s)
+
+// The user data of the visitors, which counts the pairs and stops after `limit` of them.
+typedef struct Visits {
+    size_t count;
+    size_t limit;
+} Visits;
+
+static bool count_pairs(void *user_data, const uint8_t *key, size_t key_len, const uint8_t *val,
+                        size_t val_len) {
+    Visits *visits = user_data;
+    if ((key == NULL && key_len > 0) || (val == NULL && val_len > 0)) {
+        return false;
+    }
+    visits->count++;
+    return visits->count < visits->limit;
+}
+
+// The same test for all the maps, whose functions are named by the prefix. The ordered maps also
+// check that the keys are iterated in the ascending order.
+#define TEST_MAP(prefix, Map, ordered)                                                  \
+    static int test_##prefix(void) {                                                    \
+        char key[32], val[32];                                                          \
+        const uint8_t *k, *v;                                                           \
+        size_t k_len, v_len, n = 0;                                                     \
+        Map *map = prefix##_new();                                                      \
+        CHECK(map != NULL);                                                             \
+        CHECK(prefix##_len(map) == 0);                                                  \
+        CHECK(prefix##_get(map, BYTES("missing"), &v, &v_len) == ErrorCode_NotFound);   \
+                                                                                        \
+        for (int i = 0; i < 1000; i++) {                                                \
+            sprintf(key, "key-%04d", i);                                                \
+            sprintf(val, "val-%d", i);                                                  \
+            CHECK(prefix##_put(map, BYTES(key), BYTES(val)) == ErrorCode_Ok);           \
+        }                                                                               \
+        CHECK(prefix##_len(map) == 1000);                                               \
+        for (int i = 0; i < 1000; i++) {                                                \
+            sprintf(key, "key-%04d", i);                                                \
+            sprintf(val, "val-%d", i);                                                  \
+            CHECK(prefix##_get(map, BYTES(key), &v, &v_len) == ErrorCode_Ok);           \
+            CHECK(v_len == strlen(val) && memcmp(v, val, v_len) == 0);                  \
+        }                                                                               \
+                                                                                        \
+        /* Replace a value, then delete the odd keys. */                                \
+        CHECK(prefix##_put(map, BYTES("key-0000"), BYTES("new")) == ErrorCode_Ok);      \
+        CHECK(prefix##_len(map) == 1000);                                               \
+        CHECK(prefix##_get(map, BYTES("key-0000"), &v, &v_len) == ErrorCode_Ok);        \
+        CHECK(v_len == 3 && memcmp(v, "new", 3) == 0);                                  \
+        for (int i = 1; i < 1000; i += 2) {                                             \
+            sprintf(key, "key-%04d", i);                                                \
+            CHECK(prefix##_delete(map, BYTES(key)) == ErrorCode_Ok);                    \
+            CHECK(prefix##_delete(map, BYTES(key)) == ErrorCode_NotFound);              \
+            CHECK(prefix##_get(map, BYTES(key), &v, &v_len) == ErrorCode_NotFound);     \
+        }                                                                               \
+        CHECK(prefix##_len(map) == 500);                                                \
+                                                                                        \
+        /* The keys and the values are bytes, which may be empty or contain NUL. */     \
+        const uint8_t binary[] = {0, 1, 0, 2};                                          \
+        CHECK(prefix##_put(map, binary, 4, NULL, 0) == ErrorCode_Ok);                   \
+        CHECK(prefix##_get(map, binary, 4, &v, &v_len) == ErrorCode_Ok);                \
+        CHECK(v_len == 0);                                                              \
+        CHECK(prefix##_get(map, binary, 3, &v, &v_len) == ErrorCode_NotFound);          \
+        CHECK(prefix##_put(map, NULL, 0, binary, 4) == ErrorCode_Ok);                   \
+        CHECK(prefix##_get(map, NULL, 0, &v, &v_len) == ErrorCode_Ok);                  \
+        CHECK(v_len == 4 && memcmp(v, binary, 4) == 0);                                 \
+        CHECK(prefix##_len(map) == 502);                                                \
+                                                                                        \
+        /* The iterator is a snapshot, which outlives the changes of the map. */        \
+        MapIter *iter = prefix##_iter(map);                                             \
+        CHECK(iter != NULL);                                                            \
+        CHECK(prefix##_delete(map, binary, 4) == ErrorCode_Ok);                         \
+        const uint8_t *prev = NULL;                                                     \
+        size_t prev_len = 0;                                                            \
+        while (map_iter_next(iter, &k, &k_len, &v, &v_len)) {                           \
+            if ((ordered) && n > 0) {                                                   \
+                size_t len = prev_len < k_len ? prev_len : k_len;                       \
+                int cmp = len == 0 ? 0 : memcmp(prev, k, len);                          \
+                CHECK(cmp < 0 || (cmp == 0 && prev_len < k_len));                       \
+            }                                                                           \
+            prev = k;                                                                   \
+            prev_len = k_len;                                                           \
+            n++;                                                                        \
+        }                                                                               \
+        CHECK(n == 502);                                                                \
+        CHECK(!map_iter_next(iter, &k, &k_len, &v, &v_len));                            \
+        map_iter_free(iter);                                                            \
+        CHECK(prefix##_len(map) == 501);                                                \
+                                                                                        \
+        /* The visitor is called with every pair, until it returns false. */            \
+        Visits visits = {0, (size_t)-1};                                                \
+        CHECK(prefix##_for_each(map, count_pairs, &visits) == ErrorCode_Ok);            \
+        CHECK(visits.count == 501);                                                     \
+        Visits stop = {0, 10};                                                          \
+        CHECK(prefix##_for_each(map, count_pairs, &stop) == ErrorCode_Ok);              \
+        CHECK(stop.count == 10);                                                        \
+        CHECK(prefix##_for_each(map, NULL, &stop) == ErrorCode_NullPointer);            \
+                                                                                        \
+        /* The null pointers are rejected instead of crashing. */                       \
+        CHECK(prefix##_put(NULL, BYTES("k"), BYTES("v")) == ErrorCode_NullPointer);     \
+        CHECK(prefix##_put(map, NULL, 1, BYTES("v")) == ErrorCode_NullPointer);         \
+        CHECK(prefix##_get(map, BYTES("key-0000"), NULL, &v_len) == ErrorCode_NullPointer); \
+        CHECK(prefix##_delete(NULL, BYTES("k")) == ErrorCode_NullPointer);              \
+        last_error_clear();                                                             \
+        CHECK(prefix##_len(NULL) == 0);                                                 \
+        CHECK(last_error_code() == ErrorCode_NullPointer);                              \
+        CHECK(prefix##_iter(NULL) == NULL);                                             \
+        prefix##_free(map);                                                             \
+        prefix##_free(NULL);                                                            \
+        return 0;                                                                       \
+    }
+
+TEST_MAP(skiplist, ByteSkipList, 1)
+TEST_MAP(avl_tree, ByteAvlTree, 1)
+TEST_MAP(hash_map, ByteHashMap, 0)
+
+int harness_errors(void) {
+    const uint8_t *v;
+    size_t v_len;
+
+    last_error_clear();
+    CHECK(last_error_code() == ErrorCode_Ok);
+    CHECK(last_error_message() == NULL);
+
+    CHECK(c_str_len(NULL) == -1);
+    CHECK(last_error_code() == ErrorCode_NullPointer);
+    CHECK(strcmp(last_error_message(), "`str` is null") == 0);
+    CHECK(c_str_len("\xff\xfe") == -1);
+    CHECK(last_error_code() == ErrorCode_InvalidUtf8);
+    CHECK(strstr(last_error_message(), "utf-8") != NULL);
+
+    // The string slices honour their lengths, instead of looking for NUL.
+    const char unterminated[3] = {'a', 'b', 'c'};
+    CString slices[] = {{"hello world", 5}, {unterminated, 3}, {"a\0b", 3}, {NULL, 0}};
+    CHECK(cstring_len(&slices[0]) == 5);
+    CHECK(cstring_len(&slices[1]) == 3);
+    CHECK(cstring_len(&slices[2]) == 3);
+    CHECK(cstring_len(&slices[3]) == 0);
+    CString null_str = {NULL, 2}, invalid = {"ok\xff", 3}, huge = {"x", 0x80000000u};
+    CHECK(cstring_len(&null_str) == -1);
+    CHECK(last_error_code() == ErrorCode_NullPointer);
+    CHECK(cstring_len(&invalid) == -1);
+    CHECK(last_error_code() == ErrorCode_InvalidUtf8);
+    CHECK(cstring_len(&huge) == -1);
+    CHECK(last_error_code() == ErrorCode_InvalidArgument);
+    CHECK(cstring_len(NULL) == -1);
+    CHECK(strcmp(last_error_message(), "`cstr` is null") == 0);
+
+    // The failures of the maps keep their messages, and the successes don't clear them.
+    ByteHashMap *map = hash_map_new();
+    CHECK(hash_map_get(map, BYTES("missing"), &v, &v_len) == ErrorCode_NotFound);
+    CHECK(strcmp(last_error_message(), "key not found: missing") == 0);
+    CHECK(hash_map_put(map, BYTES("k"), BYTES("v")) == ErrorCode_Ok);
+    CHECK(last_error_code() == ErrorCode_NotFound);
+    CHECK(hash_map_put(NULL, BYTES("k"), BYTES("v")) == ErrorCode_NullPointer);
+    CHECK(strcmp(last_error_message(), "`map` is null") == 0);
+    CHECK(hash_map_get(map, NULL, 1, &v, &v_len) == ErrorCode_NullPointer);
+    CHECK(strcmp(last_error_message(), "`key` is null") == 0);
+    hash_map_free(map);
+
+    last_error_clear();
+    CHECK(last_error_code() == ErrorCode_Ok);
+    CHECK(last_error_message() == NULL);
+    return 0;
+}
+
+int harness_map(void) {
+    int line;
+    if ((line = test_skiplist()) != 0) return line;
+    if ((line = test_avl_tree()) != 0) return line;
+    if ((line = test_hash_map()) != 0) return line;
+    map_iter_free(NULL);
+    return 0;
+}
+
+// The element of an odd size, which is sorted by the key, and the sequence number checks that the
+// stable sorts keep the order of the equal keys.
+typedef struct Record {
+    int key;
+    int seq;
+    char name[12];
+} Record;
+
+static int cmp_record(const void *a, const void *b) {
+    const Record *x = a, *y = b;
+    return (x->key > y->key) - (x->key < y->key);
+}
+
+static int cmp_int_desc(const void *a, const void *b) {
+    int x = *(const int *)a, y = *(const int *)b;
+    return (y > x) - (y < x);
+}
+
+typedef enum ErrorCode (*SortFn)(void *, size_t, size_t, Comparator);
+
+static int test_sort(SortFn sort, bool stable) {
+    enum { N = 500 };
+    Record records[N], expected[N];
+    for (int i = 0; i < N; i++) {
+        records[i].key = (i * 7919) % 37;
+        records[i].seq = i;
+        snprintf(records[i].name, sizeof(records[i].name), "r%d", i % 1000);
+    }
+    memcpy(expected, records, sizeof(records));
+    qsort(expected, N, sizeof(Record), cmp_record);
+
+    CHECK(sort(records, N, sizeof(Record), cmp_record) == ErrorCode_Ok);
+    for (int i = 0; i < N; i++) {
+        CHECK(records[i].key == expected[i].key);
+        char name[12];
+        snprintf(name, sizeof(name), "r%d", records[i].seq % 1000);
+        CHECK(strcmp(records[i].name, name) == 0);
+        if (stable && i > 0 && records[i - 1].key == records[i].key) {
+            CHECK(records[i - 1].seq < records[i].seq);
+        }
+    }
+
+    int ints[] = {3, -1, 4, 1, -5, 9, 2, 6};
+    CHECK(sort(ints, 8, sizeof(int), cmp_int_desc) == ErrorCode_Ok);
+    for (int i = 1; i < 8; i++) {
+        CHECK(ints[i - 1] >= ints[i]);
+    }
+
+    CHECK(sort(NULL, 0, sizeof(int), cmp_int_desc) == ErrorCode_Ok);
+    CHECK(sort(ints, 8, sizeof(int), NULL) == ErrorCode_NullPointer);
+    CHECK(sort(NULL, 8, sizeof(int), cmp_int_desc) == ErrorCode_NullPointer);
+    CHECK(sort(ints, 8, 0, cmp_int_desc) == ErrorCode_InvalidArgument);
+    CHECK(sort(ints, SIZE_MAX, 2, cmp_int_desc) == ErrorCode_InvalidArgument);
+    return 0;
+}
+
+int harness_sort(void) {
+    int ret;
+    if ((ret = test_sort(sort_quick, false)) != 0 || (ret = test_sort(sort_heap, false)) != 0 ||
+        (ret = test_sort(sort_merge, true)) != 0 || (ret = test_sort(sort_insertion, true)) != 0 ||
+        (ret = test_sort(sort_selection, false)) != 0) {
+        return ret;
+    }
+    return 0;
+}
+
+int harness_search(void) {
+    size_t offsets[8], count;
+
+    // KMP finds the overlapped occurrences, and counts all of them even if the buffer is small.
+    CHECK(kmp_search(BYTES("abababa"), BYTES("aba"), offsets, 8, &count) == ErrorCode_Ok);
+    CHECK(count == 3 && offsets[0] == 0 && offsets[1] == 2 && offsets[2] == 4);
+    offsets[1] = 42;
+    CHECK(kmp_search(BYTES("abababa"), BYTES("aba"), offsets, 1, &count) == ErrorCode_Ok);
+    CHECK(count == 3 && offsets[0] == 0 && offsets[1] == 42);
+    CHECK(kmp_search(BYTES("abababa"), BYTES("aba"), NULL, 0, &count) == ErrorCode_Ok);
+    CHECK(count == 3);
+    const uint8_t binary[] = {'x', 0, 'y', 0, 'y', 0};
+    const uint8_t needle[] = {0, 'y'};
+    CHECK(kmp_search(binary, 6, needle, 2, offsets, 8, &count) == ErrorCode_Ok);
+    CHECK(count == 2 && offsets[0] == 1 && offsets[1] == 3);
+    CHECK(kmp_search(NULL, 0, BYTES("a"), offsets, 8, &count) == ErrorCode_Ok);
+    CHECK(count == 0);
+    CHECK(kmp_search(BYTES("abc"), NULL, 0, offsets, 8, &count) == ErrorCode_InvalidArgument);
+    CHECK(kmp_search(BYTES("abc"), BYTES("a"), NULL, 8, &count) == ErrorCode_NullPointer);
+    CHECK(kmp_search(BYTES("abc"), BYTES("a"), offsets, 8, NULL) == ErrorCode_NullPointer);
+
+    // Aho-Corasick finds the patterns which end at the same place, the longer ones first.
+    const uint8_t *patterns[] = {(const uint8_t *)"he", (const uint8_t *)"she",
+                                 (const uint8_t *)"his", (const uint8_t *)"hers"};
+    const size_t lens[] = {2, 3, 3, 4};
+    AhoCorasick *ac = aho_corasick_new(patterns, lens, 4);
+    CHECK(ac != NULL);
+    CHECK(aho_corasick_pattern_count(ac) == 4);
+    SearchMatch matches[8];
+    CHECK(aho_corasick_search(ac, BYTES("ushers"), matches, 8, &count) == ErrorCode_Ok);
+    CHECK(count == 3);
+    CHECK(matches[0].pattern == 1 && matches[0].offset == 1 && matches[0].len == 3);
+    CHECK(matches[1].pattern == 0 && matches[1].offset == 2 && matches[1].len == 2);
+    CHECK(matches[2].pattern == 3 && matches[2].offset == 2 && matches[2].len == 4);
+    CHECK(aho_corasick_search(ac, BYTES("ushers"), matches, 2, &count) == ErrorCode_Ok);
+    CHECK(count == 3);
+    CHECK(aho_corasick_search(ac, BYTES("nothing"), matches, 8, &count) == ErrorCode_Ok);
+    CHECK(count == 0);
+    CHECK(aho_corasick_search(NULL, BYTES("he"), matches, 8, &count) == ErrorCode_NullPointer);
+    aho_corasick_free(ac);
+    aho_corasick_free(NULL);
+
+    const size_t bad_lens[] = {2, 0};
+    CHECK(aho_corasick_new(patterns, bad_lens, 2) == NULL);
+    CHECK(last_error_code() == ErrorCode_InvalidArgument);
+    CHECK(strcmp(last_error_message(), "the pattern 1 is empty") == 0);
+    CHECK(aho_corasick_new(NULL, lens, 4) == NULL);
+    CHECK(last_error_code() == ErrorCode_NullPointer);
+    ac = aho_corasick_new(NULL, NULL, 0);
+    CHECK(ac != NULL && aho_corasick_pattern_count(ac) == 0);
+    aho_corasick_free(ac);
+    last_error_clear();
+    CHECK(aho_corasick_pattern_count(NULL) == 0);
+    CHECK(last_error_code() == ErrorCode_NullPointer);
+    return 0;
+}
+
+// The user data of the callbacks of the jobs, which records the transitions it sees.
+typedef struct Recorder {
+    size_t count;
+    enum State from[8];
+    enum State to[8];
+    bool consistent;
+} Recorder;
+
+static void record(void *user_data, Job *job, enum State from, enum State to) {
+    Recorder *recorder = user_data;
+    if (recorder->count < 8) {
+        recorder->from[recorder->count] = from;
+        recorder->to[recorder->count] = to;
+    }
+    recorder->count++;
+    recorder->consistent = recorder->consistent && job_state(job) == (int)to;
+}
+
+// Start the job as soon as it's initialized, which transitions it again in the callback.
+static void auto_start(void *user_data, Job *job, enum State from, enum State to) {
+    (void)user_data;
+    (void)from;
+    if (to == Init) {
+        job_transition(job, Running);
+    }
+}
+
+int harness_job(void) {
+    Transition history[8];
+    size_t count;
+
+    Job *job = job_new();
+    CHECK(job != NULL);
+    CHECK(job_state(job) == New);
+    CHECK(job_history(job, history, 8, &count) == ErrorCode_Ok && count == 0);
+    Recorder recorder = {0, {New}, {New}, true};
+    CHECK(job_on_transition(job, record, &recorder) == ErrorCode_Ok);
+
+    // The illegal transitions are rejected without any change.
+    CHECK(job_transition(job, Running) == ErrorCode_InvalidTransition);
+    CHECK(strcmp(last_error_message(), "illegal transition from New to Running") == 0);
+    CHECK(job_transition(job, New) == ErrorCode_InvalidTransition);
+    CHECK(job_transition(job, 4) == ErrorCode_InvalidArgument);
+    CHECK(strcmp(last_error_message(), "invalid state 4") == 0);
+    CHECK(job_transition(job, -1) == ErrorCode_InvalidArgument);
+    CHECK(job_state(job) == New && recorder.count == 0);
+
+    CHECK(job_transition(job, Init) == ErrorCode_Ok);
+    CHECK(job_transition(job, Running) == ErrorCode_Ok);
+    CHECK(job_transition(job, Init) == ErrorCode_InvalidTransition);
+    CHECK(job_transition(job, Running) == ErrorCode_InvalidTransition);
+    CHECK(job_transition(job, Done) == ErrorCode_Ok);
+    CHECK(job_transition(job, Done) == ErrorCode_InvalidTransition);
+    CHECK(strcmp(last_error_message(), "illegal transition from Done to Done") == 0);
+    CHECK(job_state(job) == Done);
+
+    CHECK(recorder.count == 3 && recorder.consistent);
+    CHECK(recorder.from[0] == New && recorder.to[0] == Init);
+    CHECK(recorder.from[1] == Init && recorder.to[1] == Running);
+    CHECK(recorder.from[2] == Running && recorder.to[2] == Done);
+
+    CHECK(job_history(job, history, 8, &count) == ErrorCode_Ok && count == 3);
+    CHECK(history[0].from == New && history[0].to == Init);
+    CHECK(history[1].from == Init && history[1].to == Running);
+    CHECK(history[2].from == Running && history[2].to == Done);
+    CHECK(history[0].micros <= history[1].micros && history[1].micros <= history[2].micros);
+    CHECK(job_history(job, history, 1, &count) == ErrorCode_Ok && count == 3);
+    CHECK(job_history(job, NULL, 0, &count) == ErrorCode_Ok && count == 3);
+    job_free(job);
+
+    // The callbacks can transition the job again, and the job can finish early.
+    job = job_new();
+    Recorder nested = {0, {New}, {New}, true};
+    CHECK(job_on_transition(job, auto_start, NULL) == ErrorCode_Ok);
+    CHECK(job_on_transition(job, record, &nested) == ErrorCode_Ok);
+    CHECK(job_transition(job, Init) == ErrorCode_Ok);
+    CHECK(job_state(job) == Running);
+    CHECK(nested.count == 2 && !nested.consistent);
+    CHECK(job_history(job, history, 8, &count) == ErrorCode_Ok && count == 2);
+    CHECK(history[0].to == Init && history[1].to == Running);
+    job_free(job);
+
+    job = job_new();
+    CHECK(job_transition(job, Done) == ErrorCode_Ok);
+    CHECK(job_on_transition(job, NULL, NULL) == ErrorCode_NullPointer);
+    job_free(job);
+
+    CHECK(job_transition(NULL, Init) == ErrorCode_NullPointer);
+    CHECK(job_state(NULL) == STATE_INVALID && last_error_code() == ErrorCode_NullPointer);
+    CHECK(job_history(NULL, history, 8, &count) == ErrorCode_NullPointer);
+    job_free(NULL);
+
+    CHECK(strcmp(state_name(New), "New") == 0 && strcmp(state_name(Running), "Running") == 0);
+    CHECK(state_name(STATE_INVALID) == NULL && last_error_code() == ErrorCode_InvalidArgument);
+    return 0;
+}
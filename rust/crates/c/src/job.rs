@@ -0,0 +1,268 @@
+// The lifecycle of a job exposed to C, which moves through the states of `State`:
+//
+//   New -> Init -> Running -> Done
+//
+// A job only moves to the next state, or to `Done` from any state before it, which finishes the
+// job early, e.g. when it's cancelled. All the other transitions, including the ones to the same
+// state, are rejected as `ErrorCode_InvalidTransition` and leave the job unchanged.
+//
+// The states are passed from C as the values of `State`, from 0 to 3, and the other values are
+// rejected as `ErrorCode_InvalidArgument`. Note they aren't the values of `state_to_i32`, which maps
+// `Done` to 4.
+//
+// The callbacks are called after every transition in the order they're registered, and the job is
+// already in the new state. They may query the job, register callbacks and transition it again,
+// but not free it. The job isn't thread-safe, so it must only be used by one thread at a time.
+
+use std::ffi::{c_char, c_int, c_void};
+use std::ptr;
+use std::time::Instant;
+
+use crate::error::{catch, status, Error, ErrorCode};
+use crate::{write_out, State};
+
+/// The value returned by `job_state` on the error, which is none of the states.
+pub const STATE_INVALID: c_int = -1;
+
+pub struct Job {
+    created: Instant,
+    state: State,
+    history: Vec<Transition>,
+    // The callbacks are never None, which is rejected by `job_on_transition`.
+    callbacks: Vec<(JobCallback, *mut c_void)>,
+}
+
+/// The transition of a job, which happened `micros` microseconds after the job was created.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub from: State,
+    pub to: State,
+    pub micros: u64,
+}
+
+/// The callback of the transitions, which is called with the user data, the job and the transition.
+pub type JobCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, job: *mut Job, from: State, to: State)>;
+
+fn can_transition(from: State, to: State) -> bool {
+    matches!(
+        (from, to),
+        (State::New, State::Init)
+            | (State::Init, State::Running)
+            | (State::New | State::Init | State::Running, State::Done)
+    )
+}
+
+// The state of the value from C, which must be one of the discriminants of `State`.
+fn to_state(state: c_int) -> Result<State, Error> {
+    [State::New, State::Init, State::Running, State::Done]
+        .into_iter()
+        .find(|&s| s as c_int == state)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorCode::InvalidArgument,
+                format!("invalid state {}", state),
+            )
+        })
+}
+
+fn name(state: State) -> &'static str {
+    match state {
+        State::New => "New",
+        State::Init => "Init",
+        State::Running => "Running",
+        State::Done => "Done",
+    }
+}
+
+//
+// C API: 'state_name'
+//
+
+/// The name of the state, which is a static NUL-terminated string, or NULL if it's not a state.
+#[no_mangle]
+pub extern "C" fn state_name(state: c_int) -> *const c_char {
+    catch(ptr::null(), || {
+        let name: &'static [u8] = match to_state(state)? {
+            State::New => b"New\0",
+            State::Init => b"Init\0",
+            State::Running => b"Running\0",
+            State::Done => b"Done\0",
+        };
+        Ok(name.as_ptr() as *const c_char)
+    })
+}
+
+//
+// C API: 'job_*'
+//
+
+/// Create a job in the state `New`, which must be freed by `job_free`.
+#[no_mangle]
+pub extern "C" fn job_new() -> *mut Job {
+    Box::into_raw(Box::new(Job {
+        created: Instant::now(),
+        state: State::New,
+        history: Vec::new(),
+        callbacks: Vec::new(),
+    }))
+}
+
+/// Free the job and its history.
+///
+/// # Safety
+///
+/// The job must be NULL or come from `job_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed by its callbacks.
+#[no_mangle]
+pub unsafe extern "C" fn job_free(job: *mut Job) {
+    if !job.is_null() {
+        drop(Box::from_raw(job));
+    }
+}
+
+/// The state of the job, or `STATE_INVALID` if the job is NULL.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`.
+#[no_mangle]
+pub unsafe extern "C" fn job_state(job: *const Job) -> c_int {
+    catch(STATE_INVALID, || {
+        let job = job.as_ref().ok_or_else(|| Error::null("job"))?;
+        Ok(job.state as c_int)
+    })
+}
+
+/// Move the job to the state `to` and call the callbacks, or fail without any change if the
+/// transition is illegal.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`, and the callbacks must be safe to call
+/// with their user data. They must not free the job.
+#[no_mangle]
+pub unsafe extern "C" fn job_transition(job: *mut Job, to: c_int) -> ErrorCode {
+    status(|| {
+        let to = to_state(to)?;
+        // The callbacks may use the job again, so it's only borrowed until they're called.
+        let (from, callbacks) = {
+            let job = job.as_mut().ok_or_else(|| Error::null("job"))?;
+            let from = job.state;
+            if !can_transition(from, to) {
+                return Err(Error::new(
+                    ErrorCode::InvalidTransition,
+                    format!("illegal transition from {} to {}", name(from), name(to)),
+                ));
+            }
+            job.state = to;
+            job.history.push(Transition {
+                from,
+                to,
+                micros: job.created.elapsed().as_micros() as u64,
+            });
+            (from, job.callbacks.clone())
+        };
+        for (callback, user_data) in callbacks {
+            if let Some(callback) = callback {
+                callback(user_data, job, from, to);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Register the callback, which is called with `user_data` after every later transition of the
+/// job until it's freed.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`. The `user_data` is only passed to the
+/// callback, so it must be valid for the callback for as long as the job lives.
+#[no_mangle]
+pub unsafe extern "C" fn job_on_transition(
+    job: *mut Job,
+    callback: JobCallback,
+    user_data: *mut c_void,
+) -> ErrorCode {
+    status(|| {
+        let job = job.as_mut().ok_or_else(|| Error::null("job"))?;
+        if callback.is_none() {
+            return Err(Error::null("callback"));
+        }
+        job.callbacks.push((callback, user_data));
+        Ok(())
+    })
+}
+
+/// Copy the first `cap` transitions of the job in their order into `history`, and set `count` to
+/// the number of all of them.
+///
+/// # Safety
+///
+/// The job must be NULL or a live handle from `job_new`. The `history` must be NULL or point to
+/// `cap` writable transitions, and `count` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn job_history(
+    job: *const Job,
+    history: *mut Transition,
+    cap: usize,
+    count: *mut usize,
+) -> ErrorCode {
+    status(|| {
+        let job = job.as_ref().ok_or_else(|| Error::null("job"))?;
+        write_out(job.history.iter().copied(), history, cap, count)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_can_transition() {
+        let states = [State::New, State::Init, State::Running, State::Done];
+        let legal: Vec<(State, State)> = states
+            .iter()
+            .flat_map(|&from| states.iter().map(move |&to| (from, to)))
+            .filter(|&(from, to)| can_transition(from, to))
+            .collect();
+        assert_eq!(
+            legal,
+            vec![
+                (State::New, State::Init),
+                (State::New, State::Done),
+                (State::Init, State::Running),
+                (State::Init, State::Done),
+                (State::Running, State::Done),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_state_name() {
+        for state in [State::New, State::Init, State::Running, State::Done] {
+            let s = unsafe { std::ffi::CStr::from_ptr(state_name(state as c_int)) };
+            assert_eq!(s.to_str().unwrap(), name(state));
+        }
+        for state in [-1, 4, c_int::MAX] {
+            assert!(state_name(state).is_null());
+            assert_eq!(crate::error::last_error_code(), ErrorCode::InvalidArgument);
+        }
+    }
+
+    #[test]
+    pub fn test_invalid_state() {
+        unsafe {
+            let job = job_new();
+            assert_eq!(job_transition(job, 4), ErrorCode::InvalidArgument);
+            assert_eq!(job_transition(job, -1), ErrorCode::InvalidArgument);
+            assert_eq!(job_state(job), State::New as c_int);
+            assert_eq!(job_transition(job, State::Init as c_int), ErrorCode::Ok);
+            assert_eq!(job_state(job), State::Init as c_int);
+            job_free(job);
+            assert_eq!(job_state(ptr::null()), STATE_INVALID);
+        }
+    }
+}
@@ -1,6 +1,16 @@
 // For more details, please see: https://www.greyblake.com/blog/exposing-rust-library-to-c/
 
 use std::ffi::{c_char, CStr};
+use std::slice;
+use std::str::{self, Utf8Error};
+
+use crate::error::{catch, Error, ErrorCode};
+
+pub mod error;
+pub mod job;
+pub mod map;
+pub mod search;
+pub mod sort;
 
 //
 // C API: 'print_hello_from_rust'
@@ -16,6 +26,7 @@ pub extern "C" fn print_hello_from_rust() {
 //
 
 #[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum State {
     New,
     Init,
@@ -37,35 +48,151 @@ pub extern "C" fn state_to_i32(state: State) -> i32 {
 // C API: 'c_str_len'
 //
 
+/// The length in bytes of the NUL-terminated UTF-8 string, or -1 on the error.
+///
+/// # Safety
+///
+/// The `str` must be NULL or point to a NUL-terminated string, which is readable up to its NUL.
 #[no_mangle]
-pub extern "C" fn c_str_len(str: *const c_char) -> i32 {
-    if str.is_null() {
-        return -1;
-    }
-
-    let cstr = unsafe { CStr::from_ptr(str) };
-    match cstr.to_str() {
-        Ok(s) => s.len() as i32,
-        Err(_) => -1,
-    }
+pub unsafe extern "C" fn c_str_len(str: *const c_char) -> i32 {
+    catch(-1, || {
+        if str.is_null() {
+            return Err(Error::null("str"));
+        }
+        let s = CStr::from_ptr(str).to_str().map_err(invalid_utf8)?;
+        to_i32(s.len())
+    })
 }
 
 //
 // C API: 'cstring_len'
 //
 
+/// The string slice of `len` bytes, which needn't be NUL-terminated and may contain NUL. The `str`
+/// may be NULL only if `len` is zero.
 #[repr(C)]
 pub struct CString {
     str: *const c_char,
     len: u32,
 }
 
+impl CString {
+    /// The UTF-8 string of the first `len` bytes.
+    pub(crate) unsafe fn as_str(&self) -> Result<&str, Error> {
+        if self.len == 0 {
+            return Ok("");
+        }
+        if self.str.is_null() {
+            return Err(Error::null("str"));
+        }
+        let bytes = slice::from_raw_parts(self.str as *const u8, self.len as usize);
+        str::from_utf8(bytes).map_err(invalid_utf8)
+    }
+}
+
+/// The length in bytes of the UTF-8 string slice, which is its `len`, or -1 on the error.
+///
+/// # Safety
+///
+/// The `cstr` must be NULL or point to a readable `CString`, whose `str` points to `len` readable
+/// bytes unless `len` is zero.
 #[no_mangle]
-pub extern "C" fn cstring_len(cstr: *const CString) -> i32 {
-    if cstr.is_null() {
-        return -1;
+pub unsafe extern "C" fn cstring_len(cstr: *const CString) -> i32 {
+    catch(-1, || {
+        let cstr = cstr.as_ref().ok_or_else(|| Error::null("cstr"))?;
+        // Check the length before reading the bytes, which may not be that many.
+        let len = to_i32(cstr.len as usize)?;
+        cstr.as_str()?;
+        Ok(len)
+    })
+}
+
+/// The bytes at `p`, which may be null only if `len` is zero. The `arg` names it in the error.
+pub(crate) unsafe fn bytes<'a>(p: *const u8, len: usize, arg: &str) -> Result<&'a [u8], Error> {
+    if len == 0 {
+        Ok(&[])
+    } else if p.is_null() {
+        Err(Error::null(arg))
+    } else {
+        Ok(slice::from_raw_parts(p, len))
+    }
+}
+
+/// Write the first `cap` items into `out`, and the number of all the items into `count`, like
+/// `snprintf`. The `out` may be null only if `cap` is zero.
+pub(crate) unsafe fn write_out<T>(
+    items: impl IntoIterator<Item = T>,
+    out: *mut T,
+    cap: usize,
+    count: *mut usize,
+) -> Result<(), Error> {
+    if count.is_null() {
+        return Err(Error::null("count"));
+    }
+    if out.is_null() && cap > 0 {
+        return Err(Error::null("out"));
     }
+    let mut n = 0;
+    for item in items {
+        if n < cap {
+            out.add(n).write(item);
+        }
+        n += 1;
+    }
+    *count = n;
+    Ok(())
+}
 
-    unsafe { c_str_len((*cstr).str) }
+fn invalid_utf8(err: Utf8Error) -> Error {
+    Error::new(ErrorCode::InvalidUtf8, err.to_string())
 }
 
+fn to_i32(len: usize) -> Result<i32, Error> {
+    i32::try_from(len).map_err(|_| {
+        Error::new(
+            ErrorCode::InvalidArgument,
+            format!("the length {} overflows i32", len),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::c_int;
+
+    // The C harness compiled by `build.rs`, which calls the C APIs as a C program does, and returns
+    // zero, or the line of the first failed check.
+    #[link(name = "c_harness", kind = "static")]
+    extern "C" {
+        fn harness_map() -> c_int;
+        fn harness_errors() -> c_int;
+        fn harness_sort() -> c_int;
+        fn harness_search() -> c_int;
+        fn harness_job() -> c_int;
+    }
+
+    #[test]
+    pub fn c_harness_map() {
+        assert_eq!(unsafe { harness_map() }, 0);
+    }
+
+    #[test]
+    pub fn c_harness_errors() {
+        assert_eq!(unsafe { harness_errors() }, 0);
+    }
+
+    #[test]
+    pub fn c_harness_sort() {
+        assert_eq!(unsafe { harness_sort() }, 0);
+    }
+
+    #[test]
+    pub fn c_harness_search() {
+        assert_eq!(unsafe { harness_search() }, 0);
+    }
+
+    #[test]
+    pub fn c_harness_job() {
+        assert_eq!(unsafe { harness_job() }, 0);
+    }
+}
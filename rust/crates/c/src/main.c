@@ -3,6 +3,10 @@
 #include "string.h"
 #include "assert.h"
 
+static int cmp_int(const void *a, const void *b) {
+    return *(const int *)a - *(const int *)b;
+}
+
 int main(){
     print_hello_from_rust();
 
@@ -22,5 +26,34 @@ int main(){
     assert(-1 == cstring_len(NULL));
     printf("%d\n", cstring_len(&cstr));
 
+    // Test the skiplist
+    ByteSkipList *list = skiplist_new();
+    assert(skiplist_put(list, (const uint8_t *)"k", 1, (const uint8_t *)"v", 1) == ErrorCode_Ok);
+    const uint8_t *val;
+    size_t val_len;
+    assert(skiplist_get(list, (const uint8_t *)"k", 1, &val, &val_len) == ErrorCode_Ok);
+    assert(val_len == 1 && val[0] == 'v');
+    assert(skiplist_delete(list, (const uint8_t *)"k", 1) == ErrorCode_Ok);
+    assert(skiplist_delete(list, (const uint8_t *)"k", 1) == ErrorCode_NotFound);
+    printf("%s\n", last_error_message());
+    assert(skiplist_len(list) == 0);
+    skiplist_free(list);
+
+    // Test the sort and the search
+    int ints[] = {3, 1, 2};
+    assert(sort_merge(ints, 3, sizeof(int), cmp_int) == ErrorCode_Ok);
+    assert(ints[0] == 1 && ints[1] == 2 && ints[2] == 3);
+    size_t offsets[4], count;
+    assert(kmp_search((const uint8_t *)str, 11, (const uint8_t *)"o", 1, offsets, 4, &count) == ErrorCode_Ok);
+    assert(count == 2 && offsets[0] == 4 && offsets[1] == 7);
+
+    // Test the job
+    Job *job = job_new();
+    assert(job_transition(job, Init) == ErrorCode_Ok);
+    assert(job_transition(job, Done) == ErrorCode_Ok);
+    assert(job_transition(job, Running) == ErrorCode_InvalidTransition);
+    printf("%s: %s\n", state_name(job_state(job)), last_error_message());
+    job_free(job);
+
     return 0;
 }
\ No newline at end of file
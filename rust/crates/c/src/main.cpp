@@ -0,0 +1,154 @@
+// The test of the C++ wrappers in c.hpp, which is built by the Makefile against the static library.
+
+#include <cassert>
+#include <cstdio>
+#include <string>
+#include <type_traits>
+#include <vector>
+
+#include "c.hpp"
+
+using c::State;
+
+static_assert(!std::is_copy_constructible_v<c::SkipList>, "the handles are move-only");
+static_assert(!std::is_copy_assignable_v<c::Job>, "the handles are move-only");
+static_assert(std::is_nothrow_move_constructible_v<c::AhoCorasick>, "the handles are movable");
+
+template <typename E, typename F>
+static bool throws(F &&f) {
+  try {
+    f();
+  } catch (const E &) {
+    return true;
+  }
+  return false;
+}
+
+template <typename Map>
+static void test_map(bool ordered) {
+  Map map;
+  assert(map.size() == 0);
+  assert(!map.get("missing"));
+
+  for (int i = 0; i < 100; i++) {
+    map.put("key-" + std::to_string(1000 + i), "val-" + std::to_string(i));
+  }
+  map.put(std::string("\0k", 2), std::string("\0v", 2));
+  assert(map.size() == 101);
+  assert(map.get("key-1042") == "val-42");
+  assert(map.get(std::string("\0k", 2)) == std::string("\0v", 2));
+  assert(map.erase("key-1042"));
+  assert(!map.erase("key-1042"));
+
+  // The iterator and the visitor see the same pairs, in the ascending order for the ordered maps.
+  std::vector<std::string> keys;
+  c::MapIter iter = map.iter();
+  while (auto pair = iter.next()) {
+    keys.emplace_back(pair->first);
+  }
+  assert(keys.size() == 100);
+  if (ordered) {
+    for (size_t i = 1; i < keys.size(); i++) {
+      assert(keys[i - 1] < keys[i]);
+    }
+  }
+  size_t visited = 0;
+  map.for_each([&](std::string_view key, std::string_view) { assert(key == keys[visited++]); });
+  assert(visited == 100);
+  visited = 0;
+  map.for_each([&](std::string_view, std::string_view) { return ++visited < 10; });
+  assert(visited == 10);
+
+  // The exception of the visitor stops the iteration and comes out of `for_each`.
+  visited = 0;
+  assert(throws<std::runtime_error>([&] {
+    map.for_each([&](std::string_view, std::string_view) {
+      if (++visited == 3) {
+        throw std::runtime_error("stop");
+      }
+    });
+  }));
+  assert(visited == 3);
+
+  // The moved-from map is empty, and its calls throw.
+  Map moved = std::move(map);
+  assert(moved.size() == 100 && !map);
+  assert(throws<c::NullPointerError>([&] { map.put("k", "v"); }));
+  assert(throws<c::NullPointerError>([&] { map.size(); }));
+  map = std::move(moved);
+  assert(map.size() == 100 && !moved);
+}
+
+static void test_search() {
+  assert((c::kmp_search("abababa", "aba") == std::vector<size_t>{0, 2, 4}));
+  assert(c::kmp_search("abc", "d").empty());
+  assert(throws<c::InvalidArgumentError>([] { c::kmp_search("abc", ""); }));
+
+  c::AhoCorasick ac({"he", "she", "his", "hers"});
+  assert(ac.pattern_count() == 4);
+  std::vector<c::SearchMatch> matches = ac.search("ushers");
+  assert(matches.size() == 3);
+  assert(matches[0].pattern == 1 && matches[0].offset == 1 && matches[0].len == 3);
+  assert(matches[1].pattern == 0 && matches[1].offset == 2);
+  assert(matches[2].pattern == 3 && matches[2].offset == 2);
+
+  try {
+    c::AhoCorasick empty({"ok", ""});
+    assert(false);
+  } catch (const c::InvalidArgumentError &e) {
+    assert(e.code() == c::ErrorCode::ErrorCode_InvalidArgument);
+    assert(std::string(e.what()) == "the pattern 1 is empty");
+  }
+}
+
+static void test_job() {
+  c::Job job;
+  std::vector<std::string> seen;
+  job.on_transition([&](State from, State to) {
+    seen.push_back(std::string(c::state_name(from)) + "->" + c::state_name(to));
+  });
+  assert(job.state() == State::New);
+
+  try {
+    job.transition(State::Running);
+    assert(false);
+  } catch (const c::InvalidTransitionError &e) {
+    assert(std::string(e.what()) == "illegal transition from New to Running");
+  }
+  assert(throws<c::InvalidArgumentError>([&] { job.transition(static_cast<State>(4)); }));
+  assert(throws<c::InvalidArgumentError>([&] { c::state_name(static_cast<State>(-1)); }));
+  job.transition(State::Init);
+
+  // The job keeps its callbacks when it's moved.
+  c::Job moved = std::move(job);
+  assert(throws<c::NullPointerError>([&] { job.state(); }));
+  assert(throws<c::NullPointerError>([&] { job.transition(State::Done); }));
+  moved.transition(State::Running);
+  moved.transition(State::Done);
+  assert((seen == std::vector<std::string>{"New->Init", "Init->Running", "Running->Done"}));
+
+  std::vector<c::Transition> history = moved.history();
+  assert(history.size() == 3);
+  assert(history[0].from == State::New && history[2].to == State::Done);
+
+  // The exception of a callback comes out of `transition`, after the job has moved.
+  c::Job failing;
+  failing.on_transition([](State, State to) {
+    if (to == State::Init) {
+      throw std::logic_error("not ready");
+    }
+  });
+  assert(throws<std::logic_error>([&] { failing.transition(State::Init); }));
+  assert(failing.state() == State::Init);
+  failing.transition(State::Running);
+}
+
+int main() {
+  test_map<c::SkipList>(true);
+  test_map<c::AvlTree>(true);
+  test_map<c::HashMap>(false);
+  test_search();
+  test_job();
+  std::printf("C++ wrappers: ok\n");
+  return 0;
+}
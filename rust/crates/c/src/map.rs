@@ -0,0 +1,546 @@
+// The byte-string maps of the `algorithm` crate exposed to C by the opaque handles: the skip list,
+// the AVL tree and the hash map.
+//
+// The keys and the values are (pointer, length) pairs, which may contain any bytes including NUL,
+// and the pointer may be null only if the length is zero. They're copied into the map by `put`.
+//
+// The value returned by `get` points into the map, and it's only valid until the map is changed
+// or freed. The iterator owns a snapshot of the pairs, so the map can be changed while iterating.
+//
+// All the handles must come from the `*_new` functions of the same kind, and be freed by the
+// `*_free` functions exactly once.
+
+use std::ffi::c_void;
+use std::ptr;
+
+use algorithm::basic::avl_tree::AVLTree;
+use algorithm::basic::hash_table_v2::HashMap;
+use algorithm::basic::map::Map;
+use algorithm::basic::skiplist_unsafe::SkipList;
+
+use crate::bytes;
+use crate::error::{catch, status, Error, ErrorCode};
+
+type Bytes = Vec<u8>;
+
+pub struct ByteSkipList(SkipList<Bytes, Bytes>);
+
+pub struct ByteAvlTree(AVLTree<Bytes, Bytes>);
+
+pub struct ByteHashMap(HashMap<Bytes, Bytes>);
+
+/// The snapshot of the pairs of a map, in the ascending order of the keys for the skip list and the
+/// AVL tree, and in any order for the hash map.
+pub struct MapIter {
+    pairs: Vec<(Bytes, Bytes)>,
+    next: usize,
+}
+
+/// The visitor of `*_for_each`, which is called with the user data and every pair in the order of
+/// the iterator, and returns false to stop. The key and the value are only valid in the call.
+pub type MapVisitor = Option<
+    unsafe extern "C" fn(
+        user_data: *mut c_void,
+        key: *const u8,
+        key_len: usize,
+        val: *const u8,
+        val_len: usize,
+    ) -> bool,
+>;
+
+// The lookups by the borrowed keys, so that the keys from C aren't copied like the ones of `Map`.
+trait ByteMap: Map<Bytes, Bytes> {
+    fn get_bytes(&self, k: &[u8]) -> Option<&Bytes>;
+
+    fn remove_bytes(&mut self, k: &[u8]) -> Option<Bytes>;
+}
+
+impl ByteMap for SkipList<Bytes, Bytes> {
+    fn get_bytes(&self, k: &[u8]) -> Option<&Bytes> {
+        self.find(k)
+    }
+
+    fn remove_bytes(&mut self, k: &[u8]) -> Option<Bytes> {
+        self.delete_ref(k)
+    }
+}
+
+impl ByteMap for AVLTree<Bytes, Bytes> {
+    fn get_bytes(&self, k: &[u8]) -> Option<&Bytes> {
+        self.find(k)
+    }
+
+    fn remove_bytes(&mut self, k: &[u8]) -> Option<Bytes> {
+        self.delete_ref(k)
+    }
+}
+
+impl ByteMap for HashMap<Bytes, Bytes> {
+    fn get_bytes(&self, k: &[u8]) -> Option<&Bytes> {
+        self.find(k)
+    }
+
+    fn remove_bytes(&mut self, k: &[u8]) -> Option<Bytes> {
+        self.remove_ref(k)
+    }
+}
+
+fn not_found(key: &[u8]) -> Error {
+    Error::new(
+        ErrorCode::NotFound,
+        format!("key not found: {}", String::from_utf8_lossy(key)),
+    )
+}
+
+unsafe fn put<M: Map<Bytes, Bytes>>(
+    map: Option<&mut M>,
+    key: *const u8,
+    key_len: usize,
+    val: *const u8,
+    val_len: usize,
+) -> ErrorCode {
+    status(|| {
+        let map = map.ok_or_else(|| Error::null("map"))?;
+        let k = bytes(key, key_len, "key")?;
+        let v = bytes(val, val_len, "val")?;
+        map.insert(k.to_vec(), v.to_vec());
+        Ok(())
+    })
+}
+
+unsafe fn get<M: ByteMap>(
+    map: Option<&M>,
+    key: *const u8,
+    key_len: usize,
+    val: *mut *const u8,
+    val_len: *mut usize,
+) -> ErrorCode {
+    status(|| {
+        let map = map.ok_or_else(|| Error::null("map"))?;
+        let k = bytes(key, key_len, "key")?;
+        if val.is_null() || val_len.is_null() {
+            return Err(Error::null("val"));
+        }
+        let (p, len) = map
+            .get_bytes(k)
+            .map(|v| (v.as_ptr(), v.len()))
+            .ok_or_else(|| not_found(k))?;
+        (*val, *val_len) = (p, len);
+        Ok(())
+    })
+}
+
+unsafe fn delete<M: ByteMap>(map: Option<&mut M>, key: *const u8, key_len: usize) -> ErrorCode {
+    status(|| {
+        let map = map.ok_or_else(|| Error::null("map"))?;
+        let k = bytes(key, key_len, "key")?;
+        map.remove_bytes(k).map(|_| ()).ok_or_else(|| not_found(k))
+    })
+}
+
+fn iter<M: Map<Bytes, Bytes>>(map: Option<&M>) -> *mut MapIter {
+    catch(ptr::null_mut(), || {
+        let map = map.ok_or_else(|| Error::null("map"))?;
+        Ok(Box::into_raw(Box::new(MapIter {
+            pairs: map.to_vec(),
+            next: 0,
+        })))
+    })
+}
+
+unsafe fn for_each<M: Map<Bytes, Bytes>>(
+    map: Option<&M>,
+    visitor: MapVisitor,
+    user_data: *mut c_void,
+) -> ErrorCode {
+    status(|| {
+        let map = map.ok_or_else(|| Error::null("map"))?;
+        let visitor = visitor.ok_or_else(|| Error::null("visitor"))?;
+        for (k, v) in map.iter() {
+            if !visitor(user_data, k.as_ptr(), k.len(), v.as_ptr(), v.len()) {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+unsafe fn free<T>(p: *mut T) {
+    if !p.is_null() {
+        drop(Box::from_raw(p));
+    }
+}
+
+//
+// C API: 'skiplist_*'
+//
+
+#[no_mangle]
+pub extern "C" fn skiplist_new() -> *mut ByteSkipList {
+    Box::into_raw(Box::new(ByteSkipList(SkipList::new())))
+}
+
+/// Free the map and all its pairs.
+///
+/// # Safety
+///
+/// The map must be NULL or come from `skiplist_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed while it's visited by `skiplist_for_each`.
+#[no_mangle]
+pub unsafe extern "C" fn skiplist_free(map: *mut ByteSkipList) {
+    free(map)
+}
+
+/// Insert the pair, or replace the value if the key exists. Both of them are copied.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The `key` and `val` must be NULL or
+/// point to `key_len` and `val_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn skiplist_put(
+    map: *mut ByteSkipList,
+    key: *const u8,
+    key_len: usize,
+    val: *const u8,
+    val_len: usize,
+) -> ErrorCode {
+    put(map.as_mut().map(|m| &mut m.0), key, key_len, val, val_len)
+}
+
+/// Point `val` at the value of the key and set `val_len` to its length, or fail with
+/// `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn skiplist_get(
+    map: *const ByteSkipList,
+    key: *const u8,
+    key_len: usize,
+    val: *mut *const u8,
+    val_len: *mut usize,
+) -> ErrorCode {
+    get(map.as_ref().map(|m| &m.0), key, key_len, val, val_len)
+}
+
+/// Remove the key, or fail with `ErrorCode_NotFound`.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn skiplist_delete(
+    map: *mut ByteSkipList,
+    key: *const u8,
+    key_len: usize,
+) -> ErrorCode {
+    delete(map.as_mut().map(|m| &mut m.0), key, key_len)
+}
+
+/// The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`.
+#[no_mangle]
+pub unsafe extern "C" fn skiplist_len(map: *const ByteSkipList) -> usize {
+    catch(0, || {
+        let map = map.as_ref().ok_or_else(|| Error::null("map"))?;
+        Ok(map.0.len())
+    })
+}
+
+/// Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+/// error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`.
+#[no_mangle]
+pub unsafe extern "C" fn skiplist_iter(map: *const ByteSkipList) -> *mut MapIter {
+    iter(map.as_ref().map(|m| &m.0))
+}
+
+/// Call the visitor with every pair until it returns false.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `skiplist_new`. The map is borrowed while it's visited, so
+/// the visitor must neither change nor free it by `skiplist_put`, `skiplist_delete` or `skiplist_free`. Use
+/// `skiplist_iter` instead to change the map while iterating.
+#[no_mangle]
+pub unsafe extern "C" fn skiplist_for_each(
+    map: *const ByteSkipList,
+    visitor: MapVisitor,
+    user_data: *mut c_void,
+) -> ErrorCode {
+    for_each(map.as_ref().map(|m| &m.0), visitor, user_data)
+}
+
+//
+// C API: 'avl_tree_*'
+//
+
+#[no_mangle]
+pub extern "C" fn avl_tree_new() -> *mut ByteAvlTree {
+    Box::into_raw(Box::new(ByteAvlTree(AVLTree::new())))
+}
+
+/// Free the map and all its pairs.
+///
+/// # Safety
+///
+/// The map must be NULL or come from `avl_tree_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed while it's visited by `avl_tree_for_each`.
+#[no_mangle]
+pub unsafe extern "C" fn avl_tree_free(map: *mut ByteAvlTree) {
+    free(map)
+}
+
+/// Insert the pair, or replace the value if the key exists. Both of them are copied.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The `key` and `val` must be NULL or
+/// point to `key_len` and `val_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn avl_tree_put(
+    map: *mut ByteAvlTree,
+    key: *const u8,
+    key_len: usize,
+    val: *const u8,
+    val_len: usize,
+) -> ErrorCode {
+    put(map.as_mut().map(|m| &mut m.0), key, key_len, val, val_len)
+}
+
+/// Point `val` at the value of the key and set `val_len` to its length, or fail with
+/// `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn avl_tree_get(
+    map: *const ByteAvlTree,
+    key: *const u8,
+    key_len: usize,
+    val: *mut *const u8,
+    val_len: *mut usize,
+) -> ErrorCode {
+    get(map.as_ref().map(|m| &m.0), key, key_len, val, val_len)
+}
+
+/// Remove the key, or fail with `ErrorCode_NotFound`.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn avl_tree_delete(
+    map: *mut ByteAvlTree,
+    key: *const u8,
+    key_len: usize,
+) -> ErrorCode {
+    delete(map.as_mut().map(|m| &mut m.0), key, key_len)
+}
+
+/// The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn avl_tree_len(map: *const ByteAvlTree) -> usize {
+    catch(0, || {
+        let map = map.as_ref().ok_or_else(|| Error::null("map"))?;
+        Ok(map.0.len())
+    })
+}
+
+/// Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+/// error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`.
+#[no_mangle]
+pub unsafe extern "C" fn avl_tree_iter(map: *const ByteAvlTree) -> *mut MapIter {
+    iter(map.as_ref().map(|m| &m.0))
+}
+
+/// Call the visitor with every pair until it returns false.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `avl_tree_new`. The map is borrowed while it's visited, so
+/// the visitor must neither change nor free it by `avl_tree_put`, `avl_tree_delete` or `avl_tree_free`. Use
+/// `avl_tree_iter` instead to change the map while iterating.
+#[no_mangle]
+pub unsafe extern "C" fn avl_tree_for_each(
+    map: *const ByteAvlTree,
+    visitor: MapVisitor,
+    user_data: *mut c_void,
+) -> ErrorCode {
+    for_each(map.as_ref().map(|m| &m.0), visitor, user_data)
+}
+
+//
+// C API: 'hash_map_*'
+//
+
+#[no_mangle]
+pub extern "C" fn hash_map_new() -> *mut ByteHashMap {
+    Box::into_raw(Box::new(ByteHashMap(HashMap::new())))
+}
+
+/// Free the map and all its pairs.
+///
+/// # Safety
+///
+/// The map must be NULL or come from `hash_map_new`, and be freed exactly once. It must not be used
+/// after this, and not be freed while it's visited by `hash_map_for_each`.
+#[no_mangle]
+pub unsafe extern "C" fn hash_map_free(map: *mut ByteHashMap) {
+    free(map)
+}
+
+/// Insert the pair, or replace the value if the key exists. Both of them are copied.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The `key` and `val` must be NULL or
+/// point to `key_len` and `val_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn hash_map_put(
+    map: *mut ByteHashMap,
+    key: *const u8,
+    key_len: usize,
+    val: *const u8,
+    val_len: usize,
+) -> ErrorCode {
+    put(map.as_mut().map(|m| &mut m.0), key, key_len, val, val_len)
+}
+
+/// Point `val` at the value of the key and set `val_len` to its length, or fail with
+/// `ErrorCode_NotFound`. The value is only valid until the map is changed or freed.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes, and `val` and `val_len` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn hash_map_get(
+    map: *const ByteHashMap,
+    key: *const u8,
+    key_len: usize,
+    val: *mut *const u8,
+    val_len: *mut usize,
+) -> ErrorCode {
+    get(map.as_ref().map(|m| &m.0), key, key_len, val, val_len)
+}
+
+/// Remove the key, or fail with `ErrorCode_NotFound`.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The `key` must be NULL or point to
+/// `key_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn hash_map_delete(
+    map: *mut ByteHashMap,
+    key: *const u8,
+    key_len: usize,
+) -> ErrorCode {
+    delete(map.as_mut().map(|m| &mut m.0), key, key_len)
+}
+
+/// The number of the pairs, or 0 if the map is NULL, which is also kept as the last error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn hash_map_len(map: *const ByteHashMap) -> usize {
+    catch(0, || {
+        let map = map.as_ref().ok_or_else(|| Error::null("map"))?;
+        Ok(map.0.len())
+    })
+}
+
+/// Take the snapshot of the pairs, which must be freed by `map_iter_free`, or return NULL on the
+/// error.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`.
+#[no_mangle]
+pub unsafe extern "C" fn hash_map_iter(map: *const ByteHashMap) -> *mut MapIter {
+    iter(map.as_ref().map(|m| &m.0))
+}
+
+/// Call the visitor with every pair until it returns false.
+///
+/// # Safety
+///
+/// The map must be NULL or a live handle from `hash_map_new`. The map is borrowed while it's visited, so
+/// the visitor must neither change nor free it by `hash_map_put`, `hash_map_delete` or `hash_map_free`. Use
+/// `hash_map_iter` instead to change the map while iterating.
+#[no_mangle]
+pub unsafe extern "C" fn hash_map_for_each(
+    map: *const ByteHashMap,
+    visitor: MapVisitor,
+    user_data: *mut c_void,
+) -> ErrorCode {
+    for_each(map.as_ref().map(|m| &m.0), visitor, user_data)
+}
+
+//
+// C API: 'map_iter_*'
+//
+
+/// Move to the next pair and return true, or return false at the end or on the error. The key and
+/// the value point into the iterator, and they're valid until it's freed.
+///
+/// # Safety
+///
+/// The iterator must be NULL or a live handle from `*_iter`, and `key`, `key_len`, `val` and
+/// `val_len` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn map_iter_next(
+    iter: *mut MapIter,
+    key: *mut *const u8,
+    key_len: *mut usize,
+    val: *mut *const u8,
+    val_len: *mut usize,
+) -> bool {
+    catch(false, || {
+        let iter = iter.as_mut().ok_or_else(|| Error::null("iter"))?;
+        if key.is_null() || key_len.is_null() || val.is_null() || val_len.is_null() {
+            return Err(Error::null("key or val"));
+        }
+        let Some((k, v)) = iter.pairs.get(iter.next) else {
+            return Ok(false);
+        };
+        iter.next += 1;
+        (*key, *key_len) = (k.as_ptr(), k.len());
+        (*val, *val_len) = (v.as_ptr(), v.len());
+        Ok(true)
+    })
+}
+
+/// Free the iterator and its snapshot.
+///
+/// # Safety
+///
+/// The iterator must be NULL or come from `*_iter`, and be freed exactly once.
+#[no_mangle]
+pub unsafe extern "C" fn map_iter_free(iter: *mut MapIter) {
+    free(iter)
+}
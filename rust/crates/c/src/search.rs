@@ -0,0 +1,155 @@
+// The string searches of the `algorithm` crate exposed to C, which search the byte buffers of any
+// bytes including NUL: KMP for one pattern, and the Aho–Corasick automaton for many patterns.
+//
+// The matches are written into the buffer of the caller like `snprintf`: at most `cap` of them are
+// written, and `count` is always set to the number of all the matches, so the caller can retry
+// with a bigger buffer. The buffer may be NULL only if `cap` is zero. The empty patterns are
+// rejected as `ErrorCode_InvalidArgument`, since they'd match everywhere.
+
+use std::ptr;
+
+use algorithm::basic::{aho_corasick, kmp};
+
+use crate::error::{catch, status, Error, ErrorCode};
+use crate::{bytes, write_out};
+
+/// The Aho–Corasick automaton of a set of patterns, which can search many texts.
+pub struct AhoCorasick(aho_corasick::AhoCorasick);
+
+/// The occurrence of the pattern at the index `pattern` at `text[offset..offset + len]`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SearchMatch {
+    pub pattern: usize,
+    pub offset: usize,
+    pub len: usize,
+}
+
+fn empty_pattern(i: usize) -> Error {
+    Error::new(
+        ErrorCode::InvalidArgument,
+        format!("the pattern {} is empty", i),
+    )
+}
+
+//
+// C API: 'kmp_search'
+//
+
+/// Find the offsets of all the occurrences of the pattern in the text by KMP, in the ascending
+/// order and including the overlapped ones.
+///
+/// # Safety
+///
+/// The `text` and `pattern` must be NULL or point to `text_len` and `pattern_len` readable bytes.
+/// The `offsets` must be NULL or point to `cap` writable offsets, and `count` must be NULL or
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn kmp_search(
+    text: *const u8,
+    text_len: usize,
+    pattern: *const u8,
+    pattern_len: usize,
+    offsets: *mut usize,
+    cap: usize,
+    count: *mut usize,
+) -> ErrorCode {
+    status(|| {
+        let text = bytes(text, text_len, "text")?;
+        let pattern = bytes(pattern, pattern_len, "pattern")?;
+        if pattern.is_empty() {
+            return Err(empty_pattern(0));
+        }
+        write_out(kmp::find_all(text, pattern), offsets, cap, count)
+    })
+}
+
+//
+// C API: 'aho_corasick_*'
+//
+
+/// Build the automaton of `n` patterns, where the pattern `i` is `patterns[i]` of `lens[i]` bytes,
+/// or return NULL on the error. The patterns are copied, and identified by their indexes.
+///
+/// # Safety
+///
+/// Unless `n` is zero, `patterns` and `lens` must point to `n` readable items, and every
+/// `patterns[i]` must be NULL or point to `lens[i]` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aho_corasick_new(
+    patterns: *const *const u8,
+    lens: *const usize,
+    n: usize,
+) -> *mut AhoCorasick {
+    catch(ptr::null_mut(), || {
+        if n > 0 && (patterns.is_null() || lens.is_null()) {
+            return Err(Error::null("patterns or lens"));
+        }
+        let mut all = Vec::with_capacity(n);
+        for i in 0..n {
+            let p = bytes(*patterns.add(i), *lens.add(i), "pattern")?;
+            if p.is_empty() {
+                return Err(empty_pattern(i));
+            }
+            all.push(p);
+        }
+        let ac = aho_corasick::AhoCorasick::new(&all);
+        Ok(Box::into_raw(Box::new(AhoCorasick(ac))))
+    })
+}
+
+/// Free the automaton.
+///
+/// # Safety
+///
+/// The `ac` must be NULL or come from `aho_corasick_new`, and be freed exactly once. It must not be
+/// used after this.
+#[no_mangle]
+pub unsafe extern "C" fn aho_corasick_free(ac: *mut AhoCorasick) {
+    if !ac.is_null() {
+        drop(Box::from_raw(ac));
+    }
+}
+
+/// The number of the patterns, or 0 if the automaton is NULL, which is also kept as the last
+/// error.
+///
+/// # Safety
+///
+/// The `ac` must be NULL or a live handle from `aho_corasick_new`.
+#[no_mangle]
+pub unsafe extern "C" fn aho_corasick_pattern_count(ac: *const AhoCorasick) -> usize {
+    catch(0, || {
+        let ac = ac.as_ref().ok_or_else(|| Error::null("ac"))?;
+        Ok(ac.0.pattern_count())
+    })
+}
+
+/// Find all the occurrences of the patterns in the text, in the ascending order of their ends, and
+/// the longer patterns first for the same end.
+///
+/// # Safety
+///
+/// The `ac` must be NULL or a live handle from `aho_corasick_new`, and `text` must be NULL or point
+/// to `text_len` readable bytes. The `matches` must be NULL or point to `cap` writable matches, and
+/// `count` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn aho_corasick_search(
+    ac: *const AhoCorasick,
+    text: *const u8,
+    text_len: usize,
+    matches: *mut SearchMatch,
+    cap: usize,
+    count: *mut usize,
+) -> ErrorCode {
+    status(|| {
+        let ac = ac.as_ref().ok_or_else(|| Error::null("ac"))?;
+        let text = bytes(text, text_len, "text")?;
+        let found = ac.0.find_all(text).into_iter().map(|m| SearchMatch {
+            pattern: m.pattern,
+            offset: m.start,
+            len: m.end - m.start,
+        });
+        write_out(found, matches, cap, count)
+    })
+}
@@ -0,0 +1,157 @@
+// The sorts of the `algorithm` crate exposed to C with the same arguments as `qsort` of libc: the
+// array of `nmemb` elements of `size` bytes each, and the comparator which returns a negative
+// number, zero or a positive number when the first element is less than, equal to or greater than
+// the second one.
+//
+// The elements are sorted as pointers by the comparator, which always gets the pointers into the
+// array as `qsort` does, and then moved into their places through a copy of `nmemb * size` bytes.
+
+use std::cmp::Ordering;
+use std::ffi::{c_int, c_void};
+use std::ptr;
+
+use algorithm::basic::{heapsort, insert_sort, msort, qsort, select_sort};
+
+use crate::bytes;
+use crate::error::{status, Error, ErrorCode};
+
+/// The comparator of the sorts, which is the same as the one of `qsort`.
+pub type Comparator = Option<unsafe extern "C" fn(a: *const c_void, b: *const c_void) -> c_int>;
+
+type Elem = *const u8;
+
+type Cmp<'a> = &'a mut dyn FnMut(&Elem, &Elem) -> Ordering;
+
+unsafe fn sort_with(
+    base: *mut c_void,
+    nmemb: usize,
+    size: usize,
+    compar: Comparator,
+    sort: impl FnOnce(&mut [Elem], Cmp),
+) -> ErrorCode {
+    status(|| {
+        let compar = compar.ok_or_else(|| Error::null("compar"))?;
+        if nmemb == 0 {
+            return Ok(());
+        }
+        let total = nmemb
+            .checked_mul(size)
+            .filter(|&total| size > 0 && total <= isize::MAX as usize)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorCode::InvalidArgument,
+                    format!("invalid array of {} elements of {} bytes", nmemb, size),
+                )
+            })?;
+        let base = bytes(base as *const u8, total, "base")?.as_ptr();
+
+        let mut elems: Vec<Elem> = (0..nmemb).map(|i| base.add(i * size)).collect();
+        sort(&mut elems, &mut |a, b| {
+            compar(*a as *const c_void, *b as *const c_void).cmp(&0)
+        });
+
+        let mut sorted = Vec::with_capacity(total);
+        for e in elems {
+            sorted.extend_from_slice(bytes(e, size, "base")?);
+        }
+        ptr::copy_nonoverlapping(sorted.as_ptr(), base as *mut u8, total);
+        Ok(())
+    })
+}
+
+//
+// C API: 'sort_*'
+//
+
+/// Sort by the quick sort, which isn't stable.
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+#[no_mangle]
+pub unsafe extern "C" fn sort_quick(
+    base: *mut c_void,
+    nmemb: usize,
+    size: usize,
+    compar: Comparator,
+) -> ErrorCode {
+    sort_with(base, nmemb, size, compar, |v, cmp| qsort::qsort_by(v, cmp))
+}
+
+/// Sort by the heap sort, which isn't stable.
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+#[no_mangle]
+pub unsafe extern "C" fn sort_heap(
+    base: *mut c_void,
+    nmemb: usize,
+    size: usize,
+    compar: Comparator,
+) -> ErrorCode {
+    sort_with(base, nmemb, size, compar, |v, cmp| {
+        heapsort::sort_by(v, cmp)
+    })
+}
+
+/// Sort by the merge sort, which is stable.
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+#[no_mangle]
+pub unsafe extern "C" fn sort_merge(
+    base: *mut c_void,
+    nmemb: usize,
+    size: usize,
+    compar: Comparator,
+) -> ErrorCode {
+    sort_with(base, nmemb, size, compar, |v, cmp| {
+        msort::merge_sort_by(v, cmp)
+    })
+}
+
+/// Sort by the insertion sort, which is stable and O(n^2).
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+#[no_mangle]
+pub unsafe extern "C" fn sort_insertion(
+    base: *mut c_void,
+    nmemb: usize,
+    size: usize,
+    compar: Comparator,
+) -> ErrorCode {
+    sort_with(base, nmemb, size, compar, |v, cmp| {
+        insert_sort::insert_sort_by(v, cmp)
+    })
+}
+
+/// Sort by the selection sort, which isn't stable and is O(n^2).
+///
+/// # Safety
+///
+/// The `base` must point to `nmemb * size` readable and writable bytes unless `nmemb` is zero, and
+/// the comparator must be safe to call with any two pointers to the elements in the array. It must
+/// not change the array, which is read by the sort while it's called.
+#[no_mangle]
+pub unsafe extern "C" fn sort_selection(
+    base: *mut c_void,
+    nmemb: usize,
+    size: usize,
+    compar: Comparator,
+) -> ErrorCode {
+    sort_with(base, nmemb, size, compar, |v, cmp| {
+        select_sort::select_sort_by(v, cmp)
+    })
+}